@@ -0,0 +1,406 @@
+use super::{
+    client::Client,
+    models::{
+        APIResult, AccountInformation, Balance, ExchangeApiResult, Order, OrderResponse, OrderSide,
+        OrderType, TimeInForce, TradeHistory, Transaction,
+    },
+};
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde_json;
+use std::collections::BTreeMap;
+use tracing::{debug, error};
+
+#[derive(Clone)]
+pub struct Account {
+    pub client: Client,
+}
+
+impl Account {
+    pub fn new(api_key: Option<String>, secret_key: Option<String>) -> Self {
+        Account {
+            client: Client::new(&api_key.unwrap(), &secret_key.unwrap()),
+        }
+    }
+
+    pub fn limit_buy(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<Transaction> {
+        self.limit_order(
+            symbol,
+            OrderSide::Buy,
+            amount,
+            price,
+            TimeInForce::GTC,
+            custom_order_id,
+        )
+    }
+
+    pub fn limit_sell(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<Transaction> {
+        self.limit_order(
+            symbol,
+            OrderSide::Sell,
+            amount,
+            price,
+            TimeInForce::GTC,
+            custom_order_id,
+        )
+    }
+
+    pub fn limit_order(
+        &self,
+        symbol: &str,
+        order_side: OrderSide,
+        amount: f64,
+        price: f64,
+        time_in_force: TimeInForce,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<Transaction> {
+        let mut params = order_params(custom_order_id);
+        params.insert("price".to_string(), price.to_string());
+        params.insert(
+            "timeInForce".to_string(),
+            time_in_force.as_str().to_string(),
+        );
+
+        self.custom_order(symbol, order_side, OrderType::Limit, amount, params)
+    }
+
+    // Limit order placed when the last price reaches the stop price
+    pub fn stop_limit_order(
+        &self,
+        symbol: &str,
+        order_side: OrderSide,
+        amount: f64,
+        price: f64,
+        stop_price: f64,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<Transaction> {
+        let mut params = order_params(custom_order_id);
+        params.insert("price".to_string(), price.to_string());
+        params.insert("stopPrice".to_string(), stop_price.to_string());
+        params.insert(
+            "timeInForce".to_string(),
+            TimeInForce::GTC.as_str().to_string(),
+        );
+
+        self.custom_order(symbol, order_side, OrderType::StopLossLimit, amount, params)
+    }
+
+    pub fn market_buy(&self, symbol: &str, amount: f64) -> ExchangeApiResult<Transaction> {
+        self.custom_order(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Market,
+            amount,
+            BTreeMap::new(),
+        )
+    }
+
+    pub fn market_sell(&self, symbol: &str, amount: f64) -> ExchangeApiResult<Transaction> {
+        self.custom_order(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Market,
+            amount,
+            BTreeMap::new(),
+        )
+    }
+
+    // Order with its own type parameters, e.g. price, stopPrice, timeInForce and newClientOrderId
+    pub fn custom_order(
+        &self,
+        symbol: &str,
+        order_side: OrderSide,
+        order_type: OrderType,
+        amount: f64,
+        mut params: BTreeMap<String, String>,
+    ) -> ExchangeApiResult<Transaction> {
+        params.insert("symbol".to_string(), symbol.to_string());
+        params.insert("side".to_string(), order_side.as_str().to_string());
+        params.insert("type".to_string(), order_type.as_str().to_string());
+        params.insert("quantity".to_string(), amount.to_string());
+
+        match self.signed::<OrderResponse>(Method::POST, "/api/v3/order", params.clone()) {
+            Ok(order) => {
+                debug!("[Binance API] New order: {:?}", params);
+
+                Ok(Transaction {
+                    symbol: order.symbol,
+                    order_id: order.order_id,
+                })
+            }
+            Err(e) => {
+                error!("Binance new order error {}", e);
+
+                Err("Binance New order Error")
+            }
+        }
+    }
+
+    pub fn cancel_order(&self, symbol: &str, order_id: u64) -> ExchangeApiResult<Transaction> {
+        let mut params = BTreeMap::new();
+        params.insert("orderId".to_string(), order_id.to_string());
+
+        self.cancel(symbol, params)
+    }
+
+    pub fn cancel_order_with_client_id(
+        &self,
+        symbol: &str,
+        custom_order_id: &str,
+    ) -> ExchangeApiResult<Transaction> {
+        let mut params = BTreeMap::new();
+        params.insert("origClientOrderId".to_string(), custom_order_id.to_string());
+
+        self.cancel(symbol, params)
+    }
+
+    pub fn get_open_orders(&self, symbol: &str) -> APIResult<Vec<Order>> {
+        let mut params = BTreeMap::new();
+        params.insert("symbol".to_string(), symbol.to_string());
+
+        self.signed(Method::GET, "/api/v3/openOrders", params)
+    }
+
+    // Open orders of all symbols
+    pub fn get_all_open_orders(&self) -> APIResult<Vec<Order>> {
+        self.signed(Method::GET, "/api/v3/openOrders", BTreeMap::new())
+    }
+
+    // Balances of all assets of the account in one request
+    pub fn get_balances(&self) -> APIResult<Vec<Balance>> {
+        let account: AccountInformation =
+            self.signed(Method::GET, "/api/v3/account", BTreeMap::new())?;
+
+        debug!("[Binance] Get balances result: {:?} ", account.balances);
+
+        Ok(account.balances)
+    }
+
+    // Asset in upper case, e.g. BTC
+    pub fn get_balance(&self, asset: &str) -> APIResult<Balance> {
+        self.get_balances()?
+            .into_iter()
+            .find(|balance| balance.asset == asset)
+            .ok_or_else(|| "Asset not found".into())
+    }
+
+    pub fn trade_history(&self, symbol: &str) -> APIResult<Vec<TradeHistory>> {
+        let mut params = BTreeMap::new();
+        params.insert("symbol".to_string(), symbol.to_string());
+
+        self.signed(Method::GET, "/api/v3/myTrades", params)
+    }
+
+    fn cancel(
+        &self,
+        symbol: &str,
+        mut params: BTreeMap<String, String>,
+    ) -> ExchangeApiResult<Transaction> {
+        params.insert("symbol".to_string(), symbol.to_string());
+
+        match self.signed::<OrderResponse>(Method::DELETE, "/api/v3/order", params) {
+            Ok(order) => {
+                debug!(
+                    "[Binance] Order was canceled: symbol {}, {}",
+                    order.symbol, order.client_order_id
+                );
+
+                Ok(Transaction {
+                    symbol: order.symbol,
+                    order_id: order.order_id,
+                })
+            }
+            Err(e) => {
+                error!("Binance cancel order error {}", e);
+
+                Err("Binance Cancel order Error")
+            }
+        }
+    }
+
+    fn signed<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        params: BTreeMap<String, String>,
+    ) -> APIResult<T> {
+        let data = self.client.signed(method, endpoint, params)?;
+
+        Ok(serde_json::from_str(data.as_str())?)
+    }
+}
+
+fn order_params(custom_order_id: Option<String>) -> BTreeMap<String, String> {
+    let mut params = BTreeMap::new();
+
+    if let Some(id) = custom_order_id {
+        params.insert("newClientOrderId".to_string(), id);
+    }
+
+    params
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc::{channel, Receiver};
+    use std::thread;
+
+    // Local mock server answers one request with the recorded status and body
+    fn mock_server(
+        status: &'static str,
+        response_body: &'static str,
+    ) -> (String, Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let (request_sender, request_receiver) = channel();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+
+            // Parameters are in the query string, the request has no body
+            loop {
+                let read = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..read]);
+
+                if read == 0 || String::from_utf8_lossy(&request).contains("\r\n\r\n") {
+                    break;
+                }
+            }
+
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                response_body.len(),
+                response_body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+
+            request_sender
+                .send(String::from_utf8_lossy(&request).to_string())
+                .unwrap();
+        });
+
+        (address, request_receiver)
+    }
+
+    fn account(host: &str) -> Account {
+        Account {
+            client: Client::with_host("API_KEY", "SECRET_KEY", host),
+        }
+    }
+
+    // Value of the query parameter in the request line
+    fn query_param(request: &str, name: &str) -> Option<String> {
+        let target = request.lines().next()?.split(' ').nth(1)?;
+        let query = target.splitn(2, '?').nth(1)?;
+
+        query
+            .split('&')
+            .filter_map(|pair| {
+                let mut pair = pair.splitn(2, '=');
+                Some((pair.next()?, pair.next()?))
+            })
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.to_string())
+    }
+
+    #[test]
+    fn limit_buy_mock_server() {
+        let (host, request) = mock_server(
+            "200 OK",
+            r#"{"symbol":"BTCUSDT","orderId":28,"clientOrderId":"Custom123","transactTime":1507725176595}"#,
+        );
+
+        let transaction = account(&host)
+            .limit_buy("BTCUSDT", 0.5, 30010., Some("Custom123".to_string()))
+            .unwrap();
+
+        assert_eq!(transaction.order_id, 28);
+
+        let request = request.recv().unwrap();
+
+        assert!(request.starts_with("POST /api/v3/order?"));
+        assert!(request.to_lowercase().contains("x-mbx-apikey: api_key"));
+        assert_eq!(query_param(&request, "type").unwrap(), "LIMIT");
+        assert_eq!(query_param(&request, "timeInForce").unwrap(), "GTC");
+        assert_eq!(query_param(&request, "price").unwrap(), "30010");
+        assert_eq!(
+            query_param(&request, "newClientOrderId").unwrap(),
+            "Custom123"
+        );
+        assert!(query_param(&request, "signature").is_some());
+    }
+
+    #[test]
+    fn exchange_timestamp_mock_server() {
+        let (host, request) = mock_server(
+            "200 OK",
+            r#"{"symbol":"BTCUSDT","orderId":28,"clientOrderId":"Custom123"}"#,
+        );
+
+        // Local clock is an hour ahead of the exchange
+        let account = Account {
+            client: Client::with_host("API_KEY", "SECRET_KEY", &host)
+                .with_clock(|| chrono::Utc::now().timestamp_millis() - 3_600_000),
+        };
+
+        account
+            .cancel_order_with_client_id("BTCUSDT", "Custom123")
+            .unwrap();
+
+        let request = request.recv().unwrap();
+        let timestamp: i64 = query_param(&request, "timestamp").unwrap().parse().unwrap();
+
+        assert!(request.starts_with("DELETE /api/v3/order?"));
+        assert!((chrono::Utc::now().timestamp_millis() - 3_600_000 - timestamp).abs() < 60_000);
+        assert!(query_param(&request, "recvWindow").is_some());
+    }
+
+    #[test]
+    fn balances_mock_server() {
+        let (host, request) = mock_server(
+            "200 OK",
+            r#"{"balances":[{"asset":"BTC","free":"0.5","locked":"0.1"},{"asset":"USDT","free":"1000","locked":"0"}]}"#,
+        );
+
+        let balances = account(&host).get_balances().unwrap();
+
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances[0].asset, "BTC");
+        assert_eq!(balances[0].free, 0.5);
+        assert_eq!(balances[1].free, 1000.);
+
+        assert!(request.recv().unwrap().starts_with("GET /api/v3/account?"));
+    }
+
+    #[test]
+    fn api_error_mock_server() {
+        let (host, _request) = mock_server(
+            "400 Bad Request",
+            r#"{"code":-2010,"msg":"Account has insufficient balance for requested action."}"#,
+        );
+
+        assert!(account(&host)
+            .limit_sell("BTCUSDT", 1., 30000., None)
+            .is_err());
+    }
+}
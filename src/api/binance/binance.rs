@@ -0,0 +1,84 @@
+use super::models::{APIResult, DepthSnapshot, Kline, ServerTime};
+use reqwest;
+use tracing::debug;
+
+pub static BINANCE_API_HOST: &'static str = "https://api.binance.com";
+
+pub struct BinanceApi;
+
+impl BinanceApi {
+    /// Binance API "/api/v3/depth"
+    pub fn depth(symbol: &str, limit: u16) -> APIResult<DepthSnapshot> {
+        debug!("[Binance] get depth snapshot {}", symbol);
+
+        let url = format!(
+            "{}/api/v3/depth?symbol={}&limit={}",
+            BINANCE_API_HOST,
+            symbol.to_uppercase(),
+            limit
+        );
+
+        let data = reqwest::blocking::get(&url)?.error_for_status()?.text()?;
+
+        let snapshot: DepthSnapshot = serde_json::from_str(data.as_str())?;
+
+        Ok(snapshot)
+    }
+
+    /// Binance API "/api/v3/klines", candles from the oldest one
+    pub fn klines(symbol: &str, interval: &str, limit: u16) -> APIResult<Vec<Kline>> {
+        debug!("[Binance] get {} klines {}", interval, symbol);
+
+        let url = format!(
+            "{}/api/v3/klines?symbol={}&interval={}&limit={}",
+            BINANCE_API_HOST,
+            symbol.to_uppercase(),
+            interval,
+            limit
+        );
+
+        let data = reqwest::blocking::get(&url)?.error_for_status()?.text()?;
+
+        let klines: Vec<Kline> = serde_json::from_str(data.as_str())?;
+
+        Ok(klines)
+    }
+
+    /// Binance API "/api/v3/time", unix milliseconds
+    pub fn server_time() -> APIResult<u64> {
+        let url = format!("{}/api/v3/time", BINANCE_API_HOST);
+
+        let data = reqwest::blocking::get(&url)?.error_for_status()?.text()?;
+
+        let server_time: ServerTime = serde_json::from_str(data.as_str())?;
+
+        Ok(server_time.server_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::BinanceApi;
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn depth() {
+        println!("{:?}", BinanceApi::depth("BTCUSDT", 100).unwrap());
+    }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn klines() {
+        println!("{:?}", BinanceApi::klines("BTCUSDT", "1m", 10).unwrap());
+    }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn server_time() {
+        println!("{}", BinanceApi::server_time().unwrap());
+    }
+}
@@ -0,0 +1,146 @@
+use super::binance::BINANCE_API_HOST;
+use super::error::BinanceError;
+use super::models::{APIResult, ErrorResponse};
+use crate::api::clock;
+use data_encoding::HEXLOWER;
+use reqwest;
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, USER_AGENT};
+use reqwest::Method;
+use ring::{digest, hmac};
+use std::collections::BTreeMap;
+use tracing::debug;
+
+// Milliseconds after the request timestamp the request is valid for the server
+static BINANCE_RECV_WINDOW: u64 = 5000;
+
+#[derive(Clone)]
+pub struct Client {
+    api_key: String,
+    secret_key: String,
+    host: String,
+    // Exchange time in unix milliseconds which requests are stamped by
+    now: fn() -> i64,
+}
+
+impl Client {
+    pub fn new(api_key: &str, secret_key: &str) -> Self {
+        Client::with_host(api_key, secret_key, BINANCE_API_HOST)
+    }
+
+    // Host can be changed to a local mock server
+    pub fn with_host(api_key: &str, secret_key: &str, host: &str) -> Self {
+        Client {
+            api_key: api_key.into(),
+            secret_key: secret_key.into(),
+            host: host.into(),
+            now: || clock::exchange_now("Binance"),
+        }
+    }
+
+    // Clock can be replaced, e.g. by a skewed one in tests
+    pub fn with_clock(mut self, now: fn() -> i64) -> Self {
+        self.now = now;
+        self
+    }
+
+    // Signed endpoints take all parameters in the query string.
+    // Timestamp is the exchange time, so local clock drift doesn't get requests rejected.
+    pub fn signed(
+        &self,
+        method: Method,
+        endpoint: &str,
+        mut parameters: BTreeMap<String, String>,
+    ) -> APIResult<String> {
+        parameters.insert("recvWindow".into(), BINANCE_RECV_WINDOW.to_string());
+        parameters.insert("timestamp".into(), (self.now)().to_string());
+
+        let query = build_query_string(parameters);
+        let signature = sign_hmac_sha256_hex(&self.secret_key, &query);
+
+        let path = format!("{}?{}&signature={}", endpoint, query, signature);
+
+        let mut headers = build_headers()?;
+        headers.insert("X-MBX-APIKEY", HeaderValue::from_str(&self.api_key)?);
+
+        self.request(method, &path, headers)
+    }
+
+    fn request(&self, method: Method, path: &str, headers: HeaderMap) -> APIResult<String> {
+        let request = format!("{}{}", self.host, path);
+
+        debug!("[Binance] Make {} request: {:?}", method, request);
+
+        let client = reqwest::blocking::Client::new();
+        let response = client
+            .request(method, request.as_str())
+            .headers(headers)
+            .send()?;
+
+        let status = response.status();
+        let body = response.text()?;
+
+        debug!("[Binance] Responce body: {:?}", body);
+
+        // Errors are returned with the code and message of the reason
+        if !status.is_success() {
+            let error: ErrorResponse = serde_json::from_str(body.as_str())?;
+
+            return Err(Box::new(BinanceError::ApiError(error.code, error.msg)));
+        }
+
+        Ok(body)
+    }
+}
+
+// Signature is HMAC SHA256 of the query string in hex
+pub fn sign_hmac_sha256_hex(secret: &str, query: &str) -> String {
+    let signed_key = hmac::SigningKey::new(&digest::SHA256, secret.as_bytes());
+    let signature = hmac::sign(&signed_key, query.as_bytes());
+
+    HEXLOWER.encode(signature.as_ref())
+}
+
+pub fn build_query_string(parameters: BTreeMap<String, String>) -> String {
+    parameters
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, percent_encode(&value)))
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+pub fn percent_encode(source: &str) -> String {
+    use percent_encoding::{utf8_percent_encode, USERINFO_ENCODE_SET};
+
+    utf8_percent_encode(&source, USERINFO_ENCODE_SET).to_string()
+}
+
+pub fn build_headers() -> APIResult<HeaderMap> {
+    let mut custom_headers = HeaderMap::new();
+
+    custom_headers.insert(USER_AGENT, HeaderValue::from_static("rs"));
+    custom_headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/x-www-form-urlencoded"),
+    );
+
+    Ok(custom_headers)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // Example from Binance API documentation
+    const SECRET: &str = "NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j";
+
+    #[test]
+    fn sign_new_order() {
+        let query = "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000&timestamp=1499827319559";
+
+        assert_eq!(
+            sign_hmac_sha256_hex(SECRET, query),
+            "c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71"
+        );
+    }
+}
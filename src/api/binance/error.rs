@@ -0,0 +1,21 @@
+use core::fmt;
+use std::error::Error;
+
+#[derive(Debug, Clone)]
+pub enum BinanceError {
+    ApiError(i64, String),
+}
+
+impl fmt::Display for BinanceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.clone() {
+            BinanceError::ApiError(code, why) => write!(f, "ApiError {}: {}", code, why),
+        }
+    }
+}
+
+impl Error for BinanceError {
+    fn description(&self) -> &str {
+        "Binance Error"
+    }
+}
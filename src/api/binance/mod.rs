@@ -0,0 +1,10 @@
+pub mod binance;
+pub mod models;
+pub mod websocket_data;
+
+mod account;
+mod client;
+mod error;
+
+pub use account::Account;
+pub use binance::BinanceApi;
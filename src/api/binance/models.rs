@@ -0,0 +1,282 @@
+use serde::{de, Deserialize, Deserializer};
+
+pub type APIResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+pub type ExchangeApiResult<T> = Result<T, &'static str>;
+
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    pub symbol: String,
+    pub order_id: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderType {
+    Limit,
+    // Limit order rejected if it would immediately match as a taker
+    LimitMaker,
+    Market,
+    StopLossLimit,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TimeInForce {
+    GTC,
+    IOC,
+    FOK,
+}
+
+impl OrderSide {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderSide::Buy => "BUY",
+            OrderSide::Sell => "SELL",
+        }
+    }
+}
+
+impl OrderType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderType::Limit => "LIMIT",
+            OrderType::LimitMaker => "LIMIT_MAKER",
+            OrderType::Market => "MARKET",
+            OrderType::StopLossLimit => "STOP_LOSS_LIMIT",
+        }
+    }
+}
+
+impl TimeInForce {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeInForce::GTC => "GTC",
+            TimeInForce::IOC => "IOC",
+            TimeInForce::FOK => "FOK",
+        }
+    }
+}
+
+// Error response of REST requests
+#[derive(Debug, Clone, Deserialize)]
+pub struct ErrorResponse {
+    pub code: i64,
+    pub msg: String,
+}
+
+// REST "/api/v3/order" response of new and canceled orders
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderResponse {
+    pub symbol: String,
+    pub order_id: u64,
+    pub client_order_id: String,
+}
+
+// REST "/api/v3/openOrders" order
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Order {
+    pub symbol: String,
+    pub order_id: u64,
+    pub client_order_id: String,
+    #[serde(deserialize_with = "string_f64")]
+    pub price: f64,
+    #[serde(deserialize_with = "string_f64")]
+    pub orig_qty: f64,
+    #[serde(deserialize_with = "string_f64")]
+    pub executed_qty: f64,
+    pub side: String,
+}
+
+// REST "/api/v3/account" response
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInformation {
+    pub balances: Vec<Balance>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Balance {
+    pub asset: String,
+    #[serde(deserialize_with = "string_f64")]
+    pub free: f64,
+    #[serde(deserialize_with = "string_f64")]
+    pub locked: f64,
+}
+
+// REST "/api/v3/myTrades" trade
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeHistory {
+    pub id: u64,
+    pub order_id: u64,
+    #[serde(deserialize_with = "string_f64")]
+    pub price: f64,
+    #[serde(deserialize_with = "string_f64")]
+    pub qty: f64,
+    #[serde(deserialize_with = "string_f64")]
+    pub commission: f64,
+    pub commission_asset: String,
+    pub time: u64,
+    pub is_buyer: bool,
+    pub is_maker: bool,
+}
+
+// REST "/api/v3/depth" response
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepthSnapshot {
+    pub last_update_id: u64,
+    #[serde(deserialize_with = "string_levels")]
+    pub bids: Vec<[f64; 2]>, // [price, size]
+    #[serde(deserialize_with = "string_levels")]
+    pub asks: Vec<[f64; 2]>,
+}
+
+// REST "/api/v3/time" response
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerTime {
+    pub server_time: u64,
+}
+
+// Diff depth stream event "depthUpdate"
+#[derive(Debug, Clone, Deserialize)]
+pub struct DepthUpdate {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "U")]
+    pub first_update_id: u64,
+    #[serde(rename = "u")]
+    pub last_update_id: u64,
+    #[serde(rename = "b", deserialize_with = "string_levels")]
+    pub bids: Vec<[f64; 2]>,
+    #[serde(rename = "a", deserialize_with = "string_levels")]
+    pub asks: Vec<[f64; 2]>,
+}
+
+// Trade stream event "trade" or aggregated trade stream event "aggTrade"
+#[derive(Debug, Clone, Deserialize)]
+pub struct TradeEvent {
+    #[serde(rename = "e")]
+    pub event_type: String,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    // Trade id of "trade", it's missing in "aggTrade"
+    #[serde(rename = "t")]
+    pub id: Option<u64>,
+    // Aggregate trade id of "aggTrade", seller order id of "trade"
+    #[serde(rename = "a")]
+    pub agg_id: Option<u64>,
+    #[serde(rename = "p", deserialize_with = "string_f64")]
+    pub price: f64,
+    #[serde(rename = "q", deserialize_with = "string_f64")]
+    pub qty: f64,
+    #[serde(rename = "T")]
+    pub trade_time: u64,
+    #[serde(rename = "m")]
+    pub is_buyer_maker: bool,
+}
+
+impl TradeEvent {
+    pub fn trade_id(&self) -> u64 {
+        match self.event_type.as_str() {
+            "aggTrade" => self.agg_id.unwrap_or_default(),
+            _ => self.id.unwrap_or_default(),
+        }
+    }
+}
+
+// REST "/api/v3/klines" candle:
+// [open time, open, high, low, close, volume, close time, quote volume, trades, taker buy volume,
+// taker buy quote volume, ignore]
+#[derive(Debug, Clone, Deserialize)]
+pub struct Kline(
+    pub u64,
+    #[serde(deserialize_with = "string_f64")] pub f64,
+    #[serde(deserialize_with = "string_f64")] pub f64,
+    #[serde(deserialize_with = "string_f64")] pub f64,
+    #[serde(deserialize_with = "string_f64")] pub f64,
+    #[serde(deserialize_with = "string_f64")] pub f64,
+    pub u64,
+    pub String,
+    pub u64,
+    pub String,
+    pub String,
+    pub String,
+);
+
+// Binance sends prices and sizes as strings
+fn string_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(de::Error::custom)
+}
+
+fn string_levels<'de, D>(deserializer: D) -> Result<Vec<[f64; 2]>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let levels = Vec::<[String; 2]>::deserialize(deserializer)?;
+
+    levels
+        .iter()
+        .map(|level| {
+            Ok([
+                level[0].parse().map_err(de::Error::custom)?,
+                level[1].parse().map_err(de::Error::custom)?,
+            ])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn depth_snapshot() {
+        let snapshot: DepthSnapshot = serde_json::from_str(
+            r#"{"lastUpdateId":1027024,"bids":[["4.00000000","431.00000000"]],"asks":[["4.00000200","12.00000000"]]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(snapshot.last_update_id, 1027024);
+        assert_eq!(snapshot.bids, vec![[4., 431.]]);
+        assert_eq!(snapshot.asks, vec![[4.000002, 12.]]);
+    }
+
+    #[test]
+    fn trade_event() {
+        let trade: TradeEvent = serde_json::from_str(
+            r#"{"e":"trade","E":123456789,"s":"BNBBTC","t":12345,"p":"0.001","q":"100","b":88,"a":50,"T":123456785,"m":true,"M":true}"#,
+        )
+        .unwrap();
+
+        assert_eq!(trade.trade_id(), 12345);
+        assert_eq!(trade.price, 0.001);
+        assert_eq!(trade.trade_time, 123456785);
+    }
+
+    #[test]
+    fn klines() {
+        let klines: Vec<Kline> = serde_json::from_str(
+            r#"[[1499040000000,"0.01634790","0.80000000","0.01575800","0.01577100","148976.11427815",1499644799999,"2434.19055334",308,"1756.87402397","28.46694368","0"]]"#,
+        )
+        .unwrap();
+
+        assert_eq!(klines[0].0, 1499040000000);
+        assert_eq!(klines[0].2, 0.8);
+        assert_eq!(klines[0].5, 148976.11427815);
+    }
+}
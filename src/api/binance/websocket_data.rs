@@ -0,0 +1,207 @@
+use super::models::{DepthUpdate, TradeEvent};
+use crate::api::websocket::{self, WS_READ_TIMEOUT};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tracing::{debug, error};
+use tungstenite::{client::AutoStream, connect, Message, WebSocket};
+use url::Url;
+
+// Combined streams endpoint wraps every payload with its stream name
+const WS_BINANCE_URL: &str = "wss://stream.binance.com:9443/stream";
+
+const BINANCE_CONNECT_ERROR: &str = "Can't connect to Binance websocket";
+
+const BINANCE_READ_MESSAGE_ERROR: &str = "Error reading Binance websocket message";
+
+const DEPTH_CHANNEL: &str = "depth@100ms";
+
+// Aggregated trades of the same taker order and price
+const TRADE_CHANNEL: &str = "aggTrade";
+
+#[derive(Debug)]
+pub struct BinanceWS {
+    socket: WebSocket<AutoStream>,
+    // <stream symbol, configured symbol>
+    symbols: HashMap<String, String>,
+}
+
+impl BinanceWS {
+    // Subscribes to diff depth streams of all symbols
+    pub fn connect(symbols: &[&str]) -> Result<Self, &'static str> {
+        Self::connect_channel(symbols, DEPTH_CHANNEL)
+    }
+
+    // Subscribes to public trades of all symbols
+    pub fn connect_trades(symbols: &[&str]) -> Result<Self, &'static str> {
+        Self::connect_channel(symbols, TRADE_CHANNEL)
+    }
+
+    fn connect_channel(symbols: &[&str], channel: &str) -> Result<Self, &'static str> {
+        debug!("Connecting to Binance WebSocket");
+
+        let url = format!(
+            "{}?streams={}",
+            WS_BINANCE_URL,
+            Self::streams(symbols, channel)
+        );
+
+        let (socket, _response) = match connect(Url::parse(&url).unwrap()) {
+            Ok(connection) => connection,
+            Err(e) => {
+                error!("[Binance WS] {}: {}", BINANCE_CONNECT_ERROR, e);
+                return Err(BINANCE_CONNECT_ERROR);
+            }
+        };
+
+        // Updates come every 100ms, so silence means the connection is lost
+        websocket::set_read_timeout(&socket, WS_READ_TIMEOUT)?;
+
+        Ok(BinanceWS {
+            socket,
+            symbols: Self::symbols(symbols),
+        })
+    }
+
+    // It returns updated symbol with its depth update
+    pub fn get_update(&mut self) -> Result<(String, DepthUpdate), &'static str> {
+        self.get_data()
+    }
+
+    // It returns symbol with its trade
+    pub fn get_trade(&mut self) -> Result<(String, TradeEvent), &'static str> {
+        self.get_data()
+    }
+
+    fn get_data<T: DeserializeOwned>(&mut self) -> Result<(String, T), &'static str> {
+        // Loop until get stream data
+        loop {
+            // Pings are answered by tungstenite on read
+            let msg = match self.socket.read_message() {
+                Ok(msg) => msg,
+                Err(e) => {
+                    error!("[Binance WS] {}: {}", BINANCE_READ_MESSAGE_ERROR, e);
+                    return Err(BINANCE_READ_MESSAGE_ERROR);
+                }
+            };
+
+            let text = match msg {
+                Message::Text(text) => text,
+                _ => continue,
+            };
+
+            match Self::parse_data(&self.symbols, &text) {
+                Some(symbol_update) => return Ok(symbol_update),
+                None => debug!("[Binance WS] Skip message {}", text),
+            }
+        }
+    }
+
+    fn streams(symbols: &[&str], channel: &str) -> String {
+        symbols
+            .iter()
+            .map(|symbol| format!("{}@{}", symbol.to_lowercase(), channel))
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+
+    fn symbols(symbols: &[&str]) -> HashMap<String, String> {
+        symbols
+            .iter()
+            .map(|symbol| (symbol.to_lowercase(), symbol.to_string()))
+            .collect()
+    }
+
+    fn parse_data<T: DeserializeOwned>(
+        symbols: &HashMap<String, String>,
+        text: &str,
+    ) -> Option<(String, T)> {
+        let stream_msg = serde_json::from_str::<StreamMessage<T>>(text).ok()?;
+
+        // Stream name is "<symbol>@<channel>"
+        let stream_symbol = stream_msg.stream.split('@').next()?;
+        let symbol = symbols.get(stream_symbol)?.clone();
+
+        Some((symbol, stream_msg.data))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamMessage<T> {
+    stream: String,
+    data: T,
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{BinanceWS, DEPTH_CHANNEL, TRADE_CHANNEL};
+    use crate::api::binance::models::{DepthUpdate, TradeEvent};
+
+    // Recorded combined stream message
+    const DEPTH_UPDATE: &str = r#"{"stream":"ethusdt@depth@100ms","data":{
+        "e":"depthUpdate","E":1630000000000,"s":"ETHUSDT","U":157,"u":160,
+        "b":[["2500.10","1.5"],["2500.00","0.00000000"]],
+        "a":[["2500.20","0.7"]]}}"#;
+
+    const AGG_TRADE: &str = r#"{"stream":"btcusdt@aggTrade","data":{
+        "e":"aggTrade","E":1630000000001,"s":"BTCUSDT","a":26129,"p":"47000.10","q":"0.015",
+        "f":100,"l":105,"T":1630000000000,"m":true,"M":true}}"#;
+
+    #[test]
+    fn streams() {
+        assert_eq!(
+            BinanceWS::streams(&["BTCUSDT", "ETHUSDT"], DEPTH_CHANNEL),
+            "btcusdt@depth@100ms/ethusdt@depth@100ms"
+        );
+        assert_eq!(
+            BinanceWS::streams(&["BTCUSDT"], TRADE_CHANNEL),
+            "btcusdt@aggTrade"
+        );
+    }
+
+    #[test]
+    fn parse_update() {
+        let symbols = BinanceWS::symbols(&["BTCUSDT", "ETHUSDT"]);
+
+        let (symbol, update) =
+            BinanceWS::parse_data::<DepthUpdate>(&symbols, DEPTH_UPDATE).unwrap();
+
+        assert_eq!(symbol, "ETHUSDT");
+        assert_eq!(update.first_update_id, 157);
+        assert_eq!(update.last_update_id, 160);
+        assert_eq!(update.bids, vec![[2500.1, 1.5], [2500., 0.]]);
+        assert_eq!(update.asks, vec![[2500.2, 0.7]]);
+    }
+
+    #[test]
+    fn skip_unknown_stream() {
+        let symbols = BinanceWS::symbols(&["BTCUSDT"]);
+
+        assert!(BinanceWS::parse_data::<DepthUpdate>(&symbols, DEPTH_UPDATE).is_none());
+    }
+
+    #[test]
+    fn parse_trade() {
+        let symbols = BinanceWS::symbols(&["BTCUSDT"]);
+
+        let (symbol, trade) = BinanceWS::parse_data::<TradeEvent>(&symbols, AGG_TRADE).unwrap();
+
+        assert_eq!(symbol, "BTCUSDT");
+        assert_eq!(trade.trade_id(), 26129);
+        assert_eq!(trade.price, 47000.1);
+        assert_eq!(trade.qty, 0.015);
+        assert!(trade.is_buyer_maker);
+    }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn get_update() {
+        let mut binance_ws = BinanceWS::connect(&["BTCUSDT", "ETHUSDT"]).unwrap();
+
+        loop {
+            println!("{:?}", binance_ws.get_update());
+        }
+    }
+}
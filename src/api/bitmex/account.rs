@@ -0,0 +1,419 @@
+use super::{
+    client::Client,
+    models::{order_id_from_uuid, APIResult, ExchangeApiResult, Margin, Order, Transaction},
+};
+use serde_json::{self, json};
+use std::collections::BTreeMap;
+use tracing::{debug, error};
+
+#[derive(Clone)]
+pub struct Account {
+    pub client: Client,
+}
+
+impl Account {
+    pub fn new(api_key: Option<String>, secret_key: Option<String>) -> Self {
+        Account {
+            client: Client::new(&api_key.unwrap(), &secret_key.unwrap()),
+        }
+    }
+
+    pub fn limit_buy(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        client_order_id: Option<String>,
+    ) -> ExchangeApiResult<Transaction> {
+        match self._place_order(symbol, "Buy", amount, Some(price), client_order_id) {
+            Ok(placed_order) => {
+                debug!(
+                    "[BitMEX API] Limit buy: symbol {}, amount {}, price {}",
+                    symbol, amount, price
+                );
+
+                Ok(Transaction {
+                    symbol: symbol.to_string(),
+                    order_id: order_id_from_uuid(&placed_order.order_id),
+                })
+            }
+            Err(e) => {
+                error!("BitMEX limit buy error {:?}", e);
+
+                Err("BitMEX Limit buy Error")
+            }
+        }
+    }
+
+    pub fn limit_sell(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        client_order_id: Option<String>,
+    ) -> ExchangeApiResult<Transaction> {
+        match self._place_order(symbol, "Sell", amount, Some(price), client_order_id) {
+            Ok(placed_order) => {
+                debug!(
+                    "[BitMEX API] Limit sell: symbol {}, amount {}, price {}",
+                    symbol, amount, price
+                );
+
+                Ok(Transaction {
+                    symbol: symbol.to_string(),
+                    order_id: order_id_from_uuid(&placed_order.order_id),
+                })
+            }
+            Err(e) => {
+                error!("BitMEX limit sell error {:?}", e);
+
+                Err("BitMEX Limit sell Error")
+            }
+        }
+    }
+
+    pub fn market_buy(&self, symbol: &str, amount: f64) -> ExchangeApiResult<Transaction> {
+        match self._place_order(symbol, "Buy", amount, None, None) {
+            Ok(placed_order) => {
+                debug!(
+                    "[BitMEX API] Market buy: symbol {}, amount {}",
+                    symbol, amount,
+                );
+
+                Ok(Transaction {
+                    symbol: symbol.to_string(),
+                    order_id: order_id_from_uuid(&placed_order.order_id),
+                })
+            }
+            Err(_) => Err("BitMEX Market buy Error"),
+        }
+    }
+
+    pub fn market_sell(&self, symbol: &str, amount: f64) -> ExchangeApiResult<Transaction> {
+        match self._place_order(symbol, "Sell", amount, None, None) {
+            Ok(placed_order) => {
+                debug!(
+                    "[BitMEX API] Market sell: symbol {}, amount {}",
+                    symbol, amount,
+                );
+
+                Ok(Transaction {
+                    symbol: symbol.to_string(),
+                    order_id: order_id_from_uuid(&placed_order.order_id),
+                })
+            }
+            Err(_) => Err("BitMEX Market sell Error"),
+        }
+    }
+
+    fn _place_order(
+        &self,
+        symbol: &str,
+        side: &str,
+        amount: f64,
+        price: Option<f64>,
+        client_order_id: Option<String>,
+    ) -> APIResult<Order> {
+        let mut body = json!({
+            "symbol": symbol,
+            "side": side,
+            "orderQty": amount,
+        });
+
+        match price {
+            Some(price) => {
+                body["ordType"] = json!("Limit");
+                body["price"] = json!(price);
+            }
+            None => body["ordType"] = json!("Market"),
+        }
+
+        if let Some(id) = client_order_id {
+            body["clOrdID"] = json!(id);
+        }
+
+        self._post_order(&body)
+    }
+
+    // Order with its own type fields, e.g. ordType, stopPx, timeInForce and execInst
+    pub fn custom_order(
+        &self,
+        symbol: &str,
+        side: &str,
+        amount: f64,
+        client_order_id: &str,
+        order_fields: serde_json::Value,
+    ) -> ExchangeApiResult<Transaction> {
+        let mut body = json!({
+            "symbol": symbol,
+            "side": side,
+            "orderQty": amount,
+            "clOrdID": client_order_id,
+        });
+
+        if let (Some(body), Some(order_fields)) = (body.as_object_mut(), order_fields.as_object()) {
+            for (field, value) in order_fields {
+                body.insert(field.clone(), value.clone());
+            }
+        }
+
+        match self._post_order(&body) {
+            Ok(placed_order) => {
+                debug!("[BitMEX API] Custom order: {}", body);
+
+                Ok(Transaction {
+                    symbol: symbol.to_string(),
+                    order_id: order_id_from_uuid(&placed_order.order_id),
+                })
+            }
+            Err(e) => {
+                error!("BitMEX custom order error {:?}", e);
+
+                Err("BitMEX Custom order Error")
+            }
+        }
+    }
+
+    // Amended order keeps its exchange id and gets the new client order id
+    pub fn amend_order(
+        &self,
+        symbol: &str,
+        orig_client_order_id: &str,
+        client_order_id: &str,
+        amount: f64,
+        price: f64,
+    ) -> ExchangeApiResult<Transaction> {
+        let body = json!({
+            "origClOrdID": orig_client_order_id,
+            "clOrdID": client_order_id,
+            "orderQty": amount,
+            "price": price,
+        });
+
+        match self.client.put_signed("/order", &body) {
+            Ok(data) => match serde_json::from_str::<Order>(data.as_str()) {
+                Ok(amended_order) => {
+                    debug!("[BitMEX API] Amend order: {}", body);
+
+                    Ok(Transaction {
+                        symbol: symbol.to_string(),
+                        order_id: order_id_from_uuid(&amended_order.order_id),
+                    })
+                }
+                Err(_e) => Err("BitMEX Amend order Error"),
+            },
+            Err(e) => {
+                error!("BitMEX amend order error {:?}", e);
+
+                Err("BitMEX Amend order Error")
+            }
+        }
+    }
+
+    fn _post_order(&self, body: &serde_json::Value) -> APIResult<Order> {
+        let data = self.client.post_signed("/order", body)?;
+
+        let order: Order = serde_json::from_str(data.as_str())?;
+
+        Ok(order)
+    }
+
+    pub fn cancel_order_with_custom_id(
+        &self,
+        symbol: &str,
+        custom_order_id: &str,
+    ) -> ExchangeApiResult<Transaction> {
+        let body = json!({ "clOrdID": custom_order_id });
+
+        match self.client.delete_signed("/order", &body) {
+            Ok(data) => {
+                let canceled_orders: Vec<Order> =
+                    serde_json::from_str(data.as_str()).unwrap_or_default();
+
+                debug!(
+                    "[BitMEX] Order was canceled: symbol {}, {}",
+                    symbol, custom_order_id
+                );
+
+                Ok(Transaction {
+                    symbol: symbol.to_string(),
+                    order_id: canceled_orders
+                        .first()
+                        .map(|order| order_id_from_uuid(&order.order_id))
+                        .unwrap_or(0),
+                })
+            }
+            Err(_e) => Err("BitMEX Request error"),
+        }
+    }
+
+    pub fn cancel_all_orders(&self, symbol: Option<&str>) -> APIResult<Vec<Order>> {
+        let body = match symbol {
+            Some(symbol) => json!({ "symbol": symbol }),
+            None => json!({}),
+        };
+
+        let data = self.client.delete_signed("/order/all", &body)?;
+
+        debug!("[BitMEX] Cancel all orders {:?}", data);
+
+        let canceled_orders: Vec<Order> = serde_json::from_str(data.as_str())?;
+
+        Ok(canceled_orders)
+    }
+
+    pub fn get_open_orders(&self, symbol: &str) -> APIResult<Vec<Order>> {
+        let mut params: BTreeMap<String, String> = BTreeMap::new();
+
+        params.insert("symbol".into(), symbol.into());
+        params.insert("filter".into(), json!({ "open": true }).to_string());
+
+        let data = self.client.get_signed("/order", params)?;
+
+        debug!("[BitMEX] Get open orders {:?}", data);
+
+        let open_orders: Vec<Order> = serde_json::from_str(data.as_str())?;
+
+        Ok(open_orders)
+    }
+
+    // Currency in BitMEX notation, e.g. "XBt" or "USDt"
+    pub fn get_margin(&self, currency: &str) -> APIResult<Margin> {
+        let mut params: BTreeMap<String, String> = BTreeMap::new();
+
+        params.insert("currency".into(), currency.into());
+
+        let data = self.client.get_signed("/user/margin", params)?;
+
+        debug!("[BitMEX] Get margin result: {:?} ", data);
+
+        let margin: Margin = serde_json::from_str(data.as_str())?;
+
+        Ok(margin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc::{channel, Receiver};
+    use std::thread;
+
+    // Local mock server answers one request with the recorded body
+    fn mock_server(response_body: &'static str) -> (String, Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let (request_sender, request_receiver) = channel();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+
+            // Read headers and body of the request
+            loop {
+                let read = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..read]);
+
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(headers_end) = text.find("\r\n\r\n") {
+                    let content_length = text
+                        .lines()
+                        .find(|line| line.to_lowercase().starts_with("content-length:"))
+                        .and_then(|line| line[15..].trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+
+                    if request.len() >= headers_end + 4 + content_length {
+                        break;
+                    }
+                }
+            }
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                response_body.len(),
+                response_body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+
+            request_sender
+                .send(String::from_utf8_lossy(&request).to_string())
+                .unwrap();
+        });
+
+        (address, request_receiver)
+    }
+
+    fn account(host: &str) -> Account {
+        Account {
+            client: Client::with_host("API_KEY", "SECRET_KEY", host),
+        }
+    }
+
+    #[test]
+    fn limit_buy_mock_server() {
+        let (host, request) = mock_server(
+            r#"{"orderID":"00000000-0000-0002-0000-000000000000","clOrdID":"Custom123","symbol":"XBTUSD","side":"Buy","orderQty":100,"price":30000,"ordType":"Limit","ordStatus":"New"}"#,
+        );
+
+        let transaction = account(&host)
+            .limit_buy("XBTUSD", 100., 30000., Some("Custom123".to_string()))
+            .unwrap();
+
+        assert_eq!(transaction.order_id, 2);
+
+        let request = request.recv().unwrap();
+
+        assert!(request.starts_with("POST /api/v1/order "));
+        assert!(request.contains("api-signature"));
+        assert!(request.contains("\"clOrdID\":\"Custom123\""));
+        assert!(request.contains("\"ordType\":\"Limit\""));
+    }
+
+    #[test]
+    fn cancel_order_mock_server() {
+        let (host, request) = mock_server(
+            r#"[{"orderID":"00000000-0000-0003-0000-000000000000","clOrdID":"Custom123","symbol":"XBTUSD","ordStatus":"Canceled"}]"#,
+        );
+
+        let transaction = account(&host)
+            .cancel_order_with_custom_id("XBTUSD", "Custom123")
+            .unwrap();
+
+        assert_eq!(transaction.order_id, 3);
+        assert!(request.recv().unwrap().starts_with("DELETE /api/v1/order "));
+    }
+
+    #[test]
+    fn amend_order_mock_server() {
+        let (host, request) = mock_server(
+            r#"{"orderID":"00000000-0000-0002-0000-000000000000","clOrdID":"Custom456","symbol":"XBTUSD","side":"Buy","orderQty":200,"price":31000,"ordType":"Limit","ordStatus":"New"}"#,
+        );
+
+        let transaction = account(&host)
+            .amend_order("XBTUSD", "Custom123", "Custom456", 200., 31000.)
+            .unwrap();
+
+        assert_eq!(transaction.order_id, 2);
+
+        let request = request.recv().unwrap();
+
+        assert!(request.starts_with("PUT /api/v1/order "));
+        assert!(request.contains("\"origClOrdID\":\"Custom123\""));
+        assert!(request.contains("\"clOrdID\":\"Custom456\""));
+    }
+
+    #[test]
+    fn api_error_mock_server() {
+        let (host, _request) =
+            mock_server(r#"{"error":{"message":"Invalid API Key.","name":"HTTPError"}}"#);
+
+        assert!(account(&host)
+            .limit_sell("XBTUSD", 100., 30000., None)
+            .is_err());
+    }
+}
@@ -0,0 +1,53 @@
+use super::client::Client;
+use super::models::*;
+use serde_json;
+use std::collections::BTreeMap;
+use tracing::info;
+
+pub use super::account::Account;
+
+pub struct BitmexApi;
+
+impl BitmexApi {
+    /// BitMEX API "/instrument/active"
+    pub fn instruments() -> APIResult<Vec<Instrument>> {
+        info!("[BitMEX] get instruments");
+
+        let client = Client::new("", "");
+        let data = client.get("/instrument/active", BTreeMap::new())?;
+
+        let instruments: Vec<Instrument> = serde_json::from_str(data.as_str())?;
+
+        Ok(instruments)
+    }
+
+    /// BitMEX API "/", server time in unix milliseconds
+    pub fn server_time() -> APIResult<u64> {
+        let client = Client::new("", "");
+        let data = client.get("/", BTreeMap::new())?;
+
+        let api_info: ApiInfo = serde_json::from_str(data.as_str())?;
+
+        Ok(api_info.timestamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::BitmexApi;
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn instruments() {
+        println!("{:#?}", BitmexApi::instruments().unwrap());
+    }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn server_time() {
+        println!("{}", BitmexApi::server_time().unwrap());
+    }
+}
@@ -0,0 +1,211 @@
+use super::error::*;
+use super::models::{APIErrorResponse, APIResult};
+use crate::api::clock;
+use reqwest;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE, USER_AGENT};
+use reqwest::Method;
+use ring::{digest, hmac};
+use std::collections::BTreeMap;
+use tracing::debug;
+
+static BITMEX_API_HOST: &'static str = "https://www.bitmex.com";
+
+static BITMEX_API_PATH: &'static str = "/api/v1";
+
+// Signed request is valid during this time in seconds
+const BITMEX_REQUEST_EXPIRES: u64 = 60;
+
+#[derive(Clone)]
+pub struct Client {
+    api_key: String,
+    secret_key: String,
+    host: String,
+}
+
+impl Client {
+    pub fn new(api_key: &str, secret_key: &str) -> Self {
+        Client::with_host(api_key, secret_key, BITMEX_API_HOST)
+    }
+
+    // Host can be changed to testnet or to a local mock server
+    pub fn with_host(api_key: &str, secret_key: &str, host: &str) -> Self {
+        Client {
+            api_key: api_key.into(),
+            secret_key: secret_key.into(),
+            host: host.into(),
+        }
+    }
+
+    pub fn get(&self, endpoint: &str, parameters: BTreeMap<String, String>) -> APIResult<String> {
+        self.request(Method::GET, endpoint, parameters, None, false)
+    }
+
+    pub fn get_signed(
+        &self,
+        endpoint: &str,
+        parameters: BTreeMap<String, String>,
+    ) -> APIResult<String> {
+        self.request(Method::GET, endpoint, parameters, None, true)
+    }
+
+    pub fn post_signed(&self, endpoint: &str, body: &serde_json::Value) -> APIResult<String> {
+        self.request(
+            Method::POST,
+            endpoint,
+            BTreeMap::new(),
+            Some(body.to_string()),
+            true,
+        )
+    }
+
+    pub fn put_signed(&self, endpoint: &str, body: &serde_json::Value) -> APIResult<String> {
+        self.request(
+            Method::PUT,
+            endpoint,
+            BTreeMap::new(),
+            Some(body.to_string()),
+            true,
+        )
+    }
+
+    pub fn delete_signed(&self, endpoint: &str, body: &serde_json::Value) -> APIResult<String> {
+        self.request(
+            Method::DELETE,
+            endpoint,
+            BTreeMap::new(),
+            Some(body.to_string()),
+            true,
+        )
+    }
+
+    fn request(
+        &self,
+        method: Method,
+        endpoint: &str,
+        parameters: BTreeMap<String, String>,
+        body: Option<String>,
+        signed: bool,
+    ) -> APIResult<String> {
+        let mut path = format!("{}{}", BITMEX_API_PATH, endpoint);
+
+        if !parameters.is_empty() {
+            path = format!("{}?{}", path, build_query_string(parameters));
+        }
+
+        let body = body.unwrap_or_default();
+
+        let mut headers = build_headers()?;
+
+        if signed {
+            let expires = get_expires();
+            let signature = sign_hmac_sha256_hex(
+                &self.secret_key,
+                &signature_message(method.as_str(), &path, expires, &body),
+            );
+
+            headers.insert("api-expires", HeaderValue::from_str(&expires.to_string())?);
+            headers.insert("api-key", HeaderValue::from_str(&self.api_key)?);
+            headers.insert("api-signature", HeaderValue::from_str(&signature)?);
+        }
+
+        let request = format!("{}{}", self.host, path);
+
+        debug!("[BitMEX] Make {} request: {:?} {}", method, request, body);
+
+        let client = reqwest::blocking::Client::new();
+        let response = client
+            .request(method, request.as_str())
+            .headers(headers)
+            .body(body)
+            .send()?;
+
+        let body = response.text()?;
+
+        debug!("[BitMEX] Responce body: {:?}", body);
+
+        // check for errors
+        if let Ok(err_response) = serde_json::from_str::<APIErrorResponse>(body.as_str()) {
+            if let Some(error) = err_response.error {
+                return Err(Box::new(BitmexError::ApiError(format!(
+                    "{}: {}",
+                    error.name, error.message
+                ))));
+            }
+        }
+
+        Ok(body)
+    }
+}
+
+// BitMEX signs "verb + path + expires + body", path includes query string
+pub fn signature_message(verb: &str, path: &str, expires: u64, body: &str) -> String {
+    format!("{}{}{}{}", verb, path, expires, body)
+}
+
+pub fn sign_hmac_sha256_hex(secret: &str, message: &str) -> String {
+    use data_encoding::HEXLOWER;
+
+    let signed_key = hmac::SigningKey::new(&digest::SHA256, secret.as_bytes());
+    let signature = hmac::sign(&signed_key, message.as_bytes());
+
+    HEXLOWER.encode(signature.as_ref())
+}
+
+pub fn build_query_string(parameters: BTreeMap<String, String>) -> String {
+    parameters
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, percent_encode(&value)))
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+pub fn percent_encode(source: &str) -> String {
+    use percent_encoding::{utf8_percent_encode, USERINFO_ENCODE_SET};
+
+    utf8_percent_encode(&source, USERINFO_ENCODE_SET).to_string()
+}
+
+// Seconds by the clock of BitMEX
+pub fn get_expires() -> u64 {
+    (clock::exchange_now("BitMEX") / 1000) as u64 + BITMEX_REQUEST_EXPIRES
+}
+
+pub fn build_headers() -> APIResult<HeaderMap> {
+    let mut custom_headers = HeaderMap::new();
+
+    custom_headers.insert(USER_AGENT, HeaderValue::from_static("rs"));
+    custom_headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    custom_headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+
+    Ok(custom_headers)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // Examples from BitMEX API documentation
+    const SECRET: &str = "chNOOS4KvNXR_Xq4k4c9qsfoKWvnDecLATCRlcBwyKDYnWgO";
+
+    #[test]
+    fn sign_get_request() {
+        let message = signature_message("GET", "/api/v1/instrument", 1518064236, "");
+
+        assert_eq!(
+            sign_hmac_sha256_hex(SECRET, &message),
+            "c7682d435d0cfe87c16098df34ef2eb5a549d4c5a3c2b1f0f77b8af73423bf00"
+        );
+    }
+
+    #[test]
+    fn sign_post_request() {
+        let body = r#"{"symbol":"XBTM15","price":219.0,"clOrdID":"mm_bitmex_1a/oemUeQ4CAJZgP3fjHsA","orderQty":98}"#;
+        let message = signature_message("POST", "/api/v1/order", 1518064238, body);
+
+        assert_eq!(
+            sign_hmac_sha256_hex(SECRET, &message),
+            "1749cd2ccae4aa49048ae09f0b95110cee706e0944e6a14ad0b3a8cb45bd336b"
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use core::fmt;
+use std::error::Error;
+
+#[derive(Debug, Clone)]
+pub enum BitmexError {
+    ApiError(String),
+}
+
+impl fmt::Display for BitmexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.clone() {
+            BitmexError::ApiError(why) => write!(f, "ApiError: {}", why),
+        }
+    }
+}
+
+impl Error for BitmexError {
+    fn description(&self) -> &str {
+        "BitMEX Error"
+    }
+}
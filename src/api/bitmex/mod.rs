@@ -0,0 +1,10 @@
+pub mod bitmex;
+pub mod models;
+pub mod websocket_account;
+pub mod websocket_data;
+
+mod account;
+mod client;
+mod error;
+
+pub use bitmex::{Account, BitmexApi};
@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+
+pub type APIResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+pub type ExchangeApiResult<T> = Result<T, &'static str>;
+
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    pub symbol: String,
+    pub order_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct APIErrorResponse {
+    pub error: Option<APIError>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct APIError {
+    pub message: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Order {
+    #[serde(rename = "orderID")]
+    pub order_id: String,
+    #[serde(rename = "clOrdID")]
+    pub cl_ord_id: Option<String>,
+    pub symbol: String,
+    pub side: Option<String>,
+    pub order_qty: Option<f64>,
+    pub price: Option<f64>,
+    pub ord_type: Option<String>,
+    pub ord_status: Option<String>,
+    pub leaves_qty: Option<f64>,
+    pub cum_qty: Option<f64>,
+    pub avg_px: Option<f64>,
+    pub timestamp: Option<String>,
+}
+
+// REST "/" response, timestamp is the server time in unix milliseconds
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiInfo {
+    pub name: String,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Instrument {
+    pub symbol: String,
+    pub root_symbol: String,
+    pub state: String,
+    pub underlying: String,
+    pub quote_currency: String,
+    // FFWCSX is perpetual contract, FFCCSX is future, IFXXXP is spot
+    pub typ: Option<String>,
+    pub settl_currency: Option<String>,
+    pub tick_size: f64,
+    pub lot_size: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Margin {
+    pub currency: String,
+    // Balances are in the smallest units of currency, e.g. satoshi for XBt
+    pub wallet_balance: Option<i64>,
+    pub available_margin: Option<i64>,
+}
+
+impl Margin {
+    pub fn available(&self) -> f64 {
+        self.available_margin.unwrap_or(0) as f64 / Self::units(&self.currency)
+    }
+
+    fn units(currency: &str) -> f64 {
+        match currency {
+            "XBt" => 100_000_000.,
+            "USDt" => 1_000_000.,
+            _ => 1.,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Execution {
+    #[serde(rename = "execID")]
+    pub exec_id: String,
+    #[serde(rename = "orderID")]
+    pub order_id: String,
+    #[serde(rename = "clOrdID")]
+    pub cl_ord_id: Option<String>,
+    pub symbol: String,
+    pub side: Option<String>,
+    pub last_qty: Option<f64>,
+    pub last_px: Option<f64>,
+    pub exec_type: String,
+    pub ord_status: Option<String>,
+    pub commission: Option<f64>,
+}
+
+// orderBookL2 row, update and delete messages carry only id and changed fields
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct L2Entry {
+    pub symbol: String,
+    pub id: u64,
+    pub side: String,
+    pub size: Option<f64>,
+    pub price: Option<f64>,
+}
+
+// Websocket table message, e.g. {"table":"orderBookL2_25","action":"partial","data":[...]}
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TableMessage<T> {
+    pub table: String,
+    pub action: String,
+    pub data: Vec<T>,
+}
+
+// BitMEX order ids are UUIDs, platform keeps numeric ids
+pub fn order_id_from_uuid(uuid: &str) -> u64 {
+    let digits = uuid
+        .chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .take(16)
+        .collect::<String>();
+
+    u64::from_str_radix(&digits, 16).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_order() {
+        let order_json = r#"{
+            "orderID": "6c4a6c5c-9e3a-4b3c-8b2e-5d1f7f1d8a6b",
+            "clOrdID": "Custom123",
+            "account": 1234,
+            "symbol": "XBTUSD",
+            "side": "Buy",
+            "orderQty": 100,
+            "price": 30000.5,
+            "ordType": "Limit",
+            "ordStatus": "New",
+            "leavesQty": 100,
+            "cumQty": 0,
+            "avgPx": null,
+            "timestamp": "2021-06-01T10:00:00.000Z"
+        }"#;
+
+        let order: Order = serde_json::from_str(order_json).unwrap();
+
+        assert_eq!(order.cl_ord_id, Some("Custom123".to_string()));
+        assert_eq!(order.order_qty, Some(100.));
+        assert_eq!(order.price, Some(30000.5));
+    }
+
+    #[test]
+    fn parse_instrument() {
+        let instrument_json = r#"{
+            "symbol": "XBTUSD",
+            "rootSymbol": "XBT",
+            "state": "Open",
+            "typ": "FFWCSX",
+            "underlying": "XBT",
+            "quoteCurrency": "USD",
+            "settlCurrency": "XBt",
+            "tickSize": 0.5,
+            "lotSize": 100
+        }"#;
+
+        let instrument: Instrument = serde_json::from_str(instrument_json).unwrap();
+
+        assert_eq!(instrument.underlying, "XBT");
+        assert_eq!(instrument.tick_size, 0.5);
+    }
+
+    #[test]
+    fn parse_error() {
+        let error_json = r#"{"error":{"message":"Invalid API Key.","name":"HTTPError"}}"#;
+
+        let error: APIErrorResponse = serde_json::from_str(error_json).unwrap();
+
+        assert_eq!(error.error.unwrap().message, "Invalid API Key.");
+    }
+
+    #[test]
+    fn margin_available() {
+        let margin = Margin {
+            currency: "XBt".to_string(),
+            wallet_balance: Some(150_000_000),
+            available_margin: Some(50_000_000),
+        };
+
+        assert_eq!(margin.available(), 0.5);
+    }
+
+    #[test]
+    fn order_id() {
+        assert_eq!(
+            order_id_from_uuid("00000000-0000-0001-0000-000000000000"),
+            1
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use super::client::{get_expires, sign_hmac_sha256_hex, signature_message};
+use super::models::*;
+use super::websocket_data::BITMEX_PING_INTERVAL;
+use crate::api::websocket::{self, Heartbeat};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{debug, info};
+use tungstenite::client::AutoStream;
+use tungstenite::handshake::client::Response;
+use tungstenite::protocol::WebSocket;
+use tungstenite::{connect, Message};
+use url::Url;
+
+static WEBSOCKET_URL: &'static str = "wss://ws.bitmex.com/realtime";
+
+pub enum WebsocketEvent {
+    Execution(Vec<Execution>),
+}
+
+pub struct WebSockets<'a> {
+    pub socket: Option<(WebSocket<AutoStream>, Response)>,
+    handler: Box<dyn FnMut(WebsocketEvent) -> APIResult<()> + 'a>,
+}
+
+impl<'a> WebSockets<'a> {
+    pub fn new<Callback>(handler: Callback) -> WebSockets<'a>
+    where
+        Callback: FnMut(WebsocketEvent) -> APIResult<()> + 'a,
+    {
+        WebSockets {
+            socket: None,
+            handler: Box::new(handler),
+        }
+    }
+
+    // Authenticates connection and subscribes to executions of all instruments
+    pub fn connect_auth(&mut self, access_key: &str, secret_key: &str) -> APIResult<()> {
+        let url = Url::parse(WEBSOCKET_URL)?;
+
+        match connect(url) {
+            Ok(answer) => {
+                websocket::set_read_timeout(&answer.0, BITMEX_PING_INTERVAL)?;
+
+                self.socket = Some(answer);
+
+                let expires = get_expires();
+                let signature = sign_hmac_sha256_hex(
+                    secret_key,
+                    &signature_message("GET", "/realtime", expires, ""),
+                );
+
+                let auth_message = json!({
+                    "op": "authKeyExpires",
+                    "args": [access_key, expires, signature],
+                });
+
+                let subscribe_message = json!({
+                    "op": "subscribe",
+                    "args": ["execution"],
+                });
+
+                if let Some(ref mut socket) = self.socket {
+                    socket
+                        .0
+                        .write_message(Message::Text(auth_message.to_string()))?;
+                    debug!("[BitMEX] Write auth message");
+
+                    socket
+                        .0
+                        .write_message(Message::Text(subscribe_message.to_string()))?;
+                    debug!("[BitMEX] Write message {}", subscribe_message.to_string());
+                };
+
+                Ok(())
+            }
+            Err(e) => {
+                info!("Error during handshake {}", e);
+                Err(Box::new(e))
+            }
+        }
+    }
+
+    pub fn disconnect(&mut self) -> APIResult<()> {
+        if let Some(ref mut socket) = self.socket {
+            socket.0.close(None)?;
+            Ok(())
+        } else {
+            info!("Not able to close the connection");
+            Ok(())
+        }
+    }
+
+    pub fn event_loop(&mut self, running: &AtomicBool) -> APIResult<()> {
+        let mut heartbeat = Heartbeat::default();
+
+        while running.load(Ordering::Relaxed) {
+            if let Some(ref mut socket) = self.socket {
+                let message = match socket.0.read_message() {
+                    Ok(message) => message,
+                    Err(e) if websocket::is_timeout(&e) => {
+                        // Executions are rare, so the connection is checked by ping
+                        heartbeat.timeout()?;
+                        socket.0.write_message(Message::Text("ping".to_string()))?;
+                        continue;
+                    }
+                    Err(e) => return Err(Box::new(e)),
+                };
+
+                heartbeat.received();
+
+                match message {
+                    Message::Text(text) => {
+                        if text == "pong" {
+                            continue;
+                        }
+
+                        if let Some(executions) = Self::parse_executions(&text) {
+                            (self.handler)(WebsocketEvent::Execution(executions))?;
+                        } else {
+                            debug!("[BitMEX] WebSockets message {}", text);
+                        }
+                    }
+
+                    Message::Ping(_bin) | Message::Pong(_bin) | Message::Binary(_bin) => {
+                        debug!("[BitMEX] WebSockets binary message");
+                    }
+
+                    Message::Close(e) => {
+                        info!("Disconnected {:?}", e);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_executions(text: &str) -> Option<Vec<Execution>> {
+        match serde_json::from_str::<TableMessage<Execution>>(text) {
+            Ok(table_msg) if table_msg.table == "execution" && table_msg.action != "delete" => {
+                Some(table_msg.data)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::WebSockets;
+
+    // Recorded execution message for a partially filled limit order
+    const EXECUTION: &str = r#"{"table":"execution","action":"insert","data":[{
+        "execID":"0193e879-cb6f-2891-d099-2c4eb40fee21",
+        "orderID":"00000000-0000-0000-0000-000000000000",
+        "clOrdID":"Custom123",
+        "account":2,
+        "symbol":"XBTUSD",
+        "side":"Sell",
+        "lastQty":100,
+        "lastPx":3500.5,
+        "orderQty":300,
+        "price":3500.5,
+        "execType":"Trade",
+        "ordType":"Limit",
+        "ordStatus":"PartiallyFilled",
+        "commission":0.00075,
+        "timestamp":"2021-06-01T10:00:00.000Z"}]}"#;
+
+    #[test]
+    fn parse_executions() {
+        let executions = WebSockets::parse_executions(EXECUTION).unwrap();
+
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].exec_type, "Trade");
+        assert_eq!(executions[0].cl_ord_id, Some("Custom123".to_string()));
+        assert_eq!(executions[0].last_qty, Some(100.));
+    }
+
+    #[test]
+    fn skip_other_tables() {
+        let subscription = r#"{"success":true,"subscribe":"execution"}"#;
+
+        assert!(WebSockets::parse_executions(subscription).is_none());
+    }
+}
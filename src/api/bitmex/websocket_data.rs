@@ -0,0 +1,289 @@
+use super::models::{L2Entry, TableMessage};
+use crate::api::websocket::{self, Heartbeat};
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, error};
+use tungstenite::{client::AutoStream, connect, Message, WebSocket};
+use url::Url;
+
+const WS_BITMEX_URL: &str = "wss://ws.bitmex.com/realtime";
+
+const BITMEX_CONNECT_ERROR: &str = "Can't connect to BitMEX websocket";
+
+const BITMEX_READ_MESSAGE_ERROR: &str = "Error reading BitMEX websocket message";
+
+const BITMEX_WRITE_MESSAGE_ERROR: &str = "Error writing BitMEX websocket message";
+
+// BitMEX doesn't ping, client sends "ping" after that time without messages
+pub const BITMEX_PING_INTERVAL: Duration = Duration::from_secs(5);
+
+// Number of levels published from the local book
+const BITMEX_DEPTH_LEVELS: usize = 20;
+
+#[derive(Debug)]
+pub struct BitmexWS {
+    socket: WebSocket<AutoStream>,
+    // <symbol, book>
+    books: HashMap<String, OrderBookL2>,
+    heartbeat: Heartbeat,
+}
+
+impl BitmexWS {
+    pub fn connect(symbols: &[&str]) -> Result<Self, &'static str> {
+        debug!("Connecting to BitMEX WebSocket");
+
+        let (mut socket, _response) = match connect(Url::parse(WS_BITMEX_URL).unwrap()) {
+            Ok(connection) => connection,
+            Err(e) => {
+                error!("[BitMEX WS] {}: {}", BITMEX_CONNECT_ERROR, e);
+                return Err(BITMEX_CONNECT_ERROR);
+            }
+        };
+
+        websocket::set_read_timeout(&socket, BITMEX_PING_INTERVAL)?;
+
+        let sub_msg = json!({
+            "op": "subscribe",
+            "args": symbols
+                .iter()
+                .map(|symbol| format!("orderBookL2_25:{}", symbol))
+                .collect::<Vec<String>>(),
+        });
+
+        let mut bitmex_ws = BitmexWS {
+            socket,
+            books: HashMap::new(),
+            heartbeat: Heartbeat::default(),
+        };
+
+        // Send subscription message
+        bitmex_ws.write_message(sub_msg.to_string())?;
+
+        Ok(bitmex_ws)
+    }
+
+    // It returns updated symbol with its depth
+    pub fn get_depth(&mut self) -> Result<(String, Depth), &'static str> {
+        // Loop until get depth data
+        loop {
+            let msg = match self.socket.read_message() {
+                Ok(msg) => msg,
+                Err(e) if websocket::is_timeout(&e) => {
+                    self.heartbeat.timeout()?;
+                    self.write_message("ping".to_string())?;
+                    continue;
+                }
+                Err(e) => {
+                    error!("[BitMEX WS] {}: {}", BITMEX_READ_MESSAGE_ERROR, e);
+                    return Err(BITMEX_READ_MESSAGE_ERROR);
+                }
+            };
+
+            self.heartbeat.received();
+
+            let text = match msg {
+                Message::Text(text) => text,
+                _ => continue,
+            };
+
+            match serde_json::from_str::<TableMessage<L2Entry>>(&text) {
+                Ok(table_msg) if table_msg.table.starts_with("orderBookL2") => {
+                    if let Some(symbol) = Self::apply(&mut self.books, &table_msg) {
+                        let depth = self.books[&symbol].to_depth(BITMEX_DEPTH_LEVELS);
+
+                        return Ok((symbol, depth));
+                    }
+                }
+                // Subscription responses and other tables
+                _ => debug!("[BitMEX WS] Skip message {}", text),
+            }
+        }
+    }
+
+    fn write_message(&mut self, text: String) -> Result<(), &'static str> {
+        match self.socket.write_message(Message::Text(text)) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                error!("[BitMEX WS] {}: {}", BITMEX_WRITE_MESSAGE_ERROR, e);
+                Err(BITMEX_WRITE_MESSAGE_ERROR)
+            }
+        }
+    }
+
+    fn apply(
+        books: &mut HashMap<String, OrderBookL2>,
+        table_msg: &TableMessage<L2Entry>,
+    ) -> Option<String> {
+        let symbol = table_msg.data.first()?.symbol.clone();
+
+        books
+            .entry(symbol.clone())
+            .or_insert_with(OrderBookL2::default)
+            .apply(&table_msg.action, &table_msg.data);
+
+        Some(symbol)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Level {
+    side: String,
+    price: f64,
+    size: f64,
+}
+
+// Local copy of BitMEX orderBookL2 table
+#[derive(Debug, Default)]
+pub struct OrderBookL2 {
+    // <id, level>
+    levels: HashMap<u64, Level>,
+}
+
+impl OrderBookL2 {
+    pub fn apply(&mut self, action: &str, entries: &[L2Entry]) {
+        if action == "partial" {
+            self.levels.clear();
+        }
+
+        for entry in entries {
+            match action {
+                "partial" | "insert" => {
+                    self.levels.insert(
+                        entry.id,
+                        Level {
+                            side: entry.side.clone(),
+                            price: entry.price.unwrap_or(0.),
+                            size: entry.size.unwrap_or(0.),
+                        },
+                    );
+                }
+                "update" => {
+                    if let Some(level) = self.levels.get_mut(&entry.id) {
+                        if let Some(size) = entry.size {
+                            level.size = size;
+                        }
+                        if let Some(price) = entry.price {
+                            level.price = price;
+                        }
+                    }
+                }
+                "delete" => {
+                    self.levels.remove(&entry.id);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn to_depth(&self, levels: usize) -> Depth {
+        let mut bids = self.side("Buy");
+        let mut asks = self.side("Sell");
+
+        bids.sort_by(|a, b| b.price.partial_cmp(&a.price).unwrap());
+        asks.sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap());
+
+        bids.truncate(levels);
+        asks.truncate(levels);
+
+        Depth {
+            exchange: "BitMEX".to_string(),
+            bids,
+            asks,
+        }
+    }
+
+    fn side(&self, side: &str) -> Vec<Ticker> {
+        self.levels
+            .values()
+            .filter(|level| level.side == side)
+            .map(|level| Ticker {
+                price: level.price,
+                qty: level.size,
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Ticker {
+    pub price: f64,
+    pub qty: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Depth {
+    pub exchange: String,
+    pub bids: Vec<Ticker>,
+    pub asks: Vec<Ticker>,
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // Recorded orderBookL2_25 messages
+    const PARTIAL: &str = r#"{"table":"orderBookL2_25","action":"partial","keys":["symbol","id","side"],"data":[
+        {"symbol":"XBTUSD","id":8799650000,"side":"Sell","size":1000,"price":3500},
+        {"symbol":"XBTUSD","id":8799650500,"side":"Sell","size":2000,"price":3499.5},
+        {"symbol":"XBTUSD","id":8799651000,"side":"Buy","size":1500,"price":3499},
+        {"symbol":"XBTUSD","id":8799651500,"side":"Buy","size":500,"price":3498.5}]}"#;
+
+    const UPDATE: &str = r#"{"table":"orderBookL2_25","action":"update","data":[
+        {"symbol":"XBTUSD","id":8799651000,"side":"Buy","size":700}]}"#;
+
+    const INSERT: &str = r#"{"table":"orderBookL2_25","action":"insert","data":[
+        {"symbol":"XBTUSD","id":8799652000,"side":"Buy","size":300,"price":3498}]}"#;
+
+    const DELETE: &str = r#"{"table":"orderBookL2_25","action":"delete","data":[
+        {"symbol":"XBTUSD","id":8799650500,"side":"Sell"}]}"#;
+
+    fn apply(books: &mut HashMap<String, OrderBookL2>, msg: &str) -> Option<String> {
+        let table_msg: TableMessage<L2Entry> = serde_json::from_str(msg).unwrap();
+        BitmexWS::apply(books, &table_msg)
+    }
+
+    #[test]
+    fn partial_snapshot() {
+        let mut books = HashMap::new();
+
+        assert_eq!(apply(&mut books, PARTIAL), Some("XBTUSD".to_string()));
+
+        let depth = books["XBTUSD"].to_depth(10);
+
+        assert_eq!(depth.bids[0].price, 3499.);
+        assert_eq!(depth.asks[0].price, 3499.5);
+        assert_eq!(depth.bids.len(), 2);
+        assert_eq!(depth.asks.len(), 2);
+    }
+
+    #[test]
+    fn incremental_updates() {
+        let mut books = HashMap::new();
+
+        apply(&mut books, PARTIAL);
+        apply(&mut books, UPDATE);
+        apply(&mut books, INSERT);
+        apply(&mut books, DELETE);
+
+        let depth = books["XBTUSD"].to_depth(10);
+
+        assert_eq!(depth.bids.len(), 3);
+        assert_eq!(depth.bids[0].qty, 700.);
+        assert_eq!(depth.bids[2].price, 3498.);
+        assert_eq!(depth.asks.len(), 1);
+        assert_eq!(depth.asks[0].price, 3500.);
+    }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn get_depth() {
+        let mut bitmex_ws = BitmexWS::connect(&["XBTUSD"]).unwrap();
+
+        loop {
+            println!("{:?}", bitmex_ws.get_depth());
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use chrono::Utc;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+lazy_static! {
+    // <Exchange, Server time - local time in milliseconds>
+    static ref CLOCK_OFFSETS: RwLock<HashMap<String, i64>> = RwLock::new(HashMap::new());
+}
+
+// Offset is estimated by the gateways of the exchange, it's 0 until they sample the server time
+pub fn clock_offset(exchange: &str) -> i64 {
+    CLOCK_OFFSETS
+        .read()
+        .unwrap()
+        .get(exchange)
+        .copied()
+        .unwrap_or(0)
+}
+
+pub fn set_clock_offset(exchange: &str, offset: i64) {
+    CLOCK_OFFSETS
+        .write()
+        .unwrap()
+        .insert(exchange.to_string(), offset);
+}
+
+// Local time corrected by the exchange clock offset in unix milliseconds.
+// Signed requests are stamped by it, so local clock drift doesn't get them rejected.
+pub fn exchange_now(exchange: &str) -> i64 {
+    Utc::now().timestamp_millis() + clock_offset(exchange)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn corrected_time() {
+        let local_now = Utc::now().timestamp_millis();
+        assert!(exchange_now("ClockExchange") - local_now < 1000);
+
+        set_clock_offset("ClockExchange", -60_000);
+        assert!((exchange_now("ClockExchange") - (local_now - 60_000)).abs() < 1000);
+    }
+}
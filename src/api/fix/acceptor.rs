@@ -0,0 +1,75 @@
+use super::message::FixMessage;
+use super::session::{FixSession, SessionConfig};
+use super::store::SequenceStore;
+use crossbeam::channel::{unbounded, Receiver};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Instant;
+
+// Local stand-in of FIX venue for tests, it runs the acceptor session over TCP.
+// Received application messages are passed to the responder and to the returned receiver,
+// responder replies are sent back to the initiator.
+pub fn spawn<Responder>(mut responder: Responder) -> (String, Receiver<FixMessage>)
+where
+    Responder: FnMut(&FixMessage) -> Vec<FixMessage> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let (sender, receiver) = unbounded();
+
+    thread::spawn(move || {
+        let mut session = FixSession::new(
+            SessionConfig {
+                sender_comp_id: "VENUE".to_string(),
+                target_comp_id: "MX".to_string(),
+                ..SessionConfig::default()
+            },
+            SequenceStore::memory(),
+        );
+
+        // Initiator can reconnect, the session keeps its sequence numbers
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+
+            let mut buffer = Vec::new();
+            let mut read_buffer = [0u8; 4096];
+
+            loop {
+                let read = match stream.read(&mut read_buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => read,
+                };
+                buffer.extend_from_slice(&read_buffer[..read]);
+
+                while let Ok(Some(message)) = FixMessage::decode(&mut buffer) {
+                    let received = match session.receive(message, Instant::now()) {
+                        Ok(received) => received,
+                        Err(_) => break,
+                    };
+
+                    let mut replies = received.replies;
+
+                    if let Some(application) = received.application {
+                        for reply in responder(&application) {
+                            replies.push(session.prepare(reply));
+                        }
+
+                        let _ = sender.send(application);
+                    }
+
+                    for reply in replies {
+                        let _ = stream.write_all(&reply.encode());
+                    }
+                }
+            }
+
+            session.disconnected();
+        }
+    });
+
+    (address, receiver)
+}
@@ -0,0 +1,215 @@
+use super::message::FixMessage;
+use super::session::{FixSession, SessionState};
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info};
+
+// Read timeout of the socket, session timers are checked at least that often
+const FIX_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// TCP connection of the initiator session.
+// Session runs in the reading thread, application messages are sent from any thread.
+#[derive(Debug)]
+pub struct FixInitiator {
+    // host:port of the acceptor
+    address: String,
+    session: Mutex<FixSession>,
+    // Write half of the connection, it's set while the session is connected
+    stream: Mutex<Option<TcpStream>>,
+}
+
+impl FixInitiator {
+    pub fn new(address: &str, session: FixSession) -> Self {
+        FixInitiator {
+            address: address.to_string(),
+            session: Mutex::new(session),
+            stream: Mutex::new(None),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.session.lock().unwrap().state() == SessionState::Active
+    }
+
+    // Application message is sent over the logged on session only
+    pub fn send(&self, message: FixMessage) -> Result<(), &'static str> {
+        let mut session = self.session.lock().unwrap();
+
+        if session.state() != SessionState::Active {
+            return Err("FIX session isn't logged on");
+        }
+
+        // Session is locked until the message is written, so sequence numbers go in order
+        let message = session.prepare(message);
+
+        self.write(&message)
+    }
+
+    // Session is closed after the counterparty confirms logout
+    pub fn logout(&self, text: &str) -> Result<(), &'static str> {
+        let mut session = self.session.lock().unwrap();
+
+        let logout = session.logout(text);
+
+        self.write(&logout)
+    }
+
+    fn write(&self, message: &FixMessage) -> Result<(), &'static str> {
+        debug!("[FIX] Send {}", message);
+
+        match self.stream.lock().unwrap().as_mut() {
+            Some(stream) => stream.write_all(&message.encode()).map_err(|e| {
+                error!("[FIX] Write error: {}", e);
+                "FIX write error"
+            }),
+            None => Err("FIX session isn't connected"),
+        }
+    }
+
+    // Connects, logs on and runs the session, application messages are passed to handler.
+    // It returns error when the connection is lost or logged out, so it's connected again by the caller.
+    pub fn run(&self, handler: &mut dyn FnMut(FixMessage)) -> Result<(), &'static str> {
+        let stream = match TcpStream::connect(&self.address) {
+            Ok(stream) => stream,
+            Err(e) => {
+                error!("[FIX] Can't connect to {}: {}", self.address, e);
+                return Err("Can't connect to FIX acceptor");
+            }
+        };
+
+        let write_stream = stream
+            .try_clone()
+            .and_then(|write_stream| {
+                stream.set_read_timeout(Some(FIX_POLL_INTERVAL))?;
+                Ok(write_stream)
+            })
+            .map_err(|_| "FIX socket error")?;
+
+        *self.stream.lock().unwrap() = Some(write_stream);
+
+        info!("[FIX] Connected to {}", self.address);
+
+        let result = self.session_loop(stream, handler);
+
+        self.session.lock().unwrap().disconnected();
+        *self.stream.lock().unwrap() = None;
+
+        result
+    }
+
+    fn session_loop(
+        &self,
+        mut stream: TcpStream,
+        handler: &mut dyn FnMut(FixMessage),
+    ) -> Result<(), &'static str> {
+        {
+            let mut session = self.session.lock().unwrap();
+            let logon = session.logon();
+            self.write(&logon)?;
+        }
+
+        let mut buffer = Vec::new();
+        let mut read_buffer = [0u8; 4096];
+
+        loop {
+            match stream.read(&mut read_buffer) {
+                Ok(0) => return Err("FIX connection is closed"),
+                Ok(read) => buffer.extend_from_slice(&read_buffer[..read]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {}
+                Err(e) => {
+                    error!("[FIX] Read error: {}", e);
+                    return Err("FIX read error");
+                }
+            }
+
+            while let Some(message) = FixMessage::decode(&mut buffer)? {
+                debug!("[FIX] Received {}", message);
+
+                let received = {
+                    let mut session = self.session.lock().unwrap();
+
+                    let received = match session.receive(message, Instant::now()) {
+                        Ok(received) => received,
+                        Err(e) => {
+                            let logout = session.logout(e);
+                            let _ = self.write(&logout);
+                            return Err(e);
+                        }
+                    };
+
+                    for reply in &received.replies {
+                        self.write(reply)?;
+                    }
+
+                    received
+                };
+
+                if let Some(application) = received.application {
+                    handler(application);
+                }
+
+                if received.logout {
+                    return Err("FIX session is logged out");
+                }
+            }
+
+            let mut session = self.session.lock().unwrap();
+            if let Some(message) = session.timer(Instant::now())? {
+                self.write(&message)?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::FixInitiator;
+    use crate::api::fix::acceptor;
+    use crate::api::fix::message::{msg_type, tags, FixMessage};
+    use crate::api::fix::{FixSession, SequenceStore, SessionConfig};
+    use crossbeam::channel::unbounded;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn send_application_messages() {
+        // Acceptor answers every order by execution report
+        let (address, received) = acceptor::spawn(|message: &FixMessage| {
+            vec![FixMessage::new(msg_type::EXECUTION_REPORT).with(
+                tags::CL_ORD_ID,
+                message.get(tags::CL_ORD_ID).unwrap_or_default(),
+            )]
+        });
+
+        let initiator: &'static FixInitiator = Box::leak(Box::new(FixInitiator::new(
+            &address,
+            FixSession::new(SessionConfig::default(), SequenceStore::memory()),
+        )));
+        let (sender, receiver) = unbounded();
+
+        thread::spawn(move || initiator.run(&mut |message| sender.send(message).unwrap()));
+
+        // Order isn't sent before logon
+        while !initiator.is_active() {
+            assert!(initiator
+                .send(FixMessage::new(msg_type::NEW_ORDER_SINGLE))
+                .is_err());
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        initiator
+            .send(FixMessage::new(msg_type::NEW_ORDER_SINGLE).with(tags::CL_ORD_ID, "Custom123"))
+            .unwrap();
+
+        let order = received.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(order.get(tags::CL_ORD_ID), Some("Custom123"));
+        assert_eq!(order.seq_num(), 2);
+
+        let execution_report = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(execution_report.msg_type(), msg_type::EXECUTION_REPORT);
+        assert_eq!(execution_report.get(tags::CL_ORD_ID), Some("Custom123"));
+    }
+}
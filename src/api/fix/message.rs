@@ -0,0 +1,312 @@
+use std::fmt;
+
+pub const BEGIN_STRING: &str = "FIX.4.4";
+
+// Field delimiter
+pub const SOH: u8 = 0x01;
+
+pub mod tags {
+    pub const ACCOUNT: u32 = 1;
+    pub const BEGIN_SEQ_NO: u32 = 7;
+    pub const BEGIN_STRING: u32 = 8;
+    pub const BODY_LENGTH: u32 = 9;
+    pub const CHECKSUM: u32 = 10;
+    pub const CL_ORD_ID: u32 = 11;
+    pub const COMMISSION: u32 = 12;
+    pub const CUM_QTY: u32 = 14;
+    pub const END_SEQ_NO: u32 = 16;
+    pub const EXEC_INST: u32 = 18;
+    pub const LAST_PX: u32 = 31;
+    pub const LAST_QTY: u32 = 32;
+    pub const MSG_SEQ_NUM: u32 = 34;
+    pub const MSG_TYPE: u32 = 35;
+    pub const NEW_SEQ_NO: u32 = 36;
+    pub const ORDER_ID: u32 = 37;
+    pub const ORDER_QTY: u32 = 38;
+    pub const ORD_STATUS: u32 = 39;
+    pub const ORD_TYPE: u32 = 40;
+    pub const ORIG_CL_ORD_ID: u32 = 41;
+    pub const POSS_DUP_FLAG: u32 = 43;
+    pub const PRICE: u32 = 44;
+    pub const REF_SEQ_NUM: u32 = 45;
+    pub const SENDER_COMP_ID: u32 = 49;
+    pub const SENDING_TIME: u32 = 52;
+    pub const SIDE: u32 = 54;
+    pub const SYMBOL: u32 = 55;
+    pub const TARGET_COMP_ID: u32 = 56;
+    pub const TEXT: u32 = 58;
+    pub const TIME_IN_FORCE: u32 = 59;
+    pub const TRANSACT_TIME: u32 = 60;
+    pub const ENCRYPT_METHOD: u32 = 98;
+    pub const STOP_PX: u32 = 99;
+    pub const HEART_BT_INT: u32 = 108;
+    pub const TEST_REQ_ID: u32 = 112;
+    pub const GAP_FILL_FLAG: u32 = 123;
+    pub const EXPIRE_TIME: u32 = 126;
+    pub const RESET_SEQ_NUM_FLAG: u32 = 141;
+    pub const EXEC_TYPE: u32 = 150;
+    pub const LEAVES_QTY: u32 = 151;
+    pub const COMM_CURRENCY: u32 = 479;
+    pub const USERNAME: u32 = 553;
+    pub const PASSWORD: u32 = 554;
+    pub const MASS_STATUS_REQ_ID: u32 = 584;
+    pub const MASS_STATUS_REQ_TYPE: u32 = 585;
+    pub const LAST_RPT_REQUESTED: u32 = 912;
+}
+
+pub mod msg_type {
+    pub const HEARTBEAT: &str = "0";
+    pub const TEST_REQUEST: &str = "1";
+    pub const RESEND_REQUEST: &str = "2";
+    pub const REJECT: &str = "3";
+    pub const SEQUENCE_RESET: &str = "4";
+    pub const LOGOUT: &str = "5";
+    pub const EXECUTION_REPORT: &str = "8";
+    pub const ORDER_CANCEL_REJECT: &str = "9";
+    pub const LOGON: &str = "A";
+    pub const NEW_ORDER_SINGLE: &str = "D";
+    pub const ORDER_CANCEL_REQUEST: &str = "F";
+    pub const ORDER_MASS_STATUS_REQUEST: &str = "AF";
+}
+
+// Header fields which follow MsgType in this order
+const HEADER_TAGS: [u32; 5] = [
+    tags::SENDER_COMP_ID,
+    tags::TARGET_COMP_ID,
+    tags::MSG_SEQ_NUM,
+    tags::POSS_DUP_FLAG,
+    tags::SENDING_TIME,
+];
+
+// Message without BeginString, BodyLength and CheckSum, they're calculated on encoding
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FixMessage {
+    fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+    pub fn new(msg_type: &str) -> Self {
+        FixMessage {
+            fields: vec![(tags::MSG_TYPE, msg_type.to_string())],
+        }
+    }
+
+    pub fn with<T: ToString>(mut self, tag: u32, value: T) -> Self {
+        self.set(tag, value);
+        self
+    }
+
+    // Field is replaced if the message has it already
+    pub fn set<T: ToString>(&mut self, tag: u32, value: T) {
+        let value = value.to_string();
+
+        match self
+            .fields
+            .iter_mut()
+            .find(|(field_tag, _)| *field_tag == tag)
+        {
+            Some(field) => field.1 = value,
+            None => self.fields.push((tag, value)),
+        }
+    }
+
+    pub fn get(&self, tag: u32) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field_tag, _)| *field_tag == tag)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_f64(&self, tag: u32) -> Option<f64> {
+        self.get(tag)?.parse().ok()
+    }
+
+    pub fn get_u64(&self, tag: u32) -> Option<u64> {
+        self.get(tag)?.parse().ok()
+    }
+
+    pub fn is_flag_set(&self, tag: u32) -> bool {
+        self.get(tag) == Some("Y")
+    }
+
+    pub fn msg_type(&self) -> &str {
+        self.get(tags::MSG_TYPE).unwrap_or_default()
+    }
+
+    pub fn seq_num(&self) -> u64 {
+        self.get_u64(tags::MSG_SEQ_NUM).unwrap_or(0)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let position = |tag: u32| match tag {
+            tags::MSG_TYPE => 0,
+            _ => HEADER_TAGS
+                .iter()
+                .position(|header_tag| *header_tag == tag)
+                .map_or(HEADER_TAGS.len() + 1, |position| position + 1),
+        };
+
+        let mut fields = self
+            .fields
+            .iter()
+            .filter(|(tag, _)| {
+                ![tags::BEGIN_STRING, tags::BODY_LENGTH, tags::CHECKSUM].contains(tag)
+            })
+            .collect::<Vec<_>>();
+
+        // Stable sort keeps the order of the body fields
+        fields.sort_by_key(|(tag, _)| position(*tag));
+
+        let mut body = Vec::new();
+        for (tag, value) in fields {
+            body.extend_from_slice(format!("{}={}", tag, value).as_bytes());
+            body.push(SOH);
+        }
+
+        let mut message = format!("8={}\x019={}\x01", BEGIN_STRING, body.len()).into_bytes();
+        message.extend_from_slice(&body);
+
+        let checksum = Self::checksum(&message);
+        message.extend_from_slice(format!("10={:03}\x01", checksum).as_bytes());
+
+        message
+    }
+
+    // Sum of the bytes modulo 256
+    fn checksum(bytes: &[u8]) -> u8 {
+        (bytes.iter().map(|byte| *byte as u32).sum::<u32>() % 256) as u8
+    }
+
+    // Takes the first complete message out of the buffer, it returns None if more bytes are needed.
+    // Garbage before BeginString is skipped, wrong length or checksum is an error.
+    pub fn decode(buffer: &mut Vec<u8>) -> Result<Option<FixMessage>, &'static str> {
+        let begin = format!("8={}\x019=", BEGIN_STRING).into_bytes();
+
+        let start = match buffer
+            .windows(begin.len())
+            .position(|window| window == begin.as_slice())
+        {
+            Some(start) => start,
+            None => return Ok(None),
+        };
+
+        let length_start = start + begin.len();
+        let length_end = match buffer[length_start..].iter().position(|byte| *byte == SOH) {
+            Some(position) => length_start + position,
+            None => return Ok(None),
+        };
+
+        let body_length = std::str::from_utf8(&buffer[length_start..length_end])
+            .ok()
+            .and_then(|length| length.parse::<usize>().ok())
+            .ok_or("Wrong FIX BodyLength")?;
+
+        let body_start = length_end + 1;
+        let checksum_start = body_start + body_length;
+
+        // CheckSum field is "10=NNN<SOH>"
+        let message_end = checksum_start + 7;
+        if buffer.len() < message_end {
+            return Ok(None);
+        }
+
+        let raw_message = buffer.drain(..message_end).skip(start).collect::<Vec<u8>>();
+        let checksum_start = checksum_start - start;
+
+        if &raw_message[checksum_start..checksum_start + 3] != b"10=" {
+            return Err("Wrong FIX BodyLength");
+        }
+
+        let checksum = std::str::from_utf8(&raw_message[checksum_start + 3..checksum_start + 6])
+            .ok()
+            .and_then(|checksum| checksum.parse::<u8>().ok());
+
+        if checksum != Some(Self::checksum(&raw_message[..checksum_start])) {
+            return Err("Wrong FIX CheckSum");
+        }
+
+        let body = std::str::from_utf8(&raw_message[body_start - start..checksum_start])
+            .map_err(|_| "FIX message isn't UTF-8")?;
+
+        let mut fields = Vec::new();
+
+        for field in body.split('\x01').filter(|field| !field.is_empty()) {
+            let mut tag_value = field.splitn(2, '=');
+
+            let tag = tag_value
+                .next()
+                .and_then(|tag| tag.parse::<u32>().ok())
+                .ok_or("Wrong FIX field tag")?;
+            let value = tag_value.next().ok_or("FIX field has no value")?;
+
+            fields.push((tag, value.to_string()));
+        }
+
+        Ok(Some(FixMessage { fields }))
+    }
+}
+
+// Readable form with "|" instead of SOH
+impl fmt::Display for FixMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            String::from_utf8_lossy(&self.encode()).replace('\x01', "|")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn encode_heartbeat() {
+        let heartbeat = FixMessage::new(msg_type::HEARTBEAT)
+            .with(tags::SENDING_TIME, "20210601-10:00:00.000")
+            .with(tags::MSG_SEQ_NUM, 2)
+            .with(tags::TARGET_COMP_ID, "VENUE")
+            .with(tags::SENDER_COMP_ID, "MX");
+
+        assert_eq!(
+            heartbeat.to_string(),
+            "8=FIX.4.4|9=50|35=0|49=MX|56=VENUE|34=2|52=20210601-10:00:00.000|10=215|"
+        );
+    }
+
+    #[test]
+    fn decode_messages() {
+        let logon = FixMessage::new(msg_type::LOGON)
+            .with(tags::MSG_SEQ_NUM, 1)
+            .with(tags::HEART_BT_INT, 30);
+        let heartbeat = FixMessage::new(msg_type::HEARTBEAT).with(tags::MSG_SEQ_NUM, 2);
+
+        let mut buffer = b"garbage".to_vec();
+        buffer.extend(logon.encode());
+        buffer.extend(heartbeat.encode());
+
+        // The second message isn't complete yet
+        buffer.truncate(buffer.len() - 3);
+
+        let decoded = FixMessage::decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(decoded, logon);
+        assert_eq!(decoded.get_u64(tags::HEART_BT_INT), Some(30));
+
+        assert_eq!(FixMessage::decode(&mut buffer), Ok(None));
+
+        buffer.extend_from_slice(&heartbeat.encode()[heartbeat.encode().len() - 3..]);
+        assert_eq!(FixMessage::decode(&mut buffer), Ok(Some(heartbeat)));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn wrong_checksum() {
+        let mut buffer = FixMessage::new(msg_type::HEARTBEAT).encode();
+        let checksum_position = buffer.len() - 2;
+        buffer[checksum_position] = b'0' + (buffer[checksum_position] - b'0' + 1) % 10;
+
+        assert_eq!(FixMessage::decode(&mut buffer), Err("Wrong FIX CheckSum"));
+    }
+}
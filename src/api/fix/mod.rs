@@ -0,0 +1,12 @@
+pub mod initiator;
+pub mod message;
+pub mod session;
+pub mod store;
+
+#[cfg(test)]
+pub mod acceptor;
+
+pub use initiator::FixInitiator;
+pub use message::FixMessage;
+pub use session::{FixSession, SessionConfig};
+pub use store::SequenceStore;
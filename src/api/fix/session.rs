@@ -0,0 +1,511 @@
+use super::message::{msg_type, tags, FixMessage};
+use super::store::SequenceStore;
+use chrono::Utc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    pub sender_comp_id: String,
+    pub target_comp_id: String,
+    pub heartbeat_interval: Duration,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    // Both sides start from the first message on every logon
+    pub reset_on_logon: bool,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        SessionConfig {
+            sender_comp_id: "MX".to_string(),
+            target_comp_id: "VENUE".to_string(),
+            heartbeat_interval: Duration::from_secs(30),
+            username: None,
+            password: None,
+            reset_on_logon: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionState {
+    Disconnected,
+    LogonSent,
+    Active,
+    LogoutSent,
+}
+
+// Result of the received message: session replies which have to be sent,
+// application message for the exchange adapter and logout of the session
+#[derive(Debug, Default)]
+pub struct Received {
+    pub replies: Vec<FixMessage>,
+    pub application: Option<FixMessage>,
+    pub logout: bool,
+}
+
+// FIX 4.4 session layer without IO: logon, heartbeats, sequence numbers and resend requests.
+// Initiator sends Logon first, acceptor answers the received one.
+// Sent application messages aren't stored, so resend requests are answered by gap fill
+// and stale orders are never sent again.
+#[derive(Debug)]
+pub struct FixSession {
+    config: SessionConfig,
+    store: SequenceStore,
+    state: SessionState,
+    last_sent: Instant,
+    last_received: Instant,
+    // TestReqID which has to come back in Heartbeat
+    test_request: Option<String>,
+    // Gap is being resent by the counterparty up to this sequence number
+    resend_until: Option<u64>,
+}
+
+impl FixSession {
+    pub fn new(config: SessionConfig, store: SequenceStore) -> Self {
+        FixSession {
+            config,
+            store,
+            state: SessionState::Disconnected,
+            last_sent: Instant::now(),
+            last_received: Instant::now(),
+            test_request: None,
+            resend_until: None,
+        }
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    pub fn store(&self) -> &SequenceStore {
+        &self.store
+    }
+
+    pub fn logon(&mut self) -> FixMessage {
+        if self.config.reset_on_logon {
+            self.store.reset();
+        }
+
+        self.state = SessionState::LogonSent;
+        self.last_received = Instant::now();
+
+        let logon = self.logon_message();
+
+        self.prepare(logon)
+    }
+
+    fn logon_message(&self) -> FixMessage {
+        let mut logon = FixMessage::new(msg_type::LOGON)
+            .with(tags::ENCRYPT_METHOD, 0)
+            .with(tags::HEART_BT_INT, self.config.heartbeat_interval.as_secs());
+
+        if self.config.reset_on_logon {
+            logon.set(tags::RESET_SEQ_NUM_FLAG, "Y");
+        }
+        if let Some(username) = &self.config.username {
+            logon.set(tags::USERNAME, username);
+        }
+        if let Some(password) = &self.config.password {
+            logon.set(tags::PASSWORD, password);
+        }
+
+        logon
+    }
+
+    pub fn logout(&mut self, text: &str) -> FixMessage {
+        self.state = SessionState::LogoutSent;
+
+        self.prepare(FixMessage::new(msg_type::LOGOUT).with(tags::TEXT, text))
+    }
+
+    pub fn disconnected(&mut self) {
+        self.state = SessionState::Disconnected;
+        self.test_request = None;
+        self.resend_until = None;
+    }
+
+    // Adds the header with the next sequence number, the message has to be sent after that
+    pub fn prepare(&mut self, mut message: FixMessage) -> FixMessage {
+        let seq_num = self.store.incr_sender_seq();
+
+        self.set_header(&mut message, seq_num);
+
+        message
+    }
+
+    fn set_header(&mut self, message: &mut FixMessage, seq_num: u64) {
+        message.set(tags::SENDER_COMP_ID, &self.config.sender_comp_id);
+        message.set(tags::TARGET_COMP_ID, &self.config.target_comp_id);
+        message.set(tags::MSG_SEQ_NUM, seq_num);
+        // FIX has no server time request, so it's the local time, the host clock has to be in sync
+        message.set(tags::SENDING_TIME, Utc::now().format("%Y%m%d-%H:%M:%S%.3f"));
+
+        self.last_sent = Instant::now();
+    }
+
+    // Error means broken session, it has to be logged out and disconnected
+    pub fn receive(&mut self, message: FixMessage, now: Instant) -> Result<Received, &'static str> {
+        self.last_received = now;
+
+        if message.get(tags::SENDER_COMP_ID) != Some(self.config.target_comp_id.as_str())
+            || message.get(tags::TARGET_COMP_ID) != Some(self.config.sender_comp_id.as_str())
+        {
+            return Err("Wrong FIX CompID");
+        }
+
+        let msg_type = message.msg_type().to_string();
+        let seq_num = message.seq_num();
+
+        if self.state != SessionState::Active
+            && msg_type != msg_type::LOGON
+            && msg_type != msg_type::LOGOUT
+        {
+            return Err("FIX session isn't logged on");
+        }
+
+        // Counterparty starts from the first message
+        if msg_type == msg_type::LOGON && message.is_flag_set(tags::RESET_SEQ_NUM_FLAG) {
+            self.store.set_next_target_seq(seq_num);
+        }
+
+        // SequenceReset-Reset sets the next sequence number regardless of the current one
+        if msg_type == msg_type::SEQUENCE_RESET && !message.is_flag_set(tags::GAP_FILL_FLAG) {
+            if let Some(new_seq_num) = message.get_u64(tags::NEW_SEQ_NO) {
+                self.store.set_next_target_seq(new_seq_num);
+            }
+
+            return Ok(Received::default());
+        }
+
+        let expected_seq_num = self.store.next_target_seq();
+
+        if seq_num < expected_seq_num {
+            // Resent duplicate which was processed already
+            if message.is_flag_set(tags::POSS_DUP_FLAG) {
+                return Ok(Received::default());
+            }
+
+            return Err("FIX MsgSeqNum is lower than expected");
+        }
+
+        let mut received = Received::default();
+
+        // Missed messages are requested once, the gap is filled by the resent ones
+        if seq_num > expected_seq_num {
+            if self.resend_until.is_none() {
+                warn!(
+                    "[FIX] Sequence gap, expected {} received {}",
+                    expected_seq_num, seq_num
+                );
+
+                let resend_request = FixMessage::new(msg_type::RESEND_REQUEST)
+                    .with(tags::BEGIN_SEQ_NO, expected_seq_num)
+                    .with(tags::END_SEQ_NO, 0);
+
+                received.replies.push(self.prepare(resend_request));
+            }
+
+            self.resend_until = Some(self.resend_until.unwrap_or(0).max(seq_num));
+
+            match msg_type.as_str() {
+                msg_type::LOGON => self.on_logon(&mut received),
+                msg_type::LOGOUT => self.on_logout(&mut received),
+                _ => {}
+            }
+
+            return Ok(received);
+        }
+
+        self.store.set_next_target_seq(seq_num + 1);
+
+        match msg_type.as_str() {
+            msg_type::LOGON => self.on_logon(&mut received),
+            msg_type::LOGOUT => self.on_logout(&mut received),
+            msg_type::HEARTBEAT => {
+                if message.get(tags::TEST_REQ_ID) == self.test_request.as_deref() {
+                    self.test_request = None;
+                }
+            }
+            msg_type::TEST_REQUEST => {
+                let mut heartbeat = FixMessage::new(msg_type::HEARTBEAT);
+                if let Some(test_req_id) = message.get(tags::TEST_REQ_ID) {
+                    heartbeat.set(tags::TEST_REQ_ID, test_req_id);
+                }
+
+                received.replies.push(self.prepare(heartbeat));
+            }
+            msg_type::RESEND_REQUEST => {
+                let begin_seq_num = message.get_u64(tags::BEGIN_SEQ_NO).unwrap_or(1);
+
+                if let Some(gap_fill) = self.gap_fill(begin_seq_num) {
+                    received.replies.push(gap_fill);
+                }
+            }
+            msg_type::SEQUENCE_RESET => {
+                if let Some(new_seq_num) = message.get_u64(tags::NEW_SEQ_NO) {
+                    if new_seq_num > seq_num {
+                        self.store.set_next_target_seq(new_seq_num);
+                    }
+                }
+            }
+            msg_type::REJECT => warn!("[FIX] Session level reject: {}", message),
+            _ => received.application = Some(message),
+        }
+
+        // The gap is filled
+        if let Some(resend_until) = self.resend_until {
+            if self.store.next_target_seq() > resend_until {
+                info!("[FIX] Sequence gap is filled");
+                self.resend_until = None;
+            }
+        }
+
+        Ok(received)
+    }
+
+    // Acceptor answers Logon with its own one
+    fn on_logon(&mut self, received: &mut Received) {
+        if self.state == SessionState::Disconnected {
+            let logon = self.logon_message();
+            received.replies.insert(0, self.prepare(logon));
+        }
+
+        info!(
+            "[FIX] Session {}->{} is logged on",
+            self.config.sender_comp_id, self.config.target_comp_id
+        );
+
+        self.state = SessionState::Active;
+    }
+
+    // Logout is confirmed by Logout if it's initiated by the counterparty
+    fn on_logout(&mut self, received: &mut Received) {
+        if self.state != SessionState::LogoutSent {
+            received
+                .replies
+                .push(self.prepare(FixMessage::new(msg_type::LOGOUT)));
+        }
+
+        self.disconnected();
+        received.logout = true;
+    }
+
+    // Resent range is replaced by SequenceReset-GapFill up to the next sequence number
+    fn gap_fill(&mut self, begin_seq_num: u64) -> Option<FixMessage> {
+        let next_seq_num = self.store.next_sender_seq();
+
+        if begin_seq_num >= next_seq_num {
+            return None;
+        }
+
+        let mut gap_fill = FixMessage::new(msg_type::SEQUENCE_RESET)
+            .with(tags::POSS_DUP_FLAG, "Y")
+            .with(tags::GAP_FILL_FLAG, "Y")
+            .with(tags::NEW_SEQ_NO, next_seq_num);
+
+        self.set_header(&mut gap_fill, begin_seq_num);
+
+        Some(gap_fill)
+    }
+
+    // Heartbeat after the interval without sent messages, TestRequest after the silent one.
+    // Error means lost connection.
+    pub fn timer(&mut self, now: Instant) -> Result<Option<FixMessage>, &'static str> {
+        let heartbeat_interval = self.config.heartbeat_interval;
+        let silence = now.saturating_duration_since(self.last_received);
+
+        match self.state {
+            SessionState::Disconnected => return Ok(None),
+            SessionState::LogonSent | SessionState::LogoutSent => {
+                if silence > heartbeat_interval {
+                    return Err("FIX session response timeout");
+                }
+
+                return Ok(None);
+            }
+            SessionState::Active => {}
+        }
+
+        if self.test_request.is_some() {
+            if silence >= heartbeat_interval * 2 {
+                return Err("FIX heartbeat timeout");
+            }
+        } else if silence >= heartbeat_interval + heartbeat_interval / 5 {
+            let test_req_id = format!("TEST{}", self.store.next_sender_seq());
+            self.test_request = Some(test_req_id.clone());
+
+            let test_request =
+                FixMessage::new(msg_type::TEST_REQUEST).with(tags::TEST_REQ_ID, test_req_id);
+
+            return Ok(Some(self.prepare(test_request)));
+        }
+
+        if now.saturating_duration_since(self.last_sent) >= heartbeat_interval {
+            return Ok(Some(self.prepare(FixMessage::new(msg_type::HEARTBEAT))));
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn sessions() -> (FixSession, FixSession) {
+        let initiator = FixSession::new(SessionConfig::default(), SequenceStore::memory());
+        let acceptor = FixSession::new(
+            SessionConfig {
+                sender_comp_id: "VENUE".to_string(),
+                target_comp_id: "MX".to_string(),
+                ..SessionConfig::default()
+            },
+            SequenceStore::memory(),
+        );
+
+        (initiator, acceptor)
+    }
+
+    // Initiator and acceptor are logged on to each other
+    fn logged_on() -> (FixSession, FixSession) {
+        let (mut initiator, mut acceptor) = sessions();
+
+        let logon = initiator.logon();
+        let received = acceptor.receive(logon, Instant::now()).unwrap();
+        assert_eq!(received.replies[0].msg_type(), msg_type::LOGON);
+
+        let received = initiator
+            .receive(received.replies[0].clone(), Instant::now())
+            .unwrap();
+        assert!(received.replies.is_empty());
+
+        (initiator, acceptor)
+    }
+
+    fn order(seq_num: u64) -> FixMessage {
+        FixMessage::new(msg_type::EXECUTION_REPORT)
+            .with(tags::SENDER_COMP_ID, "VENUE")
+            .with(tags::TARGET_COMP_ID, "MX")
+            .with(tags::MSG_SEQ_NUM, seq_num)
+    }
+
+    #[test]
+    fn logon() {
+        let (initiator, acceptor) = logged_on();
+
+        assert_eq!(initiator.state(), SessionState::Active);
+        assert_eq!(acceptor.state(), SessionState::Active);
+        assert_eq!(initiator.store().next_sender_seq(), 2);
+        assert_eq!(initiator.store().next_target_seq(), 2);
+    }
+
+    #[test]
+    fn heartbeats() {
+        let (mut initiator, _acceptor) = logged_on();
+        let now = Instant::now();
+        let interval = Duration::from_secs(30);
+
+        assert!(initiator.timer(now).unwrap().is_none());
+
+        let message = initiator.timer(now + interval).unwrap().unwrap();
+        assert_eq!(message.msg_type(), msg_type::HEARTBEAT);
+
+        let message = initiator.timer(now + interval * 2).unwrap().unwrap();
+        assert_eq!(message.msg_type(), msg_type::TEST_REQUEST);
+
+        assert!(initiator.timer(now + interval * 3).is_err());
+    }
+
+    #[test]
+    fn resend_request_on_gap() {
+        let (mut initiator, _acceptor) = logged_on();
+
+        // Messages 2 and 3 are lost
+        let received = initiator.receive(order(4), Instant::now()).unwrap();
+        assert!(received.application.is_none());
+        assert_eq!(received.replies[0].msg_type(), msg_type::RESEND_REQUEST);
+        assert_eq!(received.replies[0].get_u64(tags::BEGIN_SEQ_NO), Some(2));
+
+        // Resend isn't requested again during the gap
+        let received = initiator.receive(order(5), Instant::now()).unwrap();
+        assert!(received.replies.is_empty());
+
+        let gap_fill = FixMessage::new(msg_type::SEQUENCE_RESET)
+            .with(tags::SENDER_COMP_ID, "VENUE")
+            .with(tags::TARGET_COMP_ID, "MX")
+            .with(tags::MSG_SEQ_NUM, 2)
+            .with(tags::GAP_FILL_FLAG, "Y")
+            .with(tags::NEW_SEQ_NO, 4);
+        initiator.receive(gap_fill, Instant::now()).unwrap();
+
+        assert!(initiator
+            .receive(order(4), Instant::now())
+            .unwrap()
+            .application
+            .is_some());
+        assert!(initiator
+            .receive(order(5), Instant::now())
+            .unwrap()
+            .application
+            .is_some());
+        assert_eq!(initiator.store().next_target_seq(), 6);
+    }
+
+    #[test]
+    fn gap_fill_on_resend_request() {
+        let (mut initiator, mut acceptor) = logged_on();
+
+        initiator.prepare(FixMessage::new(msg_type::NEW_ORDER_SINGLE));
+        initiator.prepare(FixMessage::new(msg_type::NEW_ORDER_SINGLE));
+
+        let resend_request = acceptor.prepare(
+            FixMessage::new(msg_type::RESEND_REQUEST)
+                .with(tags::BEGIN_SEQ_NO, 2)
+                .with(tags::END_SEQ_NO, 0),
+        );
+
+        let received = initiator.receive(resend_request, Instant::now()).unwrap();
+        let gap_fill = &received.replies[0];
+
+        assert_eq!(gap_fill.msg_type(), msg_type::SEQUENCE_RESET);
+        assert_eq!(gap_fill.seq_num(), 2);
+        assert_eq!(gap_fill.get_u64(tags::NEW_SEQ_NO), Some(4));
+        assert!(gap_fill.is_flag_set(tags::POSS_DUP_FLAG));
+    }
+
+    #[test]
+    fn low_sequence_number() {
+        let (mut initiator, _acceptor) = logged_on();
+
+        let duplicate = order(1).with(tags::POSS_DUP_FLAG, "Y");
+        assert!(initiator
+            .receive(duplicate, Instant::now())
+            .unwrap()
+            .application
+            .is_none());
+
+        assert!(initiator.receive(order(1), Instant::now()).is_err());
+    }
+
+    #[test]
+    fn logout() {
+        let (mut initiator, mut acceptor) = logged_on();
+
+        let logout = acceptor.logout("End of day");
+        let received = initiator.receive(logout, Instant::now()).unwrap();
+
+        assert!(received.logout);
+        assert_eq!(received.replies[0].msg_type(), msg_type::LOGOUT);
+        assert_eq!(initiator.state(), SessionState::Disconnected);
+
+        assert!(
+            acceptor
+                .receive(received.replies[0].clone(), Instant::now())
+                .unwrap()
+                .logout
+        );
+    }
+}
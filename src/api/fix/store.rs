@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::PathBuf;
+use tracing::error;
+
+// Next sequence numbers of the session, they survive reconnects and restarts.
+// File keeps "<next sender seq> <next target seq>", store without file is kept in memory.
+#[derive(Debug, Clone)]
+pub struct SequenceStore {
+    path: Option<PathBuf>,
+    next_sender_seq: u64,
+    next_target_seq: u64,
+}
+
+impl SequenceStore {
+    pub fn open(path: &str) -> Result<Self, &'static str> {
+        let path = PathBuf::from(path);
+
+        let (next_sender_seq, next_target_seq) = match fs::read_to_string(&path) {
+            Ok(content) => Self::parse(&content).ok_or("Wrong FIX sequence store")?,
+            // New session starts from the first message
+            Err(_) => (1, 1),
+        };
+
+        if let Some(dir) = path.parent() {
+            if fs::create_dir_all(dir).is_err() {
+                return Err("Can't create FIX sequence store");
+            }
+        }
+
+        Ok(SequenceStore {
+            path: Some(path),
+            next_sender_seq,
+            next_target_seq,
+        })
+    }
+
+    pub fn memory() -> Self {
+        SequenceStore {
+            path: None,
+            next_sender_seq: 1,
+            next_target_seq: 1,
+        }
+    }
+
+    fn parse(content: &str) -> Option<(u64, u64)> {
+        let mut numbers = content
+            .split_whitespace()
+            .map(|number| number.parse::<u64>());
+
+        match (numbers.next(), numbers.next()) {
+            (Some(Ok(sender)), Some(Ok(target))) => Some((sender, target)),
+            _ => None,
+        }
+    }
+
+    pub fn next_sender_seq(&self) -> u64 {
+        self.next_sender_seq
+    }
+
+    pub fn next_target_seq(&self) -> u64 {
+        self.next_target_seq
+    }
+
+    // It returns the sequence number of the message being sent
+    pub fn incr_sender_seq(&mut self) -> u64 {
+        let seq_num = self.next_sender_seq;
+
+        self.next_sender_seq += 1;
+        self.persist();
+
+        seq_num
+    }
+
+    pub fn set_next_target_seq(&mut self, seq_num: u64) {
+        self.next_target_seq = seq_num;
+        self.persist();
+    }
+
+    // Both sides start from the first message, e.g. on logon with ResetSeqNumFlag
+    pub fn reset(&mut self) {
+        self.next_sender_seq = 1;
+        self.next_target_seq = 1;
+        self.persist();
+    }
+
+    fn persist(&self) {
+        if let Some(path) = &self.path {
+            let content = format!("{} {}", self.next_sender_seq, self.next_target_seq);
+
+            if let Err(e) = fs::write(path, content) {
+                error!("[FIX] Can't persist sequence numbers: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::SequenceStore;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn persisted_sequence_numbers() {
+        let path = env::temp_dir().join(format!("mx_fix_store_{}.seq", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut store = SequenceStore::open(path).unwrap();
+        assert_eq!(store.incr_sender_seq(), 1);
+        assert_eq!(store.incr_sender_seq(), 2);
+        store.set_next_target_seq(5);
+
+        // Session is restored after restart
+        let store = SequenceStore::open(path).unwrap();
+        assert_eq!(store.next_sender_seq(), 3);
+        assert_eq!(store.next_target_seq(), 5);
+
+        fs::remove_file(path).unwrap();
+    }
+}
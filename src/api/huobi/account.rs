@@ -146,6 +146,58 @@ impl Account {
         }
     }
 
+    // Order of the type like "buy-limit-maker" or "sell-stop-limit",
+    // stop order has the stop price with its operator "gte" or "lte"
+    pub fn custom_order(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        type_: &str,
+        stop: Option<(f64, &str)>,
+        client_order_id: &str,
+    ) -> ExchangeApiResult<Transaction> {
+        let params: BTreeMap<String, String> = BTreeMap::new();
+        let mut body: BTreeMap<String, String> = BTreeMap::new();
+
+        body.insert("account-id".into(), self.account_id.clone());
+        body.insert("amount".into(), amount.to_string());
+        body.insert("price".into(), price.to_string());
+        body.insert("source".into(), "api".into());
+        body.insert("symbol".into(), symbol.to_lowercase());
+        body.insert("type".into(), type_.into());
+        body.insert("client-order-id".into(), client_order_id.into());
+
+        if let Some((stop_price, operator)) = stop {
+            body.insert("stop-price".into(), stop_price.to_string());
+            body.insert("operator".into(), operator.into());
+        }
+
+        let placed_order = self
+            .client
+            .post_signed("/v1/order/orders/place", params, &body)
+            .and_then(|data| Ok(serde_json::from_str::<PlaceOrderResult>(data.as_str())?));
+
+        match placed_order {
+            Ok(placed_order) => {
+                debug!(
+                    "[Huobi API] Custom order: {} {}, amount {}, price {}",
+                    type_, symbol, amount, price
+                );
+
+                Ok(Transaction {
+                    symbol: symbol.to_string(),
+                    order_id: placed_order.data.parse().unwrap_or_default(),
+                })
+            }
+            Err(e) => {
+                error!("Huobi custom order error {:?}", e);
+
+                Err("Huobi Custom order Error")
+            }
+        }
+    }
+
     fn _place_order(
         &self,
         account_id: &str,
@@ -1,5 +1,7 @@
 use super::error::*;
 use super::models::{APIErrorResponse, APIResult};
+use crate::api::clock;
+use chrono::TimeZone;
 use reqwest;
 use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE, USER_AGENT};
 use ring::{digest, hmac};
@@ -214,8 +216,9 @@ pub fn percent_encode(source: &str) -> String {
     signature
 }
 
+// Huobi rejects requests which are stamped too far from its server time
 pub fn get_timestamp() -> String {
-    let utc_time = chrono::Utc::now();
+    let utc_time = chrono::Utc.timestamp_millis(clock::exchange_now("Huobi"));
     let formatted_time = utc_time.format("%Y-%m-%dT%H:%M:%S").to_string();
 
     formatted_time
@@ -58,7 +58,54 @@ impl HuobiApi {
         let symbols = serde_json::from_reader(body.reader());
         Ok(symbols.unwrap())
     }
+
+    /// Huobi API "/v1/common/timestamp", unix milliseconds
+    pub async fn timestamp() -> APIResult<u64> {
+        let body = HuobiApi::get_request("/v1/common/timestamp").await?;
+        let timestamp: TimestampResult = serde_json::from_reader(body.reader())?;
+
+        Ok(timestamp.data)
+    }
+
+    /// Huobi API "/market/history/kline"
+    pub async fn klines(symbol: &str, period: &str, size: usize) -> APIResult<KlinesResult> {
+        info!("[Huobi] get {} klines {}", period, symbol);
+        let endpoint = format!(
+            "/market/history/kline?symbol={}&period={}&size={}",
+            symbol.to_lowercase(),
+            period,
+            size
+        );
+        let body = HuobiApi::get_request(&endpoint).await?;
+        let klines: KlinesResult = serde_json::from_reader(body.reader())?;
+
+        if klines.status != "ok" {
+            return Err(format!("Huobi klines status {}", klines.status).into());
+        }
+
+        Ok(klines)
+    }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+
+    use super::HuobiApi;
+
+    #[tokio::test]
+    #[ignore]
+    // For local testing
+    async fn klines() {
+        println!(
+            "{:?}",
+            HuobiApi::klines("btcusdt", "1min", 10).await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    // For local testing
+    async fn timestamp() {
+        println!("{}", HuobiApi::timestamp().await.unwrap());
+    }
+}
@@ -34,6 +34,35 @@ pub struct ResultSymbol {
     pub data: Vec<Symbol>,
 }
 
+// REST "/v1/common/timestamp" response, unix milliseconds
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TimestampResult {
+    pub status: String,
+    pub data: u64,
+}
+
+// REST "/market/history/kline" response, candles are from the newest one
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KlinesResult {
+    pub status: String,
+    #[serde(default)]
+    pub data: Vec<Kline>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Kline {
+    // Open time in unix seconds
+    pub id: u64,
+    pub open: f64,
+    pub close: f64,
+    pub low: f64,
+    pub high: f64,
+    // Base currency volume
+    pub amount: f64,
+    pub vol: f64,
+    pub count: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AccountsResult {
     data: Vec<Account>,
@@ -214,6 +243,26 @@ pub struct OrderSubs {
     pub data: EventType,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccountSubs {
+    pub action: String,
+    pub ch: String,
+    pub data: AccountChange,
+}
+
+// Push of accounts.update#2, balance and available are changed together
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountChange {
+    pub currency: String,
+    pub account_id: u64,
+    pub balance: Option<String>,
+    pub available: Option<String>,
+    pub change_type: Option<String>,
+    pub account_type: Option<String>,
+    pub change_time: Option<u64>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum EventType {
@@ -1,5 +1,6 @@
 use super::client::{build_query_string, get_timestamp, sign_hmac_sha256_base64};
 use super::models::*;
+use crate::api::websocket::{self, WS_READ_TIMEOUT};
 use lazy_static::lazy_static;
 use serde::Deserialize;
 use serde_json::json;
@@ -35,6 +36,7 @@ struct PingData {
 
 pub enum WebsocketEvent {
     OrderUpdate(OrderSubs),
+    AccountUpdate(AccountSubs),
 }
 
 pub struct WebSockets<'a> {
@@ -63,8 +65,13 @@ impl<'a> WebSockets<'a> {
     ) -> APIResult<()> {
         let url = Url::parse(WEBSOCKET_URL)?;
 
+        // Symbols are kept on reconnect, they are resubscribed after auth
         for symbol in symbols {
-            SYMBOLS.lock().unwrap().push(symbol.to_string());
+            let mut symbols_lock = SYMBOLS.lock().unwrap();
+
+            if !symbols_lock.iter().any(|s| s == symbol) {
+                symbols_lock.push(symbol.to_string());
+            }
         }
 
         // for channel in channels {
@@ -73,6 +80,9 @@ impl<'a> WebSockets<'a> {
 
         match connect(url) {
             Ok(answer) => {
+                // Huobi pings every 20 seconds, missed pings mean the connection is lost
+                websocket::set_read_timeout(&answer.0, WS_READ_TIMEOUT)?;
+
                 self.socket = Some(answer);
 
                 let mut params: BTreeMap<String, String> = BTreeMap::new();
@@ -197,6 +207,20 @@ impl<'a> WebSockets<'a> {
                                                     );
                                                 };
                                             }
+
+                                            // Balance and available balance of every change
+                                            let subscribe_message = json!({
+                                                    "action": "sub",
+                                                    "ch": "accounts.update#2"
+                                            });
+
+                                            if let Some(ref mut socket) = self.socket {
+                                                socket.0.write_message(
+                                                    tungstenite::Message::Text(
+                                                        subscribe_message.to_string(),
+                                                    ),
+                                                )?;
+                                            };
                                         }
 
                                         "sub" => {
@@ -206,6 +230,22 @@ impl<'a> WebSockets<'a> {
                                         "push" => {
                                             debug!("push {:?}", text);
 
+                                            let channel = msg
+                                                .get("ch")
+                                                .and_then(|channel| channel.as_str())
+                                                .unwrap_or_default();
+
+                                            if channel.starts_with("accounts.update") {
+                                                let account_sub: AccountSubs =
+                                                    serde_json::from_str(&text)?;
+
+                                                (self.handler)(WebsocketEvent::AccountUpdate(
+                                                    account_sub,
+                                                ))?;
+
+                                                continue;
+                                            }
+
                                             let order_sub: OrderSubs =
                                                 serde_json::from_str(&text).unwrap();
 
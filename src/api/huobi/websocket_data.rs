@@ -1,27 +1,64 @@
+use crate::api::websocket::{self, WS_READ_TIMEOUT};
 use flate2::read::GzDecoder;
 use serde::{Deserialize, Serialize};
 use serde_json;
+use std::collections::HashMap;
 use std::io::prelude::*;
-use tracing::debug;
+use tracing::{debug, error};
 use tungstenite::{client::AutoStream, connect, Message, WebSocket};
 use url::Url;
 
 const WS_HUOBI_URL: &str = "wss://api.huobi.pro/ws";
 
-const HUOBI_READ_MESSAGE_ERROR: &str = "Error reading message";
+const HUOBI_CONNECT_ERROR: &str = "Can't connect to Huobi websocket";
+
+const HUOBI_READ_MESSAGE_ERROR: &str = "Error reading Huobi websocket message";
+
+const HUOBI_WRITE_MESSAGE_ERROR: &str = "Error writing Huobi websocket message";
+
+// Incremental market by price channel depth
+const HUOBI_MBP_LEVELS: u16 = 150;
 
 #[derive(Debug)]
 pub struct HuobiWS {
     socket: WebSocket<AutoStream>,
+    // <channel, instrument>
+    channels: HashMap<String, String>,
 }
 
 impl HuobiWS {
-    pub fn connect(instrument: &str) -> Self {
+    // Subscribes to incremental depth of all instruments on one socket
+    // and requests their snapshots
+    pub fn connect(instruments: &[&str]) -> Result<Self, &'static str> {
+        let mut huobi_ws = HuobiWS::subscribe(instruments, HuobiWS::channel)?;
+
+        // Snapshots are requested after subscription, so updates between them are not lost
+        for instrument in instruments {
+            huobi_ws.request_snapshot(instrument)?;
+        }
+
+        // Responce statuses are skipped by get_event
+
+        Ok(huobi_ws)
+    }
+
+    // Subscribes to public trades of all instruments on one socket
+    pub fn connect_trades(instruments: &[&str]) -> Result<Self, &'static str> {
+        HuobiWS::subscribe(instruments, HuobiWS::trade_channel)
+    }
+
+    // Connects and subscribes to the channel of every instrument
+    fn subscribe(instruments: &[&str], channel: fn(&str) -> String) -> Result<Self, &'static str> {
         debug!("Connecting to Huobi WebSocket");
 
         // Connect to Huobi WebSockets
-        let (mut socket, response) =
-            connect(Url::parse(WS_HUOBI_URL).unwrap()).expect("Can't connect");
+        let (mut socket, response) = match connect(Url::parse(WS_HUOBI_URL).unwrap()) {
+            Ok(connection) => connection,
+            Err(e) => {
+                error!("[Huobi WS] {}: {}", HUOBI_CONNECT_ERROR, e);
+                return Err(HUOBI_CONNECT_ERROR);
+            }
+        };
 
         debug!("Huobi WebSocket headers");
 
@@ -29,30 +66,81 @@ impl HuobiWS {
             debug!("* {}", header);
         }
 
+        // Huobi pings every 5 seconds, missed pings mean the connection is lost
+        websocket::set_read_timeout(&socket, WS_READ_TIMEOUT)?;
+
         // Read Huobi ping message
-        let msg = socket.read_message().expect(HUOBI_READ_MESSAGE_ERROR);
-        let data = msg.into_data();
+        let data = HuobiWS::read_socket(&mut socket)?.into_data();
         let ping_number = HuobiWS::ping_number(&data);
 
         // Send back pong message to Huobi
-        HuobiWS::send_pong_message(ping_number, &mut socket);
+        HuobiWS::send_pong_message(ping_number, &mut socket)?;
+
+        let mut channels = HashMap::new();
+
+        for (index, instrument) in instruments.iter().enumerate() {
+            let channel = channel(instrument);
+
+            let sub_msg = SubscriptionMessage {
+                sub: channel.clone(),
+                id: format!("id{}", index + 1),
+            };
+
+            let sub_msg_str = serde_json::to_string(&sub_msg).unwrap();
+
+            // Send subscription message
+            HuobiWS::write_socket(&mut socket, sub_msg_str)?;
+
+            channels.insert(channel, instrument.to_string());
+        }
+
+        Ok(HuobiWS { socket, channels })
+    }
+
+    // Snapshot comes with the next events
+    pub fn request_snapshot(&mut self, instrument: &str) -> Result<(), &'static str> {
+        debug!("[Huobi WS] Request snapshot {}", instrument);
 
-        let sub_msg = SubscriptionMessage {
-            sub: format!("market.{}.mbp.refresh.20", instrument.to_lowercase()),
-            id: "id1".to_string(),
+        let req_msg = RequestMessage {
+            req: HuobiWS::channel(instrument),
+            id: format!("snapshot.{}", instrument.to_lowercase()),
         };
 
-        let sub_msg_str = serde_json::to_string(&sub_msg).unwrap();
+        let req_msg_str = serde_json::to_string(&req_msg).unwrap();
 
-        // Send subscription message
-        socket
-            .write_message(Message::Text(sub_msg_str.into()))
-            .unwrap();
+        HuobiWS::write_socket(&mut self.socket, req_msg_str)
+    }
 
-        // Receive responce status
-        let _status_msg = socket.read_message().expect(HUOBI_READ_MESSAGE_ERROR);
+    fn read_socket(socket: &mut WebSocket<AutoStream>) -> Result<Message, &'static str> {
+        match socket.read_message() {
+            Ok(msg) => Ok(msg),
+            Err(e) => {
+                error!("[Huobi WS] {}: {}", HUOBI_READ_MESSAGE_ERROR, e);
+                Err(HUOBI_READ_MESSAGE_ERROR)
+            }
+        }
+    }
 
-        HuobiWS { socket }
+    fn write_socket(socket: &mut WebSocket<AutoStream>, text: String) -> Result<(), &'static str> {
+        match socket.write_message(Message::Text(text)) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                error!("[Huobi WS] {}: {}", HUOBI_WRITE_MESSAGE_ERROR, e);
+                Err(HUOBI_WRITE_MESSAGE_ERROR)
+            }
+        }
+    }
+
+    fn channel(instrument: &str) -> String {
+        format!(
+            "market.{}.mbp.{}",
+            instrument.to_lowercase(),
+            HUOBI_MBP_LEVELS
+        )
+    }
+
+    fn trade_channel(instrument: &str) -> String {
+        format!("market.{}.trade.detail", instrument.to_lowercase())
     }
 
     fn decode_message(data: &Vec<u8>) -> String {
@@ -77,34 +165,49 @@ impl HuobiWS {
         ping_msg.ping
     }
 
-    fn send_pong_message(pong: i64, socket: &mut WebSocket<AutoStream>) {
+    fn send_pong_message(
+        pong: i64,
+        socket: &mut WebSocket<AutoStream>,
+    ) -> Result<(), &'static str> {
         debug!("[Huobi WS] Send pong message, pong = {}", pong);
 
         let pong_msg = PongMessage { pong: pong };
 
         let pong_str = serde_json::to_string(&pong_msg).unwrap();
 
-        match socket.write_message(Message::Text(pong_str.into())) {
-            Ok(_) => {}
-            Err(e) => println!("error send pong message {}", e),
-        };
+        HuobiWS::write_socket(socket, pong_str)
     }
 
-    fn read_message(&mut self) -> String {
-        debug!("[Huobi WS] Read message");
-
-        let msg = self.socket.read_message().expect(HUOBI_READ_MESSAGE_ERROR);
-        let data = msg.into_data();
+    // It returns instrument with its snapshot or incremental update
+    pub fn get_event(&mut self) -> Result<(String, BookEvent), &'static str> {
+        // Loop until get depth data
+        loop {
+            let decoded_msg = self.read_message()?;
 
-        HuobiWS::decode_message(&data)
+            match HuobiWS::parse_event(&self.channels, &decoded_msg) {
+                Some(instrument_event) => return Ok(instrument_event),
+                None => debug!("[Huobi WS] Skip message {}", decoded_msg),
+            }
+        }
     }
 
-    pub fn get_depth(&mut self) -> Depth {
-        // Loop until get depth data
+    // It returns instrument with its trades of one taker order
+    pub fn get_trades(&mut self) -> Result<(String, Vec<Trade>), &'static str> {
+        // Loop until get trade data
         loop {
-            let msg = self.socket.read_message().expect(HUOBI_READ_MESSAGE_ERROR);
+            let decoded_msg = self.read_message()?;
 
-            let data = msg.into_data();
+            match HuobiWS::parse_trades(&self.channels, &decoded_msg) {
+                Some(instrument_trades) => return Ok(instrument_trades),
+                None => debug!("[Huobi WS] Skip message {}", decoded_msg),
+            }
+        }
+    }
+
+    // Pings are answered, it returns the next decoded message
+    fn read_message(&mut self) -> Result<String, &'static str> {
+        loop {
+            let data = HuobiWS::read_socket(&mut self.socket)?.into_data();
             let decoded_msg = HuobiWS::decode_message(&data);
 
             if decoded_msg.contains("ping") {
@@ -112,39 +215,42 @@ impl HuobiWS {
 
                 let ping_number = HuobiWS::ping_number(&data);
 
-                HuobiWS::send_pong_message(ping_number, &mut self.socket);
+                HuobiWS::send_pong_message(ping_number, &mut self.socket)?;
             } else {
-                match serde_json::from_str::<DepthMessage>(&decoded_msg) {
-                    Ok(depth_msg) => {
-                        return HuobiWS::tick_to_depth(depth_msg.tick);
-                    }
-                    Err(e) => println!("err get depth {}", e),
-                }
+                return Ok(decoded_msg);
             }
         }
     }
 
-    fn tick_to_depth(tick: Tick) -> Depth {
-        Depth {
-            exchange: "Huobi".to_string(),
-            bids: tick
-                .bids
-                .iter()
-                .map(|b| Ticker {
-                    price: b[0],
-                    qty: b[1],
-                })
-                .collect(),
-            asks: tick
-                .asks
-                .iter()
-                .map(|a| Ticker {
-                    price: a[0],
-                    qty: a[1],
-                })
-                .collect(),
+    fn parse_event(channels: &HashMap<String, String>, text: &str) -> Option<(String, BookEvent)> {
+        let book_msg = serde_json::from_str::<BookMessage>(text).ok()?;
+
+        // Updates come with "ch", snapshots with "rep"
+        match (book_msg.ch, book_msg.rep, book_msg.tick, book_msg.data) {
+            (Some(channel), _, Some(tick), _) => {
+                Some((channels.get(&channel)?.clone(), BookEvent::Update(tick)))
+            }
+            (_, Some(channel), _, Some(tick)) => {
+                Some((channels.get(&channel)?.clone(), BookEvent::Snapshot(tick)))
+            }
+            _ => None,
         }
     }
+
+    fn parse_trades(
+        channels: &HashMap<String, String>,
+        text: &str,
+    ) -> Option<(String, Vec<Trade>)> {
+        let trade_msg = serde_json::from_str::<TradeMessage>(text).ok()?;
+
+        Some((channels.get(&trade_msg.ch)?.clone(), trade_msg.tick.data))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum BookEvent {
+    Snapshot(Tick),
+    Update(Tick),
 }
 
 #[derive(Debug, Deserialize)]
@@ -163,68 +269,142 @@ struct SubscriptionMessage {
     id: String,
 }
 
+#[derive(Debug, Serialize)]
+struct RequestMessage {
+    req: String,
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BookMessage {
+    ch: Option<String>,
+    rep: Option<String>,
+    tick: Option<Tick>,
+    data: Option<Tick>,
+}
+
 #[derive(Debug, Deserialize)]
-struct DepthMessage {
+struct TradeMessage {
     ch: String,
-    ts: i64, //system update time
-    tick: Tick,
+    tick: TradeTick,
 }
 
 #[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Tick {
-    seq_num: i64,
-    bids: Vec<[f64; 2]>, // [price, size]
-    asks: Vec<[f64; 2]>,
+struct TradeTick {
+    data: Vec<Trade>,
 }
 
-#[derive(Clone, Debug)]
-pub struct Ticker {
+// Trade of "market.$symbol.trade.detail" channel, its "id" is longer than u64
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trade {
+    pub trade_id: u64,
+    pub ts: u64,
+    pub amount: f64,
     pub price: f64,
-    pub qty: f64,
+    // Side of the taker, buy or sell
+    pub direction: String,
 }
 
-impl Default for Ticker {
-    fn default() -> Self {
-        Ticker {
-            price: 1.1,
-            qty: 10.01,
-        }
-    }
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tick {
+    pub seq_num: u64,
+    // Snapshot has no previous sequence number
+    pub prev_seq_num: Option<u64>,
+    #[serde(default)]
+    pub bids: Vec<[f64; 2]>, // [price, size]
+    #[serde(default)]
+    pub asks: Vec<[f64; 2]>,
 }
 
-#[derive(Clone, Debug)]
-pub struct Depth {
-    pub exchange: String,
-    pub bids: Vec<Ticker>,
-    pub asks: Vec<Ticker>,
-}
+#[cfg(test)]
+mod tests {
+
+    use super::{BookEvent, HuobiWS};
+    use std::collections::HashMap;
+
+    // Recorded incremental update and snapshot
+    const UPDATE: &str = r#"{"ch":"market.ethusdt.mbp.150","ts":1630000000000,
+        "tick":{"seqNum":101,"prevSeqNum":100,"bids":[[3000.5,0]],"asks":[[3000.6,0.5]]}}"#;
 
-impl Default for Depth {
-    fn default() -> Self {
-        Depth {
-            exchange: "StubExchange".to_string(),
-            bids: vec![Ticker::default()],
-            asks: vec![Ticker::default()],
+    const SNAPSHOT: &str = r#"{"id":"snapshot.ethusdt","rep":"market.ethusdt.mbp.150","status":"ok",
+        "data":{"seqNum":100,"bids":[[3000.5,1.2],[3000.4,2]],"asks":[[3000.7,1]]}}"#;
+
+    const TRADES: &str = r#"{"ch":"market.btcusdt.trade.detail","ts":1630994963175,
+        "tick":{"id":137005445109,"ts":1630994963173,"data":[
+        {"id":137005445109359286410323766,"ts":1630994963173,"tradeId":102523573486,
+        "amount":0.006754,"price":52648.62,"direction":"buy"}]}}"#;
+
+    fn channels(instruments: &[&str]) -> HashMap<String, String> {
+        instruments
+            .iter()
+            .map(|instrument| (HuobiWS::channel(instrument), instrument.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_update() {
+        let channels = channels(&["BTCUSDT", "ETHUSDT"]);
+
+        match HuobiWS::parse_event(&channels, UPDATE).unwrap() {
+            (instrument, BookEvent::Update(tick)) => {
+                assert_eq!(instrument, "ETHUSDT");
+                assert_eq!(tick.prev_seq_num, Some(100));
+                assert_eq!(tick.bids, vec![[3000.5, 0.]]);
+            }
+            event => panic!("Unexpected event {:?}", event),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
+    #[test]
+    fn parse_snapshot() {
+        let channels = channels(&["ETHUSDT"]);
+
+        match HuobiWS::parse_event(&channels, SNAPSHOT).unwrap() {
+            (instrument, BookEvent::Snapshot(tick)) => {
+                assert_eq!(instrument, "ETHUSDT");
+                assert_eq!(tick.seq_num, 100);
+                assert_eq!(tick.bids.len(), 2);
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+    }
 
-    use super::HuobiWS;
+    #[test]
+    fn parse_trades() {
+        let channels = ["BTCUSDT"]
+            .iter()
+            .map(|instrument| (HuobiWS::trade_channel(instrument), instrument.to_string()))
+            .collect();
+
+        let (instrument, trades) = HuobiWS::parse_trades(&channels, TRADES).unwrap();
+
+        assert_eq!(instrument, "BTCUSDT");
+        assert_eq!(trades[0].trade_id, 102523573486);
+        assert_eq!(trades[0].price, 52648.62);
+        assert_eq!(trades[0].direction, "buy");
+    }
+
+    #[test]
+    fn skip_subscription_status() {
+        let channels = channels(&["ETHUSDT"]);
+        let status =
+            r#"{"id":"id1","status":"ok","subbed":"market.ethusdt.mbp.150","ts":1630000000000}"#;
+
+        assert!(HuobiWS::parse_event(&channels, status).is_none());
+    }
 
     #[test]
     #[ignore]
     // For local testing
-    fn get_depth() {
-        let instrument = "btcusdt";
+    fn get_event() {
+        let instruments = ["btcusdt", "ethusdt"];
 
-        let mut huobi_ws = HuobiWS::connect(instrument);
+        let mut huobi_ws = HuobiWS::connect(&instruments).unwrap();
 
         loop {
-            println!("{:?}", huobi_ws.get_depth());
+            println!("{:?}", huobi_ws.get_event());
         }
     }
 }
@@ -0,0 +1,278 @@
+use super::error::KrakenError;
+use super::{
+    client::Client,
+    models::{
+        order_id_from_txid, APIResult, AddOrderResult, ExchangeApiResult, ExtendedBalance,
+        KrakenResponse, OpenOrder, OpenOrders, Transaction, WebSocketsToken,
+    },
+};
+use serde::de::DeserializeOwned;
+use serde_json;
+use std::collections::{BTreeMap, HashMap};
+use tracing::{debug, error};
+
+#[derive(Clone)]
+pub struct Account {
+    pub client: Client,
+}
+
+impl Account {
+    pub fn new(api_key: Option<String>, secret_key: Option<String>) -> Self {
+        Account {
+            client: Client::new(&api_key.unwrap(), &secret_key.unwrap()),
+        }
+    }
+
+    // Pair in Kraken notation, e.g. XBTEUR
+    pub fn limit_buy(
+        &self,
+        pair: &str,
+        amount: f64,
+        price: f64,
+        client_order_id: Option<String>,
+    ) -> ExchangeApiResult<Transaction> {
+        let mut params = BTreeMap::new();
+        params.insert("price".to_string(), price.to_string());
+
+        if let Some(id) = client_order_id {
+            params.insert("cl_ord_id".to_string(), id);
+        }
+
+        self.custom_order(pair, "buy", "limit", amount, params)
+    }
+
+    pub fn limit_sell(
+        &self,
+        pair: &str,
+        amount: f64,
+        price: f64,
+        client_order_id: Option<String>,
+    ) -> ExchangeApiResult<Transaction> {
+        let mut params = BTreeMap::new();
+        params.insert("price".to_string(), price.to_string());
+
+        if let Some(id) = client_order_id {
+            params.insert("cl_ord_id".to_string(), id);
+        }
+
+        self.custom_order(pair, "sell", "limit", amount, params)
+    }
+
+    pub fn market_buy(&self, pair: &str, amount: f64) -> ExchangeApiResult<Transaction> {
+        self.custom_order(pair, "buy", "market", amount, BTreeMap::new())
+    }
+
+    pub fn market_sell(&self, pair: &str, amount: f64) -> ExchangeApiResult<Transaction> {
+        self.custom_order(pair, "sell", "market", amount, BTreeMap::new())
+    }
+
+    // Order with its own type parameters, e.g. price2, timeinforce, expiretm and oflags
+    pub fn custom_order(
+        &self,
+        pair: &str,
+        side: &str,
+        order_type: &str,
+        amount: f64,
+        mut params: BTreeMap<String, String>,
+    ) -> ExchangeApiResult<Transaction> {
+        params.insert("pair".into(), pair.into());
+        params.insert("type".into(), side.into());
+        params.insert("ordertype".into(), order_type.into());
+        params.insert("volume".into(), amount.to_string());
+
+        match self.private::<AddOrderResult>("/private/AddOrder", params.clone()) {
+            Ok(added_order) => {
+                debug!("[Kraken API] Add order: {:?}", params);
+
+                Ok(Transaction {
+                    symbol: pair.to_string(),
+                    order_id: added_order
+                        .txid
+                        .first()
+                        .map(|txid| order_id_from_txid(txid))
+                        .unwrap_or(0),
+                })
+            }
+            Err(e) => {
+                error!("Kraken add order error {:?}", e);
+
+                Err("Kraken Add order Error")
+            }
+        }
+    }
+
+    pub fn cancel_order_with_custom_id(
+        &self,
+        pair: &str,
+        custom_order_id: &str,
+    ) -> ExchangeApiResult<Transaction> {
+        let mut params = BTreeMap::new();
+        params.insert("cl_ord_id".to_string(), custom_order_id.to_string());
+
+        match self.private::<serde_json::Value>("/private/CancelOrder", params) {
+            Ok(_) => {
+                debug!(
+                    "[Kraken] Order was canceled: pair {}, {}",
+                    pair, custom_order_id
+                );
+
+                Ok(Transaction {
+                    symbol: pair.to_string(),
+                    order_id: 0,
+                })
+            }
+            Err(_e) => Err("Kraken Request error"),
+        }
+    }
+
+    // <Transaction id, Open order> of all pairs
+    pub fn get_open_orders(&self) -> APIResult<HashMap<String, OpenOrder>> {
+        let open_orders = self.private::<OpenOrders>("/private/OpenOrders", BTreeMap::new())?;
+
+        debug!("[Kraken] Get open orders {:?}", open_orders);
+
+        Ok(open_orders.open)
+    }
+
+    // <Asset in Kraken notation, Balance>
+    pub fn get_balances(&self) -> APIResult<HashMap<String, ExtendedBalance>> {
+        let balances = self.private("/private/BalanceEx", BTreeMap::new())?;
+
+        debug!("[Kraken] Get balances result: {:?} ", balances);
+
+        Ok(balances)
+    }
+
+    // Token of the private websocket, it has to be used within 15 minutes
+    pub fn get_websockets_token(&self) -> APIResult<String> {
+        let token: WebSocketsToken =
+            self.private("/private/GetWebSocketsToken", BTreeMap::new())?;
+
+        Ok(token.token)
+    }
+
+    fn private<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        params: BTreeMap<String, String>,
+    ) -> APIResult<T> {
+        let data = self.client.post_signed(endpoint, params)?;
+
+        let response: KrakenResponse<T> = serde_json::from_str(data.as_str())?;
+
+        response
+            .result
+            .ok_or_else(|| KrakenError::ApiError("Empty result".to_string()).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc::{channel, Receiver};
+    use std::thread;
+
+    // Local mock server answers one request with the recorded body
+    fn mock_server(response_body: &'static str) -> (String, Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let (request_sender, request_receiver) = channel();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+
+            // Read headers and body of the request
+            loop {
+                let read = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..read]);
+
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(headers_end) = text.find("\r\n\r\n") {
+                    let content_length = text
+                        .lines()
+                        .find(|line| line.to_lowercase().starts_with("content-length:"))
+                        .and_then(|line| line[15..].trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+
+                    if request.len() >= headers_end + 4 + content_length {
+                        break;
+                    }
+                }
+            }
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                response_body.len(),
+                response_body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+
+            request_sender
+                .send(String::from_utf8_lossy(&request).to_string())
+                .unwrap();
+        });
+
+        (address, request_receiver)
+    }
+
+    fn account(host: &str) -> Account {
+        Account {
+            // Secret key is base64 encoded
+            client: Client::with_host("API_KEY", "U0VDUkVUX0tFWQ==", host),
+        }
+    }
+
+    #[test]
+    fn limit_buy_mock_server() {
+        let (host, request) = mock_server(
+            r#"{"error":[],"result":{"descr":{"order":"buy 1.25000000 XBTEUR @ limit 30010.0"},"txid":["OQCLML-BW3P3-BUCMWZ"]}}"#,
+        );
+
+        let transaction = account(&host)
+            .limit_buy("XBTEUR", 1.25, 30010., Some("Custom123".to_string()))
+            .unwrap();
+
+        assert_eq!(
+            transaction.order_id,
+            order_id_from_txid("OQCLML-BW3P3-BUCMWZ")
+        );
+
+        let request = request.recv().unwrap();
+
+        assert!(request.starts_with("POST /0/private/AddOrder "));
+        assert!(request.to_lowercase().contains("api-sign"));
+        assert!(request.contains("cl_ord_id=Custom123"));
+        assert!(request.contains("ordertype=limit"));
+        assert!(request.contains("pair=XBTEUR"));
+        assert!(request.contains("price=30010"));
+    }
+
+    #[test]
+    fn cancel_order_mock_server() {
+        let (host, request) = mock_server(r#"{"error":[],"result":{"count":1}}"#);
+
+        assert!(account(&host)
+            .cancel_order_with_custom_id("XBTEUR", "Custom123")
+            .is_ok());
+
+        let request = request.recv().unwrap();
+
+        assert!(request.starts_with("POST /0/private/CancelOrder "));
+        assert!(request.contains("cl_ord_id=Custom123"));
+    }
+
+    #[test]
+    fn api_error_mock_server() {
+        let (host, _request) = mock_server(r#"{"error":["EOrder:Insufficient funds"]}"#);
+
+        assert!(account(&host)
+            .limit_sell("XBTEUR", 1., 30000., None)
+            .is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use super::error::*;
+use super::models::{APIResult, KrakenResponse};
+use data_encoding::BASE64;
+use reqwest;
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, USER_AGENT};
+use reqwest::Method;
+use ring::{digest, hmac};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+static KRAKEN_API_HOST: &'static str = "https://api.kraken.com";
+
+static KRAKEN_API_PATH: &'static str = "/0";
+
+// Nonce of the API key has to increase with every private request
+static LAST_NONCE: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone)]
+pub struct Client {
+    api_key: String,
+    secret_key: String,
+    host: String,
+}
+
+impl Client {
+    pub fn new(api_key: &str, secret_key: &str) -> Self {
+        Client::with_host(api_key, secret_key, KRAKEN_API_HOST)
+    }
+
+    // Host can be changed to a local mock server
+    pub fn with_host(api_key: &str, secret_key: &str, host: &str) -> Self {
+        Client {
+            api_key: api_key.into(),
+            secret_key: secret_key.into(),
+            host: host.into(),
+        }
+    }
+
+    pub fn get(&self, endpoint: &str, parameters: BTreeMap<String, String>) -> APIResult<String> {
+        let mut path = format!("{}{}", KRAKEN_API_PATH, endpoint);
+
+        if !parameters.is_empty() {
+            path = format!("{}?{}", path, build_query_string(parameters));
+        }
+
+        self.request(Method::GET, &path, String::new(), build_headers()?)
+    }
+
+    // Private endpoints are POST requests with form encoded parameters and nonce
+    pub fn post_signed(
+        &self,
+        endpoint: &str,
+        mut parameters: BTreeMap<String, String>,
+    ) -> APIResult<String> {
+        let path = format!("{}{}", KRAKEN_API_PATH, endpoint);
+
+        let nonce = get_nonce();
+        parameters.insert("nonce".into(), nonce.to_string());
+
+        let post_data = build_query_string(parameters);
+        let signature =
+            sign_hmac_sha512_base64(&self.secret_key, &path, &nonce.to_string(), &post_data)?;
+
+        let mut headers = build_headers()?;
+        headers.insert("API-Key", HeaderValue::from_str(&self.api_key)?);
+        headers.insert("API-Sign", HeaderValue::from_str(&signature)?);
+
+        self.request(Method::POST, &path, post_data, headers)
+    }
+
+    fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: String,
+        headers: HeaderMap,
+    ) -> APIResult<String> {
+        let request = format!("{}{}", self.host, path);
+
+        debug!("[Kraken] Make {} request: {:?} {}", method, request, body);
+
+        let client = reqwest::blocking::Client::new();
+        let response = client
+            .request(method, request.as_str())
+            .headers(headers)
+            .body(body)
+            .send()?;
+
+        let body = response.text()?;
+
+        debug!("[Kraken] Responce body: {:?}", body);
+
+        // Every response has the error list, it's empty on success
+        let response: KrakenResponse<serde_json::Value> = serde_json::from_str(body.as_str())?;
+
+        if !response.error.is_empty() {
+            return Err(Box::new(KrakenError::ApiError(response.error.join(", "))));
+        }
+
+        Ok(body)
+    }
+}
+
+// Kraken signs "path + SHA256(nonce + post data)" by the decoded secret
+pub fn sign_hmac_sha512_base64(
+    secret: &str,
+    path: &str,
+    nonce: &str,
+    post_data: &str,
+) -> APIResult<String> {
+    let secret = BASE64.decode(secret.as_bytes())?;
+
+    let hashed_data = digest::digest(
+        &digest::SHA256,
+        format!("{}{}", nonce, post_data).as_bytes(),
+    );
+
+    let mut message = path.as_bytes().to_vec();
+    message.extend_from_slice(hashed_data.as_ref());
+
+    let signed_key = hmac::SigningKey::new(&digest::SHA512, &secret);
+    let signature = hmac::sign(&signed_key, &message);
+
+    Ok(BASE64.encode(signature.as_ref()))
+}
+
+pub fn build_query_string(parameters: BTreeMap<String, String>) -> String {
+    parameters
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, percent_encode(&value)))
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+pub fn percent_encode(source: &str) -> String {
+    use percent_encoding::{utf8_percent_encode, USERINFO_ENCODE_SET};
+
+    utf8_percent_encode(&source, USERINFO_ENCODE_SET).to_string()
+}
+
+// Milliseconds, it's increased if requests are sent in the same millisecond
+pub fn get_nonce() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    let mut last_nonce = LAST_NONCE.load(Ordering::SeqCst);
+
+    loop {
+        let nonce = now.max(last_nonce + 1);
+
+        match LAST_NONCE.compare_exchange(last_nonce, nonce, Ordering::SeqCst, Ordering::SeqCst) {
+            Ok(_) => return nonce,
+            Err(current) => last_nonce = current,
+        }
+    }
+}
+
+pub fn build_headers() -> APIResult<HeaderMap> {
+    let mut custom_headers = HeaderMap::new();
+
+    custom_headers.insert(USER_AGENT, HeaderValue::from_static("rs"));
+    custom_headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/x-www-form-urlencoded"),
+    );
+
+    Ok(custom_headers)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // Example from Kraken API documentation
+    const SECRET: &str =
+        "kQH5HW/8p1uGOVjbgWA7FunAmGO8lsSUXNsu3eow76sz84Q18fWxnyRzBHCd3pd5nE9qa99HAZtuZuj6F1huXg==";
+
+    #[test]
+    fn sign_add_order() {
+        let post_data =
+            "nonce=1616492376594&ordertype=limit&pair=XBTUSD&price=37500&type=buy&volume=1.25";
+
+        assert_eq!(
+            sign_hmac_sha512_base64(SECRET, "/0/private/AddOrder", "1616492376594", post_data)
+                .unwrap(),
+            "4/dpxb3iT4tp/ZCVEwSnEsLxx0bqyhLpdfOpc6fn7OR8+UClSV5n9E6aSS8MPtnRfp32bAb0nmbRn6H8ndwLUQ=="
+        );
+    }
+
+    #[test]
+    fn increasing_nonce() {
+        let first = get_nonce();
+        let second = get_nonce();
+
+        assert!(second > first);
+    }
+}
@@ -0,0 +1,21 @@
+use core::fmt;
+use std::error::Error;
+
+#[derive(Debug, Clone)]
+pub enum KrakenError {
+    ApiError(String),
+}
+
+impl fmt::Display for KrakenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.clone() {
+            KrakenError::ApiError(why) => write!(f, "ApiError: {}", why),
+        }
+    }
+}
+
+impl Error for KrakenError {
+    fn description(&self) -> &str {
+        "Kraken Error"
+    }
+}
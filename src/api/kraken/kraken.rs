@@ -0,0 +1,84 @@
+use super::client::Client;
+use super::models::*;
+use serde_json;
+use std::collections::{BTreeMap, HashMap};
+use tracing::info;
+
+pub use super::account::Account;
+
+pub struct KrakenApi;
+
+impl KrakenApi {
+    /// Kraken API "/public/AssetPairs"
+    pub fn asset_pairs() -> APIResult<HashMap<String, AssetPair>> {
+        info!("[Kraken] get asset pairs");
+
+        let client = Client::new("", "");
+        let data = client.get("/public/AssetPairs", BTreeMap::new())?;
+
+        let response: KrakenResponse<HashMap<String, AssetPair>> =
+            serde_json::from_str(data.as_str())?;
+
+        Ok(response.result.unwrap_or_default())
+    }
+
+    /// Kraken API "/public/Time", unix milliseconds with seconds precision
+    pub fn server_time() -> APIResult<u64> {
+        let client = Client::new("", "");
+        let data = client.get("/public/Time", BTreeMap::new())?;
+
+        let response: KrakenResponse<ServerTime> = serde_json::from_str(data.as_str())?;
+
+        match response.result {
+            Some(server_time) => Ok(server_time.unixtime * 1000),
+            None => Err(format!("Kraken server time error {:?}", response.error).into()),
+        }
+    }
+
+    /// Kraken API "/public/Depth", pair in Kraken notation, e.g. XBTEUR
+    pub fn depth(pair: &str, count: u32) -> APIResult<OrderBook> {
+        let mut params = BTreeMap::new();
+        params.insert("pair".to_string(), pair.to_string());
+        params.insert("count".to_string(), count.to_string());
+
+        let client = Client::new("", "");
+        let data = client.get("/public/Depth", params)?;
+
+        // Result is keyed by the pair name which may differ from the requested one
+        let response: KrakenResponse<HashMap<String, OrderBook>> =
+            serde_json::from_str(data.as_str())?;
+
+        Ok(response
+            .result
+            .and_then(|books| books.into_iter().next())
+            .map(|(_, book)| book)
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::KrakenApi;
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn asset_pairs() {
+        println!("{:#?}", KrakenApi::asset_pairs().unwrap());
+    }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn depth() {
+        println!("{:#?}", KrakenApi::depth("XBTEUR", 10).unwrap());
+    }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn server_time() {
+        println!("{}", KrakenApi::server_time().unwrap());
+    }
+}
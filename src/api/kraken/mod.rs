@@ -0,0 +1,10 @@
+pub mod kraken;
+pub mod models;
+pub mod websocket_account;
+pub mod websocket_data;
+
+mod account;
+mod client;
+mod error;
+
+pub use kraken::{Account, KrakenApi};
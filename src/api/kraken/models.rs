@@ -0,0 +1,346 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub type APIResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+pub type ExchangeApiResult<T> = Result<T, &'static str>;
+
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    pub symbol: String,
+    pub order_id: u64,
+}
+
+// Every REST response, e.g. {"error":["EOrder:Insufficient funds"]}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KrakenResponse<T> {
+    pub error: Vec<String>,
+    pub result: Option<T>,
+}
+
+// "/public/Time" result
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ServerTime {
+    // Unix seconds
+    pub unixtime: u64,
+    pub rfc1123: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AssetPair {
+    // XBTEUR
+    pub altname: String,
+    // XBT/EUR, name of the pair in websocket subscriptions
+    pub wsname: Option<String>,
+    // XXBT
+    pub base: String,
+    // ZEUR
+    pub quote: String,
+    pub pair_decimals: u8,
+    pub lot_decimals: u8,
+    pub ordermin: Option<String>,
+    pub costmin: Option<String>,
+}
+
+impl AssetPair {
+    // BTCEUR
+    pub fn symbol(&self) -> String {
+        format!(
+            "{}{}",
+            platform_asset(&self.base),
+            platform_asset(&self.quote)
+        )
+    }
+}
+
+// Levels are [price, volume, timestamp]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct OrderBook {
+    pub asks: Vec<(String, String, u64)>,
+    pub bids: Vec<(String, String, u64)>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AddOrderResult {
+    pub txid: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OpenOrders {
+    pub open: HashMap<String, OpenOrder>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OpenOrder {
+    pub status: String,
+    pub cl_ord_id: Option<String>,
+    pub descr: OrderDescription,
+    pub vol: String,
+    pub vol_exec: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrderDescription {
+    // XBTEUR
+    pub pair: String,
+    // buy or sell
+    #[serde(rename = "type")]
+    pub side: String,
+    pub ordertype: String,
+    pub price: String,
+}
+
+// Balance of the asset with the amount held by open orders
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExtendedBalance {
+    pub balance: String,
+    pub hold_trade: Option<String>,
+}
+
+impl ExtendedBalance {
+    pub fn available(&self) -> f64 {
+        let balance = self.balance.parse::<f64>().unwrap_or(0.);
+        let hold_trade = self
+            .hold_trade
+            .as_ref()
+            .and_then(|hold_trade| hold_trade.parse::<f64>().ok())
+            .unwrap_or(0.);
+
+        balance - hold_trade
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebSocketsToken {
+    pub token: String,
+    pub expires: u64,
+}
+
+// Private websocket trade, pair in websocket notation, e.g. XBT/EUR
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OwnTrade {
+    pub ordertxid: String,
+    pub pair: String,
+    #[serde(rename = "type")]
+    pub side: String,
+    pub price: String,
+    pub vol: String,
+    pub fee: String,
+}
+
+// Private websocket order, status changes carry only the changed fields
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrderUpdate {
+    pub status: Option<String>,
+    pub cl_ord_id: Option<String>,
+    pub descr: Option<OrderUpdateDescription>,
+    pub vol: Option<String>,
+    pub vol_exec: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrderUpdateDescription {
+    pub pair: String,
+}
+
+// Kraken names Bitcoin XBT and Dogecoin XDG
+pub fn kraken_asset(asset: &str) -> String {
+    match asset {
+        "BTC" => "XBT".to_string(),
+        "DOGE" => "XDG".to_string(),
+        _ => asset.to_string(),
+    }
+}
+
+// Legacy asset codes have X prefix for crypto and Z prefix for fiat, e.g. XXBT and ZEUR.
+// Balance codes have suffixes for staked and opt-in rewards assets, e.g. DOT.S
+pub fn platform_asset(asset: &str) -> String {
+    let asset = asset.split('.').next().unwrap_or(asset);
+
+    let asset = if asset.len() == 4 && (asset.starts_with('X') || asset.starts_with('Z')) {
+        &asset[1..]
+    } else {
+        asset
+    };
+
+    match asset {
+        "XBT" => "BTC".to_string(),
+        "XDG" => "DOGE".to_string(),
+        _ => asset.to_string(),
+    }
+}
+
+// Kraken order ids are strings like OQCLML-BW3P3-BUCMWZ, platform keeps numeric ids
+pub fn order_id_from_txid(txid: &str) -> u64 {
+    // FNV-1a
+    txid.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_asset_pairs() {
+        let asset_pairs_json = r#"{
+            "error": [],
+            "result": {
+                "XXBTZEUR": {
+                    "altname": "XBTEUR",
+                    "wsname": "XBT/EUR",
+                    "aclass_base": "currency",
+                    "base": "XXBT",
+                    "aclass_quote": "currency",
+                    "quote": "ZEUR",
+                    "lot": "unit",
+                    "cost_decimals": 5,
+                    "pair_decimals": 1,
+                    "lot_decimals": 8,
+                    "lot_multiplier": 1,
+                    "fee_volume_currency": "ZUSD",
+                    "margin_call": 80,
+                    "margin_stop": 40,
+                    "ordermin": "0.0001",
+                    "costmin": "0.5",
+                    "tick_size": "0.1",
+                    "status": "online"
+                }
+            }
+        }"#;
+
+        let response: KrakenResponse<HashMap<String, AssetPair>> =
+            serde_json::from_str(asset_pairs_json).unwrap();
+        let asset_pair = &response.result.unwrap()["XXBTZEUR"];
+
+        assert_eq!(asset_pair.symbol(), "BTCEUR");
+        assert_eq!(asset_pair.wsname, Some("XBT/EUR".to_string()));
+        assert_eq!(asset_pair.pair_decimals, 1);
+        assert_eq!(asset_pair.ordermin, Some("0.0001".to_string()));
+    }
+
+    #[test]
+    fn parse_order_book() {
+        let depth_json = r#"{
+            "error": [],
+            "result": {
+                "XXBTZEUR": {
+                    "asks": [["30010.10000", "0.250", 1688671834], ["30012.00000", "1.000", 1688671830]],
+                    "bids": [["30010.00000", "1.500", 1688671834]]
+                }
+            }
+        }"#;
+
+        let response: KrakenResponse<HashMap<String, OrderBook>> =
+            serde_json::from_str(depth_json).unwrap();
+        let order_book = &response.result.unwrap()["XXBTZEUR"];
+
+        assert_eq!(order_book.asks.len(), 2);
+        assert_eq!(order_book.bids[0].0, "30010.00000");
+        assert_eq!(order_book.bids[0].1, "1.500");
+    }
+
+    #[test]
+    fn parse_open_orders() {
+        let open_orders_json = r#"{
+            "error": [],
+            "result": {
+                "open": {
+                    "OQCLML-BW3P3-BUCMWZ": {
+                        "refid": null,
+                        "userref": 0,
+                        "cl_ord_id": "Custom123",
+                        "status": "open",
+                        "opentm": 1688666559.8974,
+                        "starttm": 0,
+                        "expiretm": 0,
+                        "descr": {
+                            "pair": "XBTEUR",
+                            "type": "buy",
+                            "ordertype": "limit",
+                            "price": "30010.0",
+                            "price2": "0",
+                            "leverage": "none",
+                            "order": "buy 1.25000000 XBTEUR @ limit 30010.0",
+                            "close": ""
+                        },
+                        "vol": "1.25000000",
+                        "vol_exec": "0.37500000",
+                        "cost": "11253.7",
+                        "fee": "0.00000",
+                        "price": "30010.0",
+                        "stopprice": "0.00000",
+                        "limitprice": "0.00000",
+                        "misc": "",
+                        "oflags": "fciq"
+                    }
+                }
+            }
+        }"#;
+
+        let response: KrakenResponse<OpenOrders> = serde_json::from_str(open_orders_json).unwrap();
+        let open_order = &response.result.unwrap().open["OQCLML-BW3P3-BUCMWZ"];
+
+        assert_eq!(open_order.cl_ord_id, Some("Custom123".to_string()));
+        assert_eq!(open_order.descr.pair, "XBTEUR");
+        assert_eq!(open_order.descr.side, "buy");
+        assert_eq!(open_order.vol_exec, "0.37500000");
+    }
+
+    #[test]
+    fn parse_error() {
+        let error_json = r#"{"error":["EGeneral:Invalid arguments"]}"#;
+
+        let response: KrakenResponse<serde_json::Value> = serde_json::from_str(error_json).unwrap();
+
+        assert_eq!(response.error, vec!["EGeneral:Invalid arguments"]);
+        assert!(response.result.is_none());
+    }
+
+    #[test]
+    fn parse_balances() {
+        let balances_json = r#"{
+            "error": [],
+            "result": {
+                "XXBT": {"balance": "1.2500000000", "hold_trade": "0.2500000000"},
+                "ZEUR": {"balance": "1500.0000", "hold_trade": "0.0000"},
+                "DOT.S": {"balance": "10.0000000000"}
+            }
+        }"#;
+
+        let response: KrakenResponse<HashMap<String, ExtendedBalance>> =
+            serde_json::from_str(balances_json).unwrap();
+        let balances = response.result.unwrap();
+
+        assert_eq!(balances["XXBT"].available(), 1.);
+        assert_eq!(balances["ZEUR"].available(), 1500.);
+        assert_eq!(balances["DOT.S"].available(), 10.);
+    }
+
+    #[test]
+    fn asset_names() {
+        assert_eq!(kraken_asset("BTC"), "XBT");
+        assert_eq!(kraken_asset("EUR"), "EUR");
+
+        assert_eq!(platform_asset("XXBT"), "BTC");
+        assert_eq!(platform_asset("ZEUR"), "EUR");
+        assert_eq!(platform_asset("XXDG"), "DOGE");
+        assert_eq!(platform_asset("XBT"), "BTC");
+        assert_eq!(platform_asset("USDT"), "USDT");
+        assert_eq!(platform_asset("DOT.S"), "DOT");
+    }
+
+    #[test]
+    fn order_id() {
+        assert_eq!(
+            order_id_from_txid("OQCLML-BW3P3-BUCMWZ"),
+            order_id_from_txid("OQCLML-BW3P3-BUCMWZ")
+        );
+        assert_ne!(
+            order_id_from_txid("OQCLML-BW3P3-BUCMWZ"),
+            order_id_from_txid("OQCLML-BW3P3-BUCMWY")
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use super::models::*;
+use super::websocket_data::KRAKEN_PING_INTERVAL;
+use crate::api::websocket::{self, Heartbeat};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{debug, info};
+use tungstenite::client::AutoStream;
+use tungstenite::handshake::client::Response;
+use tungstenite::protocol::WebSocket;
+use tungstenite::{connect, Message};
+use url::Url;
+
+static WEBSOCKET_URL: &'static str = "wss://ws-auth.kraken.com";
+
+// Updates are keyed by the order transaction id
+pub enum WebsocketEvent {
+    OwnTrades(Vec<(String, OwnTrade)>),
+    OpenOrders(Vec<(String, OrderUpdate)>),
+}
+
+pub struct WebSockets<'a> {
+    pub socket: Option<(WebSocket<AutoStream>, Response)>,
+    handler: Box<dyn FnMut(WebsocketEvent) -> APIResult<()> + 'a>,
+}
+
+impl<'a> WebSockets<'a> {
+    pub fn new<Callback>(handler: Callback) -> WebSockets<'a>
+    where
+        Callback: FnMut(WebsocketEvent) -> APIResult<()> + 'a,
+    {
+        WebSockets {
+            socket: None,
+            handler: Box::new(handler),
+        }
+    }
+
+    // Token is received by REST request, trades snapshot isn't needed after reconnect
+    pub fn connect_auth(&mut self, token: &str) -> APIResult<()> {
+        let url = Url::parse(WEBSOCKET_URL)?;
+
+        match connect(url) {
+            Ok(answer) => {
+                websocket::set_read_timeout(&answer.0, KRAKEN_PING_INTERVAL)?;
+
+                self.socket = Some(answer);
+
+                let subscribe_messages = [
+                    json!({
+                        "event": "subscribe",
+                        "subscription": {"name": "ownTrades", "token": token, "snapshot": false},
+                    }),
+                    json!({
+                        "event": "subscribe",
+                        "subscription": {"name": "openOrders", "token": token},
+                    }),
+                ];
+
+                if let Some(ref mut socket) = self.socket {
+                    for subscribe_message in subscribe_messages.iter() {
+                        socket
+                            .0
+                            .write_message(Message::Text(subscribe_message.to_string()))?;
+                        debug!(
+                            "[Kraken] Write message {}",
+                            subscribe_message["subscription"]["name"]
+                        );
+                    }
+                };
+
+                Ok(())
+            }
+            Err(e) => {
+                info!("Error during handshake {}", e);
+                Err(Box::new(e))
+            }
+        }
+    }
+
+    pub fn disconnect(&mut self) -> APIResult<()> {
+        if let Some(ref mut socket) = self.socket {
+            socket.0.close(None)?;
+            Ok(())
+        } else {
+            info!("Not able to close the connection");
+            Ok(())
+        }
+    }
+
+    pub fn event_loop(&mut self, running: &AtomicBool) -> APIResult<()> {
+        let mut heartbeat = Heartbeat::default();
+
+        while running.load(Ordering::Relaxed) {
+            if let Some(ref mut socket) = self.socket {
+                let message = match socket.0.read_message() {
+                    Ok(message) => message,
+                    Err(e) if websocket::is_timeout(&e) => {
+                        heartbeat.timeout()?;
+                        socket
+                            .0
+                            .write_message(Message::Text(json!({"event": "ping"}).to_string()))?;
+                        continue;
+                    }
+                    Err(e) => return Err(Box::new(e)),
+                };
+
+                heartbeat.received();
+
+                match message {
+                    Message::Text(text) => {
+                        if let Some(event) = Self::parse_event(&text) {
+                            (self.handler)(event)?;
+                        } else {
+                            debug!("[Kraken] WebSockets message {}", text);
+                        }
+                    }
+
+                    Message::Ping(_bin) | Message::Pong(_bin) | Message::Binary(_bin) => {
+                        debug!("[Kraken] WebSockets binary message");
+                    }
+
+                    Message::Close(e) => {
+                        info!("Disconnected {:?}", e);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Private messages are [[{txid: data}, ...], channel name, {"sequence": n}]
+    fn parse_event(text: &str) -> Option<WebsocketEvent> {
+        let message = match serde_json::from_str::<Value>(text) {
+            Ok(Value::Array(message)) if message.len() >= 2 => message,
+            _ => return None,
+        };
+
+        match message[1].as_str()? {
+            "ownTrades" => Some(WebsocketEvent::OwnTrades(Self::parse_entries(&message[0]))),
+            "openOrders" => Some(WebsocketEvent::OpenOrders(Self::parse_entries(&message[0]))),
+            _ => None,
+        }
+    }
+
+    fn parse_entries<T: DeserializeOwned>(data: &Value) -> Vec<(String, T)> {
+        data.as_array()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(|entry| entry.as_object())
+                    .flat_map(|entry| entry.iter())
+                    .filter_map(|(txid, value)| {
+                        serde_json::from_value::<T>(value.clone())
+                            .ok()
+                            .map(|value| (txid.clone(), value))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{WebSockets, WebsocketEvent};
+
+    // Recorded trade of a partially filled limit order
+    const OWN_TRADES: &str = r#"[[{"TDLH43-DVQXD-2KHVYY":{
+        "cost":"1000.00000",
+        "fee":"1.60000",
+        "margin":"0.00000",
+        "ordertxid":"OQCLML-BW3P3-BUCMWZ",
+        "ordertype":"limit",
+        "pair":"XBT/EUR",
+        "postxid":"TKH2SE-M7IF5-CFI7LT",
+        "price":"30000.00000",
+        "time":"1560516023.070651",
+        "type":"sell",
+        "vol":"0.03333333"}}],"ownTrades",{"sequence":2}]"#;
+
+    // Recorded new order and status change of another order
+    const OPEN_ORDERS: &str = r#"[[
+        {"OQCLML-BW3P3-BUCMWZ":{
+            "avg_price":"0.00000",
+            "cl_ord_id":"Custom123",
+            "cost":"0.00000",
+            "descr":{"close":null,"leverage":null,"order":"sell 0.10000000 XBT/EUR @ limit 30000.00000","ordertype":"limit","pair":"XBT/EUR","price":"30000.00000","price2":"0.00000","type":"sell"},
+            "expiretm":null,
+            "fee":"0.00000",
+            "limitprice":"0.00000",
+            "misc":"",
+            "oflags":"fciq",
+            "opentm":"1688666559.897403",
+            "status":"pending",
+            "userref":0,
+            "vol":"0.10000000",
+            "vol_exec":"0.00000000"}},
+        {"OGTT3Y-C6I3P-XRI6HX":{"status":"canceled","cost":"0.00000","vol_exec":"0.00000000","fee":"0.00000","avg_price":"0.00000","userref":0,"cancel_reason":"User requested"}}
+        ],"openOrders",{"sequence":3}]"#;
+
+    #[test]
+    fn parse_own_trades() {
+        match WebSockets::parse_event(OWN_TRADES) {
+            Some(WebsocketEvent::OwnTrades(trades)) => {
+                assert_eq!(trades.len(), 1);
+                assert_eq!(trades[0].0, "TDLH43-DVQXD-2KHVYY");
+                assert_eq!(trades[0].1.ordertxid, "OQCLML-BW3P3-BUCMWZ");
+                assert_eq!(trades[0].1.side, "sell");
+                assert_eq!(trades[0].1.fee, "1.60000");
+            }
+            _ => panic!("Trades aren't parsed"),
+        }
+    }
+
+    #[test]
+    fn parse_open_orders() {
+        match WebSockets::parse_event(OPEN_ORDERS) {
+            Some(WebsocketEvent::OpenOrders(orders)) => {
+                assert_eq!(orders.len(), 2);
+                assert_eq!(orders[0].1.cl_ord_id, Some("Custom123".to_string()));
+                assert_eq!(orders[0].1.descr.as_ref().unwrap().pair, "XBT/EUR");
+                assert_eq!(orders[1].0, "OGTT3Y-C6I3P-XRI6HX");
+                assert_eq!(orders[1].1.status, Some("canceled".to_string()));
+            }
+            _ => panic!("Orders aren't parsed"),
+        }
+    }
+
+    #[test]
+    fn skip_events() {
+        let subscription = r#"{"channelName":"ownTrades","event":"subscriptionStatus","status":"subscribed","subscription":{"name":"ownTrades"}}"#;
+
+        assert!(WebSockets::parse_event(subscription).is_none());
+        assert!(WebSockets::parse_event(r#"{"event":"heartbeat"}"#).is_none());
+    }
+}
@@ -0,0 +1,387 @@
+use crate::api::websocket::{self, Heartbeat};
+use flate2::Crc;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, error};
+use tungstenite::{client::AutoStream, connect, Message, WebSocket};
+use url::Url;
+
+const WS_KRAKEN_URL: &str = "wss://ws.kraken.com";
+
+const KRAKEN_CONNECT_ERROR: &str = "Can't connect to Kraken websocket";
+
+const KRAKEN_READ_MESSAGE_ERROR: &str = "Error reading Kraken websocket message";
+
+const KRAKEN_WRITE_MESSAGE_ERROR: &str = "Error writing Kraken websocket message";
+
+const KRAKEN_CHECKSUM_ERROR: &str = "Kraken book checksum mismatch";
+
+// Kraken sends heartbeat every second without updates, client pings after that time
+pub const KRAKEN_PING_INTERVAL: Duration = Duration::from_secs(5);
+
+// Subscribed book depth, checksum is calculated over 10 levels
+const KRAKEN_DEPTH_LEVELS: usize = 10;
+
+#[derive(Debug)]
+pub struct KrakenWS {
+    socket: WebSocket<AutoStream>,
+    // <pair, book>
+    books: HashMap<String, Book>,
+    heartbeat: Heartbeat,
+}
+
+impl KrakenWS {
+    // Pairs in websocket notation, e.g. XBT/EUR
+    pub fn connect(pairs: &[&str]) -> Result<Self, &'static str> {
+        debug!("Connecting to Kraken WebSocket");
+
+        let (mut socket, _response) = match connect(Url::parse(WS_KRAKEN_URL).unwrap()) {
+            Ok(connection) => connection,
+            Err(e) => {
+                error!("[Kraken WS] {}: {}", KRAKEN_CONNECT_ERROR, e);
+                return Err(KRAKEN_CONNECT_ERROR);
+            }
+        };
+
+        websocket::set_read_timeout(&socket, KRAKEN_PING_INTERVAL)?;
+
+        let sub_msg = json!({
+            "event": "subscribe",
+            "pair": pairs,
+            "subscription": {"name": "book", "depth": KRAKEN_DEPTH_LEVELS},
+        });
+
+        let mut kraken_ws = KrakenWS {
+            socket,
+            books: HashMap::new(),
+            heartbeat: Heartbeat::default(),
+        };
+
+        // Send subscription message
+        kraken_ws.write_message(sub_msg.to_string())?;
+
+        Ok(kraken_ws)
+    }
+
+    // It returns updated pair with its depth.
+    // Checksum mismatch returns error, so the book is received again after reconnect.
+    pub fn get_depth(&mut self) -> Result<(String, Depth), &'static str> {
+        // Loop until get depth data
+        loop {
+            let msg = match self.socket.read_message() {
+                Ok(msg) => msg,
+                Err(e) if websocket::is_timeout(&e) => {
+                    self.heartbeat.timeout()?;
+                    self.write_message(json!({"event": "ping"}).to_string())?;
+                    continue;
+                }
+                Err(e) => {
+                    error!("[Kraken WS] {}: {}", KRAKEN_READ_MESSAGE_ERROR, e);
+                    return Err(KRAKEN_READ_MESSAGE_ERROR);
+                }
+            };
+
+            self.heartbeat.received();
+
+            let text = match msg {
+                Message::Text(text) => text,
+                _ => continue,
+            };
+
+            match Self::apply(&mut self.books, &text)? {
+                Some(pair) => {
+                    let depth = self.books[&pair].to_depth();
+
+                    return Ok((pair, depth));
+                }
+                // Heartbeats, subscription statuses and pongs
+                None => debug!("[Kraken WS] Skip message {}", text),
+            }
+        }
+    }
+
+    fn write_message(&mut self, text: String) -> Result<(), &'static str> {
+        match self.socket.write_message(Message::Text(text)) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                error!("[Kraken WS] {}: {}", KRAKEN_WRITE_MESSAGE_ERROR, e);
+                Err(KRAKEN_WRITE_MESSAGE_ERROR)
+            }
+        }
+    }
+
+    // Book messages are arrays [channel id, data..., channel name, pair],
+    // update has separate objects for asks and bids, the last one has the checksum.
+    fn apply(
+        books: &mut HashMap<String, Book>,
+        text: &str,
+    ) -> Result<Option<String>, &'static str> {
+        let message = match serde_json::from_str::<Value>(text) {
+            Ok(Value::Array(message)) if message.len() >= 4 => message,
+            _ => return Ok(None),
+        };
+
+        let pair = match (&message[message.len() - 2], &message[message.len() - 1]) {
+            (Value::String(channel), Value::String(pair)) if channel.starts_with("book") => {
+                pair.clone()
+            }
+            _ => return Ok(None),
+        };
+
+        let book = books.entry(pair.clone()).or_insert_with(Book::default);
+        let mut checksum = None;
+
+        for data in &message[1..message.len() - 2] {
+            if let (Some(asks), Some(bids)) = (data.get("as"), data.get("bs")) {
+                book.snapshot(Book::levels(asks), Book::levels(bids));
+                continue;
+            }
+
+            if let Some(asks) = data.get("a") {
+                book.update_asks(Book::levels(asks));
+            }
+            if let Some(bids) = data.get("b") {
+                book.update_bids(Book::levels(bids));
+            }
+            if let Some(Value::String(value)) = data.get("c") {
+                checksum = value.parse::<u32>().ok();
+            }
+        }
+
+        match checksum {
+            Some(checksum) if checksum != book.checksum() => {
+                error!("[Kraken WS] {} {}", KRAKEN_CHECKSUM_ERROR, pair);
+                books.remove(&pair);
+
+                Err(KRAKEN_CHECKSUM_ERROR)
+            }
+            _ => Ok(Some(pair)),
+        }
+    }
+}
+
+// Price and volume are kept as received, the checksum is calculated from the strings
+#[derive(Debug, Clone, PartialEq)]
+struct Level {
+    price: String,
+    volume: String,
+}
+
+impl Level {
+    fn price(&self) -> f64 {
+        self.price.parse().unwrap_or(0.)
+    }
+
+    fn volume(&self) -> f64 {
+        self.volume.parse().unwrap_or(0.)
+    }
+}
+
+// Local copy of Kraken book, asks are sorted ascending and bids descending
+#[derive(Debug, Default)]
+pub struct Book {
+    asks: Vec<Level>,
+    bids: Vec<Level>,
+}
+
+impl Book {
+    // Levels are [price, volume, timestamp] with optional "r" flag of republished update
+    fn levels(data: &Value) -> Vec<Level> {
+        data.as_array()
+            .map(|levels| {
+                levels
+                    .iter()
+                    .filter_map(|level| {
+                        Some(Level {
+                            price: level.get(0)?.as_str()?.to_string(),
+                            volume: level.get(1)?.as_str()?.to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn snapshot(&mut self, asks: Vec<Level>, bids: Vec<Level>) {
+        self.asks = asks;
+        self.bids = bids;
+
+        Self::sort(&mut self.asks, false);
+        Self::sort(&mut self.bids, true);
+    }
+
+    fn update_asks(&mut self, levels: Vec<Level>) {
+        Self::update(&mut self.asks, levels, false);
+    }
+
+    fn update_bids(&mut self, levels: Vec<Level>) {
+        Self::update(&mut self.bids, levels, true);
+    }
+
+    // Zero volume deletes the level, levels out of the subscribed depth are dropped
+    fn update(side: &mut Vec<Level>, levels: Vec<Level>, descending: bool) {
+        for level in levels {
+            let price = level.price();
+
+            side.retain(|side_level| side_level.price() != price);
+
+            if level.volume() != 0. {
+                side.push(level);
+            }
+        }
+
+        Self::sort(side, descending);
+    }
+
+    fn sort(side: &mut Vec<Level>, descending: bool) {
+        side.sort_by(|a, b| a.price().partial_cmp(&b.price()).unwrap());
+
+        if descending {
+            side.reverse();
+        }
+
+        side.truncate(KRAKEN_DEPTH_LEVELS);
+    }
+
+    // CRC32 of top asks and bids, each level is price and volume without dots and leading zeros
+    pub fn checksum(&self) -> u32 {
+        let checksum_string = self
+            .asks
+            .iter()
+            .chain(self.bids.iter())
+            .take(KRAKEN_DEPTH_LEVELS * 2)
+            .map(|level| {
+                format!(
+                    "{}{}",
+                    Self::checksum_value(&level.price),
+                    Self::checksum_value(&level.volume)
+                )
+            })
+            .collect::<String>();
+
+        let mut crc = Crc::new();
+        crc.update(checksum_string.as_bytes());
+
+        crc.sum()
+    }
+
+    fn checksum_value(value: &str) -> String {
+        value.replace('.', "").trim_start_matches('0').to_string()
+    }
+
+    pub fn to_depth(&self) -> Depth {
+        let tickers = |side: &Vec<Level>| {
+            side.iter()
+                .map(|level| Ticker {
+                    price: level.price(),
+                    qty: level.volume(),
+                })
+                .collect()
+        };
+
+        Depth {
+            exchange: "Kraken".to_string(),
+            bids: tickers(&self.bids),
+            asks: tickers(&self.asks),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Ticker {
+    pub price: f64,
+    pub qty: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Depth {
+    pub exchange: String,
+    pub bids: Vec<Ticker>,
+    pub asks: Vec<Ticker>,
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // Recorded book-10 messages
+    const SNAPSHOT: &str = r#"[0,{
+        "as":[["5541.30000","2.50700000","1534614248.123678"],["5541.80000","0.33000000","1534614098.345543"],["5542.70000","0.64700000","1534614244.654432"]],
+        "bs":[["5541.20000","1.52900000","1534614248.765567"],["5539.90000","0.30000000","1534614241.769870"],["5539.50000","5.00000000","1534613831.243486"]]
+        },"book-10","XBT/EUR"]"#;
+
+    const UPDATE: &str = r#"[0,
+        {"a":[["5541.30000","0.00000000","1534614335.345903"],["5543.00000","0.10000000","1534614335.345903","r"]]},
+        {"b":[["5541.20000","1.00000000","1534614335.345903"]],"c":"3060526352"},
+        "book-10","XBT/EUR"]"#;
+
+    const HEARTBEAT: &str = r#"{"event":"heartbeat"}"#;
+
+    #[test]
+    fn book_snapshot() {
+        let mut books = HashMap::new();
+
+        assert_eq!(
+            KrakenWS::apply(&mut books, SNAPSHOT),
+            Ok(Some("XBT/EUR".to_string()))
+        );
+
+        let book = &books["XBT/EUR"];
+        assert_eq!(book.checksum(), 1710400350);
+
+        let depth = book.to_depth();
+        assert_eq!(depth.asks[0].price, 5541.3);
+        assert_eq!(depth.bids[0].price, 5541.2);
+        assert_eq!(depth.bids[2].qty, 5.);
+    }
+
+    #[test]
+    fn book_update() {
+        let mut books = HashMap::new();
+
+        KrakenWS::apply(&mut books, SNAPSHOT).unwrap();
+
+        assert_eq!(
+            KrakenWS::apply(&mut books, UPDATE),
+            Ok(Some("XBT/EUR".to_string()))
+        );
+
+        let depth = books["XBT/EUR"].to_depth();
+        assert_eq!(depth.asks.len(), 3);
+        assert_eq!(depth.asks[0].price, 5541.8);
+        assert_eq!(depth.asks[2].price, 5543.);
+        assert_eq!(depth.bids[0].qty, 1.);
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let mut books = HashMap::new();
+
+        // Update without the snapshot
+        assert_eq!(
+            KrakenWS::apply(&mut books, UPDATE),
+            Err(KRAKEN_CHECKSUM_ERROR)
+        );
+        assert!(books.is_empty());
+    }
+
+    #[test]
+    fn skip_events() {
+        let mut books = HashMap::new();
+
+        assert_eq!(KrakenWS::apply(&mut books, HEARTBEAT), Ok(None));
+    }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn get_depth() {
+        let mut kraken_ws = KrakenWS::connect(&["XBT/EUR"]).unwrap();
+
+        loop {
+            println!("{:?}", kraken_ws.get_depth());
+        }
+    }
+}
@@ -1,2 +1,8 @@
+pub mod binance;
+pub mod bitmex;
+pub mod clock;
+pub mod fix;
 pub mod huobi;
+pub mod kraken;
 pub mod stub_server;
+pub mod websocket;
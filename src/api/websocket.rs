@@ -0,0 +1,90 @@
+use std::io::ErrorKind;
+use std::time::Duration;
+use tracing::error;
+use tungstenite::{client::AutoStream, stream::Stream, Error, WebSocket};
+
+// Connection is treated as dead if nothing (data or ping) came during that time
+pub const WS_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Reading from socket returns timeout error instead of blocking forever
+pub fn set_read_timeout(
+    socket: &WebSocket<AutoStream>,
+    timeout: Duration,
+) -> Result<(), &'static str> {
+    let tcp_stream = match socket.get_ref() {
+        Stream::Plain(tcp_stream) => tcp_stream,
+        Stream::Tls(tls_stream) => tls_stream.get_ref(),
+    };
+
+    match tcp_stream.set_read_timeout(Some(timeout)) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error!("Can't set websocket read timeout: {}", e);
+            Err("Websocket read timeout error")
+        }
+    }
+}
+
+// Keepalive for exchanges which wait for client pings.
+// Ping is sent on the first read timeout, the next one without any message means lost connection.
+#[derive(Debug, Default)]
+pub struct Heartbeat {
+    ping_sent: bool,
+}
+
+impl Heartbeat {
+    pub fn received(&mut self) {
+        self.ping_sent = false;
+    }
+
+    // It returns Ok if ping has to be sent
+    pub fn timeout(&mut self) -> Result<(), &'static str> {
+        if self.ping_sent {
+            return Err("Websocket pong timeout");
+        }
+
+        self.ping_sent = true;
+
+        Ok(())
+    }
+}
+
+pub fn is_timeout(error: &Error) -> bool {
+    match error {
+        Error::Io(io_error) => {
+            io_error.kind() == ErrorKind::WouldBlock || io_error.kind() == ErrorKind::TimedOut
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{is_timeout, Heartbeat};
+    use std::io::{Error as IoError, ErrorKind};
+    use tungstenite::Error;
+
+    #[test]
+    fn timeout_errors() {
+        assert!(is_timeout(&Error::Io(IoError::from(ErrorKind::WouldBlock))));
+        assert!(is_timeout(&Error::Io(IoError::from(ErrorKind::TimedOut))));
+
+        assert!(!is_timeout(&Error::Io(IoError::from(
+            ErrorKind::ConnectionReset
+        ))));
+        assert!(!is_timeout(&Error::ConnectionClosed));
+    }
+
+    #[test]
+    fn heartbeat() {
+        let mut heartbeat = Heartbeat::default();
+
+        assert!(heartbeat.timeout().is_ok());
+        assert!(heartbeat.timeout().is_err());
+
+        heartbeat.received();
+
+        assert!(heartbeat.timeout().is_ok());
+    }
+}
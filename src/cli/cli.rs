@@ -1,4 +1,5 @@
 use super::commands::{self, *};
+use crate::gateway::Secret;
 use std::io::{self, BufRead};
 
 const HELP: &str = r#"      Available commands:
@@ -22,8 +23,11 @@ gateway
     status <gateway_name> - Get status of the Gateway by name
     info <gateway_name> - Get info of the Gateway by name
     config <gateway_name> <file_path> - Set configuration for the Gateway
+    flatten <gateway_name> [symbol|*] [account] - Cancel all open orders of the Gateway and lock its Robots
     up - Start all Gateways
     list - Get all available Gateways on the Platform
+keystore
+    add <name> - Encrypt the secret of the next line into the local keystore by MX_KEYSTORE_PASSPHRASE
 exit - Disconnect from Trading Platform and quit
 "#;
 
@@ -154,6 +158,14 @@ impl CLI {
                         }
                     }
 
+                    Some("flatten") => {
+                        let name = command.next();
+                        // "*" flattens all symbols of the account
+                        let symbol = command.next().filter(|symbol| *symbol != "*");
+                        let account = command.next();
+                        commands::GatewayCommand::flatten(name, symbol, account).await;
+                    }
+
                     Some("up") => {
                         commands::GatewayCommand::up().await;
                     }
@@ -166,6 +178,27 @@ impl CLI {
                     }
                 },
 
+                Some("keystore") => match command.next() {
+                    Some("add") => match command.next() {
+                        Some(name) => {
+                            let mut secret_line = String::new();
+                            stdin.lock().read_line(&mut secret_line).unwrap();
+                            let secret_line = Secret::new(secret_line);
+
+                            commands::KeystoreCommand::add(
+                                name,
+                                &Secret::from(secret_line.expose().trim_end()),
+                            );
+                        }
+                        None => {
+                            eprintln!("Command error: you should specify the secret name");
+                        }
+                    },
+                    _ => {
+                        eprintln!("Unknown command for keystore");
+                    }
+                },
+
                 _ => {
                     eprintln!("Unknown command for CLI");
                 }
@@ -1,8 +1,10 @@
 use super::config::{CLIConfig, CLI_CONFIG_FILE};
 use super::requests::request::Request;
 use crate::config::get_config;
+use crate::gateway::{Keystore, Secret, KEYSTORE_FILE, KEYSTORE_PASSPHRASE_ENV};
 use crate::paths::*;
 use async_trait::async_trait;
+use std::env;
 use std::marker::Send;
 
 pub trait Command: Send {
@@ -252,6 +254,22 @@ impl SetConfig for GatewayCommand {
     }
 }
 
+impl GatewayCommand {
+    pub async fn flatten(name: Option<&str>, symbol: Option<&str>, account: Option<&str>) {
+        println!("Canceling open orders of the {:?} gateway...", name);
+
+        let mut params = Vec::new();
+        if let Some(symbol) = symbol {
+            params.push(("symbol", symbol));
+        }
+        if let Some(account) = account {
+            params.push(("account", account));
+        }
+
+        Self::post_request(&GATEWAY_FLATTEN.replace("{name}", name.unwrap()), &params).await;
+    }
+}
+
 #[async_trait]
 impl UpCommand for GatewayCommand {
     async fn up() {
@@ -267,3 +285,28 @@ impl ListCommand for GatewayCommand {
         Self::list_command(GATEWAY_LIST).await;
     }
 }
+
+// Keystore is local, secrets aren't sent to the Platform
+pub struct KeystoreCommand;
+
+impl KeystoreCommand {
+    pub fn add(name: &str, secret: &Secret) {
+        let passphrase = match env::var(KEYSTORE_PASSPHRASE_ENV) {
+            Ok(passphrase) => Secret::new(passphrase),
+            Err(_) => {
+                eprintln!("Keystore error: {} isn't set", KEYSTORE_PASSPHRASE_ENV);
+                return;
+            }
+        };
+
+        let added = Keystore::load(KEYSTORE_FILE).and_then(|mut keystore| {
+            keystore.insert(name, secret, &passphrase)?;
+            keystore.save(KEYSTORE_FILE)
+        });
+
+        match added {
+            Ok(_) => println!("Secret {} was added to {}", name, KEYSTORE_FILE),
+            Err(e) => eprintln!("Keystore error: {}", e),
+        }
+    }
+}
@@ -0,0 +1,429 @@
+use super::CandleHistoryMsg;
+use crate::gateway::{Depth, PublicTrade};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use strum_macros::{Display, EnumString};
+use tracing::info;
+
+// Number of the last closed candles kept for every series, it's also the backfill size
+pub const CANDLES_HISTORY: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumString, Display)]
+pub enum CandleInterval {
+    #[strum(serialize = "1s")]
+    S1,
+    #[strum(serialize = "1m")]
+    M1,
+    #[strum(serialize = "5m")]
+    M5,
+    #[strum(serialize = "1h")]
+    H1,
+}
+
+impl CandleInterval {
+    pub fn millis(&self) -> u64 {
+        match self {
+            CandleInterval::S1 => 1_000,
+            CandleInterval::M1 => 60_000,
+            CandleInterval::M5 => 300_000,
+            CandleInterval::H1 => 3_600_000,
+        }
+    }
+
+    // Open time of the candle which contains the time, both in unix milliseconds
+    pub fn open_time(&self, timestamp: u64) -> u64 {
+        timestamp - timestamp % self.millis()
+    }
+}
+
+// OHLCV bar of the interval
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candle {
+    // Unix time in milliseconds
+    pub open_time: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    // Traded base amount, it's 0 for candles of mid prices
+    pub volume: f64,
+}
+
+impl Candle {
+    fn new(open_time: u64, price: f64, qty: f64) -> Self {
+        Candle {
+            open_time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: qty,
+        }
+    }
+
+    fn update(&mut self, price: f64, qty: f64) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume += qty;
+    }
+}
+
+// Candles are built from public trades if the exchange streams them, from mid prices otherwise
+#[derive(Clone, Copy, Debug, PartialEq, Display)]
+pub enum CandleSource {
+    Trades,
+    MidPrice,
+}
+
+// Candles of the gateway symbol and interval.
+// Intervals without trades or depth updates have no candle.
+#[derive(Clone, Debug)]
+pub struct CandleSeries {
+    pub gateway_name: String,
+    pub exchange_name: String,
+    pub symbol: String,
+    pub interval: CandleInterval,
+    pub source: CandleSource,
+    // Last closed candles from the oldest one
+    pub closed: Vec<Candle>,
+    // Forming candle of the current interval
+    pub current: Option<Candle>,
+}
+
+impl CandleSeries {
+    // Update of the closed candle is late, it's skipped
+    fn update(&mut self, price: f64, qty: f64, timestamp: u64) {
+        let open_time = self.interval.open_time(timestamp);
+
+        match &mut self.current {
+            Some(current) if current.open_time == open_time => current.update(price, qty),
+            Some(current) if current.open_time > open_time => {}
+            _ => {
+                if let Some(last_closed) = self.closed.last() {
+                    if last_closed.open_time >= open_time {
+                        return;
+                    }
+                }
+
+                self.close();
+                self.current = Some(Candle::new(open_time, price, qty));
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        if let Some(current) = self.current.take() {
+            self.closed.push(current);
+        }
+
+        if self.closed.len() > CANDLES_HISTORY {
+            self.closed.drain(..self.closed.len() - CANDLES_HISTORY);
+        }
+    }
+
+    // Current candle is closed when its interval has passed by the time in unix milliseconds
+    fn roll(&mut self, now: u64) {
+        let is_expired = self.current.as_ref().map_or(false, |current| {
+            current.open_time + self.interval.millis() <= now
+        });
+
+        if is_expired {
+            self.close();
+        }
+    }
+
+    // Exchange candles precede the ones which are built already.
+    // The last exchange candle is still forming, it becomes the current one if nothing is built yet.
+    fn backfill(&mut self, mut candles: Vec<Candle>, now: u64) {
+        let first_built = self
+            .closed
+            .first()
+            .or_else(|| self.current.as_ref())
+            .map(|candle| candle.open_time);
+
+        candles.retain(|candle| first_built.map_or(true, |open_time| candle.open_time < open_time));
+        candles.sort_by_key(|candle| candle.open_time);
+
+        if first_built.is_none() {
+            let is_forming = candles
+                .last()
+                .map_or(false, |last| last.open_time + self.interval.millis() > now);
+
+            if is_forming {
+                self.current = candles.pop();
+            }
+        }
+
+        candles.append(&mut self.closed);
+        self.closed = candles;
+        self.close();
+    }
+}
+
+// Aggregates public trades or mid prices into candles of every gateway symbol.
+// Series are created by the gateway backfill which it sends for its configured intervals on start.
+#[derive(Debug, Default)]
+pub struct CandleBuilder {
+    // <(Gateway, Symbol), Series of the intervals>
+    series: RwLock<HashMap<(String, String), Vec<CandleSeries>>>,
+}
+
+impl CandleBuilder {
+    pub fn new() -> Self {
+        CandleBuilder::default()
+    }
+
+    pub fn backfill(&self, candle_history: CandleHistoryMsg, now: u64) {
+        info!(
+            "[Candles] {} {} candles of {} {} are backfilled",
+            candle_history.candles.len(),
+            candle_history.interval,
+            candle_history.gateway_name,
+            candle_history.symbol
+        );
+
+        let mut series_lock = self.series.write().unwrap();
+
+        let symbol_series = series_lock
+            .entry((
+                candle_history.gateway_name.clone(),
+                candle_history.symbol.clone(),
+            ))
+            .or_insert_with(Vec::new);
+
+        let position = symbol_series
+            .iter()
+            .position(|series| series.interval == candle_history.interval);
+
+        let series = match position {
+            Some(position) => &mut symbol_series[position],
+            None => {
+                symbol_series.push(CandleSeries {
+                    gateway_name: candle_history.gateway_name,
+                    exchange_name: candle_history.exchange_name,
+                    symbol: candle_history.symbol,
+                    interval: candle_history.interval,
+                    source: candle_history.source,
+                    closed: Vec::new(),
+                    current: None,
+                });
+                symbol_series.last_mut().unwrap()
+            }
+        };
+
+        series.source = candle_history.source;
+        series.backfill(candle_history.candles, now);
+    }
+
+    pub fn add_trade(&self, gateway: &str, symbol: &str, trade: &PublicTrade) {
+        self.update(gateway, symbol, CandleSource::Trades, |series| {
+            series.update(trade.price, trade.qty, trade.timestamp)
+        });
+    }
+
+    // Mid price of the depth at the time in unix milliseconds
+    pub fn add_depth(&self, gateway: &str, symbol: &str, depth: &Depth, now: u64) {
+        let mid_price = match (depth.bids.first(), depth.asks.first()) {
+            (Some(bid), Some(ask)) => (bid.price + ask.price) / 2.,
+            _ => return,
+        };
+
+        self.update(gateway, symbol, CandleSource::MidPrice, |series| {
+            series.update(mid_price, 0., now)
+        });
+    }
+
+    fn update<F>(&self, gateway: &str, symbol: &str, source: CandleSource, update: F)
+    where
+        F: Fn(&mut CandleSeries),
+    {
+        let mut series_lock = self.series.write().unwrap();
+
+        if let Some(symbol_series) = series_lock.get_mut(&(gateway.to_string(), symbol.to_string()))
+        {
+            symbol_series
+                .iter_mut()
+                .filter(|series| series.source == source)
+                .for_each(update);
+        }
+    }
+
+    // All series at the time in unix milliseconds, current candles of the passed intervals are closed
+    pub fn series(&self, now: u64) -> Vec<CandleSeries> {
+        let mut series_lock = self.series.write().unwrap();
+
+        series_lock
+            .values_mut()
+            .flat_map(|symbol_series| symbol_series.iter_mut())
+            .map(|series| {
+                series.roll(now);
+                series.clone()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::gateway::Ticker;
+    use crate::order_manager::OrderSide;
+    use std::str::FromStr;
+    use std::time::Instant;
+
+    fn history(
+        interval: CandleInterval,
+        source: CandleSource,
+        candles: Vec<Candle>,
+    ) -> CandleHistoryMsg {
+        CandleHistoryMsg {
+            gateway_name: "Binance".to_string(),
+            exchange_name: "Binance".to_string(),
+            symbol: "BTCUSDT".to_string(),
+            interval,
+            source,
+            candles,
+            created_at: Instant::now(),
+        }
+    }
+
+    fn trade(price: f64, qty: f64, timestamp: u64) -> PublicTrade {
+        PublicTrade {
+            trade_id: 0,
+            price,
+            qty,
+            aggressor: OrderSide::Buy,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn interval_from_config() {
+        assert_eq!(CandleInterval::from_str("5m").unwrap(), CandleInterval::M5);
+        assert_eq!(CandleInterval::H1.to_string(), "1h");
+        assert!(CandleInterval::from_str("2m").is_err());
+
+        assert_eq!(CandleInterval::M1.open_time(125_000), 120_000);
+    }
+
+    #[test]
+    fn trade_candles() {
+        let candle_builder = CandleBuilder::new();
+
+        // Trades of the symbol without the series aren't aggregated
+        candle_builder.add_trade("Binance", "BTCUSDT", &trade(100., 1., 0));
+        assert!(candle_builder.series(0).is_empty());
+
+        candle_builder.backfill(
+            history(CandleInterval::S1, CandleSource::Trades, Vec::new()),
+            0,
+        );
+
+        candle_builder.add_trade("Binance", "BTCUSDT", &trade(100., 1., 100));
+        candle_builder.add_trade("Binance", "BTCUSDT", &trade(102., 2., 500));
+        candle_builder.add_trade("Binance", "BTCUSDT", &trade(99., 1., 900));
+        candle_builder.add_trade("Binance", "BTCUSDT", &trade(101., 3., 1_200));
+        // Late trade of the closed candle
+        candle_builder.add_trade("Binance", "BTCUSDT", &trade(90., 1., 950));
+
+        let series = candle_builder.series(1_500);
+        assert_eq!(
+            series[0].closed,
+            vec![Candle {
+                open_time: 0,
+                open: 100.,
+                high: 102.,
+                low: 99.,
+                close: 99.,
+                volume: 4.,
+            }]
+        );
+        assert_eq!(series[0].current, Some(Candle::new(1_000, 101., 3.)));
+
+        // Current candle is closed without updates when its interval has passed
+        let series = candle_builder.series(2_000);
+        assert_eq!(series[0].closed.len(), 2);
+        assert_eq!(series[0].current, None);
+
+        // Mid prices don't update candles of trades
+        candle_builder.add_depth(
+            "Binance",
+            "BTCUSDT",
+            &Depth {
+                exchange: "Binance".to_string(),
+                bids: vec![Ticker {
+                    price: 80.,
+                    qty: 1.,
+                }],
+                asks: vec![Ticker {
+                    price: 81.,
+                    qty: 1.,
+                }],
+            },
+            2_100,
+        );
+        assert_eq!(candle_builder.series(2_200)[0].current, None);
+    }
+
+    #[test]
+    fn mid_price_candles_with_backfill() {
+        let candle_builder = CandleBuilder::new();
+
+        // Last exchange candle is forming at 130s
+        let exchange_candles = vec![
+            Candle::new(120_000, 104., 5.),
+            Candle::new(0, 100., 5.),
+            Candle::new(60_000, 102., 5.),
+        ];
+
+        candle_builder.backfill(
+            history(CandleInterval::M1, CandleSource::MidPrice, exchange_candles),
+            130_000,
+        );
+
+        let depth = Depth {
+            exchange: "Binance".to_string(),
+            bids: vec![Ticker {
+                price: 105.,
+                qty: 1.,
+            }],
+            asks: vec![Ticker {
+                price: 107.,
+                qty: 1.,
+            }],
+        };
+        candle_builder.add_depth("Binance", "BTCUSDT", &depth, 131_000);
+
+        let series = candle_builder.series(131_000);
+        assert_eq!(
+            series[0]
+                .closed
+                .iter()
+                .map(|candle| candle.open_time)
+                .collect::<Vec<u64>>(),
+            vec![0, 60_000]
+        );
+
+        let current = series[0].current.as_ref().unwrap();
+        assert_eq!(current.open, 104.);
+        assert_eq!(current.high, 106.);
+        assert_eq!(current.close, 106.);
+        assert_eq!(current.volume, 5.);
+
+        // Repeated backfill, e.g. after gateway restart, keeps the built candles
+        candle_builder.backfill(
+            history(
+                CandleInterval::M1,
+                CandleSource::MidPrice,
+                vec![Candle::new(60_000, 1., 1.), Candle::new(120_000, 1., 1.)],
+            ),
+            132_000,
+        );
+
+        let series = candle_builder.series(132_000);
+        assert_eq!(series[0].closed[1].open, 102.);
+        assert_eq!(series[0].current.as_ref().unwrap().close, 106.);
+    }
+}
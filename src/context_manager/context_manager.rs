@@ -1,10 +1,12 @@
 use super::models::{
-    ActiveOrder, ContextInfo, ContextMsg, DepthInfo, FilledInfo, GatewayMsg, OrderBookInfo,
-    Position,
+    ActiveOrder, CandleHistoryMsg, ContextInfo, ContextMsg, DepthInfo, FilledInfo, GatewayMsg,
+    LegacyFilledInfo, OrderBookInfo, OrderStateMsg, Position, PublicTradeMsg, RejectedOrder,
+    StreamHealth, TradeTape,
 };
-use super::{DepthMsg, FilledOrder};
-use crate::gateway::OrderBook;
+use super::{CandleBuilder, DepthMsg, FilledOrder, MarketRecorder, RecordEvent};
+use crate::gateway::{OrderBook, StreamKind};
 use bincode;
+use chrono::Utc;
 use crossbeam::channel::{bounded, Receiver, Sender};
 use lazy_static::lazy_static;
 use std::collections::HashMap;
@@ -22,6 +24,9 @@ lazy_static! {
 
 const FILLED_INFO_STORAGE_FILE_PATH: &str = "./data/positions.bin";
 
+// Number of the last public trades kept for every symbol
+const TRADE_TAPE_LENGTH: usize = 100;
+
 pub struct ContextManager {
     // External calculated formulas
     pub calculated_formulas: Vec<f64>,
@@ -40,6 +45,17 @@ pub struct ContextManager {
     // <Custom Order Id, Active Order>
     active_orders_info: RwLock<HashMap<String, ActiveOrder>>,
 
+    // Last connection status of the exchange streams
+    // <Gateway, <(Stream, Account), StreamHealth>>
+    streams_health: RwLock<HashMap<String, HashMap<(StreamKind, String), StreamHealth>>>,
+
+    // Last public trades
+    // <Gateway, <Symbol, TradeTape>>
+    trade_tapes: RwLock<HashMap<String, HashMap<String, TradeTape>>>,
+
+    // Candles of the gateway symbols which are backfilled by their gateways
+    candles: CandleBuilder,
+
     // Stores Filled Info, grouped by robots
     // <Robot Id, [Filled Info]>
     filled_orders_info_store: RwLock<HashMap<String, Vec<FilledInfo>>>,
@@ -47,6 +63,9 @@ pub struct ContextManager {
     // List of gateways on platform
     gateways: Vec<String>,
 
+    // Writes depths and fills of the gateways which are recorded
+    recorder: MarketRecorder,
+
     // <RobotID, <Gateway, [Symbols]>>
     subscriptions: HashMap<String, HashMap<String, Vec<String>>>,
 
@@ -68,6 +87,7 @@ impl<'a> ContextManager {
         info_receiver: Receiver<GatewayMsg>,
         gateways: Vec<String>,
         subscriptions: HashMap<String, HashMap<String, Vec<String>>>,
+        recorder: MarketRecorder,
     ) -> Self {
         ContextManager {
             calculated_formulas: vec![],
@@ -77,10 +97,14 @@ impl<'a> ContextManager {
 
             depth_info: RwLock::new(HashMap::new()),
             active_orders_info: RwLock::new(HashMap::new()),
+            streams_health: RwLock::new(HashMap::new()),
+            trade_tapes: RwLock::new(HashMap::new()),
+            candles: CandleBuilder::new(),
             filled_orders_info_store: RwLock::new(Self::load_filled_info().unwrap()),
 
             gateways,
             subscriptions,
+            recorder,
 
             current_state: RwLock::new(ContextManagerState::Stopped),
 
@@ -194,6 +218,24 @@ impl<'a> ContextManager {
                     }
                 }
 
+                let streams_health = self
+                    .streams_health
+                    .read()
+                    .unwrap()
+                    .values()
+                    .flat_map(|gateway_streams| gateway_streams.values().cloned())
+                    .collect::<Vec<StreamHealth>>();
+
+                let trade_tapes = self
+                    .trade_tapes
+                    .read()
+                    .unwrap()
+                    .values()
+                    .flat_map(|gateway_tapes| gateway_tapes.values().cloned())
+                    .collect::<Vec<TradeTape>>();
+
+                let candles = self.candles.series(Utc::now().timestamp_millis() as u64);
+
                 let filled_info = self.filled_orders_info_store.read().unwrap();
 
                 for robot_name in self.info_senders.keys() {
@@ -206,6 +248,7 @@ impl<'a> ContextManager {
                                 symbol: filled_info.symbol.clone(),
                                 amount: filled_info.amount.parse().unwrap(),
                                 price: filled_info.price,
+                                fee: filled_info.commission.quote_amount,
                                 order_side: filled_info.order_side.clone(),
                                 strategy_params: filled_info.strategy_params.clone(),
                             })
@@ -219,6 +262,9 @@ impl<'a> ContextManager {
                     let context_msg = ContextMsg::ContextInfo(ContextInfo {
                         orderbooks_info: orderbooks_info.clone(), // Without subscription, all orderbooks. TODO
                         positions,
+                        streams_health: streams_health.clone(),
+                        trade_tapes: trade_tapes.clone(),
+                        candles: candles.clone(),
                         created_at: *LAST_RECEIVED_MESSAGE_TIME.lock().unwrap(),
                     });
 
@@ -265,6 +311,11 @@ impl<'a> ContextManager {
             GatewayMsg::DepthMsg(depth_msg) => self.handle_depth(depth_msg),
             GatewayMsg::ActiveOrder(active_order) => self.handle_active_order(active_order),
             GatewayMsg::FilledOrder(filled_order) => self.handle_filled_order(filled_order),
+            GatewayMsg::StreamHealth(stream_health) => self.handle_stream_health(stream_health),
+            GatewayMsg::RejectedOrder(rejected_order) => self.handle_rejected_order(rejected_order),
+            GatewayMsg::OrderState(order_state_msg) => self.handle_order_state(order_state_msg),
+            GatewayMsg::PublicTrade(public_trade_msg) => self.handle_public_trade(public_trade_msg),
+            GatewayMsg::CandleHistory(candle_history) => self.handle_candle_history(candle_history),
         }
     }
 
@@ -275,12 +326,28 @@ impl<'a> ContextManager {
 
         *LAST_RECEIVED_MESSAGE_TIME.lock().unwrap() = depth_msg.created_at;
 
+        if let Err(e) = self.recorder.record(
+            &depth_info.gateway_name,
+            &depth_info.symbol,
+            RecordEvent::from_depth(&depth_info.depth),
+        ) {
+            error!("[Context Manager] Depth isn't recorded: {}", e);
+        }
+
+        self.candles.add_depth(
+            &depth_info.gateway_name,
+            &depth_info.symbol,
+            &depth_info.depth,
+            Utc::now().timestamp_millis() as u64,
+        );
+
         match self.depth_info.write() {
             Ok(mut depth_info_lock) => {
-                let mut symbol = HashMap::new();
-                symbol.insert(depth_info.symbol.clone(), depth_info.clone());
-
-                depth_info_lock.insert(depth_info.exchange_name, symbol);
+                // Keeps depth of the other symbols of the exchange
+                depth_info_lock
+                    .entry(depth_info.exchange_name.clone())
+                    .or_insert_with(HashMap::new)
+                    .insert(depth_info.symbol.clone(), depth_info);
             }
             Err(error) => {
                 error!("Poison error: {}", error)
@@ -290,6 +357,71 @@ impl<'a> ContextManager {
         Ok(())
     }
 
+    fn handle_stream_health(&self, stream_health: StreamHealth) -> Result<(), &'static str> {
+        debug!(
+            "[Context Manager] {} {} {} stream is {:?}",
+            stream_health.gateway_name,
+            stream_health.account,
+            stream_health.stream,
+            stream_health.status
+        );
+
+        match self.streams_health.write() {
+            Ok(mut streams_health_lock) => {
+                streams_health_lock
+                    .entry(stream_health.gateway_name.clone())
+                    .or_insert_with(HashMap::new)
+                    .insert(
+                        (stream_health.stream, stream_health.account.clone()),
+                        stream_health,
+                    );
+            }
+            Err(error) => error!("Poison error: {}", error),
+        }
+
+        Ok(())
+    }
+
+    // Keeps the last trades of every gateway symbol
+    fn handle_public_trade(&self, public_trade_msg: PublicTradeMsg) -> Result<(), &'static str> {
+        self.candles.add_trade(
+            &public_trade_msg.gateway_name,
+            &public_trade_msg.symbol,
+            &public_trade_msg.trade,
+        );
+
+        match self.trade_tapes.write() {
+            Ok(mut trade_tapes_lock) => {
+                let trade_tape = trade_tapes_lock
+                    .entry(public_trade_msg.gateway_name.clone())
+                    .or_insert_with(HashMap::new)
+                    .entry(public_trade_msg.symbol.clone())
+                    .or_insert_with(|| TradeTape {
+                        gateway_name: public_trade_msg.gateway_name.clone(),
+                        exchange_name: public_trade_msg.exchange_name.clone(),
+                        symbol: public_trade_msg.symbol.clone(),
+                        trades: Vec::with_capacity(TRADE_TAPE_LENGTH + 1),
+                    });
+
+                trade_tape.trades.push(public_trade_msg.trade);
+
+                if trade_tape.trades.len() > TRADE_TAPE_LENGTH {
+                    trade_tape.trades.remove(0);
+                }
+            }
+            Err(error) => error!("Poison error: {}", error),
+        }
+
+        Ok(())
+    }
+
+    fn handle_candle_history(&self, candle_history: CandleHistoryMsg) -> Result<(), &'static str> {
+        self.candles
+            .backfill(candle_history, Utc::now().timestamp_millis() as u64);
+
+        Ok(())
+    }
+
     fn handle_active_order(&self, active_order: ActiveOrder) -> Result<(), &'static str> {
         debug!("[Context Manager] Got Active Order");
 
@@ -304,13 +436,86 @@ impl<'a> ContextManager {
         Ok(())
     }
 
+    // Returns rejected order to its robot
+    fn handle_rejected_order(&self, rejected_order: RejectedOrder) -> Result<(), &'static str> {
+        warn!(
+            "[Context Manager] Order of {} robot was rejected by {}: {}",
+            rejected_order.robot_id, rejected_order.gateway_name, rejected_order.reason
+        );
+
+        match self.info_senders.get(&rejected_order.robot_id) {
+            Some(info_sender) => {
+                if let Err(e) = info_sender.send(ContextMsg::RejectedOrder(rejected_order)) {
+                    error!("[Context Manager] Error to send Rejected Order: {:?}", e);
+                }
+            }
+            None => error!(
+                "[Context Manager] Robot {} not found",
+                rejected_order.robot_id
+            ),
+        }
+
+        Ok(())
+    }
+
+    // Final order isn't active anymore, its fills are already counted.
+    // New state is sent to the robot of the order.
+    fn handle_order_state(&self, order_state_msg: OrderStateMsg) -> Result<(), &'static str> {
+        let order_state = &order_state_msg.order_state;
+
+        debug!(
+            "[Context Manager] Order {} of {} robot is {}",
+            order_state.custom_order_id, order_state_msg.robot_id, order_state.status
+        );
+
+        if order_state.status.is_final() {
+            match self.active_orders_info.write() {
+                Ok(mut active_orders_lock) => {
+                    active_orders_lock.remove(&order_state.custom_order_id);
+                }
+                Err(error) => error!("Poison error: {}", error),
+            }
+        }
+
+        match self.info_senders.get(&order_state_msg.robot_id) {
+            Some(info_sender) => {
+                if let Err(e) = info_sender.send(ContextMsg::OrderState(order_state_msg)) {
+                    error!("[Context Manager] Error to send Order State: {:?}", e);
+                }
+            }
+            None => error!(
+                "[Context Manager] Robot {} not found",
+                order_state_msg.robot_id
+            ),
+        }
+
+        Ok(())
+    }
+
     fn handle_filled_order(&self, filled_order: FilledOrder) -> Result<(), &'static str> {
-        debug!("[Context Manager] Got Filled Order");
+        debug!(
+            "[Context Manager] Got Filled Order from {} account",
+            filled_order.account
+        );
 
         let active_orders_lock = self.active_orders_info.read().unwrap();
 
         match active_orders_lock.get(&filled_order.custom_order_id) {
             Some(active_order) => {
+                if let Err(e) = self.recorder.record(
+                    &active_order.gateway,
+                    &active_order.symbol,
+                    RecordEvent::Fill {
+                        custom_order_id: filled_order.custom_order_id.clone(),
+                        order_id: filled_order.order_id,
+                        order_side: active_order.order_side.clone(),
+                        amount: filled_order.amount,
+                        price: filled_order.price,
+                    },
+                ) {
+                    error!("[Context Manager] Fill isn't recorded: {}", e);
+                }
+
                 self.write_filled_info(active_order, &filled_order)?;
 
                 // Saves filled info into a file
@@ -335,8 +540,14 @@ impl<'a> ContextManager {
                     gateway: active_order.gateway.clone(),
                     robot_id: active_order.robot_id.clone(),
                     symbol: active_order.symbol.clone(),
-                    amount: filled_order.amount.clone(),
-                    price: active_order.price,
+                    amount: filled_order.amount.to_string(),
+                    // Order price is used if exchange doesn't report the fill price
+                    price: if filled_order.price > 0. {
+                        filled_order.price
+                    } else {
+                        active_order.price
+                    },
+                    commission: filled_order.commission.clone().unwrap_or_default(),
                     order_side: active_order.order_side.clone(),
                     strategy_params: active_order.strategy_params.clone(),
                 };
@@ -358,15 +569,19 @@ impl<'a> ContextManager {
 
     // Loads filled info from persistent storage on start
     fn load_filled_info() -> Result<HashMap<String, Vec<FilledInfo>>, &'static str> {
+        Self::load_filled_info_from(FILLED_INFO_STORAGE_FILE_PATH)
+    }
+
+    fn load_filled_info_from(path: &str) -> Result<HashMap<String, Vec<FilledInfo>>, &'static str> {
         let empty = HashMap::new();
 
-        match File::open(FILLED_INFO_STORAGE_FILE_PATH) {
+        match File::open(path) {
             Ok(mut filled_info_storage) => {
                 let mut buf = Vec::new();
 
                 filled_info_storage.read_to_end(&mut buf).unwrap();
 
-                match bincode::deserialize::<HashMap<String, Vec<FilledInfo>>>(&buf) {
+                match Self::deserialize_filled_info(&buf) {
                     Ok(positions) => {
                         info!("Deserialize positions");
 
@@ -391,6 +606,25 @@ impl<'a> ContextManager {
         }
     }
 
+    // File written before commissions were recorded is deserialized by the legacy layout
+    fn deserialize_filled_info(buf: &[u8]) -> bincode::Result<HashMap<String, Vec<FilledInfo>>> {
+        bincode::deserialize::<HashMap<String, Vec<FilledInfo>>>(buf).or_else(|error| {
+            match bincode::deserialize::<HashMap<String, Vec<LegacyFilledInfo>>>(buf) {
+                Ok(legacy_positions) => {
+                    info!("Deserialize positions without commissions");
+
+                    Ok(legacy_positions
+                        .into_iter()
+                        .map(|(key, filled_info)| {
+                            (key, filled_info.into_iter().map(FilledInfo::from).collect())
+                        })
+                        .collect())
+                }
+                Err(_) => Err(error),
+            }
+        })
+    }
+
     // Saves filled info to persistent storage on finish or its change
     fn save_filled_info(&self) -> Result<(), &'static str> {
         match File::create(FILLED_INFO_STORAGE_FILE_PATH) {
@@ -433,6 +667,8 @@ impl<'a> ContextManager {
 
                     *state = ContextManagerState::Stopped;
 
+                    self.recorder.finish_all();
+
                     Ok(())
                 }
                 ContextManagerState::Stopped => {
@@ -469,6 +705,7 @@ impl ContextManagerUtils {
             stub_info_receiver,
             vec![],
             HashMap::new(),
+            MarketRecorder::default(),
         )))
     }
 
@@ -489,10 +726,14 @@ impl ContextManagerUtils {
 
             depth_info: RwLock::new(latest_info),
             active_orders_info: RwLock::new(HashMap::new()),
+            streams_health: RwLock::new(HashMap::new()),
+            trade_tapes: RwLock::new(HashMap::new()),
+            candles: CandleBuilder::new(),
             filled_orders_info_store: RwLock::new(HashMap::new()),
 
             gateways,
             subscriptions,
+            recorder: MarketRecorder::default(),
 
             current_state: RwLock::new(ContextManagerState::Stopped),
 
@@ -506,7 +747,10 @@ impl ContextManagerUtils {
 mod tests {
 
     use super::*;
-    use crate::context_manager::FilledOrder;
+    use crate::context_manager::{CandleInterval, CandleSource, Commission, FilledOrder};
+    use crate::gateway::{ConnectionStatus, Depth, PublicTrade, RejectReason};
+    use crate::order_manager::{Order, OrderEvent, OrderSide, OrderState, OrderStatus};
+    use crate::robot::strategy::StrategyParams;
 
     #[test]
     fn start_context_manager() {
@@ -601,10 +845,264 @@ mod tests {
         context_manager.update_context_info(filled_order).unwrap();
     }
 
+    #[test]
+    fn update_context_info_rejected_order() {
+        let (info_sender, info_receiver) = crossbeam::channel::unbounded();
+
+        let mut info_senders = HashMap::new();
+        info_senders.insert("Robot1".to_string(), info_sender);
+
+        let context_manager = ContextManager::init(
+            info_senders,
+            crossbeam::channel::unbounded().1,
+            vec![],
+            HashMap::new(),
+            MarketRecorder::default(),
+        );
+
+        let rejected_order = GatewayMsg::RejectedOrder(RejectedOrder {
+            robot_id: "Robot1".to_string(),
+            gateway_name: "GatewayStub".to_string(),
+            order: Order::default(),
+            reason: RejectReason::UnknownInstrument,
+            created_at: Instant::now(),
+        });
+
+        context_manager.update_context_info(rejected_order).unwrap();
+
+        match info_receiver.try_recv().unwrap() {
+            ContextMsg::RejectedOrder(rejected_order) => {
+                assert_eq!(rejected_order.reason, RejectReason::UnknownInstrument)
+            }
+            context_msg => panic!("Unexpected message {:?}", context_msg),
+        }
+    }
+
+    #[test]
+    fn update_context_info_final_order_state() {
+        let (info_sender, info_receiver) = crossbeam::channel::unbounded();
+
+        let mut info_senders = HashMap::new();
+        info_senders.insert("Robot1".to_string(), info_sender);
+
+        let context_manager = ContextManager::init(
+            info_senders,
+            crossbeam::channel::unbounded().1,
+            vec![],
+            HashMap::new(),
+            MarketRecorder::default(),
+        );
+
+        let active_order = ActiveOrder {
+            custom_order_id: "Order1".to_string(),
+            robot_id: "Robot1".to_string(),
+            ..ActiveOrder::default()
+        };
+        context_manager
+            .update_context_info(GatewayMsg::ActiveOrder(active_order))
+            .unwrap();
+
+        let mut order_state = OrderState::new("Order1", "BTCUSDT", OrderSide::Buy, 1., 100.);
+        order_state.apply(&OrderEvent::Cancelled).unwrap();
+
+        let order_state_msg = GatewayMsg::OrderState(OrderStateMsg {
+            robot_id: "Robot1".to_string(),
+            gateway_name: "GatewayStub".to_string(),
+            account: "StubAccount".to_string(),
+            order_state,
+            instrument_id: None,
+            created_at: Instant::now(),
+        });
+
+        context_manager
+            .update_context_info(order_state_msg)
+            .unwrap();
+
+        assert!(context_manager
+            .active_orders_info
+            .read()
+            .unwrap()
+            .is_empty());
+
+        match info_receiver.try_recv().unwrap() {
+            ContextMsg::OrderState(order_state_msg) => {
+                assert_eq!(order_state_msg.order_state.status, OrderStatus::Cancelled)
+            }
+            context_msg => panic!("Unexpected message {:?}", context_msg),
+        }
+    }
+
+    #[test]
+    fn update_context_info_depth_per_symbol() {
+        let context_manager = ContextManagerUtils::stub();
+
+        for symbol in vec!["BTCUSDT", "ETHUSDT"] {
+            let depth_msg = GatewayMsg::DepthMsg(DepthMsg {
+                depth_info: DepthInfo {
+                    gateway_name: "GatewayStub".to_string(),
+                    exchange_name: "ExchangeStub".to_string(),
+                    symbol: symbol.to_string(),
+                    instrument_id: None,
+                    depth: Depth::default(),
+                },
+                created_at: Instant::now(),
+            });
+
+            context_manager.update_context_info(depth_msg).unwrap();
+        }
+
+        let depth_info_lock = context_manager.depth_info.read().unwrap();
+        let exchange_depth = depth_info_lock.get("ExchangeStub").unwrap();
+
+        assert!(exchange_depth.contains_key("BTCUSDT"));
+        assert!(exchange_depth.contains_key("ETHUSDT"));
+    }
+
+    #[test]
+    fn update_context_info_stream_health() {
+        let context_manager = ContextManagerUtils::stub();
+
+        for status in vec![
+            ConnectionStatus::Connected,
+            ConnectionStatus::Disconnected("Error reading message".to_string()),
+        ] {
+            let health_msg = GatewayMsg::StreamHealth(StreamHealth {
+                gateway_name: "GatewayStub".to_string(),
+                exchange_name: "ExchangeStub".to_string(),
+                account: "StubAccount".to_string(),
+                stream: StreamKind::Depth,
+                status,
+                created_at: Instant::now(),
+            });
+
+            context_manager.update_context_info(health_msg).unwrap();
+        }
+
+        let streams_health_lock = context_manager.streams_health.read().unwrap();
+        let depth_health =
+            &streams_health_lock["GatewayStub"][&(StreamKind::Depth, "StubAccount".to_string())];
+
+        // Only the last status is kept
+        assert!(!depth_health.is_connected());
+        assert_eq!(streams_health_lock["GatewayStub"].len(), 1);
+    }
+
+    #[test]
+    fn update_context_info_trade_tape() {
+        let context_manager = ContextManagerUtils::stub();
+
+        for trade_id in 0..=TRADE_TAPE_LENGTH as u64 {
+            let trade_msg = GatewayMsg::PublicTrade(PublicTradeMsg {
+                gateway_name: "GatewayStub".to_string(),
+                exchange_name: "ExchangeStub".to_string(),
+                symbol: "BTCUSDT".to_string(),
+                instrument_id: None,
+                trade: PublicTrade {
+                    trade_id,
+                    price: 100.,
+                    qty: 1.,
+                    aggressor: OrderSide::Buy,
+                    timestamp: 1630000000000 + trade_id,
+                },
+                created_at: Instant::now(),
+            });
+
+            context_manager.update_context_info(trade_msg).unwrap();
+        }
+
+        let trade_tapes_lock = context_manager.trade_tapes.read().unwrap();
+        let trades = &trade_tapes_lock["GatewayStub"]["BTCUSDT"].trades;
+
+        // The oldest trade is dropped
+        assert_eq!(trades.len(), TRADE_TAPE_LENGTH);
+        assert_eq!(trades[0].trade_id, 1);
+    }
+
+    #[test]
+    fn update_context_info_candles() {
+        let context_manager = ContextManagerUtils::stub();
+        let now = Utc::now().timestamp_millis() as u64;
+
+        let trade_msg = GatewayMsg::PublicTrade(PublicTradeMsg {
+            gateway_name: "GatewayStub".to_string(),
+            exchange_name: "ExchangeStub".to_string(),
+            symbol: "BTCUSDT".to_string(),
+            instrument_id: None,
+            trade: PublicTrade {
+                trade_id: 1,
+                price: 100.,
+                qty: 2.,
+                aggressor: OrderSide::Buy,
+                timestamp: now,
+            },
+            created_at: Instant::now(),
+        });
+
+        // Series doesn't exist before the gateway backfill
+        context_manager
+            .update_context_info(trade_msg.clone())
+            .unwrap();
+        assert!(context_manager.candles.series(now).is_empty());
+
+        let history_msg = GatewayMsg::CandleHistory(CandleHistoryMsg {
+            gateway_name: "GatewayStub".to_string(),
+            exchange_name: "ExchangeStub".to_string(),
+            symbol: "BTCUSDT".to_string(),
+            interval: CandleInterval::H1,
+            source: CandleSource::Trades,
+            candles: Vec::new(),
+            created_at: Instant::now(),
+        });
+
+        context_manager.update_context_info(history_msg).unwrap();
+        context_manager.update_context_info(trade_msg).unwrap();
+
+        let series = context_manager.candles.series(now);
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].current.as_ref().unwrap().volume, 2.);
+    }
+
     #[test]
     fn get_state() {
         let context_manager = ContextManagerUtils::stub();
 
         assert!(context_manager.state().is_ok());
     }
+
+    #[test]
+    fn load_legacy_filled_info() {
+        let path = std::env::temp_dir().join(format!("mx_positions_{}.bin", std::process::id()));
+
+        let mut legacy_positions = HashMap::new();
+        legacy_positions.insert(
+            "Robot1".to_string(),
+            vec![LegacyFilledInfo {
+                order_id: 123,
+                custom_order_id: "Custom123".to_string(),
+                gateway: "GatewayStub".to_string(),
+                robot_id: "Robot1".to_string(),
+                symbol: "BTCUSDT".to_string(),
+                amount: "0.5".to_string(),
+                price: 30000.,
+                order_side: OrderSide::Buy,
+                strategy_params: StrategyParams::Stub,
+            }],
+        );
+
+        // Positions file written before commissions were recorded
+        File::create(&path)
+            .unwrap()
+            .write_all(&bincode::serialize(&legacy_positions).unwrap())
+            .unwrap();
+
+        let positions = ContextManager::load_filled_info_from(path.to_str().unwrap()).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        let filled_info = &positions["Robot1"][0];
+        assert_eq!(filled_info.custom_order_id, "Custom123");
+        assert_eq!(filled_info.amount, "0.5");
+        assert_eq!(filled_info.price, 30000.);
+        assert_eq!(filled_info.commission, Commission::default());
+    }
 }
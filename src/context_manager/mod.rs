@@ -1,10 +1,17 @@
+mod candles;
 mod context_manager;
 mod error;
 mod models;
+mod recorder;
 
+pub use candles::{
+    Candle, CandleBuilder, CandleInterval, CandleSeries, CandleSource, CANDLES_HISTORY,
+};
 pub use context_manager::ContextManager;
+pub use recorder::{MarketRecorder, Record, RecordEvent, RECORDS_DIR};
 
 pub use models::{
-    ActiveOrder, ContextInfo, ContextMsg, DepthInfo, DepthMsg, FilledOrder, GatewayMsg,
-    OrderBookInfo, Position,
+    ActiveOrder, CandleHistoryMsg, Commission, ContextInfo, ContextMsg, DepthInfo, DepthMsg,
+    FilledOrder, GatewayMsg, OrderBookInfo, OrderStateMsg, Position, PublicTradeMsg,
+    RejectedOrder, StreamHealth, TradeTape,
 };
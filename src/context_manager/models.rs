@@ -1,5 +1,8 @@
-use crate::gateway::{Depth, OrderBook};
-use crate::order_manager::OrderSide;
+use super::{Candle, CandleInterval, CandleSeries, CandleSource};
+use crate::gateway::{
+    ConnectionStatus, Depth, OrderBook, PublicTrade, RejectReason, StreamKind, INSTRUMENT_REGISTRY,
+};
+use crate::order_manager::{Order, OrderSide, OrderState};
 use crate::robot::strategy::{ArbitrationParams, StrategyParams};
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
@@ -10,6 +13,11 @@ pub enum GatewayMsg {
     DepthMsg(DepthMsg),
     ActiveOrder(ActiveOrder),
     FilledOrder(FilledOrder),
+    StreamHealth(StreamHealth),
+    RejectedOrder(RejectedOrder),
+    OrderState(OrderStateMsg),
+    PublicTrade(PublicTradeMsg),
+    CandleHistory(CandleHistoryMsg),
 }
 
 #[derive(Clone, Debug)]
@@ -23,9 +31,87 @@ pub struct DepthInfo {
     pub gateway_name: String,
     pub exchange_name: String,
     pub symbol: String,
+    // Canonical id of the instrument if it's registered, e.g. BTC-USDT
+    pub instrument_id: Option<String>,
     pub depth: Depth,
 }
 
+// Public trade of the exchange market
+#[derive(Clone, Debug)]
+pub struct PublicTradeMsg {
+    pub gateway_name: String,
+    pub exchange_name: String,
+    pub symbol: String,
+    // Canonical id of the instrument if it's registered, e.g. BTC-USDT
+    pub instrument_id: Option<String>,
+    pub trade: PublicTrade,
+    pub created_at: Instant,
+}
+
+// Last public trades of the symbol from the oldest one
+#[derive(Clone, Debug)]
+pub struct TradeTape {
+    pub gateway_name: String,
+    pub exchange_name: String,
+    pub symbol: String,
+    pub trades: Vec<PublicTrade>,
+}
+
+// Exchange candles of the symbol which the gateway backfills on start.
+// Context Manager builds the further candles of the interval from the source.
+#[derive(Clone, Debug)]
+pub struct CandleHistoryMsg {
+    pub gateway_name: String,
+    pub exchange_name: String,
+    pub symbol: String,
+    pub interval: CandleInterval,
+    pub source: CandleSource,
+    pub candles: Vec<Candle>,
+    pub created_at: Instant,
+}
+
+// Order that wasn't accepted by the gateway or the exchange.
+// It's returned to its robot with the reason.
+#[derive(Clone, Debug)]
+pub struct RejectedOrder {
+    pub robot_id: String,
+    pub gateway_name: String,
+    pub order: Order,
+    pub reason: RejectReason,
+    pub created_at: Instant,
+}
+
+// Order state after the exchange event.
+// It's sent to Order Manager, Context Manager and the robot of the order.
+#[derive(Clone, Debug)]
+pub struct OrderStateMsg {
+    pub robot_id: String,
+    pub gateway_name: String,
+    pub account: String,
+    pub order_state: OrderState,
+    // Canonical id of the instrument if it's registered, e.g. BTC-USDT
+    pub instrument_id: Option<String>,
+    pub created_at: Instant,
+}
+
+// Connection status change of the exchange stream
+#[derive(Clone, Debug)]
+pub struct StreamHealth {
+    pub gateway_name: String,
+    pub exchange_name: String,
+    // Gateway account the stream is connected with
+    pub account: String,
+    pub stream: StreamKind,
+    pub status: ConnectionStatus,
+    pub created_at: Instant,
+}
+
+impl StreamHealth {
+    pub fn is_connected(&self) -> bool {
+        self.status == ConnectionStatus::Connected
+    }
+}
+
 // Order that was successfully sent to the exchange and its response returned to gateway
 #[derive(Clone, Debug)]
 pub struct ActiveOrder {
@@ -62,7 +148,16 @@ pub struct FilledOrder {
     pub custom_order_id: String,
     pub order_id: u64,
     pub symbol: String,
-    pub amount: String,
+    // Amount of the last fill
+    pub amount: f64,
+    // Fill price
+    pub price: f64,
+    // Exchange reports it with the fill, otherwise it's charged by the configured fee
+    pub commission: Option<Commission>,
+    // Gateway account the order was filled on
+    pub account: String,
+    // Canonical id of the instrument if it's registered, e.g. BTC-USDT
+    pub instrument_id: Option<String>,
 }
 
 impl Default for FilledOrder {
@@ -71,11 +166,23 @@ impl Default for FilledOrder {
             custom_order_id: "Custom123".to_string(),
             order_id: 123,
             symbol: "BTCUSDT".to_string(),
-            amount: "0.001".to_string(),
+            amount: 0.001,
+            price: 30000.,
+            commission: None,
+            account: "StubAccount".to_string(),
+            instrument_id: None,
         }
     }
 }
 
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Commission {
+    pub amount: f64,
+    pub asset: String,
+    // Commission in the quote asset of the symbol, PnL is calculated with it
+    pub quote_amount: f64,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FilledInfo {
     pub order_id: u64,
@@ -85,14 +192,48 @@ pub struct FilledInfo {
     pub symbol: String,
     pub amount: String,
     pub price: f64,
+    pub commission: Commission,
     pub order_side: OrderSide,
     pub strategy_params: StrategyParams,
 }
 
+// Filled info stored before commissions were recorded, it's loaded with zero commission
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LegacyFilledInfo {
+    pub order_id: u64,
+    pub custom_order_id: String,
+    pub gateway: String,
+    pub robot_id: String,
+    pub symbol: String,
+    pub amount: String,
+    pub price: f64,
+    pub order_side: OrderSide,
+    pub strategy_params: StrategyParams,
+}
+
+impl From<LegacyFilledInfo> for FilledInfo {
+    fn from(legacy: LegacyFilledInfo) -> Self {
+        FilledInfo {
+            order_id: legacy.order_id,
+            custom_order_id: legacy.custom_order_id,
+            gateway: legacy.gateway,
+            robot_id: legacy.robot_id,
+            symbol: legacy.symbol,
+            amount: legacy.amount,
+            price: legacy.price,
+            commission: Commission::default(),
+            order_side: legacy.order_side,
+            strategy_params: legacy.strategy_params,
+        }
+    }
+}
+
 // Message that is sent to robots
 #[derive(Clone, Debug)]
 pub enum ContextMsg {
     ContextInfo(ContextInfo),
+    RejectedOrder(RejectedOrder),
+    OrderState(OrderStateMsg),
 }
 
 #[derive(Clone, Debug)]
@@ -101,6 +242,15 @@ pub struct ContextInfo {
 
     pub positions: Vec<Position>,
 
+    // Last known status of every gateway stream
+    pub streams_health: Vec<StreamHealth>,
+
+    // Rolling public trades of every gateway symbol
+    pub trade_tapes: Vec<TradeTape>,
+
+    // Last closed and forming candles of every gateway symbol and interval
+    pub candles: Vec<CandleSeries>,
+
     pub created_at: Instant,
 }
 
@@ -109,9 +259,29 @@ impl ContextInfo {
         Self {
             orderbooks_info: Vec::new(),
             positions: Vec::new(),
+            streams_health: Vec::new(),
+            trade_tapes: Vec::new(),
+            candles: Vec::new(),
             created_at: Instant::now(),
         }
     }
+
+    // Instrument is addressed by the exchange symbol or by the canonical id, e.g. BTC-USDT
+    pub fn order_book_info(&self, gateway: &str, instrument: &str) -> Option<&OrderBookInfo> {
+        let symbol = INSTRUMENT_REGISTRY.exchange_symbol(gateway, instrument);
+
+        self.orderbooks_info
+            .iter()
+            .find(|info| info.gateway_name == gateway && info.symbol == symbol)
+    }
+
+    pub fn trade_tape(&self, gateway: &str, instrument: &str) -> Option<&TradeTape> {
+        let symbol = INSTRUMENT_REGISTRY.exchange_symbol(gateway, instrument);
+
+        self.trade_tapes
+            .iter()
+            .find(|tape| tape.gateway_name == gateway && tape.symbol == symbol)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -120,6 +290,8 @@ pub struct Position {
     pub symbol: String,
     pub amount: f64,
     pub price: f64,
+    // Paid commissions in the quote asset
+    pub fee: f64,
     pub order_side: OrderSide,
     pub strategy_params: StrategyParams,
 }
@@ -139,6 +311,7 @@ impl Default for GatewayMsg {
                 gateway_name: "GatewayStub".to_string(),
                 exchange_name: "ExchangeStub".to_string(),
                 symbol: "BTCUSDT".to_string(),
+                instrument_id: None,
                 depth: Depth::default(),
             },
             created_at: Instant::now(),
@@ -168,6 +341,9 @@ impl Default for ContextInfo {
         ContextInfo {
             orderbooks_info: vec![OrderBookInfo::default()],
             positions: Vec::new(),
+            streams_health: Vec::new(),
+            trade_tapes: Vec::new(),
+            candles: Vec::new(),
             created_at: Instant::now(),
         }
     }
@@ -180,6 +356,7 @@ impl Default for Position {
             symbol: "BTCUSDT".to_string(),
             amount: 1.,
             price: 1.,
+            fee: 0.,
             order_side: OrderSide::Buy,
             strategy_params: StrategyParams::Stub,
         }
@@ -0,0 +1,414 @@
+use crate::gateway::Depth;
+use crate::order_manager::OrderSide;
+use chrono::{NaiveDate, TimeZone, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+pub const RECORDS_DIR: &str = "./data/records";
+
+// Records are readable from the file after the flush
+const RECORDER_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Record {
+    // Unix time in milliseconds when Context Manager received it
+    pub timestamp: u64,
+    pub event: RecordEvent,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RecordEvent {
+    // (Price, Qty) levels
+    Depth {
+        bids: Vec<(f64, f64)>,
+        asks: Vec<(f64, f64)>,
+    },
+    Fill {
+        custom_order_id: String,
+        order_id: u64,
+        order_side: OrderSide,
+        amount: f64,
+        price: f64,
+    },
+}
+
+impl RecordEvent {
+    pub fn from_depth(depth: &Depth) -> Self {
+        RecordEvent::Depth {
+            bids: depth.bids.iter().map(|bid| (bid.price, bid.qty)).collect(),
+            asks: depth.asks.iter().map(|ask| (ask.price, ask.qty)).collect(),
+        }
+    }
+}
+
+struct RecordFile {
+    day: NaiveDate,
+    encoder: GzEncoder<File>,
+    flushed_at: Instant,
+}
+
+// Writes depths and fills of the recorded gateways to the files per gateway, symbol and UTC day.
+// Records are bincode encoded one after another, the file is gzip compressed.
+// Restarted recorder writes the day to the next part file, so a file left unfinished by a crash
+// isn't appended.
+#[derive(Default)]
+pub struct MarketRecorder {
+    dir: PathBuf,
+    gateways: Vec<String>,
+    // <(Gateway, Symbol), File of the current day>
+    files: Mutex<HashMap<(String, String), RecordFile>>,
+}
+
+impl MarketRecorder {
+    pub fn new(dir: &str, gateways: Vec<String>) -> Self {
+        if !gateways.is_empty() {
+            info!("[Recorder] Market data of {:?} is recorded", gateways);
+        }
+
+        MarketRecorder {
+            dir: PathBuf::from(dir),
+            gateways,
+            files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_recorded(&self, gateway: &str) -> bool {
+        self.gateways.iter().any(|recorded| recorded == gateway)
+    }
+
+    // {dir}/{gateway}/{symbol}/{YYYY-MM-DD}.bin.gz for the first part of the day,
+    // {YYYY-MM-DD}.{part}.bin.gz for the next ones
+    pub fn file_path(
+        dir: &Path,
+        gateway: &str,
+        symbol: &str,
+        day: NaiveDate,
+        part: usize,
+    ) -> PathBuf {
+        let day = day.format("%Y-%m-%d");
+
+        let file_name = match part {
+            0 => format!("{}.bin.gz", day),
+            part => format!("{}.{}.bin.gz", day, part),
+        };
+
+        dir.join(gateway).join(symbol).join(file_name)
+    }
+
+    // Day and part of the records file, None if it isn't the records file
+    fn file_part(path: &Path) -> Option<(NaiveDate, usize)> {
+        let file_name = path.file_name()?.to_str()?.strip_suffix(".bin.gz")?;
+        let mut fields = file_name.splitn(2, '.');
+
+        let day = NaiveDate::parse_from_str(fields.next()?, "%Y-%m-%d").ok()?;
+        let part = match fields.next() {
+            Some(part) => part.parse().ok()?,
+            None => 0,
+        };
+
+        Some((day, part))
+    }
+
+    // Records files of the symbol directory in the order of their days and parts
+    pub fn record_files(symbol_dir: &Path) -> Result<Vec<PathBuf>, &'static str> {
+        let mut files = fs::read_dir(symbol_dir)
+            .map_err(|_| "Can't read records directory")?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter_map(|path| Self::file_part(&path).map(|part| (part, path)))
+            .collect::<Vec<_>>();
+
+        files.sort();
+
+        Ok(files.into_iter().map(|(_, path)| path).collect())
+    }
+
+    pub fn record(
+        &self,
+        gateway: &str,
+        symbol: &str,
+        event: RecordEvent,
+    ) -> Result<(), &'static str> {
+        if !self.is_recorded(gateway) {
+            return Ok(());
+        }
+
+        self.write(
+            gateway,
+            symbol,
+            Record {
+                timestamp: Utc::now().timestamp_millis() as u64,
+                event,
+            },
+        )
+    }
+
+    // File is rotated when the record is of the next day
+    fn write(&self, gateway: &str, symbol: &str, record: Record) -> Result<(), &'static str> {
+        let day = Utc
+            .timestamp_millis(record.timestamp as i64)
+            .date()
+            .naive_utc();
+
+        let mut files = self.files.lock().unwrap();
+        let key = (gateway.to_string(), symbol.to_string());
+
+        if files.get(&key).map_or(true, |file| file.day != day) {
+            if let Some(file) = files.remove(&key) {
+                Self::finish(file);
+            }
+
+            files.insert(key.clone(), self.open(gateway, symbol, day)?);
+        }
+
+        let file = files.get_mut(&key).unwrap();
+
+        bincode::serialize_into(&mut file.encoder, &record).map_err(|e| {
+            error!(
+                "[Recorder] Can't write {} {} record: {}",
+                gateway, symbol, e
+            );
+            "Can't write record"
+        })?;
+
+        if file.flushed_at.elapsed() >= RECORDER_FLUSH_INTERVAL {
+            file.encoder.flush().map_err(|_| "Can't flush records")?;
+            file.flushed_at = Instant::now();
+        }
+
+        Ok(())
+    }
+
+    // The first part of the day which doesn't exist yet is created
+    fn open(
+        &self,
+        gateway: &str,
+        symbol: &str,
+        day: NaiveDate,
+    ) -> Result<RecordFile, &'static str> {
+        let mut part = 0;
+
+        let (path, file) = loop {
+            let path = Self::file_path(&self.dir, gateway, symbol, day, part);
+
+            let file = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| OpenOptions::new().write(true).create_new(true).open(&path));
+
+            match file {
+                Ok(file) => break (path, file),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => part += 1,
+                Err(e) => {
+                    error!("[Recorder] Can't open {:?}: {}", path, e);
+                    return Err("Can't open records file");
+                }
+            }
+        };
+
+        info!("[Recorder] Records to {:?}", path);
+
+        Ok(RecordFile {
+            day,
+            encoder: GzEncoder::new(file, Compression::default()),
+            flushed_at: Instant::now(),
+        })
+    }
+
+    fn finish(file: RecordFile) {
+        if let Err(e) = file.encoder.finish() {
+            error!("[Recorder] Can't finish records file: {}", e);
+        }
+    }
+
+    // Completes the files, e.g. when Context Manager is stopped
+    pub fn finish_all(&self) {
+        for (_, file) in self.files.lock().unwrap().drain() {
+            Self::finish(file);
+        }
+    }
+
+    // Records of the file in their order. Records after the damage, e.g. the unfinished end
+    // of the crashed recorder, are skipped.
+    pub fn read(path: &Path) -> Result<Vec<Record>, &'static str> {
+        let file = File::open(path).map_err(|_| "Can't open records file")?;
+        let mut decoder = MultiGzDecoder::new(BufReader::new(file));
+        let mut records = Vec::new();
+
+        loop {
+            match bincode::deserialize_from(&mut decoder) {
+                Ok(record) => records.push(record),
+                Err(e) => match *e {
+                    bincode::ErrorKind::Io(ref e) if e.kind() == ErrorKind::UnexpectedEof => {
+                        return Ok(records)
+                    }
+                    _ => {
+                        error!(
+                            "[Recorder] Damaged records file {:?} after {} records: {}",
+                            path,
+                            records.len(),
+                            e
+                        );
+                        return Ok(records);
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{MarketRecorder, Record, RecordEvent};
+    use crate::order_manager::OrderSide;
+    use chrono::NaiveDate;
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+    use std::mem;
+
+    fn depth(price: f64) -> RecordEvent {
+        RecordEvent::Depth {
+            bids: vec![(price, 1.)],
+            asks: vec![(price + 1., 2.)],
+        }
+    }
+
+    #[test]
+    fn daily_files() {
+        let dir = env::temp_dir().join(format!("mx_records_{}", std::process::id()));
+        let recorder = MarketRecorder::new(dir.to_str().unwrap(), vec!["Binance".to_string()]);
+
+        // 2021-06-01 10:00 and 2021-06-02 00:00 UTC
+        let first_day = Record {
+            timestamp: 1_622_541_600_000,
+            event: depth(100.),
+        };
+        let fill = Record {
+            timestamp: 1_622_541_600_001,
+            event: RecordEvent::Fill {
+                custom_order_id: "Custom123".to_string(),
+                order_id: 42,
+                order_side: OrderSide::Buy,
+                amount: 1.,
+                price: 101.,
+            },
+        };
+        let next_day = Record {
+            timestamp: 1_622_592_000_000,
+            event: depth(102.),
+        };
+
+        recorder
+            .write("Binance", "BTCUSDT", first_day.clone())
+            .unwrap();
+        recorder.write("Binance", "BTCUSDT", fill.clone()).unwrap();
+        recorder
+            .write("Binance", "BTCUSDT", next_day.clone())
+            .unwrap();
+        recorder.finish_all();
+
+        // Restarted recorder writes the next part of the day
+        recorder
+            .write("Binance", "BTCUSDT", next_day.clone())
+            .unwrap();
+        recorder.finish_all();
+
+        let path = |day, part| MarketRecorder::file_path(&dir, "Binance", "BTCUSDT", day, part);
+
+        assert_eq!(
+            MarketRecorder::read(&path(NaiveDate::from_ymd(2021, 6, 1), 0)).unwrap(),
+            vec![first_day, fill]
+        );
+        assert_eq!(
+            MarketRecorder::read(&path(NaiveDate::from_ymd(2021, 6, 2), 0)).unwrap(),
+            vec![next_day.clone()]
+        );
+        assert_eq!(
+            MarketRecorder::read(&path(NaiveDate::from_ymd(2021, 6, 2), 1)).unwrap(),
+            vec![next_day]
+        );
+
+        assert_eq!(
+            MarketRecorder::record_files(&dir.join("Binance").join("BTCUSDT")).unwrap(),
+            vec![
+                path(NaiveDate::from_ymd(2021, 6, 1), 0),
+                path(NaiveDate::from_ymd(2021, 6, 2), 0),
+                path(NaiveDate::from_ymd(2021, 6, 2), 1),
+            ]
+        );
+
+        // Gateway isn't recorded
+        recorder.record("Huobi", "BTCUSDT", depth(100.)).unwrap();
+        assert!(!dir.join("Huobi").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn restart_after_crash() {
+        let dir = env::temp_dir().join(format!("mx_records_crash_{}", std::process::id()));
+        let day = NaiveDate::from_ymd(2021, 6, 1);
+
+        // 2021-06-01 10:00 UTC
+        let record = |timestamp, price| Record {
+            timestamp,
+            event: depth(price),
+        };
+
+        let recorder = MarketRecorder::new(dir.to_str().unwrap(), vec!["Binance".to_string()]);
+
+        for (index, price) in [100., 101.].iter().enumerate() {
+            recorder
+                .write(
+                    "Binance",
+                    "BTCUSDT",
+                    record(1_622_541_600_000 + index as u64, *price),
+                )
+                .unwrap();
+        }
+
+        // Crashed recorder has flushed its records, but the gzip member isn't finished
+        for file in recorder.files.lock().unwrap().values_mut() {
+            file.encoder.flush().unwrap();
+        }
+        mem::forget(recorder);
+
+        let recorder = MarketRecorder::new(dir.to_str().unwrap(), vec!["Binance".to_string()]);
+        recorder
+            .write("Binance", "BTCUSDT", record(1_622_541_700_000, 102.))
+            .unwrap();
+        recorder.finish_all();
+
+        let files = MarketRecorder::record_files(&dir.join("Binance").join("BTCUSDT")).unwrap();
+        assert_eq!(files.len(), 2);
+
+        // Records before the damage are kept
+        assert_eq!(
+            MarketRecorder::read(&files[0]).unwrap(),
+            vec![
+                record(1_622_541_600_000, 100.),
+                record(1_622_541_600_001, 101.)
+            ]
+        );
+        assert_eq!(
+            MarketRecorder::read(&files[1]).unwrap(),
+            vec![record(1_622_541_700_000, 102.)]
+        );
+        assert_eq!(
+            files[1],
+            MarketRecorder::file_path(&dir, "Binance", "BTCUSDT", day, 1)
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
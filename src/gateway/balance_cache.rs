@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+// Reservation smaller than that is considered released, e.g. after float rounding
+const RESERVATION_EPSILON: f64 = 1e-12;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Balance {
+    pub free: f64,
+    pub locked: f64,
+}
+
+// Funds held by the open order until it's filled or canceled
+#[derive(Debug, Clone, PartialEq)]
+struct Reservation {
+    account: String,
+    asset: String,
+    amount: f64,
+    // Reserved funds per unit of the base asset: price for buy and 1 for sell
+    per_unit: f64,
+}
+
+// Balances of every account, they're seeded from REST and updated by the user stream.
+// Assets are kept in upper case, exchanges report them in different cases.
+#[derive(Debug, Default)]
+pub struct BalanceCache {
+    // <Account, <Asset, Balance>>
+    balances: RwLock<HashMap<String, HashMap<String, Balance>>>,
+    // <Custom order id, Reservation>
+    reservations: RwLock<HashMap<String, Reservation>>,
+}
+
+impl BalanceCache {
+    pub fn new() -> Self {
+        BalanceCache::default()
+    }
+
+    pub fn is_seeded(&self, account: &str) -> bool {
+        self.balances.read().unwrap().contains_key(account)
+    }
+
+    // REST returns free funds only, reserved funds of open orders are counted as locked
+    pub fn seed(&self, account: &str, balances: HashMap<String, f64>) {
+        let account_balances = balances
+            .into_iter()
+            .map(|(asset, free)| {
+                let asset = asset.to_uppercase();
+                let locked = self.reserved(account, &asset);
+
+                (asset, Balance { free, locked })
+            })
+            .collect();
+
+        self.balances
+            .write()
+            .unwrap()
+            .insert(account.to_string(), account_balances);
+    }
+
+    // Balances are seeded again, reservations of open orders are kept
+    pub fn clear(&self) {
+        self.balances.write().unwrap().clear();
+    }
+
+    // Balance pushed by the exchange replaces the cached one
+    pub fn update(&self, account: &str, asset: &str, balance: Balance) {
+        self.balances
+            .write()
+            .unwrap()
+            .entry(account.to_string())
+            .or_default()
+            .insert(asset.to_uppercase(), balance);
+    }
+
+    // New reservation of the same order replaces the previous one
+    pub fn reserve(&self, order_id: &str, account: &str, asset: &str, amount: f64, per_unit: f64) {
+        self.reservations.write().unwrap().insert(
+            order_id.to_string(),
+            Reservation {
+                account: account.to_string(),
+                asset: asset.to_uppercase(),
+                amount,
+                per_unit,
+            },
+        );
+    }
+
+    // Filled amount of the base asset is released from the order reservation
+    pub fn release(&self, order_id: &str, filled_amount: f64) {
+        let mut reservations = self.reservations.write().unwrap();
+
+        let released = match reservations.get_mut(order_id) {
+            Some(reservation) => {
+                reservation.amount -= filled_amount * reservation.per_unit;
+                reservation.amount <= RESERVATION_EPSILON
+            }
+            None => false,
+        };
+
+        if released {
+            reservations.remove(order_id);
+        }
+    }
+
+    // Canceled or rejected order doesn't hold funds anymore
+    pub fn release_all(&self, order_id: &str) {
+        self.reservations.write().unwrap().remove(order_id);
+    }
+
+    pub fn reserved(&self, account: &str, asset: &str) -> f64 {
+        let asset = asset.to_uppercase();
+
+        self.reservations
+            .read()
+            .unwrap()
+            .values()
+            .filter(|reservation| reservation.account == account && reservation.asset == asset)
+            .map(|reservation| reservation.amount)
+            .sum()
+    }
+
+    // Funds of the order if they're reserved in the asset of the account
+    pub fn reserved_by(&self, order_id: &str, account: &str, asset: &str) -> f64 {
+        let asset = asset.to_uppercase();
+
+        match self.reservations.read().unwrap().get(order_id) {
+            Some(reservation) if reservation.account == account && reservation.asset == asset => {
+                reservation.amount
+            }
+            _ => 0.,
+        }
+    }
+
+    // Exchange doesn't lock funds of the just sent orders yet,
+    // so available is the smaller of free and total minus reserved funds.
+    // It returns None if the account wasn't seeded.
+    pub fn available(&self, account: &str, asset: &str) -> Option<f64> {
+        let balances = self.balances.read().unwrap();
+        let account_balances = balances.get(account)?;
+
+        let balance = account_balances
+            .get(&asset.to_uppercase())
+            .cloned()
+            .unwrap_or_default();
+
+        let reserved = self.reserved(account, asset);
+
+        Some(balance.free.min(balance.free + balance.locked - reserved))
+    }
+
+    pub fn balances(&self, account: &str) -> HashMap<String, Balance> {
+        self.balances
+            .read()
+            .unwrap()
+            .get(account)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // Balances of all accounts in readable form
+    pub fn info(&self) -> String {
+        let balances = self.balances.read().unwrap();
+
+        let mut lines = balances
+            .iter()
+            .flat_map(|(account, account_balances)| {
+                account_balances.iter().map(move |(asset, balance)| {
+                    format!(
+                        "{} {}: free {}, locked {}, reserved {}",
+                        account,
+                        asset,
+                        balance.free,
+                        balance.locked,
+                        self.reserved(account, asset)
+                    )
+                })
+            })
+            .collect::<Vec<String>>();
+
+        lines.sort();
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Balance, BalanceCache};
+    use std::collections::HashMap;
+
+    fn cache() -> BalanceCache {
+        let cache = BalanceCache::new();
+
+        let mut balances = HashMap::new();
+        balances.insert("btc".to_string(), 2.);
+        balances.insert("USDT".to_string(), 1000.);
+
+        cache.seed("Account1", balances);
+
+        cache
+    }
+
+    #[test]
+    fn unknown_account() {
+        assert_eq!(cache().available("Account2", "BTC"), None);
+        assert_eq!(cache().available("Account1", "ETH"), Some(0.));
+    }
+
+    #[test]
+    fn reserve_and_release() {
+        let cache = cache();
+
+        // Buy 2 BTC by 300
+        cache.reserve("Order1", "Account1", "USDT", 600., 300.);
+        assert_eq!(cache.available("Account1", "USDT"), Some(400.));
+
+        // Partial fill
+        cache.release("Order1", 1.);
+        assert_eq!(cache.available("Account1", "USDT"), Some(700.));
+
+        assert_eq!(cache.reserved_by("Order1", "Account1", "usdt"), 300.);
+        assert_eq!(cache.reserved_by("Order1", "Account1", "BTC"), 0.);
+
+        cache.release("Order1", 1.);
+        assert_eq!(cache.reserved("Account1", "USDT"), 0.);
+
+        cache.reserve("Order2", "Account1", "BTC", 1.5, 1.);
+        assert_eq!(cache.available("Account1", "btc"), Some(0.5));
+
+        cache.release_all("Order2");
+        assert_eq!(cache.available("Account1", "BTC"), Some(2.));
+    }
+
+    #[test]
+    fn reservation_locked_by_exchange() {
+        let cache = cache();
+
+        cache.reserve("Order1", "Account1", "USDT", 600., 300.);
+
+        // Exchange has locked the order funds, they aren't subtracted twice
+        cache.update(
+            "Account1",
+            "usdt",
+            Balance {
+                free: 400.,
+                locked: 600.,
+            },
+        );
+
+        assert_eq!(cache.available("Account1", "USDT"), Some(400.));
+
+        // Reserved funds are locked after reseed from REST
+        let mut balances = HashMap::new();
+        balances.insert("USDT".to_string(), 400.);
+        cache.seed("Account1", balances);
+
+        assert_eq!(cache.balances("Account1")["USDT"].locked, 600.);
+        assert_eq!(cache.available("Account1", "USDT"), Some(400.));
+    }
+}
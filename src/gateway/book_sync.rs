@@ -0,0 +1,307 @@
+use super::{Backoff, OrderBook};
+use std::collections::{HashMap, VecDeque};
+use std::mem;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+// Updates kept while the book waits for its snapshot
+const BOOK_SYNC_BUFFER_SIZE: usize = 1000;
+
+// Delays between snapshot requests of the book which isn't synced yet
+const SNAPSHOT_BACKOFF_INITIAL: Duration = Duration::from_secs(1);
+const SNAPSHOT_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+// Full book with id of the last update included into it
+#[derive(Debug, Clone)]
+pub struct BookSnapshot {
+    pub last_update_id: u64,
+    // [price, size]
+    pub bids: Vec<[f64; 2]>,
+    pub asks: Vec<[f64; 2]>,
+}
+
+// Changed levels, zero size removes the level.
+// Update covers ids from first_update_id to last_update_id,
+// e.g. Binance U and u, Huobi prevSeqNum + 1 and seqNum.
+#[derive(Debug, Clone)]
+pub struct BookUpdate {
+    pub first_update_id: u64,
+    pub last_update_id: u64,
+    pub bids: Vec<[f64; 2]>,
+    pub asks: Vec<[f64; 2]>,
+}
+
+// Local order book maintained from snapshot and incremental updates
+#[derive(Debug)]
+pub struct BookSync {
+    book: OrderBook,
+    // Id of the last applied update, None until snapshot is applied
+    last_update_id: Option<u64>,
+    // Updates received before snapshot
+    buffer: VecDeque<BookUpdate>,
+}
+
+impl BookSync {
+    pub fn new(symbol: &str, gateway_name: &str) -> Self {
+        BookSync {
+            book: OrderBook::from_vec(symbol, gateway_name, vec![], vec![]),
+            last_update_id: None,
+            buffer: VecDeque::new(),
+        }
+    }
+
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.last_update_id.is_some()
+    }
+
+    // Drops the book, it waits for a new snapshot
+    pub fn reset(&mut self) {
+        self.book.clear();
+        self.last_update_id = None;
+        self.buffer.clear();
+    }
+
+    // Replaces the book and applies buffered updates on top of it
+    pub fn apply_snapshot(&mut self, snapshot: BookSnapshot) -> Result<(), &'static str> {
+        self.book.clear();
+
+        for [price, qty] in snapshot.bids {
+            self.book.update_bid(price, qty);
+        }
+
+        for [price, qty] in snapshot.asks {
+            self.book.update_ask(price, qty);
+        }
+
+        self.last_update_id = Some(snapshot.last_update_id);
+
+        for update in mem::take(&mut self.buffer) {
+            self.apply_update(update)?;
+        }
+
+        Ok(())
+    }
+
+    // It returns true if the book was changed.
+    // Error means a gap in sequence, the book is reset and needs a new snapshot.
+    pub fn apply_update(&mut self, update: BookUpdate) -> Result<bool, &'static str> {
+        let last_update_id = match self.last_update_id {
+            Some(last_update_id) => last_update_id,
+            None => {
+                if self.buffer.len() == BOOK_SYNC_BUFFER_SIZE {
+                    self.buffer.pop_front();
+                }
+
+                self.buffer.push_back(update);
+
+                return Ok(false);
+            }
+        };
+
+        // Update is already included into the book
+        if update.last_update_id <= last_update_id {
+            return Ok(false);
+        }
+
+        // Update could overlap the book but must not skip ids
+        if update.first_update_id > last_update_id + 1 {
+            debug!(
+                "[Book Sync] {} gap: last id {}, update {}..{}",
+                self.book.instrument_name,
+                last_update_id,
+                update.first_update_id,
+                update.last_update_id
+            );
+
+            self.reset();
+
+            return Err("Order book sequence gap");
+        }
+
+        for [price, qty] in update.bids {
+            self.book.update_bid(price, qty);
+        }
+
+        for [price, qty] in update.asks {
+            self.book.update_ask(price, qty);
+        }
+
+        self.last_update_id = Some(update.last_update_id);
+
+        Ok(true)
+    }
+}
+
+// Spreads snapshot requests of unsynced books by backoff per symbol.
+// Persistent gap or stale snapshots don't request a heavy snapshot on every update.
+#[derive(Debug, Default)]
+pub struct SnapshotSchedule {
+    // <Symbol, (Backoff, Time of the next request)>
+    symbols: HashMap<String, (Backoff, Instant)>,
+}
+
+impl SnapshotSchedule {
+    pub fn new() -> Self {
+        SnapshotSchedule::default()
+    }
+
+    pub fn is_due(&self, symbol: &str, now: Instant) -> bool {
+        self.symbols
+            .get(symbol)
+            .map_or(true, |(_, next_request_at)| now >= *next_request_at)
+    }
+
+    // The next request of the symbol waits for the next backoff delay
+    pub fn requested(&mut self, symbol: &str, now: Instant) {
+        let (backoff, next_request_at) =
+            self.symbols.entry(symbol.to_string()).or_insert_with(|| {
+                (
+                    Backoff::new(SNAPSHOT_BACKOFF_INITIAL, SNAPSHOT_BACKOFF_MAX),
+                    now,
+                )
+            });
+
+        *next_request_at = now + backoff.next_delay();
+    }
+
+    // Synced book starts backoff from the initial delay
+    pub fn synced(&mut self, symbol: &str) {
+        if let Some((backoff, _)) = self.symbols.get_mut(symbol) {
+            backoff.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn snapshot(last_update_id: u64) -> BookSnapshot {
+        BookSnapshot {
+            last_update_id,
+            bids: vec![[99., 1.], [98., 2.]],
+            asks: vec![[101., 1.], [102., 2.]],
+        }
+    }
+
+    fn update(first_update_id: u64, last_update_id: u64, bids: Vec<[f64; 2]>) -> BookUpdate {
+        BookUpdate {
+            first_update_id,
+            last_update_id,
+            bids,
+            asks: vec![],
+        }
+    }
+
+    #[test]
+    fn apply_snapshot() {
+        let mut book_sync = BookSync::new("BTCUSDT", "Binance");
+        assert!(!book_sync.is_synced());
+
+        book_sync.apply_snapshot(snapshot(100)).unwrap();
+
+        assert!(book_sync.is_synced());
+        assert_eq!(
+            book_sync
+                .book()
+                .bids_volume_iter()
+                .collect::<Vec<(f64, f64)>>(),
+            vec![(99., 1.), (98., 2.)]
+        );
+    }
+
+    #[test]
+    fn apply_updates_in_sequence() {
+        let mut book_sync = BookSync::new("BTCUSDT", "Binance");
+        book_sync.apply_snapshot(snapshot(100)).unwrap();
+
+        // First update overlaps snapshot
+        assert!(book_sync
+            .apply_update(update(95, 101, vec![[99., 3.]]))
+            .unwrap());
+        // Zero size removes level
+        assert!(book_sync
+            .apply_update(update(102, 103, vec![[98., 0.]]))
+            .unwrap());
+
+        assert_eq!(
+            book_sync
+                .book()
+                .bids_volume_iter()
+                .collect::<Vec<(f64, f64)>>(),
+            vec![(99., 3.)]
+        );
+    }
+
+    #[test]
+    fn skip_old_updates() {
+        let mut book_sync = BookSync::new("BTCUSDT", "Binance");
+        book_sync.apply_snapshot(snapshot(100)).unwrap();
+
+        assert!(!book_sync
+            .apply_update(update(90, 100, vec![[99., 5.]]))
+            .unwrap());
+        assert_eq!(book_sync.book().bids_volume_iter().next(), Some((99., 1.)));
+    }
+
+    #[test]
+    fn gap_resets_book() {
+        let mut book_sync = BookSync::new("BTCUSDT", "Binance");
+        book_sync.apply_snapshot(snapshot(100)).unwrap();
+
+        assert!(book_sync.apply_update(update(105, 106, vec![])).is_err());
+        assert!(!book_sync.is_synced());
+        assert_eq!(book_sync.book().bids_volume_iter().count(), 0);
+    }
+
+    #[test]
+    fn buffered_updates_applied_after_snapshot() {
+        let mut book_sync = BookSync::new("BTCUSDT", "Huobi");
+
+        // Received before snapshot
+        book_sync
+            .apply_update(update(98, 100, vec![[97., 1.]]))
+            .unwrap();
+        book_sync
+            .apply_update(update(101, 102, vec![[99., 4.]]))
+            .unwrap();
+
+        book_sync.apply_snapshot(snapshot(100)).unwrap();
+
+        assert_eq!(
+            book_sync
+                .book()
+                .bids_volume_iter()
+                .collect::<Vec<(f64, f64)>>(),
+            vec![(99., 4.), (98., 2.)]
+        );
+    }
+
+    #[test]
+    fn snapshot_requests_backoff() {
+        let now = Instant::now();
+        let mut schedule = SnapshotSchedule::new();
+
+        assert!(schedule.is_due("BTCUSDT", now));
+        schedule.requested("BTCUSDT", now);
+
+        // Unsynced book waits 1s, then 2s for the next snapshot
+        assert!(!schedule.is_due("BTCUSDT", now + Duration::from_millis(500)));
+        assert!(schedule.is_due("BTCUSDT", now + Duration::from_secs(1)));
+        assert!(schedule.is_due("ETHUSDT", now));
+
+        schedule.requested("BTCUSDT", now + Duration::from_secs(1));
+        assert!(!schedule.is_due("BTCUSDT", now + Duration::from_millis(2_500)));
+        assert!(schedule.is_due("BTCUSDT", now + Duration::from_secs(3)));
+
+        // Backoff starts from scratch after the book is synced
+        schedule.synced("BTCUSDT");
+        schedule.requested("BTCUSDT", now + Duration::from_secs(3));
+        assert!(schedule.is_due("BTCUSDT", now + Duration::from_secs(4)));
+    }
+}
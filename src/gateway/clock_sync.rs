@@ -0,0 +1,110 @@
+use chrono::Utc;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Exchange server time is sampled so often
+pub const CLOCK_SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+// Number of the last samples which the offset is estimated by
+const CLOCK_SAMPLES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockSample {
+    // Server time - local time in milliseconds
+    pub offset: i64,
+    // Round trip of the server time request in milliseconds
+    pub rtt: i64,
+}
+
+// Estimates the exchange clock offset by its server time.
+// Server time is taken at the middle of the request round trip,
+// so the sample with the shortest round trip is the most precise one.
+#[derive(Debug, Default)]
+pub struct ClockSync {
+    samples: Mutex<VecDeque<ClockSample>>,
+}
+
+impl ClockSync {
+    pub fn new() -> Self {
+        ClockSync::default()
+    }
+
+    // Requests the server time in unix milliseconds, it returns the new estimate
+    pub fn sample<F>(&self, server_time: F) -> Result<ClockSample, &'static str>
+    where
+        F: FnOnce() -> Result<u64, &'static str>,
+    {
+        let sent_at = Utc::now().timestamp_millis();
+        let server_time = server_time()?;
+        let received_at = Utc::now().timestamp_millis();
+
+        Ok(self.add_sample(sent_at, server_time as i64, received_at))
+    }
+
+    // Local times are when the request was sent and when the response was received
+    pub fn add_sample(&self, sent_at: i64, server_time: i64, received_at: i64) -> ClockSample {
+        let mut samples = self.samples.lock().unwrap();
+
+        samples.push_back(ClockSample {
+            offset: server_time - (sent_at + received_at) / 2,
+            rtt: received_at - sent_at,
+        });
+
+        if samples.len() > CLOCK_SAMPLES {
+            samples.pop_front();
+        }
+
+        Self::best(&samples).unwrap()
+    }
+
+    pub fn estimate(&self) -> Option<ClockSample> {
+        Self::best(&self.samples.lock().unwrap())
+    }
+
+    fn best(samples: &VecDeque<ClockSample>) -> Option<ClockSample> {
+        samples.iter().min_by_key(|sample| sample.rtt).copied()
+    }
+
+    // Samples of the previous exchange are dropped, e.g. on set config
+    pub fn clear(&self) {
+        self.samples.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ClockSample, ClockSync, CLOCK_SAMPLES};
+
+    #[test]
+    fn offset_of_shortest_round_trip() {
+        let clock_sync = ClockSync::new();
+        assert_eq!(clock_sync.estimate(), None);
+
+        // Server clock is 500 ms ahead, the slow response is delayed on the way back
+        clock_sync.add_sample(1_000, 1_550, 1_100);
+        clock_sync.add_sample(2_000, 2_510, 2_400);
+
+        assert_eq!(
+            clock_sync.estimate(),
+            Some(ClockSample {
+                offset: 500,
+                rtt: 100,
+            })
+        );
+
+        // The precise sample is dropped by the newer ones
+        for i in 0..CLOCK_SAMPLES as i64 {
+            clock_sync.add_sample(i * 1_000, i * 1_000 + 700, i * 1_000 + 200);
+        }
+        assert_eq!(clock_sync.estimate().unwrap().offset, 600);
+
+        assert!(clock_sync
+            .sample(|| Err("Server time isn't supported"))
+            .is_err());
+
+        clock_sync.clear();
+        assert_eq!(clock_sync.estimate(), None);
+    }
+}
@@ -1,3 +1,4 @@
+use super::secrets::{Secret, SecretsConfig};
 use crate::config::ParseConfig;
 use serde_derive::{Deserialize, Serialize};
 use std::cmp::PartialEq;
@@ -12,14 +13,42 @@ pub struct GatewayConfig {
     pub instruments: Vec<Instrument>,
     pub fees: Vec<Fee>,
     pub limit: Limit,
+    // AdoptKnown or CancelAll, AdoptKnown if it's missing
+    pub reconcile_policy: Option<String>,
+    // Market data of Replay exchange
+    pub replay: Option<Replay>,
+    // Candle intervals which are built for every symbol: 1s, 1m, 5m or 1h
+    pub candles: Option<Vec<String>>,
+    // Files which the account secrets are referenced to
+    pub secrets: Option<SecretsConfig>,
 }
 
 #[derive(Deserialize, Debug, Serialize)]
 pub struct Account {
     pub name: String,
     pub account_id: Option<String>,
-    pub api_key: String,
-    pub secret_key: String,
+    // env:NAME, file:NAME or keystore:NAME, plain value is still accepted
+    pub api_key: Secret,
+    pub secret_key: Secret,
+    // Session of FIX exchange
+    pub fix: Option<FixSession>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct FixSession {
+    pub address: String,
+    pub sender_comp_id: String,
+    pub target_comp_id: String,
+    pub heartbeat_interval: Option<u64>,
+    pub store_dir: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct Replay {
+    // Directory of the recorded gateway or CSV file of depth snapshots
+    pub source: String,
+    // 1 is the original timing, 0 is as fast as possible
+    pub speed: Option<f64>,
 }
 
 #[derive(Deserialize, Debug, Serialize)]
@@ -55,14 +84,16 @@ impl Default for GatewayConfig {
                 Account {
                     name: "Account1".to_string(),
                     account_id: Some("12345".to_string()),
-                    api_key: "API_KEY1".to_string(),
-                    secret_key: "SECRET_KEY1".to_string(),
+                    api_key: Secret::from("API_KEY1"),
+                    secret_key: Secret::from("SECRET_KEY1"),
+                    fix: None,
                 },
                 Account {
                     name: "Account2".to_string(),
                     account_id: Some("67890".to_string()),
-                    api_key: "API_KEY2".to_string(),
-                    secret_key: "SECRET_KEY2".to_string(),
+                    api_key: Secret::from("API_KEY2"),
+                    secret_key: Secret::from("SECRET_KEY2"),
+                    fix: None,
                 },
             ],
 
@@ -95,6 +126,14 @@ impl Default for GatewayConfig {
             ],
 
             limit: Limit { rps: 10 },
+
+            reconcile_policy: None,
+
+            replay: None,
+
+            candles: None,
+
+            secrets: None,
         }
     }
 }
@@ -137,6 +176,8 @@ impl PartialEq for GatewayConfig {
             && self.instruments == other.instruments
             && self.fees == other.fees
             && self.limit == other.limit
+            && self.reconcile_policy == other.reconcile_policy
+            && self.secrets == other.secrets
     }
 }
 
@@ -29,7 +29,9 @@ mod tests {
         // Binance API throws an error if price too high or too low
         let price = 30_000.;
 
-        binance_account.limit_buy(symbol, amount, price).unwrap();
+        binance_account
+            .limit_buy(symbol, amount, price, None)
+            .unwrap();
     }
 
     #[test]
@@ -59,7 +61,9 @@ mod tests {
         let amount = 1.;
         let price = 1_000_000.;
 
-        binance_account.limit_sell(symbol, amount, price).unwrap();
+        binance_account
+            .limit_sell(symbol, amount, price, None)
+            .unwrap();
     }
 
     #[test]
@@ -157,7 +161,7 @@ mod tests {
 
         thread::sleep(Duration::from_secs(5));
 
-        match binance_account.limit_buy(symbol, amount, price) {
+        match binance_account.limit_buy(symbol, amount, price, None) {
             Ok(trx) => {
                 println!("trx {:?}", trx);
 
@@ -257,16 +261,7 @@ mod tests {
 
         thread::sleep(Duration::from_secs(5));
 
-        match binance_account.custom_order(
-            symbol,
-            amount,
-            price,
-            None,
-            binance::account::OrderSide::Buy,
-            binance::account::OrderType::Limit,
-            binance::account::TimeInForce::GTC,
-            Some(custom_order_id.to_string().clone()),
-        ) {
+        match binance_account.limit_buy(symbol, amount, price, Some(custom_order_id.to_string())) {
             Ok(trx) => {
                 println!("trx {:?}", trx);
 
@@ -274,9 +269,7 @@ mod tests {
 
                 thread::sleep(Duration::from_secs(5));
 
-                match binance_account
-                    .cancel_order_with_client_id(symbol, custom_order_id.to_string())
-                {
+                match binance_account.cancel_order_with_client_id(symbol, custom_order_id) {
                     Ok(order_canceled) => println!("canceled {:?}", order_canceled),
                     Err(e) => println!("error {:?}", e),
                 }
@@ -597,24 +590,13 @@ mod tests {
 
         let order_time = Instant::now();
 
-        match binance_account.custom_order(
-            symbol,
-            amount,
-            price,
-            None,
-            binance::account::OrderSide::Buy,
-            binance::account::OrderType::Limit,
-            binance::account::TimeInForce::GTC,
-            Some(custom_order_id.to_string()),
-        ) {
+        match binance_account.limit_buy(symbol, amount, price, Some(custom_order_id.to_string())) {
             Ok(_trx) => {
                 println!("Order time {:?}", order_time.elapsed());
 
                 let cancel_time = Instant::now();
 
-                match binance_account
-                    .cancel_order_with_client_id(symbol, custom_order_id.to_string())
-                {
+                match binance_account.cancel_order_with_client_id(symbol, custom_order_id) {
                     Ok(_order_canceled) => println!("Cancel time {:?}", cancel_time.elapsed()),
 
                     Err(e) => println!("error {:?}", e),
@@ -667,7 +649,7 @@ mod tests {
 
         let params_account = GatewayParamsUtils::binance_test_params();
 
-        let api_key_user = Some(params_account.api_key.into());
+        let api_key_user = Some(params_account.api_key.expose().to_string());
         let keep_running = AtomicBool::new(true);
         let user_stream: UserStream = Binance::new(api_key_user, None);
 
@@ -707,4 +689,87 @@ mod tests {
             println!("Not able to start an User Stream (Check your API_KEY)");
         }
     }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn limit_buy_bitmex() {
+        let params_account = GatewayParamsUtils::bitmex_test_params();
+        let bitmex_account = Accounts::bitmex(&params_account);
+
+        let symbol = "XBTUSD";
+        // Amount in contracts
+        let amount = 100.;
+        let price = 9000.;
+
+        bitmex_account
+            .limit_buy(symbol, amount, price, None)
+            .unwrap();
+    }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn get_open_orders_bitmex() {
+        let params_account = GatewayParamsUtils::bitmex_test_params();
+
+        let bitmex_account = Accounts::bitmex(&params_account);
+
+        let open_orders = bitmex_account.get_open_orders("XBTUSD");
+
+        println!("Open orders {:#?}", open_orders);
+    }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn cancel_open_orders_by_symbol_bitmex() {
+        let params_account = GatewayParamsUtils::bitmex_test_params();
+
+        let bitmex_account = Accounts::bitmex(&params_account);
+
+        match bitmex_account.cancel_all_orders(Some("XBTUSD")) {
+            Ok(orders_canceled) => println!("canceled {:?}", orders_canceled),
+            Err(e) => println!("error {:?}", e),
+        }
+    }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn get_margin_bitmex() {
+        let params_account = GatewayParamsUtils::bitmex_test_params();
+
+        let bitmex_account = Accounts::bitmex(&params_account);
+
+        println!("Margin {:#?}", bitmex_account.get_margin("XBt"));
+    }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn order_websocket_bitmex() {
+        use crate::api::bitmex::websocket_account::{WebSockets, WebsocketEvent};
+
+        let params_account = GatewayParamsUtils::bitmex_test_params();
+        let keep_running = AtomicBool::new(true);
+
+        let mut web_socket = WebSockets::new(|event: WebsocketEvent| {
+            match event {
+                WebsocketEvent::Execution(executions) => println!("{:#?}", executions),
+            }
+            Ok(())
+        });
+
+        web_socket
+            .connect_auth(
+                params_account.api_key.expose(),
+                params_account.secret_key.expose(),
+            )
+            .unwrap();
+
+        if let Err(e) = web_socket.event_loop(&keep_running) {
+            println!("Error: {:?}", e);
+        }
+    }
 }
@@ -1,7 +1,51 @@
-use crate::gateway::{gateway::ExchangeInstrumentInfo, Depth, Instrument};
+use crate::context_manager::{Candle, CandleInterval, FilledOrder};
+use crate::gateway::{
+    gateway::ExchangeInstrumentInfo, Depth, Instrument, PublicTrade, RequestClass,
+};
+use crate::order_manager::{
+    CancelOrder, LimitOrder, OrderEvent, OrderSide, StopOrder, TimeInForce,
+};
 use std::collections::HashMap;
 
 pub type ExchangeApiResult<T> = Result<T, &'static str>;
+
+// Events of the private user data stream
+#[derive(Debug, Clone)]
+pub enum UserEvent {
+    FilledOrder(FilledOrder),
+    BalanceUpdate(BalanceUpdate),
+    OrderUpdate(OrderUpdate),
+}
+
+// Order event other than fill, e.g. acknowledge or cancel.
+// Order is found by custom id or by exchange order id if the custom one is unknown.
+#[derive(Debug, Clone)]
+pub struct OrderUpdate {
+    pub custom_order_id: String,
+    pub order_id: u64,
+    pub symbol: String,
+    pub event: OrderEvent,
+}
+
+// Balance of the asset after its change
+#[derive(Debug, Clone)]
+pub struct BalanceUpdate {
+    pub asset: String,
+    pub free: f64,
+    pub locked: f64,
+}
+
+// Order which is open on the exchange, amount is its unfilled part
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenOrder {
+    pub symbol: String,
+    pub order_id: u64,
+    pub custom_order_id: String,
+    pub order_side: OrderSide,
+    pub price: f64,
+    pub amount: f64,
+}
+
 pub trait ExchangeAction: Sync + Send {
     fn inti(&self);
 
@@ -30,6 +74,55 @@ pub trait ExchangeAction: Sync + Send {
         custom_order_id: Option<String>,
     ) -> ExchangeApiResult<PlatformTransaction>;
 
+    // Limit order with its time in force, by default GTC orders only are supported
+    fn limit_order(&self, limit_order: &LimitOrder) -> ExchangeApiResult<PlatformTransaction> {
+        let custom_order_id = Some(limit_order.custom_order_id.clone());
+
+        match (limit_order.time_in_force, &limit_order.order_side) {
+            (TimeInForce::Gtc, OrderSide::Buy) => self.limit_buy(
+                &limit_order.symbol,
+                limit_order.amount,
+                limit_order.price,
+                custom_order_id,
+            ),
+            (TimeInForce::Gtc, OrderSide::Sell) => self.limit_sell(
+                &limit_order.symbol,
+                limit_order.amount,
+                limit_order.price,
+                custom_order_id,
+            ),
+            _ => Err("Time in force isn't supported"),
+        }
+    }
+
+    // Stop order which waits for the stop price on the exchange
+    fn stop_order(&self, _stop_order: &StopOrder) -> ExchangeApiResult<PlatformTransaction> {
+        Err("Stop orders aren't supported")
+    }
+
+    // Amends the open order to the new price and amount in one request
+    fn replace_order(
+        &self,
+        _cancel_order: &CancelOrder,
+        _limit_order: &LimitOrder,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        Err("Amend isn't supported")
+    }
+
+    // Without amend the gateway cancels the order and sends the new one after that
+    fn native_replace_order(&self) -> bool {
+        false
+    }
+
+    // Order types which aren't native are emulated by the gateway
+    fn native_time_in_force(&self, time_in_force: &TimeInForce) -> bool {
+        *time_in_force == TimeInForce::Gtc
+    }
+
+    fn native_stop_order(&self, _stop_order: &StopOrder) -> bool {
+        false
+    }
+
     fn market_buy(&self, symbol: &str, amount: f64) -> ExchangeApiResult<PlatformTransaction>;
 
     fn market_sell(&self, symbol: &str, amount: f64) -> ExchangeApiResult<PlatformTransaction>;
@@ -39,6 +132,62 @@ pub trait ExchangeAction: Sync + Send {
         symbol: &str,
         custom_order_id: &str,
     ) -> ExchangeApiResult<PlatformTransaction>;
+
+    // Open orders of the account for the symbols
+    fn fetch_open_orders(&self, symbols: &[String]) -> ExchangeApiResult<Vec<OpenOrder>>;
+
+    // Streams depth of the symbols to handler as (symbol, depth).
+    // Requests of the stream, e.g. book snapshots, wait for rate_limit of their class.
+    // It blocks until the stream is closed, error means lost connection.
+    fn depth_stream(
+        &self,
+        symbols: &[String],
+        rate_limit: &dyn Fn(RequestClass),
+        handler: &mut dyn FnMut(String, Depth),
+    ) -> ExchangeApiResult<()>;
+
+    // Last candles of the symbol from the exchange klines, the last one could be still forming
+    fn fetch_candles(
+        &self,
+        _symbol: &str,
+        _interval: CandleInterval,
+        _limit: usize,
+    ) -> ExchangeApiResult<Vec<Candle>> {
+        Err("Candles aren't supported")
+    }
+
+    // Gateway doesn't sync the clock with the exchange without it
+    fn has_server_time(&self) -> bool {
+        false
+    }
+
+    // Exchange server time in unix milliseconds
+    fn server_time(&self) -> ExchangeApiResult<u64> {
+        Err("Server time isn't supported")
+    }
+
+    // Gateway doesn't subscribe to public trades of the exchange without them
+    fn has_trade_stream(&self) -> bool {
+        false
+    }
+
+    // Streams public trades of the symbols to handler as (symbol, trade).
+    // It blocks until the stream is closed, error means lost connection.
+    fn trade_stream(
+        &self,
+        _symbols: &[String],
+        _handler: &mut dyn FnMut(String, PublicTrade),
+    ) -> ExchangeApiResult<()> {
+        Err("Public trades aren't supported")
+    }
+
+    // Streams private events of the account, e.g. filled orders.
+    // It blocks until the stream is closed, error means lost connection.
+    fn user_stream(
+        &self,
+        symbols: &[String],
+        handler: &mut dyn FnMut(UserEvent),
+    ) -> ExchangeApiResult<()>;
 }
 
 #[derive(Debug, Clone)]
@@ -1,27 +1,51 @@
-use crate::gateway::exchange::{ExchangeAction, ExchangeApiResult, PlatformTransaction};
+use crate::api::binance::{
+    models as binance_models, websocket_data::BinanceWS, Account, BinanceApi,
+};
+use crate::context_manager::{Candle, CandleInterval, Commission, FilledOrder};
+use crate::gateway::exchange::{
+    BalanceUpdate, ExchangeAction, ExchangeApiResult, OpenOrder, OrderUpdate, PlatformTransaction,
+    UserEvent,
+};
 use crate::gateway::gateway::ExchangeInstrumentInfo;
-use crate::gateway::{self, GatewayParamsAccount, Instrument};
+use crate::gateway::{
+    self, BookSnapshot, BookSync, BookUpdate, GatewayParamsAccount, Instrument, InstrumentType,
+    RequestClass, SnapshotSchedule,
+};
+use crate::order_manager::{LimitOrder, OrderEvent, OrderSide, StopOrder, TimeInForce};
 use binance::userstream::UserStream;
 use binance::websockets::{
     WebSockets as BinanceWebSockets, WebsocketEvent as BinanceWebsocketEvent,
 };
 use binance::{
-    account::Account,
     api,
     general::General,
-    model::{ExchangeInformation, Filters, OrderBook},
-    websockets::*,
+    model::{ExchangeInformation, Filters},
 };
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::atomic::AtomicBool;
+use std::time::Instant;
 use tracing::{debug, error, info, warn};
 
+// Number of levels in the REST snapshot of the book
+const BINANCE_SNAPSHOT_LIMIT: u16 = 1000;
+
 pub struct Binance {
     binance_account: Account,
+    config_account: GatewayParamsAccount,
 }
 
 impl Binance {
+    pub fn new(config_account: &GatewayParamsAccount) -> Self {
+        Binance {
+            binance_account: Account::new(
+                Some(config_account.api_key.expose().to_string()),
+                Some(config_account.secret_key.expose().to_string()),
+            ),
+            config_account: config_account.clone(),
+        }
+    }
+
     pub fn metadata() -> Option<ExchangeInformation> {
         let market: General = api::Binance::new(None, None);
 
@@ -34,53 +58,39 @@ impl Binance {
         }
     }
 
-    pub fn depth_ws<Handler>(handler: Handler, symbols: Vec<&str>)
-    where
-        Handler: FnMut(binance::websockets::WebsocketEvent) -> Result<(), binance::errors::Error>,
-    {
-        let _symbols: Vec<_> = symbols.into_iter().map(String::from).collect();
-
-        let mut endpoints: Vec<String> = Vec::new();
-
-        for symbol in _symbols.iter() {
-            endpoints.push(format!("{}@depth5@100ms", symbol.to_lowercase()));
-        }
-
-        let keep_running = AtomicBool::new(true);
-        let mut web_socket: WebSockets<'_> = WebSockets::new(handler);
-
-        web_socket.connect_multiple_streams(&endpoints).unwrap(); // check error
-
-        if let Err(e) = web_socket.event_loop(&keep_running) {
-            println!("Error: {:?}", e);
-        }
-        web_socket.disconnect().unwrap();
-    }
-
-    pub fn user_stream_ws<Handler>(config_account: &GatewayParamsAccount, handler: Handler)
+    // It returns error when the stream is broken
+    pub fn user_stream_ws<Handler>(
+        config_account: &GatewayParamsAccount,
+        handler: Handler,
+    ) -> ExchangeApiResult<()>
     where
         Handler: FnMut(BinanceWebsocketEvent) -> Result<(), binance::errors::Error>,
     {
         let keep_running = AtomicBool::new(true);
 
         let user_stream: UserStream =
-            binance::api::Binance::new(Some(config_account.api_key.clone()), None);
+            binance::api::Binance::new(Some(config_account.api_key.expose().to_string()), None);
 
         if let Ok(answer) = user_stream.start() {
+            // New listen key on every connect, the previous one could be expired
             let listen_key = answer.listen_key;
 
             let mut web_socket: BinanceWebSockets = BinanceWebSockets::new(handler);
 
-            web_socket.connect(&listen_key).unwrap(); // check error
+            if let Err(e) = web_socket.connect(&listen_key) {
+                error!("Binance User Stream connection error: {:?}", e);
+                return Err("Binance User Stream connection error");
+            }
+
             if let Err(e) = web_socket.event_loop(&keep_running) {
-                match e {
-                    err => {
-                        error!("Error: {:?}", err);
-                    }
-                }
+                error!("Binance User Stream error: {:?}", e);
+                return Err("Binance User Stream error");
             }
+
+            Ok(())
         } else {
             error!("Not able to start an User Stream (Check your API_KEY)");
+            Err("Not able to start Binance User Stream")
         }
     }
 
@@ -114,28 +124,126 @@ impl Binance {
         None
     }
 
-    pub fn get_depth(order_book: &OrderBook) -> gateway::Depth {
-        let depth = gateway::Depth {
-            exchange: "Binance".to_string(),
-            bids: order_book
-                .bids
-                .iter()
-                .map(|t| gateway::Ticker {
-                    price: t.price,
-                    qty: t.qty,
-                })
-                .collect(),
-            asks: order_book
-                .asks
-                .iter()
-                .map(|t| gateway::Ticker {
-                    price: t.price,
-                    qty: t.qty,
-                })
-                .collect(),
+    // Order event of the execution report other than trade
+    fn order_event(execution_type: &str, reject_reason: &str) -> Option<OrderEvent> {
+        match execution_type {
+            "NEW" => Some(OrderEvent::Accepted),
+            "CANCELED" => Some(OrderEvent::Cancelled),
+            "REJECTED" => Some(OrderEvent::Rejected(reject_reason.to_string())),
+            "EXPIRED" => Some(OrderEvent::Expired),
+            _ => None,
+        }
+    }
+
+    // It returns (step size, min quantity) from LOT_SIZE filter
+    fn lot_size(filters: &[Filters]) -> (f64, f64) {
+        for filter in filters {
+            if let Filters::LotSize {
+                min_qty, step_size, ..
+            } = filter
+            {
+                return (
+                    step_size.parse().unwrap_or(0.),
+                    min_qty.parse().unwrap_or(0.),
+                );
+            }
+        }
+
+        (0., 0.)
+    }
+
+    fn min_notional(filters: &[Filters]) -> f64 {
+        for filter in filters {
+            if let Filters::MinNotional { min_notional, .. } = filter {
+                return min_notional
+                    .as_ref()
+                    .and_then(|min_notional| min_notional.parse().ok())
+                    .unwrap_or(0.);
+            }
+        }
+
+        0.
+    }
+
+    pub fn snapshot(symbol: &str) -> Result<BookSnapshot, &'static str> {
+        match BinanceApi::depth(symbol, BINANCE_SNAPSHOT_LIMIT) {
+            Ok(snapshot) => Ok(BookSnapshot {
+                last_update_id: snapshot.last_update_id,
+                bids: snapshot.bids,
+                asks: snapshot.asks,
+            }),
+            Err(e) => {
+                error!("[Binance] Can't get {} snapshot: {}", symbol, e);
+                Err("Binance depth snapshot error")
+            }
+        }
+    }
+
+    // Order type and parameters of the limit order, post-only is LIMIT_MAKER without time in force.
+    // GTD is emulated by the gateway.
+    fn limit_params(
+        limit_order: &LimitOrder,
+    ) -> Option<(binance_models::OrderType, BTreeMap<String, String>)> {
+        let mut params = BTreeMap::new();
+
+        params.insert("price".to_string(), limit_order.price.to_string());
+        params.insert(
+            "newClientOrderId".to_string(),
+            limit_order.custom_order_id.clone(),
+        );
+
+        let time_in_force = match limit_order.time_in_force {
+            TimeInForce::PostOnly => {
+                return Some((binance_models::OrderType::LimitMaker, params));
+            }
+            TimeInForce::Gtc => binance_models::TimeInForce::GTC,
+            TimeInForce::Ioc => binance_models::TimeInForce::IOC,
+            TimeInForce::Fok => binance_models::TimeInForce::FOK,
+            TimeInForce::Gtd(_) => return None,
         };
 
-        depth
+        params.insert(
+            "timeInForce".to_string(),
+            time_in_force.as_str().to_string(),
+        );
+
+        Some((binance_models::OrderType::Limit, params))
+    }
+
+    fn order_side(order_side: &OrderSide) -> binance_models::OrderSide {
+        match order_side {
+            OrderSide::Buy => binance_models::OrderSide::Buy,
+            OrderSide::Sell => binance_models::OrderSide::Sell,
+        }
+    }
+
+    // Replaces the book with a new snapshot if the symbol's backoff has passed
+    fn sync_book(
+        symbol: &str,
+        book_sync: &mut BookSync,
+        schedule: &mut SnapshotSchedule,
+        rate_limit: &dyn Fn(RequestClass),
+    ) {
+        if !schedule.is_due(symbol, Instant::now()) {
+            return;
+        }
+
+        // Snapshot of 1000 levels is a heavy request
+        rate_limit(RequestClass::Info);
+        schedule.requested(symbol, Instant::now());
+
+        let snapshot = match Self::snapshot(symbol) {
+            Ok(snapshot) => snapshot,
+            Err(_) => return,
+        };
+
+        if let Err(e) = book_sync.apply_snapshot(snapshot) {
+            warn!("[Binance] {} order book: {}", symbol, e);
+        }
+
+        if book_sync.is_synced() {
+            schedule.synced(symbol);
+        }
     }
 }
 
@@ -147,45 +255,66 @@ impl ExchangeAction for Binance {
     fn fetch_metadata(&self) -> Vec<ExchangeInstrumentInfo> {
         let mut instruments_info = Vec::new();
 
-        let binance_metadata = Self::metadata().unwrap();
+        let binance_metadata = match tokio::task::block_in_place(Self::metadata) {
+            Some(binance_metadata) => binance_metadata,
+            None => return instruments_info,
+        };
 
         for symbol_info in binance_metadata.symbols {
+            let (qty_step, min_qty) = Self::lot_size(&symbol_info.filters);
+            let min_notional = Self::min_notional(&symbol_info.filters);
+
             instruments_info.push(ExchangeInstrumentInfo {
                 base: symbol_info.base_asset,
                 quote: symbol_info.quote_asset,
                 symbol: symbol_info.symbol,
+                instrument_type: InstrumentType::Spot,
                 precision: Self::price_precision(symbol_info.filters),
+                qty_step,
+                min_qty,
+                min_notional,
             });
         }
 
         instruments_info
     }
 
-    fn fetch_depth(&self, _symbol: &str) -> Result<gateway::Depth, &'static str> {
-        todo!()
+    fn fetch_depth(&self, symbol: &str) -> Result<gateway::Depth, &'static str> {
+        let mut book_sync = BookSync::new(symbol, "Binance");
+
+        book_sync.apply_snapshot(Self::snapshot(symbol)?)?;
+
+        Ok(book_sync.book().to_depth())
     }
 
     fn fetch_balances(
         &self,
         instruments: Vec<Instrument>,
     ) -> Result<HashMap<String, f64>, &'static str> {
-        let mut instrument_balances = HashMap::new();
+        // Account has the balances of all assets, it's requested once for all instruments
+        let balances = match self.binance_account.get_balances() {
+            Ok(balances) => balances,
+            Err(e) => {
+                error!("Binance balances error: {}", e);
+                return Err("Binance balances error");
+            }
+        };
 
-        for instrument in instruments {
-            let balance_base = self.binance_account.get_balance(&instrument.base);
-            let balance_quote = self.binance_account.get_balance(&instrument.quote);
+        info!("[Gateway] Got balance for Binance account");
 
-            info!("[Gateway] Got balance for Binance account");
+        let free = balances
+            .into_iter()
+            .map(|balance| (balance.asset, balance.free))
+            .collect::<HashMap<String, f64>>();
 
-            instrument_balances.insert(
-                instrument.base.clone(),
-                balance_base.unwrap().free.parse().unwrap(),
-            );
+        let mut instrument_balances = HashMap::new();
 
-            instrument_balances.insert(
-                instrument.quote.clone(),
-                balance_quote.unwrap().free.parse().unwrap(),
-            );
+        // Asset which isn't listed in the account has no balance
+        for instrument in instruments {
+            for asset in vec![&instrument.base, &instrument.quote] {
+                instrument_balances
+                    .insert(asset.clone(), free.get(asset).cloned().unwrap_or_default());
+            }
         }
 
         Ok(instrument_balances)
@@ -198,16 +327,10 @@ impl ExchangeAction for Binance {
         price: f64,
         custom_order_id: Option<String>,
     ) -> ExchangeApiResult<PlatformTransaction> {
-        match self.binance_account.custom_order(
-            symbol,
-            amount,
-            price,
-            None,
-            binance::account::OrderSide::Buy,
-            binance::account::OrderType::Limit,
-            binance::account::TimeInForce::GTC,
-            custom_order_id,
-        ) {
+        match self
+            .binance_account
+            .limit_buy(symbol, amount, price, custom_order_id)
+        {
             Ok(transaction) => {
                 debug!("[Binance] Ok. Limit Buy order was placed");
 
@@ -219,7 +342,7 @@ impl ExchangeAction for Binance {
 
             Err(error) => {
                 error!("Binance Limit Buy error: {}", error);
-                Err(Box::leak(Box::new(error)).description())
+                Err(error)
             }
         }
     }
@@ -231,16 +354,10 @@ impl ExchangeAction for Binance {
         price: f64,
         custom_order_id: Option<String>,
     ) -> ExchangeApiResult<PlatformTransaction> {
-        match self.binance_account.custom_order(
-            symbol,
-            amount,
-            price,
-            None,
-            binance::account::OrderSide::Sell,
-            binance::account::OrderType::Limit,
-            binance::account::TimeInForce::GTC,
-            custom_order_id,
-        ) {
+        match self
+            .binance_account
+            .limit_sell(symbol, amount, price, custom_order_id)
+        {
             Ok(transaction) => {
                 debug!("[Binance] Ok. Limit Sell order was placed");
 
@@ -251,18 +368,87 @@ impl ExchangeAction for Binance {
             }
             Err(error) => {
                 error!("Binance Limit Sell error: {}", error);
-                Err(Box::leak(Box::new(error)).description())
+                Err(error)
+            }
+        }
+    }
+
+    fn limit_order(&self, limit_order: &LimitOrder) -> ExchangeApiResult<PlatformTransaction> {
+        let (order_type, params) = match Self::limit_params(limit_order) {
+            Some(limit_params) => limit_params,
+            None => return Err("Time in force isn't supported"),
+        };
+
+        match self.binance_account.custom_order(
+            limit_order.symbol.as_str(),
+            Self::order_side(&limit_order.order_side),
+            order_type,
+            limit_order.amount,
+            params,
+        ) {
+            Ok(transaction) => {
+                debug!(
+                    "[Binance] Ok. Limit {:?} order was placed",
+                    limit_order.time_in_force
+                );
+
+                Ok(PlatformTransaction {
+                    symbol: transaction.symbol,
+                    order_id: transaction.order_id,
+                })
+            }
+            Err(error) => {
+                error!("Binance Limit order error: {}", error);
+                Err(error)
+            }
+        }
+    }
+
+    // Stop limit order only, stop market order is emulated by the gateway
+    fn stop_order(&self, stop_order: &StopOrder) -> ExchangeApiResult<PlatformTransaction> {
+        let limit_price = match stop_order.limit_price {
+            Some(limit_price) => limit_price,
+            None => return Err("Stop market orders aren't supported"),
+        };
+
+        match self.binance_account.stop_limit_order(
+            stop_order.symbol.as_str(),
+            Self::order_side(&stop_order.order_side),
+            stop_order.amount,
+            limit_price,
+            stop_order.stop_price,
+            Some(stop_order.custom_order_id.clone()),
+        ) {
+            Ok(transaction) => {
+                debug!("[Binance] Ok. Stop Limit order was placed");
+
+                Ok(PlatformTransaction {
+                    symbol: transaction.symbol,
+                    order_id: transaction.order_id,
+                })
+            }
+            Err(error) => {
+                error!("Binance Stop Limit error: {}", error);
+                Err(error)
             }
         }
     }
 
+    fn native_time_in_force(&self, time_in_force: &TimeInForce) -> bool {
+        !matches!(time_in_force, TimeInForce::Gtd(_))
+    }
+
+    fn native_stop_order(&self, stop_order: &StopOrder) -> bool {
+        stop_order.limit_price.is_some()
+    }
+
     fn market_buy(&self, symbol: &str, amount: f64) -> ExchangeApiResult<PlatformTransaction> {
         match self.binance_account.market_buy(symbol, amount) {
             Ok(transaction) => Ok(PlatformTransaction {
                 symbol: transaction.symbol,
                 order_id: transaction.order_id,
             }),
-            Err(error) => Err(Box::leak(Box::new(error)).description()),
+            Err(error) => Err(error),
         }
     }
 
@@ -272,7 +458,7 @@ impl ExchangeAction for Binance {
                 symbol: transaction.symbol,
                 order_id: transaction.order_id,
             }),
-            Err(error) => Err(Box::leak(Box::new(error)).description()),
+            Err(error) => Err(error),
         }
     }
 
@@ -283,11 +469,11 @@ impl ExchangeAction for Binance {
     ) -> ExchangeApiResult<PlatformTransaction> {
         match self
             .binance_account
-            .cancel_order_with_client_id(symbol, custom_order_id.to_string())
+            .cancel_order_with_client_id(symbol, custom_order_id)
         {
             Ok(order_canceled) => Ok(PlatformTransaction {
                 symbol: order_canceled.symbol,
-                order_id: order_canceled.order_id.unwrap_or(0),
+                order_id: order_canceled.order_id,
             }),
 
             Err(error) => {
@@ -303,12 +489,226 @@ impl ExchangeAction for Binance {
             }
         }
     }
+
+    fn fetch_open_orders(&self, symbols: &[String]) -> ExchangeApiResult<Vec<OpenOrder>> {
+        match self.binance_account.get_all_open_orders() {
+            Ok(orders) => Ok(orders
+                .into_iter()
+                .filter(|order| symbols.contains(&order.symbol))
+                .map(|order| OpenOrder {
+                    symbol: order.symbol,
+                    order_id: order.order_id,
+                    custom_order_id: order.client_order_id,
+                    order_side: if order.side == "BUY" {
+                        OrderSide::Buy
+                    } else {
+                        OrderSide::Sell
+                    },
+                    price: order.price,
+                    amount: order.orig_qty - order.executed_qty,
+                })
+                .collect()),
+            Err(error) => {
+                warn!("[Binance] Can't fetch open orders: {}", error);
+                Err("Binance open orders error")
+            }
+        }
+    }
+
+    fn depth_stream(
+        &self,
+        symbols: &[String],
+        rate_limit: &dyn Fn(RequestClass),
+        handler: &mut dyn FnMut(String, gateway::Depth),
+    ) -> ExchangeApiResult<()> {
+        let symbols = symbols.iter().map(String::as_str).collect::<Vec<&str>>();
+
+        // Combined stream keeps symbol of every update
+        let mut binance_ws = BinanceWS::connect(&symbols)?;
+
+        let mut books = symbols
+            .iter()
+            .map(|symbol| (symbol.to_string(), BookSync::new(symbol, "Binance")))
+            .collect::<HashMap<String, BookSync>>();
+
+        let mut schedule = SnapshotSchedule::new();
+
+        // Snapshots are taken after subscription, updates wait in the socket
+        for (symbol, book_sync) in books.iter_mut() {
+            Self::sync_book(symbol, book_sync, &mut schedule, rate_limit);
+        }
+
+        loop {
+            let (symbol, depth_update) = binance_ws.get_update()?;
+
+            let book_sync = match books.get_mut(&symbol) {
+                Some(book_sync) => book_sync,
+                None => continue,
+            };
+
+            match book_sync.apply_update(BookUpdate {
+                first_update_id: depth_update.first_update_id,
+                last_update_id: depth_update.last_update_id,
+                bids: depth_update.bids,
+                asks: depth_update.asks,
+            }) {
+                Ok(true) => handler(symbol.clone(), book_sync.book().to_depth()),
+                Ok(false) => {}
+                Err(e) => warn!("[Binance] {} order book: {}. Resync", symbol, e),
+            }
+
+            // Gap, failed or stale snapshot, the book needs a new one
+            if !book_sync.is_synced() {
+                Self::sync_book(&symbol, book_sync, &mut schedule, rate_limit);
+            }
+        }
+    }
+
+    // Binance intervals are named as ours
+    fn fetch_candles(
+        &self,
+        symbol: &str,
+        interval: CandleInterval,
+        limit: usize,
+    ) -> ExchangeApiResult<Vec<Candle>> {
+        match BinanceApi::klines(symbol, &interval.to_string(), limit as u16) {
+            Ok(klines) => Ok(klines
+                .into_iter()
+                .map(|kline| Candle {
+                    open_time: kline.0,
+                    open: kline.1,
+                    high: kline.2,
+                    low: kline.3,
+                    close: kline.4,
+                    volume: kline.5,
+                })
+                .collect()),
+            Err(e) => {
+                error!("[Binance] Can't get {} {} klines: {}", symbol, interval, e);
+                Err("Binance klines error")
+            }
+        }
+    }
+
+    fn has_server_time(&self) -> bool {
+        true
+    }
+
+    fn server_time(&self) -> ExchangeApiResult<u64> {
+        BinanceApi::server_time().map_err(|e| {
+            error!("[Binance] Can't get server time: {}", e);
+            "Binance server time error"
+        })
+    }
+
+    fn has_trade_stream(&self) -> bool {
+        true
+    }
+
+    fn trade_stream(
+        &self,
+        symbols: &[String],
+        handler: &mut dyn FnMut(String, gateway::PublicTrade),
+    ) -> ExchangeApiResult<()> {
+        let symbols = symbols.iter().map(String::as_str).collect::<Vec<&str>>();
+
+        let mut binance_ws = BinanceWS::connect_trades(&symbols)?;
+
+        loop {
+            let (symbol, trade) = binance_ws.get_trade()?;
+
+            handler(
+                symbol,
+                gateway::PublicTrade {
+                    trade_id: trade.trade_id(),
+                    price: trade.price,
+                    qty: trade.qty,
+                    // Buyer is maker, so the seller took liquidity
+                    aggressor: if trade.is_buyer_maker {
+                        OrderSide::Sell
+                    } else {
+                        OrderSide::Buy
+                    },
+                    timestamp: trade.trade_time,
+                },
+            );
+        }
+    }
+
+    fn user_stream(
+        &self,
+        _symbols: &[String],
+        handler: &mut dyn FnMut(UserEvent),
+    ) -> ExchangeApiResult<()> {
+        let account = self.config_account.name.clone();
+
+        // Binance api is listening all instruments
+        let user_handler = |event: BinanceWebsocketEvent| {
+            match event {
+                BinanceWebsocketEvent::OrderTrade(trade) => {
+                    if trade.execution_type != "TRADE" {
+                        if let Some(event) =
+                            Self::order_event(&trade.execution_type, &trade.order_reject_reason)
+                        {
+                            // Cancel event has the custom id of the cancel request,
+                            // so the order is found by its exchange id
+                            handler(UserEvent::OrderUpdate(OrderUpdate {
+                                custom_order_id: trade.new_client_order_id,
+                                order_id: trade.order_id,
+                                symbol: trade.symbol,
+                                event,
+                            }));
+                        }
+
+                        return Ok(());
+                    }
+
+                    info!(
+                        "[Gateway] Binance order was filled: {} {}",
+                        trade.symbol, trade.qty_last_filled_trade
+                    );
+
+                    handler(UserEvent::FilledOrder(FilledOrder {
+                        order_id: trade.order_id,
+                        custom_order_id: trade.new_client_order_id,
+                        symbol: trade.symbol.clone(),
+                        amount: trade.qty_last_filled_trade.parse().unwrap_or_default(),
+                        price: trade.price_last_filled_trade.parse().unwrap_or_default(),
+                        commission: Some(Commission {
+                            amount: trade.commission.parse().unwrap_or_default(),
+                            asset: trade.asset_commisioned.clone().unwrap_or_default(),
+                            // It's converted by the gateway
+                            quote_amount: 0.,
+                        }),
+                        account: account.clone(),
+                        instrument_id: None,
+                    }));
+                }
+                // outboundAccountPosition has every changed asset of the account
+                BinanceWebsocketEvent::AccountUpdate(account_update) => {
+                    for balance in account_update.balance {
+                        handler(UserEvent::BalanceUpdate(BalanceUpdate {
+                            asset: balance.asset,
+                            free: balance.free.parse().unwrap_or_default(),
+                            locked: balance.locked.parse().unwrap_or_default(),
+                        }));
+                    }
+                }
+                _ => {}
+            }
+            Ok(())
+        };
+
+        Self::user_stream_ws(&self.config_account, user_handler)
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::Binance;
+    use crate::api::binance::models::OrderType;
+    use crate::order_manager::{LimitOrder, OrderEvent, TimeInForce};
 
     #[test]
     #[ignore]
@@ -322,4 +722,43 @@ mod tests {
 
         assert_eq!(6, Binance::get_precision(tick_size));
     }
+
+    #[test]
+    fn order_event() {
+        assert_eq!(
+            Binance::order_event("NEW", "NONE"),
+            Some(OrderEvent::Accepted)
+        );
+        assert_eq!(
+            Binance::order_event("REJECTED", "INSUFFICIENT_BALANCE"),
+            Some(OrderEvent::Rejected("INSUFFICIENT_BALANCE".to_string()))
+        );
+        assert_eq!(Binance::order_event("TRADE", "NONE"), None);
+    }
+
+    #[test]
+    fn time_in_force() {
+        let fill_or_kill = LimitOrder {
+            time_in_force: TimeInForce::Fok,
+            ..LimitOrder::default()
+        };
+        let (order_type, params) = Binance::limit_params(&fill_or_kill).unwrap();
+        assert_eq!(order_type, OrderType::Limit);
+        assert_eq!(params["timeInForce"], "FOK");
+
+        // LIMIT_MAKER is rejected with any time in force
+        let post_only = LimitOrder {
+            time_in_force: TimeInForce::PostOnly,
+            ..LimitOrder::default()
+        };
+        let (order_type, params) = Binance::limit_params(&post_only).unwrap();
+        assert_eq!(order_type, OrderType::LimitMaker);
+        assert!(!params.contains_key("timeInForce"));
+
+        let gtd = LimitOrder {
+            time_in_force: TimeInForce::Gtd(0),
+            ..LimitOrder::default()
+        };
+        assert!(Binance::limit_params(&gtd).is_none());
+    }
 }
@@ -1,11 +1,587 @@
-// use crate::gateway::{
-//     exchange::{ExchangeAction, ExchangeApiResult, Transaction},
-//     Depth,
-// };
-// use std::collections::HashMap;
+use crate::api::bitmex::websocket_account::{
+    WebSockets as BitmexWebSockets, WebsocketEvent as BitmexWebsocketEvent,
+};
+use crate::api::{
+    self,
+    bitmex::{
+        models::{order_id_from_uuid, APIResult},
+        websocket_data::BitmexWS,
+        Account, BitmexApi,
+    },
+};
+use crate::context_manager::FilledOrder;
+use crate::gateway::{
+    self,
+    exchange::{
+        ExchangeAction, ExchangeApiResult, OpenOrder, OrderUpdate, PlatformTransaction, UserEvent,
+    },
+    gateway::ExchangeInstrumentInfo,
+    Depth, GatewayParamsAccount, Instrument, InstrumentType, RequestClass,
+};
+use crate::order_manager::{
+    CancelOrder, LimitOrder, OrderEvent, OrderSide, StopOrder, TimeInForce,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use tracing::{debug, error, info, warn};
 
-struct BitMEX;
+pub struct BitMEX {
+    bitmex_account: Account,
+    config_account: GatewayParamsAccount,
+}
 
-// impl ExchangeAction for BitMEX {
-// }
+impl BitMEX {
+    pub fn new(config_account: &GatewayParamsAccount) -> Self {
+        BitMEX {
+            bitmex_account: Account::new(
+                Some(config_account.api_key.expose().to_string()),
+                Some(config_account.secret_key.expose().to_string()),
+            ),
+            config_account: config_account.clone(),
+        }
+    }
 
+    pub fn metadata() -> Option<Vec<api::bitmex::models::Instrument>> {
+        match BitmexApi::instruments() {
+            Ok(instruments) => Some(instruments),
+            Err(e) => {
+                error!("BitMEX exchange error: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn instrument_info(instrument: api::bitmex::models::Instrument) -> ExchangeInstrumentInfo {
+        ExchangeInstrumentInfo {
+            base: instrument.underlying,
+            quote: instrument.quote_currency,
+            instrument_type: match instrument.typ.as_deref() {
+                Some("FFWCSX") => InstrumentType::Perpetual,
+                Some("IFXXXP") => InstrumentType::Spot,
+                _ => InstrumentType::Future,
+            },
+            precision: Self::price_precision(instrument.tick_size),
+            // Contracts are traded by lots
+            qty_step: instrument.lot_size.unwrap_or(0.),
+            min_qty: instrument.lot_size.unwrap_or(0.),
+            min_notional: 0.,
+            symbol: instrument.symbol,
+        }
+    }
+
+    // Order event of the execution other than trade.
+    // Rejected order comes as "New" execution with "Rejected" status.
+    fn order_event(exec_type: &str, ord_status: Option<&str>) -> Option<OrderEvent> {
+        match (exec_type, ord_status) {
+            (_, Some("Rejected")) => Some(OrderEvent::Rejected("Rejected by BitMEX".to_string())),
+            ("New", _) => Some(OrderEvent::Accepted),
+            ("Canceled", _) => Some(OrderEvent::Cancelled),
+            (_, Some("Expired")) => Some(OrderEvent::Expired),
+            _ => None,
+        }
+    }
+
+    // Order fields of the limit order, GTD is emulated by the gateway
+    fn limit_fields(limit_order: &LimitOrder) -> Option<serde_json::Value> {
+        let mut fields = json!({
+            "ordType": "Limit",
+            "price": limit_order.price,
+        });
+
+        match limit_order.time_in_force {
+            TimeInForce::Gtc => fields["timeInForce"] = json!("GoodTillCancel"),
+            TimeInForce::PostOnly => fields["execInst"] = json!("ParticipateDoNotInitiate"),
+            TimeInForce::Ioc => fields["timeInForce"] = json!("ImmediateOrCancel"),
+            TimeInForce::Fok => fields["timeInForce"] = json!("FillOrKill"),
+            TimeInForce::Gtd(_) => return None,
+        }
+
+        Some(fields)
+    }
+
+    fn stop_fields(stop_order: &StopOrder) -> serde_json::Value {
+        match stop_order.limit_price {
+            Some(limit_price) => json!({
+                "ordType": "StopLimit",
+                "stopPx": stop_order.stop_price,
+                "price": limit_price,
+            }),
+            None => json!({
+                "ordType": "Stop",
+                "stopPx": stop_order.stop_price,
+            }),
+        }
+    }
+
+    fn side(order_side: &OrderSide) -> &'static str {
+        match order_side {
+            OrderSide::Buy => "Buy",
+            OrderSide::Sell => "Sell",
+        }
+    }
+
+    pub fn price_precision(tick_size: f64) -> u8 {
+        match tick_size.to_string().split('.').nth(1) {
+            Some(float_part) => float_part.trim_end_matches('0').len() as u8,
+            None => 0,
+        }
+    }
+
+    pub fn get_depth(bitmex_depth: &api::bitmex::websocket_data::Depth) -> gateway::Depth {
+        gateway::Depth {
+            exchange: bitmex_depth.exchange.clone(),
+            bids: bitmex_depth
+                .bids
+                .iter()
+                .map(|ticker| gateway::Ticker {
+                    price: ticker.price,
+                    qty: ticker.qty,
+                })
+                .collect(),
+            asks: bitmex_depth
+                .asks
+                .iter()
+                .map(|ticker| gateway::Ticker {
+                    price: ticker.price,
+                    qty: ticker.qty,
+                })
+                .collect(),
+        }
+    }
+
+    // It returns error when the stream is broken
+    pub fn user_stream_ws<Handler>(
+        config_account: &GatewayParamsAccount,
+        handler: Handler,
+    ) -> ExchangeApiResult<()>
+    where
+        Handler: FnMut(BitmexWebsocketEvent) -> APIResult<()>,
+    {
+        let keep_running = AtomicBool::new(true);
+
+        let mut websocket: BitmexWebSockets = BitmexWebSockets::new(handler);
+
+        if let Err(e) = websocket.connect_auth(
+            config_account.api_key.expose(),
+            config_account.secret_key.expose(),
+        ) {
+            error!("BitMEX user stream connection error: {}", e);
+            return Err("BitMEX user stream connection error");
+        }
+
+        if let Err(e) = websocket.event_loop(&keep_running) {
+            error!("BitMEX user stream error: {}", e);
+            return Err("BitMEX user stream error");
+        }
+
+        Ok(())
+    }
+
+    // BitMEX margin currency for the asset
+    fn margin_currency(asset: &str) -> String {
+        match asset {
+            "XBT" | "XBt" => "XBt".to_string(),
+            "USDT" | "USDt" => "USDt".to_string(),
+            _ => asset.to_string(),
+        }
+    }
+}
+
+impl ExchangeAction for BitMEX {
+    fn inti(&self) {}
+
+    fn fetch_metadata(&self) -> Vec<ExchangeInstrumentInfo> {
+        tokio::task::block_in_place(Self::metadata)
+            .unwrap_or_default()
+            .into_iter()
+            .map(Self::instrument_info)
+            .collect()
+    }
+
+    fn fetch_depth(&self, _symbol: &str) -> Result<Depth, &'static str> {
+        Err("BitMEX depth is available through websocket only")
+    }
+
+    fn fetch_balances(
+        &self,
+        instruments: Vec<Instrument>,
+    ) -> Result<HashMap<String, f64>, &'static str> {
+        let mut instrument_balances = HashMap::new();
+
+        for instrument in instruments {
+            // Derivatives are margined by settlement currency only
+            for asset in vec![&instrument.base, &instrument.quote] {
+                match self
+                    .bitmex_account
+                    .get_margin(&Self::margin_currency(asset))
+                {
+                    Ok(margin) => {
+                        instrument_balances.insert(asset.clone(), margin.available());
+                    }
+                    Err(e) => debug!("[BitMEX] No margin for {}: {}", asset, e),
+                }
+            }
+
+            info!("[Gateway] Got balance for BitMEX account");
+        }
+
+        Ok(instrument_balances)
+    }
+
+    fn limit_buy(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        match self
+            .bitmex_account
+            .limit_buy(symbol, amount, price, custom_order_id)
+        {
+            Ok(transaction) => {
+                debug!("[BitMEX] Ok. Limit Buy order was placed");
+
+                Ok(PlatformTransaction {
+                    symbol: transaction.symbol,
+                    order_id: transaction.order_id,
+                })
+            }
+            Err(error) => {
+                error!("BitMEX Limit Buy error: {}", error);
+                Err(error)
+            }
+        }
+    }
+
+    fn limit_sell(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        match self
+            .bitmex_account
+            .limit_sell(symbol, amount, price, custom_order_id)
+        {
+            Ok(transaction) => {
+                debug!("[BitMEX] Ok. Limit Sell order was placed");
+
+                Ok(PlatformTransaction {
+                    symbol: transaction.symbol,
+                    order_id: transaction.order_id,
+                })
+            }
+            Err(error) => {
+                error!("BitMEX Limit Sell error: {}", error);
+                Err(error)
+            }
+        }
+    }
+
+    fn limit_order(&self, limit_order: &LimitOrder) -> ExchangeApiResult<PlatformTransaction> {
+        let order_fields = match Self::limit_fields(limit_order) {
+            Some(order_fields) => order_fields,
+            None => return Err("Time in force isn't supported"),
+        };
+
+        let transaction = self.bitmex_account.custom_order(
+            &limit_order.symbol,
+            Self::side(&limit_order.order_side),
+            limit_order.amount,
+            &limit_order.custom_order_id,
+            order_fields,
+        )?;
+
+        debug!(
+            "[BitMEX] Ok. Limit {:?} order was placed",
+            limit_order.time_in_force
+        );
+
+        Ok(PlatformTransaction {
+            symbol: transaction.symbol,
+            order_id: transaction.order_id,
+        })
+    }
+
+    fn stop_order(&self, stop_order: &StopOrder) -> ExchangeApiResult<PlatformTransaction> {
+        let transaction = self.bitmex_account.custom_order(
+            &stop_order.symbol,
+            Self::side(&stop_order.order_side),
+            stop_order.amount,
+            &stop_order.custom_order_id,
+            Self::stop_fields(stop_order),
+        )?;
+
+        debug!("[BitMEX] Ok. Stop order was placed");
+
+        Ok(PlatformTransaction {
+            symbol: transaction.symbol,
+            order_id: transaction.order_id,
+        })
+    }
+
+    fn replace_order(
+        &self,
+        cancel_order: &CancelOrder,
+        limit_order: &LimitOrder,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        let transaction = self.bitmex_account.amend_order(
+            &limit_order.symbol,
+            &cancel_order.custom_order_id,
+            &limit_order.custom_order_id,
+            limit_order.amount,
+            limit_order.price,
+        )?;
+
+        debug!("[BitMEX] Ok. Order was amended");
+
+        Ok(PlatformTransaction {
+            symbol: transaction.symbol,
+            order_id: transaction.order_id,
+        })
+    }
+
+    fn native_replace_order(&self) -> bool {
+        true
+    }
+
+    fn native_time_in_force(&self, time_in_force: &TimeInForce) -> bool {
+        !matches!(time_in_force, TimeInForce::Gtd(_))
+    }
+
+    fn native_stop_order(&self, _stop_order: &StopOrder) -> bool {
+        true
+    }
+
+    fn market_buy(&self, symbol: &str, amount: f64) -> ExchangeApiResult<PlatformTransaction> {
+        match self.bitmex_account.market_buy(symbol, amount) {
+            Ok(transaction) => Ok(PlatformTransaction {
+                symbol: transaction.symbol,
+                order_id: transaction.order_id,
+            }),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn market_sell(&self, symbol: &str, amount: f64) -> ExchangeApiResult<PlatformTransaction> {
+        match self.bitmex_account.market_sell(symbol, amount) {
+            Ok(transaction) => Ok(PlatformTransaction {
+                symbol: transaction.symbol,
+                order_id: transaction.order_id,
+            }),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn cancel_order(
+        &self,
+        symbol: &str,
+        custom_order_id: &str,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        match self
+            .bitmex_account
+            .cancel_order_with_custom_id(symbol, custom_order_id)
+        {
+            Ok(transaction) => Ok(PlatformTransaction {
+                symbol: transaction.symbol,
+                order_id: transaction.order_id,
+            }),
+            Err(error) => {
+                // Do not throw error
+                // Cancel order could be filled
+
+                warn!("Can't cancel order: {}. It could be filled", error);
+
+                Ok(PlatformTransaction {
+                    symbol: symbol.to_string(),
+                    order_id: 0,
+                })
+            }
+        }
+    }
+
+    fn fetch_open_orders(&self, symbols: &[String]) -> ExchangeApiResult<Vec<OpenOrder>> {
+        let mut open_orders = Vec::new();
+
+        for symbol in symbols {
+            let orders = match self.bitmex_account.get_open_orders(symbol) {
+                Ok(orders) => orders,
+                Err(e) => {
+                    error!("BitMEX open orders error: {}", e);
+                    return Err("BitMEX open orders error");
+                }
+            };
+
+            for order in orders {
+                // Orders placed outside the platform have no custom id
+                let custom_order_id = order.cl_ord_id.unwrap_or_default();
+
+                open_orders.push(OpenOrder {
+                    symbol: order.symbol,
+                    order_id: order_id_from_uuid(&order.order_id),
+                    custom_order_id,
+                    order_side: if order.side.as_deref() == Some("Buy") {
+                        OrderSide::Buy
+                    } else {
+                        OrderSide::Sell
+                    },
+                    price: order.price.unwrap_or_default(),
+                    amount: order.leaves_qty.unwrap_or_default(),
+                });
+            }
+        }
+
+        Ok(open_orders)
+    }
+
+    fn has_server_time(&self) -> bool {
+        true
+    }
+
+    fn server_time(&self) -> ExchangeApiResult<u64> {
+        BitmexApi::server_time().map_err(|e| {
+            error!("[BitMEX] Can't get server time: {}", e);
+            "BitMEX server time error"
+        })
+    }
+
+    fn depth_stream(
+        &self,
+        symbols: &[String],
+        _rate_limit: &dyn Fn(RequestClass),
+        handler: &mut dyn FnMut(String, Depth),
+    ) -> ExchangeApiResult<()> {
+        let symbols = symbols.iter().map(String::as_str).collect::<Vec<&str>>();
+
+        let mut bitmex_ws = BitmexWS::connect(&symbols)?;
+
+        loop {
+            let (symbol, bitmex_depth) = bitmex_ws.get_depth()?;
+
+            handler(symbol, Self::get_depth(&bitmex_depth));
+        }
+    }
+
+    fn user_stream(
+        &self,
+        _symbols: &[String],
+        handler: &mut dyn FnMut(UserEvent),
+    ) -> ExchangeApiResult<()> {
+        let account = self.config_account.name.clone();
+
+        let user_handler = |event: BitmexWebsocketEvent| {
+            match event {
+                BitmexWebsocketEvent::Execution(executions) => {
+                    for execution in executions {
+                        if execution.exec_type != "Trade" {
+                            let event = Self::order_event(
+                                &execution.exec_type,
+                                execution.ord_status.as_deref(),
+                            );
+
+                            if let Some(event) = event {
+                                handler(UserEvent::OrderUpdate(OrderUpdate {
+                                    custom_order_id: execution.cl_ord_id.unwrap_or_default(),
+                                    order_id: order_id_from_uuid(&execution.order_id),
+                                    symbol: execution.symbol,
+                                    event,
+                                }));
+                            }
+
+                            continue;
+                        }
+
+                        let amount = execution.last_qty.unwrap_or(0.);
+
+                        info!(
+                            "[Gateway] BitMEX order was filled: {} {}",
+                            execution.symbol, amount
+                        );
+
+                        handler(UserEvent::FilledOrder(FilledOrder {
+                            order_id: order_id_from_uuid(&execution.order_id),
+                            custom_order_id: execution.cl_ord_id.unwrap_or_default(),
+                            symbol: execution.symbol.clone(),
+                            amount,
+                            price: execution.last_px.unwrap_or_default(),
+                            // Execution has the commission rate only
+                            commission: None,
+                            account: account.clone(),
+                            instrument_id: None,
+                        }));
+                    }
+                }
+            }
+
+            Ok(())
+        };
+
+        Self::user_stream_ws(&self.config_account, user_handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::BitMEX;
+    use crate::order_manager::{LimitOrder, OrderEvent, StopOrder, TimeInForce};
+
+    #[test]
+    fn price_precision() {
+        assert_eq!(BitMEX::price_precision(0.5), 1);
+        assert_eq!(BitMEX::price_precision(0.01), 2);
+        assert_eq!(BitMEX::price_precision(1.), 0);
+        assert_eq!(BitMEX::price_precision(0.0000001), 7);
+    }
+
+    #[test]
+    fn order_event() {
+        assert_eq!(
+            BitMEX::order_event("New", Some("New")),
+            Some(OrderEvent::Accepted)
+        );
+        assert_eq!(
+            BitMEX::order_event("Canceled", Some("Canceled")),
+            Some(OrderEvent::Cancelled)
+        );
+        assert!(matches!(
+            BitMEX::order_event("New", Some("Rejected")),
+            Some(OrderEvent::Rejected(_))
+        ));
+        assert_eq!(BitMEX::order_event("Funding", None), None);
+    }
+
+    #[test]
+    fn order_fields() {
+        let post_only = LimitOrder {
+            time_in_force: TimeInForce::PostOnly,
+            ..LimitOrder::default()
+        };
+        let fields = BitMEX::limit_fields(&post_only).unwrap();
+        assert_eq!(fields["execInst"], "ParticipateDoNotInitiate");
+
+        let gtd = LimitOrder {
+            time_in_force: TimeInForce::Gtd(0),
+            ..LimitOrder::default()
+        };
+        assert!(BitMEX::limit_fields(&gtd).is_none());
+
+        let stop_limit = StopOrder {
+            limit_price: Some(9.),
+            ..StopOrder::default()
+        };
+        let fields = BitMEX::stop_fields(&stop_limit);
+        assert_eq!(fields["ordType"], "StopLimit");
+        assert_eq!(fields["stopPx"], 10.);
+    }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn metadata() {
+        println!("{:?}", BitMEX::metadata().unwrap());
+    }
+}
@@ -0,0 +1,792 @@
+use crate::api::fix::message::{msg_type, tags, FixMessage};
+use crate::api::fix::{FixInitiator, FixSession, SequenceStore, SessionConfig};
+use crate::context_manager::{Commission, FilledOrder};
+use crate::gateway::{
+    exchange::{
+        ExchangeAction, ExchangeApiResult, OpenOrder, OrderUpdate, PlatformTransaction, UserEvent,
+    },
+    gateway::ExchangeInstrumentInfo,
+    Depth, GatewayParamsAccount, Instrument, InstrumentType, RequestClass,
+};
+use crate::order_manager::{LimitOrder, OrderEvent, OrderSide, StopOrder, TimeInForce};
+use chrono::{TimeZone, Utc};
+use crossbeam::channel::{bounded, unbounded, Sender};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+// Venue answers the order or the status request during that time
+const FIX_RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// FIX venues have no metadata, prices are rounded by the venue
+const FIX_PRICE_PRECISION: u8 = 8;
+
+const FIX_TIME_FORMAT: &str = "%Y%m%d-%H:%M:%S%.3f";
+
+// Exchange connected by FIX 4.4 session, e.g. prime broker.
+// Orders are acknowledged by the first execution report, fills and cancels come to the user stream.
+// Symbols are sent as they're named in the gateway config.
+pub struct Fix {
+    config_account: GatewayParamsAccount,
+    instruments: Vec<Instrument>,
+    initiator: FixInitiator,
+    // <ClOrdID, Sender of the first execution report of the order>
+    acks: Mutex<HashMap<String, Sender<FixMessage>>>,
+    // <MassStatusReqID, Sender of the order status reports>
+    status_requests: Mutex<HashMap<String, Sender<FixMessage>>>,
+    // <ClOrdID, (Symbol, Side, Amount)>, cancel request repeats them
+    orders: Mutex<HashMap<String, (String, OrderSide, f64)>>,
+}
+
+impl Fix {
+    pub fn new(config_account: &GatewayParamsAccount, instruments: &[Instrument]) -> Self {
+        let fix_params = match &config_account.fix {
+            Some(fix_params) => fix_params.clone(),
+            None => {
+                error!(
+                    "FIX session of {} account isn't configured",
+                    config_account.name
+                );
+                Default::default()
+            }
+        };
+
+        let store_path = format!(
+            "{}/{}-{}.seq",
+            fix_params.store_dir, fix_params.sender_comp_id, fix_params.target_comp_id
+        );
+
+        let store = SequenceStore::open(&store_path).unwrap_or_else(|e| {
+            error!("FIX sequence numbers aren't persisted: {}", e);
+            SequenceStore::memory()
+        });
+
+        let non_empty = |value: &str| Some(value.to_string()).filter(|value| !value.is_empty());
+
+        let session = FixSession::new(
+            SessionConfig {
+                sender_comp_id: fix_params.sender_comp_id.clone(),
+                target_comp_id: fix_params.target_comp_id.clone(),
+                heartbeat_interval: Duration::from_secs(fix_params.heartbeat_interval),
+                username: non_empty(config_account.api_key.expose()),
+                password: non_empty(config_account.secret_key.expose()),
+                reset_on_logon: false,
+            },
+            store,
+        );
+
+        Self::with_initiator(
+            config_account,
+            instruments,
+            FixInitiator::new(&fix_params.address, session),
+        )
+    }
+
+    pub fn with_initiator(
+        config_account: &GatewayParamsAccount,
+        instruments: &[Instrument],
+        initiator: FixInitiator,
+    ) -> Self {
+        Fix {
+            config_account: config_account.clone(),
+            instruments: instruments.to_vec(),
+            initiator,
+            acks: Mutex::new(HashMap::new()),
+            status_requests: Mutex::new(HashMap::new()),
+            orders: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.initiator.is_active()
+    }
+
+    // Venue order ids are numeric or strings
+    fn order_id(order_id: &str) -> u64 {
+        order_id.parse::<u64>().unwrap_or_else(|_| {
+            // FNV-1a
+            order_id.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            })
+        })
+    }
+
+    // Unique id of the request without custom order id
+    fn request_id(prefix: &str) -> String {
+        format!("{}{}", prefix, Utc::now().timestamp_nanos())
+    }
+
+    fn side(order_side: &OrderSide) -> &'static str {
+        match order_side {
+            OrderSide::Buy => "1",
+            OrderSide::Sell => "2",
+        }
+    }
+
+    fn order_side(side: Option<&str>) -> OrderSide {
+        match side {
+            Some("1") => OrderSide::Buy,
+            _ => OrderSide::Sell,
+        }
+    }
+
+    // OrdType, price and time in force fields of the limit order
+    fn limit_fields(limit_order: &LimitOrder) -> Vec<(u32, String)> {
+        let mut fields = vec![
+            (tags::ORD_TYPE, "2".to_string()),
+            (tags::PRICE, limit_order.price.to_string()),
+        ];
+
+        match limit_order.time_in_force {
+            TimeInForce::Gtc => fields.push((tags::TIME_IN_FORCE, "1".to_string())),
+            TimeInForce::PostOnly => {
+                fields.push((tags::TIME_IN_FORCE, "1".to_string()));
+                // Participate don't initiate
+                fields.push((tags::EXEC_INST, "6".to_string()));
+            }
+            TimeInForce::Ioc => fields.push((tags::TIME_IN_FORCE, "3".to_string())),
+            TimeInForce::Fok => fields.push((tags::TIME_IN_FORCE, "4".to_string())),
+            TimeInForce::Gtd(expire_at) => {
+                fields.push((tags::TIME_IN_FORCE, "6".to_string()));
+                fields.push((
+                    tags::EXPIRE_TIME,
+                    Utc.timestamp_millis(expire_at as i64)
+                        .format(FIX_TIME_FORMAT)
+                        .to_string(),
+                ));
+            }
+        }
+
+        fields
+    }
+
+    fn stop_fields(stop_order: &StopOrder) -> Vec<(u32, String)> {
+        match stop_order.limit_price {
+            Some(limit_price) => vec![
+                (tags::ORD_TYPE, "4".to_string()),
+                (tags::STOP_PX, stop_order.stop_price.to_string()),
+                (tags::PRICE, limit_price.to_string()),
+            ],
+            None => vec![
+                (tags::ORD_TYPE, "3".to_string()),
+                (tags::STOP_PX, stop_order.stop_price.to_string()),
+            ],
+        }
+    }
+
+    // Sends NewOrderSingle and waits for its acknowledge
+    fn new_order(
+        &self,
+        symbol: &str,
+        order_side: &OrderSide,
+        amount: f64,
+        custom_order_id: &str,
+        fields: Vec<(u32, String)>,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        let mut order = FixMessage::new(msg_type::NEW_ORDER_SINGLE)
+            .with(tags::CL_ORD_ID, custom_order_id)
+            .with(tags::SYMBOL, symbol)
+            .with(tags::SIDE, Self::side(order_side))
+            .with(tags::TRANSACT_TIME, Utc::now().format(FIX_TIME_FORMAT))
+            .with(tags::ORDER_QTY, amount);
+
+        for (tag, value) in fields {
+            order.set(tag, value);
+        }
+
+        if let Some(account_id) = &self.config_account.account_id {
+            order.set(tags::ACCOUNT, account_id);
+        }
+
+        // Reports can come before the send returns
+        let (ack_sender, ack_receiver) = bounded(1);
+        self.acks
+            .lock()
+            .unwrap()
+            .insert(custom_order_id.to_string(), ack_sender);
+        self.orders.lock().unwrap().insert(
+            custom_order_id.to_string(),
+            (symbol.to_string(), order_side.clone(), amount),
+        );
+
+        if let Err(e) = self.initiator.send(order) {
+            self.acks.lock().unwrap().remove(custom_order_id);
+            self.orders.lock().unwrap().remove(custom_order_id);
+            return Err(e);
+        }
+
+        let ack = ack_receiver.recv_timeout(FIX_RESPONSE_TIMEOUT);
+        self.acks.lock().unwrap().remove(custom_order_id);
+
+        match ack {
+            Ok(execution_report) if execution_report.get(tags::EXEC_TYPE) == Some("8") => {
+                error!(
+                    "FIX order {} is rejected: {}",
+                    custom_order_id,
+                    execution_report.get(tags::TEXT).unwrap_or_default()
+                );
+                Err("FIX order is rejected")
+            }
+            Ok(execution_report) => Ok(PlatformTransaction {
+                symbol: symbol.to_string(),
+                order_id: Self::order_id(execution_report.get(tags::ORDER_ID).unwrap_or_default()),
+            }),
+            Err(_) => {
+                warn!("FIX order {} isn't acknowledged", custom_order_id);
+                Err("FIX order acknowledge timeout")
+            }
+        }
+    }
+
+    // Order event of the execution report other than trade
+    fn order_event(exec_type: &str, text: Option<&str>) -> Option<OrderEvent> {
+        match exec_type {
+            "0" => Some(OrderEvent::Accepted),
+            "4" => Some(OrderEvent::Cancelled),
+            "8" => Some(OrderEvent::Rejected(
+                text.unwrap_or("Rejected by FIX venue").to_string(),
+            )),
+            "C" => Some(OrderEvent::Expired),
+            _ => None,
+        }
+    }
+
+    // Commission is in the quote asset if the venue doesn't set its currency
+    fn user_event(&self, execution_report: &FixMessage) -> Option<UserEvent> {
+        let exec_type = execution_report.get(tags::EXEC_TYPE)?;
+        let symbol = execution_report.get(tags::SYMBOL).unwrap_or_default();
+        let order_id = Self::order_id(execution_report.get(tags::ORDER_ID).unwrap_or_default());
+
+        // Cancel is reported with ClOrdID of the cancel request and OrigClOrdID of the order
+        let custom_order_id = execution_report
+            .get(tags::ORIG_CL_ORD_ID)
+            .or_else(|| execution_report.get(tags::CL_ORD_ID))
+            .unwrap_or_default()
+            .to_string();
+
+        if exec_type != "F" {
+            let event = Self::order_event(exec_type, execution_report.get(tags::TEXT))?;
+
+            return Some(UserEvent::OrderUpdate(OrderUpdate {
+                custom_order_id,
+                order_id,
+                symbol: symbol.to_string(),
+                event,
+            }));
+        }
+
+        let amount = execution_report.get_f64(tags::LAST_QTY).unwrap_or_default();
+
+        info!("[Gateway] FIX order was filled: {} {}", symbol, amount);
+
+        let commission = execution_report
+            .get_f64(tags::COMMISSION)
+            .map(|commission| Commission {
+                amount: commission,
+                asset: execution_report
+                    .get(tags::COMM_CURRENCY)
+                    .map(str::to_string)
+                    .or_else(|| {
+                        self.instruments
+                            .iter()
+                            .find(|instrument| instrument.name == symbol)
+                            .map(|instrument| instrument.quote.clone())
+                    })
+                    .unwrap_or_default(),
+                // It's converted by the gateway
+                quote_amount: 0.,
+            });
+
+        Some(UserEvent::FilledOrder(FilledOrder {
+            order_id,
+            custom_order_id,
+            symbol: symbol.to_string(),
+            amount,
+            price: execution_report.get_f64(tags::LAST_PX).unwrap_or_default(),
+            commission,
+            account: self.config_account.name.clone(),
+            instrument_id: None,
+        }))
+    }
+
+    // Execution reports are routed to the waiting requests and to the user stream
+    fn application_message(&self, message: FixMessage, handler: &mut dyn FnMut(UserEvent)) {
+        match message.msg_type() {
+            msg_type::EXECUTION_REPORT => {
+                if let Some(request_id) = message.get(tags::MASS_STATUS_REQ_ID) {
+                    if let Some(sender) = self.status_requests.lock().unwrap().get(request_id) {
+                        let _ = sender.send(message.clone());
+                    }
+
+                    return;
+                }
+
+                let cl_ord_id = message.get(tags::CL_ORD_ID).unwrap_or_default();
+
+                if let Some(ack_sender) = self.acks.lock().unwrap().remove(cl_ord_id) {
+                    let _ = ack_sender.send(message.clone());
+                }
+
+                // Filled, canceled, rejected and expired orders are done
+                if let Some("2") | Some("4") | Some("8") | Some("C") = message.get(tags::ORD_STATUS)
+                {
+                    let custom_order_id = message.get(tags::ORIG_CL_ORD_ID).unwrap_or(cl_ord_id);
+                    self.orders.lock().unwrap().remove(custom_order_id);
+                }
+
+                if let Some(event) = self.user_event(&message) {
+                    handler(event);
+                }
+            }
+            msg_type::ORDER_CANCEL_REJECT => warn!(
+                "[FIX] Cancel of {} is rejected: {}",
+                message.get(tags::ORIG_CL_ORD_ID).unwrap_or_default(),
+                message.get(tags::TEXT).unwrap_or_default()
+            ),
+            _ => debug!("[FIX] Skip message {}", message),
+        }
+    }
+}
+
+impl ExchangeAction for Fix {
+    fn inti(&self) {}
+
+    fn fetch_metadata(&self) -> Vec<ExchangeInstrumentInfo> {
+        self.instruments
+            .iter()
+            .map(|instrument| ExchangeInstrumentInfo {
+                base: instrument.base.clone(),
+                quote: instrument.quote.clone(),
+                symbol: instrument.name.clone(),
+                instrument_type: InstrumentType::Spot,
+                precision: FIX_PRICE_PRECISION,
+                qty_step: instrument.lot_size,
+                min_qty: instrument.min_order_size,
+                min_notional: 0.,
+            })
+            .collect()
+    }
+
+    fn fetch_depth(&self, _symbol: &str) -> Result<Depth, &'static str> {
+        Err("FIX venue has no market data")
+    }
+
+    // Credit is managed by the venue, so balances aren't checked by the gateway
+    fn fetch_balances(
+        &self,
+        _instruments: Vec<Instrument>,
+    ) -> Result<HashMap<String, f64>, &'static str> {
+        Err("FIX venue has no balances")
+    }
+
+    fn limit_buy(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        self.limit_order(&LimitOrder {
+            symbol: symbol.to_string(),
+            amount,
+            price,
+            order_side: OrderSide::Buy,
+            custom_order_id: custom_order_id.unwrap_or_else(|| Self::request_id("MX")),
+            time_in_force: TimeInForce::Gtc,
+            ..LimitOrder::default()
+        })
+    }
+
+    fn limit_sell(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        self.limit_order(&LimitOrder {
+            symbol: symbol.to_string(),
+            amount,
+            price,
+            order_side: OrderSide::Sell,
+            custom_order_id: custom_order_id.unwrap_or_else(|| Self::request_id("MX")),
+            time_in_force: TimeInForce::Gtc,
+            ..LimitOrder::default()
+        })
+    }
+
+    fn limit_order(&self, limit_order: &LimitOrder) -> ExchangeApiResult<PlatformTransaction> {
+        let transaction = self.new_order(
+            &limit_order.symbol,
+            &limit_order.order_side,
+            limit_order.amount,
+            &limit_order.custom_order_id,
+            Self::limit_fields(limit_order),
+        )?;
+
+        debug!(
+            "[FIX] Ok. Limit {:?} order was placed",
+            limit_order.time_in_force
+        );
+
+        Ok(transaction)
+    }
+
+    fn stop_order(&self, stop_order: &StopOrder) -> ExchangeApiResult<PlatformTransaction> {
+        let transaction = self.new_order(
+            &stop_order.symbol,
+            &stop_order.order_side,
+            stop_order.amount,
+            &stop_order.custom_order_id,
+            Self::stop_fields(stop_order),
+        )?;
+
+        debug!("[FIX] Ok. Stop order was placed");
+
+        Ok(transaction)
+    }
+
+    fn native_time_in_force(&self, _time_in_force: &TimeInForce) -> bool {
+        true
+    }
+
+    fn native_stop_order(&self, _stop_order: &StopOrder) -> bool {
+        true
+    }
+
+    fn market_buy(&self, symbol: &str, amount: f64) -> ExchangeApiResult<PlatformTransaction> {
+        self.new_order(
+            symbol,
+            &OrderSide::Buy,
+            amount,
+            &Self::request_id("MX"),
+            vec![(tags::ORD_TYPE, "1".to_string())],
+        )
+    }
+
+    fn market_sell(&self, symbol: &str, amount: f64) -> ExchangeApiResult<PlatformTransaction> {
+        self.new_order(
+            symbol,
+            &OrderSide::Sell,
+            amount,
+            &Self::request_id("MX"),
+            vec![(tags::ORD_TYPE, "1".to_string())],
+        )
+    }
+
+    // Cancel is confirmed by the execution report in the user stream.
+    // Side and amount of the order which isn't known since restart aren't sent.
+    fn cancel_order(
+        &self,
+        symbol: &str,
+        custom_order_id: &str,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        let mut cancel_request = FixMessage::new(msg_type::ORDER_CANCEL_REQUEST)
+            .with(tags::ORIG_CL_ORD_ID, custom_order_id)
+            .with(tags::CL_ORD_ID, Self::request_id("CXL"))
+            .with(tags::SYMBOL, symbol)
+            .with(tags::TRANSACT_TIME, Utc::now().format(FIX_TIME_FORMAT));
+
+        if let Some((_, order_side, amount)) = self.orders.lock().unwrap().get(custom_order_id) {
+            cancel_request.set(tags::SIDE, Self::side(order_side));
+            cancel_request.set(tags::ORDER_QTY, amount);
+        }
+
+        self.initiator.send(cancel_request)?;
+
+        Ok(PlatformTransaction {
+            symbol: symbol.to_string(),
+            order_id: 0,
+        })
+    }
+
+    // OrderMassStatusRequest of all orders, the last status report is marked by LastRptRequested
+    fn fetch_open_orders(&self, symbols: &[String]) -> ExchangeApiResult<Vec<OpenOrder>> {
+        let request_id = Self::request_id("MS");
+        let (sender, receiver) = unbounded();
+
+        self.status_requests
+            .lock()
+            .unwrap()
+            .insert(request_id.clone(), sender);
+
+        let result = self
+            .initiator
+            .send(
+                FixMessage::new(msg_type::ORDER_MASS_STATUS_REQUEST)
+                    .with(tags::MASS_STATUS_REQ_ID, &request_id)
+                    .with(tags::MASS_STATUS_REQ_TYPE, 7),
+            )
+            .and_then(|_| {
+                let mut reports = Vec::new();
+
+                loop {
+                    let report = receiver
+                        .recv_timeout(FIX_RESPONSE_TIMEOUT)
+                        .map_err(|_| "FIX mass status timeout")?;
+
+                    let is_last = report.is_flag_set(tags::LAST_RPT_REQUESTED);
+                    reports.push(report);
+
+                    if is_last {
+                        return Ok(reports);
+                    }
+                }
+            });
+
+        self.status_requests.lock().unwrap().remove(&request_id);
+
+        let mut open_orders = Vec::new();
+
+        // New and partially filled orders are open
+        for report in result? {
+            let symbol = report.get(tags::SYMBOL).unwrap_or_default().to_string();
+
+            if !matches!(report.get(tags::ORD_STATUS), Some("0") | Some("1"))
+                || !symbols.contains(&symbol)
+            {
+                continue;
+            }
+
+            let open_order = OpenOrder {
+                symbol,
+                order_id: Self::order_id(report.get(tags::ORDER_ID).unwrap_or_default()),
+                custom_order_id: report.get(tags::CL_ORD_ID).unwrap_or_default().to_string(),
+                order_side: Self::order_side(report.get(tags::SIDE)),
+                price: report.get_f64(tags::PRICE).unwrap_or_default(),
+                amount: report.get_f64(tags::LEAVES_QTY).unwrap_or_default(),
+            };
+
+            self.orders.lock().unwrap().insert(
+                open_order.custom_order_id.clone(),
+                (
+                    open_order.symbol.clone(),
+                    open_order.order_side.clone(),
+                    open_order.amount,
+                ),
+            );
+
+            open_orders.push(open_order);
+        }
+
+        Ok(open_orders)
+    }
+
+    fn depth_stream(
+        &self,
+        _symbols: &[String],
+        _rate_limit: &dyn Fn(RequestClass),
+        _handler: &mut dyn FnMut(String, Depth),
+    ) -> ExchangeApiResult<()> {
+        Err("FIX venue has no market data")
+    }
+
+    // Session is logged on by the user stream, orders are sent while it's active
+    fn user_stream(
+        &self,
+        _symbols: &[String],
+        handler: &mut dyn FnMut(UserEvent),
+    ) -> ExchangeApiResult<()> {
+        self.initiator
+            .run(&mut |message| self.application_message(message, handler))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Fix;
+    use crate::api::fix::acceptor;
+    use crate::api::fix::message::{msg_type, tags, FixMessage};
+    use crate::api::fix::{FixInitiator, FixSession, SequenceStore, SessionConfig};
+    use crate::gateway::exchange::{ExchangeAction, UserEvent};
+    use crate::gateway::{GatewayParams, GatewayParamsAccount};
+    use crate::order_manager::{LimitOrder, OrderEvent, OrderSide, StopOrder, TimeInForce};
+    use crossbeam::channel::{unbounded, Receiver};
+    use std::thread;
+    use std::time::Duration;
+
+    // Execution report of the order or of the cancel request
+    fn execution_report(message: &FixMessage, exec_type: &str, ord_status: &str) -> FixMessage {
+        let mut execution_report = FixMessage::new(msg_type::EXECUTION_REPORT)
+            .with(tags::ORDER_ID, 1001)
+            .with(tags::EXEC_TYPE, exec_type)
+            .with(tags::ORD_STATUS, ord_status);
+
+        for tag in &[
+            tags::CL_ORD_ID,
+            tags::ORIG_CL_ORD_ID,
+            tags::SYMBOL,
+            tags::SIDE,
+        ] {
+            if let Some(value) = message.get(*tag) {
+                execution_report.set(*tag, value);
+            }
+        }
+
+        execution_report
+    }
+
+    // Venue accepts and fills BTCUSDT orders, rejects others and reports one open order
+    fn venue(message: &FixMessage) -> Vec<FixMessage> {
+        match message.msg_type() {
+            msg_type::NEW_ORDER_SINGLE if message.get(tags::SYMBOL) == Some("BTCUSDT") => vec![
+                execution_report(message, "0", "0"),
+                execution_report(message, "F", "2")
+                    .with(tags::LAST_QTY, message.get(tags::ORDER_QTY).unwrap())
+                    .with(tags::LAST_PX, message.get(tags::PRICE).unwrap())
+                    .with(tags::COMMISSION, 0.1),
+            ],
+            msg_type::NEW_ORDER_SINGLE => {
+                vec![execution_report(message, "8", "8").with(tags::TEXT, "Unknown symbol")]
+            }
+            msg_type::ORDER_CANCEL_REQUEST => vec![execution_report(message, "4", "4")],
+            msg_type::ORDER_MASS_STATUS_REQUEST => {
+                vec![FixMessage::new(msg_type::EXECUTION_REPORT)
+                    .with(
+                        tags::MASS_STATUS_REQ_ID,
+                        message.get(tags::MASS_STATUS_REQ_ID).unwrap(),
+                    )
+                    .with(tags::ORDER_ID, 1002)
+                    .with(tags::CL_ORD_ID, "Custom456")
+                    .with(tags::EXEC_TYPE, "I")
+                    .with(tags::ORD_STATUS, "1")
+                    .with(tags::SYMBOL, "BTCUSDT")
+                    .with(tags::SIDE, "2")
+                    .with(tags::PRICE, 120)
+                    .with(tags::LEAVES_QTY, 0.5)
+                    .with(tags::LAST_RPT_REQUESTED, "Y")]
+            }
+            _ => vec![],
+        }
+    }
+
+    // Exchange adapter logged on to the local venue with its user stream events
+    fn logged_on() -> (&'static Fix, Receiver<UserEvent>, Receiver<FixMessage>) {
+        let (address, received) = acceptor::spawn(venue);
+
+        let fix: &'static Fix = Box::leak(Box::new(Fix::with_initiator(
+            &GatewayParamsAccount::default(),
+            &GatewayParams::default().instruments,
+            FixInitiator::new(
+                &address,
+                FixSession::new(SessionConfig::default(), SequenceStore::memory()),
+            ),
+        )));
+        let (sender, receiver) = unbounded();
+
+        thread::spawn(move || fix.user_stream(&[], &mut |event| sender.send(event).unwrap()));
+
+        while !fix.is_active() {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        (fix, receiver, received)
+    }
+
+    #[test]
+    fn limit_order() {
+        let (fix, events, received) = logged_on();
+
+        let limit_order = LimitOrder {
+            amount: 2.,
+            price: 100.,
+            order_side: OrderSide::Buy,
+            custom_order_id: "Custom123".to_string(),
+            ..LimitOrder::default()
+        };
+
+        let transaction = fix.limit_order(&limit_order).unwrap();
+        assert_eq!(transaction.order_id, 1001);
+
+        let order = received.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(order.get(tags::ORD_TYPE), Some("2"));
+        assert_eq!(order.get(tags::SIDE), Some("1"));
+        assert_eq!(order.get(tags::TIME_IN_FORCE), Some("1"));
+
+        match events.recv_timeout(Duration::from_secs(5)).unwrap() {
+            UserEvent::OrderUpdate(order_update) => {
+                assert_eq!(order_update.custom_order_id, "Custom123");
+                assert_eq!(order_update.event, OrderEvent::Accepted);
+            }
+            _ => panic!("Order isn't accepted"),
+        }
+
+        match events.recv_timeout(Duration::from_secs(5)).unwrap() {
+            UserEvent::FilledOrder(filled_order) => {
+                assert_eq!(filled_order.custom_order_id, "Custom123");
+                assert_eq!(filled_order.amount, 2.);
+                assert_eq!(filled_order.price, 100.);
+                assert_eq!(filled_order.commission.unwrap().asset, "USDT");
+            }
+            _ => panic!("Order isn't filled"),
+        }
+    }
+
+    #[test]
+    fn rejected_order() {
+        let (fix, events, _received) = logged_on();
+
+        assert_eq!(
+            fix.limit_buy("ETHUSDT", 1., 10., Some("Custom123".to_string())),
+            Err("FIX order is rejected")
+        );
+
+        match events.recv_timeout(Duration::from_secs(5)).unwrap() {
+            UserEvent::OrderUpdate(order_update) => assert_eq!(
+                order_update.event,
+                OrderEvent::Rejected("Unknown symbol".to_string())
+            ),
+            _ => panic!("Order isn't rejected"),
+        }
+    }
+
+    #[test]
+    fn cancel_and_open_orders() {
+        let (fix, events, received) = logged_on();
+
+        let open_orders = fix.fetch_open_orders(&["BTCUSDT".to_string()]).unwrap();
+        assert_eq!(open_orders.len(), 1);
+        assert_eq!(open_orders[0].custom_order_id, "Custom456");
+        assert_eq!(open_orders[0].amount, 0.5);
+        received.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        fix.cancel_order("BTCUSDT", "Custom456").unwrap();
+
+        // Side and amount of the open order are known from its status
+        let cancel_request = received.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(cancel_request.get(tags::ORIG_CL_ORD_ID), Some("Custom456"));
+        assert_eq!(cancel_request.get(tags::SIDE), Some("2"));
+
+        match events.recv_timeout(Duration::from_secs(5)).unwrap() {
+            UserEvent::OrderUpdate(order_update) => {
+                assert_eq!(order_update.custom_order_id, "Custom456");
+                assert_eq!(order_update.event, OrderEvent::Cancelled);
+            }
+            _ => panic!("Order isn't canceled"),
+        }
+    }
+
+    #[test]
+    fn order_fields() {
+        let post_only = LimitOrder {
+            time_in_force: TimeInForce::PostOnly,
+            ..LimitOrder::default()
+        };
+        assert!(Fix::limit_fields(&post_only).contains(&(tags::EXEC_INST, "6".to_string())));
+
+        let gtd = LimitOrder {
+            time_in_force: TimeInForce::Gtd(1_622_541_600_000),
+            ..LimitOrder::default()
+        };
+        assert!(Fix::limit_fields(&gtd)
+            .contains(&(tags::EXPIRE_TIME, "20210601-10:00:00.000".to_string())));
+
+        let stop_limit = StopOrder {
+            limit_price: Some(9.),
+            ..StopOrder::default()
+        };
+        assert!(Fix::stop_fields(&stop_limit).contains(&(tags::ORD_TYPE, "4".to_string())));
+    }
+}
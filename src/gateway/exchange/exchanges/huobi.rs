@@ -1,25 +1,55 @@
 use crate::api::huobi::websocket_account::{
     WebSockets as HuobiWebSockets, WebsocketEvent as HuobiWebsocketEvent,
 };
-use crate::api::{
-    self,
-    huobi::{models::ResultSymbol, Account, HuobiApi},
+use crate::api::huobi::{
+    models::ResultSymbol,
+    websocket_data::{BookEvent as HuobiBookEvent, HuobiWS, Tick},
+    Account, HuobiApi,
 };
+use crate::context_manager::{Candle, CandleInterval, FilledOrder};
 use crate::gateway::{
     self,
-    exchange::{ExchangeAction, ExchangeApiResult, PlatformTransaction},
+    exchange::{
+        BalanceUpdate, ExchangeAction, ExchangeApiResult, OpenOrder, OrderUpdate,
+        PlatformTransaction, UserEvent,
+    },
     gateway::ExchangeInstrumentInfo,
-    Depth, GatewayParamsAccount,
+    BookSnapshot, BookSync, BookUpdate, Depth, GatewayParamsAccount, InstrumentType, PublicTrade,
+    RequestClass, SnapshotSchedule,
 };
+use crate::order_manager::{LimitOrder, OrderEvent, OrderSide, StopOrder, TimeInForce};
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::atomic::AtomicBool;
+use std::time::Instant;
+use tokio::runtime::Handle;
 use tracing::{debug, error, info, warn};
 
 pub struct Huobi {
     huobi_account: Account,
+    config_account: GatewayParamsAccount,
 }
 
 impl Huobi {
+    pub fn new(config_account: &GatewayParamsAccount) -> Self {
+        Huobi {
+            huobi_account: Account::new(
+                config_account.account_id.as_ref().unwrap(),
+                Some(config_account.api_key.expose().to_string()),
+                Some(config_account.secret_key.expose().to_string()),
+            ),
+            config_account: config_account.clone(),
+        }
+    }
+
+    // Runs async Huobi api from the synchronous trait methods
+    fn block_on<F: Future>(future: F) -> F::Output {
+        match Handle::try_current() {
+            Ok(handle) => tokio::task::block_in_place(|| handle.block_on(future)),
+            Err(_) => tokio::runtime::Runtime::new().unwrap().block_on(future),
+        }
+    }
+
     pub async fn metadata() -> Option<ResultSymbol> {
         match HuobiApi::symbols().await {
             Ok(symbols) => Some(symbols),
@@ -32,35 +62,68 @@ impl Huobi {
         }
     }
 
-    pub fn get_depth(huobi_depth: &api::huobi::websocket_data::Depth) -> gateway::Depth {
-        let depth = gateway::Depth {
-            exchange: huobi_depth.exchange.clone(),
-            bids: huobi_depth
-                .bids
-                .iter()
-                .map(|ticker| gateway::Ticker {
-                    price: ticker.price,
-                    qty: ticker.qty,
-                })
-                .collect(),
-            asks: huobi_depth
-                .asks
-                .iter()
-                .map(|ticker| gateway::Ticker {
-                    price: ticker.price,
-                    qty: ticker.qty,
-                })
-                .collect(),
+    // Huobi order type of the limit order, GTC is sent as usual limit order, GTD is emulated
+    fn limit_type(limit_order: &LimitOrder) -> Option<String> {
+        let type_ = match limit_order.time_in_force {
+            TimeInForce::PostOnly => "limit-maker",
+            TimeInForce::Ioc => "ioc",
+            TimeInForce::Fok => "limit-fok",
+            TimeInForce::Gtc | TimeInForce::Gtd(_) => return None,
         };
 
-        depth
+        Some(format!("{}-{}", Self::side(&limit_order.order_side), type_))
     }
 
+    // Buy stop is triggered when the price is greater or equal to the stop price
+    fn stop_operator(order_side: &OrderSide) -> &'static str {
+        match order_side {
+            OrderSide::Buy => "gte",
+            OrderSide::Sell => "lte",
+        }
+    }
+
+    // Huobi kline period of the interval, there are no second candles
+    fn kline_period(interval: CandleInterval) -> Option<&'static str> {
+        match interval {
+            CandleInterval::S1 => None,
+            CandleInterval::M1 => Some("1min"),
+            CandleInterval::M5 => Some("5min"),
+            CandleInterval::H1 => Some("60min"),
+        }
+    }
+
+    fn side(order_side: &OrderSide) -> &'static str {
+        match order_side {
+            OrderSide::Buy => "buy",
+            OrderSide::Sell => "sell",
+        }
+    }
+
+    // Snapshot and incremental update are both sequenced by seqNum
+    fn book_update(tick: Tick) -> BookUpdate {
+        BookUpdate {
+            first_update_id: tick.prev_seq_num.map_or(tick.seq_num, |prev| prev + 1),
+            last_update_id: tick.seq_num,
+            bids: tick.bids,
+            asks: tick.asks,
+        }
+    }
+
+    fn book_snapshot(tick: Tick) -> BookSnapshot {
+        BookSnapshot {
+            last_update_id: tick.seq_num,
+            bids: tick.bids,
+            asks: tick.asks,
+        }
+    }
+
+    // It returns error when the stream is broken
     pub fn user_stream_ws<Handler>(
         config_account: &GatewayParamsAccount,
         handler: Handler,
         symbols: Vec<&str>,
-    ) where
+    ) -> ExchangeApiResult<()>
+    where
         Handler: FnMut(HuobiWebsocketEvent) -> Result<(), Box<dyn std::error::Error>>,
     {
         let keep_running = AtomicBool::new(true);
@@ -69,23 +132,23 @@ impl Huobi {
 
         let mut websocket: HuobiWebSockets = HuobiWebSockets::new(handler);
 
-        websocket
-            .connect_auth(
-                &accountws,
-                symbols,
-                vec![],
-                &config_account.api_key,
-                &config_account.secret_key,
-            )
-            .unwrap();
+        if let Err(e) = websocket.connect_auth(
+            &accountws,
+            symbols,
+            vec![],
+            config_account.api_key.expose(),
+            config_account.secret_key.expose(),
+        ) {
+            error!("Huobi user stream connection error: {}", e);
+            return Err("Huobi user stream connection error");
+        }
 
         if let Err(e) = websocket.event_loop(&keep_running) {
-            match e {
-                err => {
-                    println!("Error: {}", err);
-                }
-            }
+            error!("Huobi user stream error: {}", e);
+            return Err("Huobi user stream error");
         }
+
+        Ok(())
     }
 }
 
@@ -95,20 +158,27 @@ impl ExchangeAction for Huobi {
     }
 
     fn fetch_metadata(&self) -> Vec<ExchangeInstrumentInfo> {
-        // let mut instruments_info = Vec::new();
-
-        // let huobi_metadata = Self::metadata().await.unwrap();
-        // for symbol_info in huobi_metadata.data {
-        //     instruments_info.push(ExchangeInstrumentInfo {
-        //         base: symbol_info.base,
-        //         quote: symbol_info.quote,
-        //         symbol: symbol_info.symbol,
-        //         precision: symbol_info.price_precision,
-        //     });
-        // }
-
-        // instruments_info
-        todo!()
+        let mut instruments_info = Vec::new();
+
+        let huobi_metadata = match Self::block_on(Self::metadata()) {
+            Some(huobi_metadata) => huobi_metadata,
+            None => return instruments_info,
+        };
+
+        for symbol_info in huobi_metadata.data {
+            instruments_info.push(ExchangeInstrumentInfo {
+                base: symbol_info.base,
+                quote: symbol_info.quote,
+                symbol: symbol_info.symbol,
+                instrument_type: InstrumentType::Spot,
+                precision: symbol_info.price_precision,
+                qty_step: 10f64.powi(-(symbol_info.amount_precision as i32)),
+                min_qty: symbol_info.min_amount,
+                min_notional: symbol_info.min_value,
+            });
+        }
+
+        instruments_info
     }
 
     fn fetch_depth(&self, _symbol: &str) -> Result<Depth, &'static str> {
@@ -119,24 +189,38 @@ impl ExchangeAction for Huobi {
         &self,
         instruments: Vec<gateway::Instrument>,
     ) -> Result<HashMap<String, f64>, &'static str> {
+        let balances = match self.huobi_account.get_all_balances() {
+            Ok(balances) => balances,
+            Err(e) => {
+                error!("Huobi balances error: {}", e);
+                return Err("Huobi balances error");
+            }
+        };
+
+        info!("[Gateway] Got balance for Huobi account");
+
+        // Asset type may be both "trade" and "frozen", currency is in lower case
+        let available = balances
+            .data
+            .list
+            .into_iter()
+            .filter(|asset| asset.trade_type == "trade")
+            .map(|asset| (asset.currency, asset.balance))
+            .collect::<HashMap<String, f64>>();
+
         let mut instrument_balances = HashMap::new();
+
+        // Asset which isn't listed in the account has no balance
         for instrument in instruments {
-            let balance_base = self
-                .huobi_account
-                .get_balance(&instrument.base)
-                .unwrap()
-                .balance;
-
-            let balance_quote = self
-                .huobi_account
-                .get_balance(&instrument.quote)
-                .unwrap()
-                .balance;
-
-            info!("[Gateway] Got balance for Huobi account");
-
-            instrument_balances.insert(instrument.base.clone(), balance_base);
-            instrument_balances.insert(instrument.quote.clone(), balance_quote);
+            for asset in vec![&instrument.base, &instrument.quote] {
+                instrument_balances.insert(
+                    asset.clone(),
+                    available
+                        .get(&asset.to_lowercase())
+                        .cloned()
+                        .unwrap_or_default(),
+                );
+            }
         }
 
         Ok(instrument_balances)
@@ -194,6 +278,82 @@ impl ExchangeAction for Huobi {
         }
     }
 
+    fn limit_order(&self, limit_order: &LimitOrder) -> ExchangeApiResult<PlatformTransaction> {
+        let custom_order_id = Some(limit_order.custom_order_id.clone());
+
+        let type_ = match (limit_order.time_in_force, Self::limit_type(limit_order)) {
+            (_, Some(type_)) => type_,
+            (TimeInForce::Gtc, None) => {
+                return match limit_order.order_side {
+                    OrderSide::Buy => self.limit_buy(
+                        &limit_order.symbol,
+                        limit_order.amount,
+                        limit_order.price,
+                        custom_order_id,
+                    ),
+                    OrderSide::Sell => self.limit_sell(
+                        &limit_order.symbol,
+                        limit_order.amount,
+                        limit_order.price,
+                        custom_order_id,
+                    ),
+                }
+            }
+            _ => return Err("Time in force isn't supported"),
+        };
+
+        let transaction = self.huobi_account.custom_order(
+            &limit_order.symbol,
+            limit_order.amount,
+            limit_order.price,
+            &type_,
+            None,
+            &limit_order.custom_order_id,
+        )?;
+
+        debug!("[Huobi] Ok. Limit {} order was placed", type_);
+
+        Ok(PlatformTransaction {
+            symbol: transaction.symbol,
+            order_id: transaction.order_id,
+        })
+    }
+
+    // Stop limit order only, stop market order is emulated by the gateway
+    fn stop_order(&self, stop_order: &StopOrder) -> ExchangeApiResult<PlatformTransaction> {
+        let limit_price = match stop_order.limit_price {
+            Some(limit_price) => limit_price,
+            None => return Err("Stop market orders aren't supported"),
+        };
+
+        let transaction = self.huobi_account.custom_order(
+            &stop_order.symbol,
+            stop_order.amount,
+            limit_price,
+            &format!("{}-stop-limit", Self::side(&stop_order.order_side)),
+            Some((
+                stop_order.stop_price,
+                Self::stop_operator(&stop_order.order_side),
+            )),
+            &stop_order.custom_order_id,
+        )?;
+
+        debug!("[Huobi] Ok. Stop Limit order was placed");
+
+        Ok(PlatformTransaction {
+            symbol: transaction.symbol,
+            order_id: transaction.order_id,
+        })
+    }
+
+    fn native_time_in_force(&self, time_in_force: &TimeInForce) -> bool {
+        !matches!(time_in_force, TimeInForce::Gtd(_))
+    }
+
+    fn native_stop_order(&self, stop_order: &StopOrder) -> bool {
+        stop_order.limit_price.is_some()
+    }
+
     fn market_buy(&self, symbol: &str, amount: f64) -> ExchangeApiResult<PlatformTransaction> {
         match self.huobi_account.market_buy(symbol, amount) {
             Ok(transaction) => Ok(PlatformTransaction {
@@ -241,12 +401,273 @@ impl ExchangeAction for Huobi {
             }
         }
     }
+
+    fn fetch_open_orders(&self, symbols: &[String]) -> ExchangeApiResult<Vec<OpenOrder>> {
+        let mut open_orders = Vec::new();
+
+        for symbol in symbols {
+            let orders = match self.huobi_account.get_open_orders(symbol) {
+                Ok(orders) => orders.data,
+                Err(e) => {
+                    error!("Huobi open orders error: {}", e);
+                    return Err("Huobi open orders error");
+                }
+            };
+
+            for order in orders {
+                let amount = order.amount.parse::<f64>().unwrap_or_default();
+                let filled_amount = order.filled_amount.parse::<f64>().unwrap_or_default();
+
+                open_orders.push(OpenOrder {
+                    // Huobi symbols are in lower case
+                    symbol: symbol.clone(),
+                    order_id: order.id,
+                    custom_order_id: order.client_order_id,
+                    // Order type is e.g. "buy-limit"
+                    order_side: if order.type_.starts_with("buy") {
+                        OrderSide::Buy
+                    } else {
+                        OrderSide::Sell
+                    },
+                    price: order.price.parse().unwrap_or_default(),
+                    amount: amount - filled_amount,
+                });
+            }
+        }
+
+        Ok(open_orders)
+    }
+
+    fn depth_stream(
+        &self,
+        symbols: &[String],
+        rate_limit: &dyn Fn(RequestClass),
+        handler: &mut dyn FnMut(String, Depth),
+    ) -> ExchangeApiResult<()> {
+        let symbols = symbols.iter().map(String::as_str).collect::<Vec<&str>>();
+
+        // Snapshots are requested on connect
+        let mut huobi_ws = HuobiWS::connect(&symbols)?;
+
+        let mut books = symbols
+            .iter()
+            .map(|symbol| (symbol.to_string(), BookSync::new(symbol, "Huobi")))
+            .collect::<HashMap<String, BookSync>>();
+
+        let mut schedule = SnapshotSchedule::new();
+        for symbol in symbols.iter() {
+            schedule.requested(symbol, Instant::now());
+        }
+
+        loop {
+            let (symbol, book_event) = huobi_ws.get_event()?;
+
+            let book_sync = match books.get_mut(&symbol) {
+                Some(book_sync) => book_sync,
+                None => continue,
+            };
+
+            let applied = match book_event {
+                HuobiBookEvent::Snapshot(tick) => book_sync
+                    .apply_snapshot(Self::book_snapshot(tick))
+                    .map(|_| true),
+                HuobiBookEvent::Update(tick) => book_sync.apply_update(Self::book_update(tick)),
+            };
+
+            match applied {
+                Ok(true) => handler(symbol.clone(), book_sync.book().to_depth()),
+                Ok(false) => {}
+                Err(e) => warn!("[Huobi] {} order book: {}. Resync", symbol, e),
+            }
+
+            // Updates are buffered until the new snapshot, it's requested again after backoff
+            // if the previous one is lost or stale
+            if book_sync.is_synced() {
+                schedule.synced(&symbol);
+            } else if schedule.is_due(&symbol, Instant::now()) {
+                rate_limit(RequestClass::Info);
+                schedule.requested(&symbol, Instant::now());
+
+                huobi_ws.request_snapshot(&symbol)?;
+            }
+        }
+    }
+
+    fn fetch_candles(
+        &self,
+        symbol: &str,
+        interval: CandleInterval,
+        limit: usize,
+    ) -> ExchangeApiResult<Vec<Candle>> {
+        let period = Self::kline_period(interval).ok_or("Huobi candle interval isn't supported")?;
+
+        match Self::block_on(HuobiApi::klines(symbol, period, limit)) {
+            Ok(klines) => Ok(klines
+                .data
+                .into_iter()
+                .rev()
+                .map(|kline| Candle {
+                    open_time: kline.id * 1000,
+                    open: kline.open,
+                    high: kline.high,
+                    low: kline.low,
+                    close: kline.close,
+                    volume: kline.amount,
+                })
+                .collect()),
+            Err(e) => {
+                error!("[Huobi] Can't get {} {} klines: {}", symbol, interval, e);
+                Err("Huobi klines error")
+            }
+        }
+    }
+
+    fn has_server_time(&self) -> bool {
+        true
+    }
+
+    fn server_time(&self) -> ExchangeApiResult<u64> {
+        Self::block_on(HuobiApi::timestamp()).map_err(|e| {
+            error!("[Huobi] Can't get server time: {}", e);
+            "Huobi server time error"
+        })
+    }
+
+    fn has_trade_stream(&self) -> bool {
+        true
+    }
+
+    fn trade_stream(
+        &self,
+        symbols: &[String],
+        handler: &mut dyn FnMut(String, PublicTrade),
+    ) -> ExchangeApiResult<()> {
+        let symbols = symbols.iter().map(String::as_str).collect::<Vec<&str>>();
+
+        let mut huobi_ws = HuobiWS::connect_trades(&symbols)?;
+
+        loop {
+            let (symbol, trades) = huobi_ws.get_trades()?;
+
+            for trade in trades {
+                handler(
+                    symbol.clone(),
+                    PublicTrade {
+                        trade_id: trade.trade_id,
+                        price: trade.price,
+                        qty: trade.amount,
+                        aggressor: match trade.direction.as_str() {
+                            "buy" => OrderSide::Buy,
+                            _ => OrderSide::Sell,
+                        },
+                        timestamp: trade.ts,
+                    },
+                );
+            }
+        }
+    }
+
+    fn user_stream(
+        &self,
+        symbols: &[String],
+        handler: &mut dyn FnMut(UserEvent),
+    ) -> ExchangeApiResult<()> {
+        use crate::api::huobi::models::EventType;
+
+        let account = self.config_account.name.clone();
+
+        let user_handler = |event: HuobiWebsocketEvent| {
+            match event {
+                HuobiWebsocketEvent::OrderUpdate(order_subscription) => {
+                    match order_subscription.data {
+                        EventType::Trade(trade) => {
+                            info!(
+                                "[Gateway] Huobi order was filled: {} {}",
+                                trade.symbol, trade.trade_volume
+                            );
+
+                            handler(UserEvent::FilledOrder(FilledOrder {
+                                order_id: trade.order_id,
+                                custom_order_id: trade.client_order_id,
+                                symbol: trade.symbol.clone(),
+                                amount: trade.trade_volume.parse().unwrap_or_default(),
+                                price: trade.trade_price.parse().unwrap_or_default(),
+                                // Trade event has no commission
+                                commission: None,
+                                account: account.clone(),
+                                instrument_id: None,
+                            }));
+                        }
+                        EventType::Creation(order) => {
+                            handler(UserEvent::OrderUpdate(OrderUpdate {
+                                custom_order_id: order.client_order_id,
+                                order_id: order.order_id,
+                                symbol: order.symbol,
+                                event: OrderEvent::Accepted,
+                            }));
+                        }
+
+                        // Partially filled order is canceled with "partial-canceled" status
+                        EventType::Cancellation(order) => {
+                            handler(UserEvent::OrderUpdate(OrderUpdate {
+                                custom_order_id: order.client_order_id,
+                                order_id: order.order_id,
+                                symbol: order.symbol,
+                                event: OrderEvent::Cancelled,
+                            }));
+                        }
+                    };
+                }
+                HuobiWebsocketEvent::AccountUpdate(account_subscription) => {
+                    let change = account_subscription.data;
+
+                    // Loan and interest accounts aren't used for trading
+                    if change.account_type.as_deref().unwrap_or("trade") != "trade" {
+                        return Ok(());
+                    }
+
+                    if let (Some(balance), Some(available)) = (change.balance, change.available) {
+                        let balance = balance.parse::<f64>().unwrap_or_default();
+                        let available = available.parse::<f64>().unwrap_or_default();
+
+                        handler(UserEvent::BalanceUpdate(BalanceUpdate {
+                            asset: change.currency,
+                            free: available,
+                            locked: balance - available,
+                        }));
+                    }
+                }
+            }
+
+            Ok(())
+        };
+
+        let symbols = symbols.iter().map(String::as_str).collect();
+
+        Self::user_stream_ws(&self.config_account, user_handler, symbols)
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::Huobi;
+    use crate::order_manager::{LimitOrder, OrderSide, TimeInForce};
+
+    #[test]
+    fn limit_type() {
+        let post_only = LimitOrder {
+            order_side: OrderSide::Sell,
+            time_in_force: TimeInForce::PostOnly,
+            ..LimitOrder::default()
+        };
+        assert_eq!(
+            Huobi::limit_type(&post_only),
+            Some("sell-limit-maker".to_string())
+        );
+
+        assert_eq!(Huobi::limit_type(&LimitOrder::default()), None);
+    }
 
     #[tokio::test]
     #[ignore]
@@ -0,0 +1,711 @@
+use crate::api::kraken::websocket_account::{
+    WebSockets as KrakenWebSockets, WebsocketEvent as KrakenWebsocketEvent,
+};
+use crate::api::{
+    self,
+    kraken::{
+        models::{order_id_from_txid, platform_asset, APIResult, AssetPair},
+        websocket_data::KrakenWS,
+        Account, KrakenApi,
+    },
+};
+use crate::context_manager::{Commission, FilledOrder};
+use crate::gateway::{
+    self,
+    exchange::{
+        ExchangeAction, ExchangeApiResult, OpenOrder, OrderUpdate, PlatformTransaction, UserEvent,
+    },
+    gateway::ExchangeInstrumentInfo,
+    Depth, GatewayParamsAccount, Instrument, InstrumentType, RequestClass,
+};
+use crate::order_manager::{LimitOrder, OrderEvent, OrderSide, StopOrder, TimeInForce};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::AtomicBool;
+use std::sync::RwLock;
+use tracing::{debug, error, info, warn};
+
+// Levels of the REST depth snapshot
+const KRAKEN_DEPTH_COUNT: u32 = 10;
+
+// Platform symbols are built from platform assets, e.g. BTCEUR for Kraken XBTEUR pair
+pub struct Kraken {
+    kraken_account: Account,
+    config_account: GatewayParamsAccount,
+    // <Platform symbol, Kraken pair>, it's loaded with the first request
+    pairs: RwLock<HashMap<String, AssetPair>>,
+}
+
+impl Kraken {
+    pub fn new(config_account: &GatewayParamsAccount) -> Self {
+        Kraken {
+            kraken_account: Account::new(
+                Some(config_account.api_key.expose().to_string()),
+                Some(config_account.secret_key.expose().to_string()),
+            ),
+            config_account: config_account.clone(),
+            pairs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn metadata() -> Option<Vec<AssetPair>> {
+        match KrakenApi::asset_pairs() {
+            Ok(asset_pairs) => Some(asset_pairs.into_iter().map(|(_, pair)| pair).collect()),
+            Err(e) => {
+                error!("Kraken exchange error: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn instrument_info(asset_pair: &AssetPair) -> ExchangeInstrumentInfo {
+        let parse = |value: &Option<String>| {
+            value
+                .as_ref()
+                .and_then(|value| value.parse::<f64>().ok())
+                .unwrap_or(0.)
+        };
+
+        ExchangeInstrumentInfo {
+            base: platform_asset(&asset_pair.base),
+            quote: platform_asset(&asset_pair.quote),
+            precision: asset_pair.pair_decimals,
+            qty_step: 10f64.powi(-(asset_pair.lot_decimals as i32)),
+            min_qty: parse(&asset_pair.ordermin),
+            min_notional: parse(&asset_pair.costmin),
+            symbol: asset_pair.symbol(),
+            instrument_type: InstrumentType::Spot,
+        }
+    }
+
+    // Kraken pair of the platform symbol
+    fn pair(&self, symbol: &str) -> ExchangeApiResult<AssetPair> {
+        if let Some(asset_pair) = self.pairs.read().unwrap().get(symbol) {
+            return Ok(asset_pair.clone());
+        }
+
+        let asset_pairs = tokio::task::block_in_place(Self::metadata)
+            .ok_or("Kraken asset pairs error")?
+            .into_iter()
+            .map(|asset_pair| (asset_pair.symbol(), asset_pair))
+            .collect::<HashMap<String, AssetPair>>();
+
+        let asset_pair = asset_pairs.get(symbol).cloned();
+
+        *self.pairs.write().unwrap() = asset_pairs;
+
+        asset_pair.ok_or("Unknown Kraken symbol")
+    }
+
+    // <Kraken pair name, platform symbol>, websocket pair names are XBT/EUR
+    fn symbols_by_pair(&self, symbols: &[String], ws_names: bool) -> HashMap<String, String> {
+        symbols
+            .iter()
+            .filter_map(|symbol| {
+                let asset_pair = self.pair(symbol).ok()?;
+                let pair_name = if ws_names {
+                    asset_pair.wsname?
+                } else {
+                    asset_pair.altname
+                };
+
+                Some((pair_name, symbol.clone()))
+            })
+            .collect()
+    }
+
+    // Order event of the open order status, fills come separately as trades
+    fn order_event(status: &str) -> Option<OrderEvent> {
+        match status {
+            "open" => Some(OrderEvent::Accepted),
+            "canceled" => Some(OrderEvent::Cancelled),
+            "expired" => Some(OrderEvent::Expired),
+            _ => None,
+        }
+    }
+
+    // Order parameters of the limit order, FOK is emulated by the gateway
+    fn limit_params(limit_order: &LimitOrder) -> Option<BTreeMap<String, String>> {
+        let mut params = BTreeMap::new();
+
+        params.insert("price".to_string(), limit_order.price.to_string());
+        params.insert("cl_ord_id".to_string(), limit_order.custom_order_id.clone());
+
+        match limit_order.time_in_force {
+            TimeInForce::Gtc => {}
+            TimeInForce::PostOnly => {
+                params.insert("oflags".to_string(), "post".to_string());
+            }
+            TimeInForce::Ioc => {
+                params.insert("timeinforce".to_string(), "IOC".to_string());
+            }
+            TimeInForce::Gtd(expire_at) => {
+                params.insert("timeinforce".to_string(), "GTD".to_string());
+                // Expiration time in unix seconds
+                params.insert("expiretm".to_string(), (expire_at / 1000).to_string());
+            }
+            TimeInForce::Fok => return None,
+        }
+
+        Some(params)
+    }
+
+    // Stop price is the trigger and price2 is the limit price of the triggered order
+    fn stop_params(stop_order: &StopOrder) -> (&'static str, BTreeMap<String, String>) {
+        let mut params = BTreeMap::new();
+
+        params.insert("price".to_string(), stop_order.stop_price.to_string());
+        params.insert("cl_ord_id".to_string(), stop_order.custom_order_id.clone());
+
+        match stop_order.limit_price {
+            Some(limit_price) => {
+                params.insert("price2".to_string(), limit_price.to_string());
+
+                ("stop-loss-limit", params)
+            }
+            None => ("stop-loss", params),
+        }
+    }
+
+    fn side(order_side: &OrderSide) -> &'static str {
+        match order_side {
+            OrderSide::Buy => "buy",
+            OrderSide::Sell => "sell",
+        }
+    }
+
+    pub fn get_depth(kraken_depth: &api::kraken::websocket_data::Depth) -> gateway::Depth {
+        gateway::Depth {
+            exchange: kraken_depth.exchange.clone(),
+            bids: kraken_depth
+                .bids
+                .iter()
+                .map(|ticker| gateway::Ticker {
+                    price: ticker.price,
+                    qty: ticker.qty,
+                })
+                .collect(),
+            asks: kraken_depth
+                .asks
+                .iter()
+                .map(|ticker| gateway::Ticker {
+                    price: ticker.price,
+                    qty: ticker.qty,
+                })
+                .collect(),
+        }
+    }
+
+    fn transaction(
+        symbol: &str,
+        transaction: api::kraken::models::Transaction,
+    ) -> PlatformTransaction {
+        PlatformTransaction {
+            symbol: symbol.to_string(),
+            order_id: transaction.order_id,
+        }
+    }
+
+    // It returns error when the stream is broken
+    pub fn user_stream_ws<Handler>(
+        kraken_account: &Account,
+        handler: Handler,
+    ) -> ExchangeApiResult<()>
+    where
+        Handler: FnMut(KrakenWebsocketEvent) -> APIResult<()>,
+    {
+        let keep_running = AtomicBool::new(true);
+
+        let token = match kraken_account.get_websockets_token() {
+            Ok(token) => token,
+            Err(e) => {
+                error!("Kraken websockets token error: {}", e);
+                return Err("Kraken websockets token error");
+            }
+        };
+
+        let mut websocket: KrakenWebSockets = KrakenWebSockets::new(handler);
+
+        if let Err(e) = websocket.connect_auth(&token) {
+            error!("Kraken user stream connection error: {}", e);
+            return Err("Kraken user stream connection error");
+        }
+
+        if let Err(e) = websocket.event_loop(&keep_running) {
+            error!("Kraken user stream error: {}", e);
+            return Err("Kraken user stream error");
+        }
+
+        Ok(())
+    }
+}
+
+impl ExchangeAction for Kraken {
+    fn inti(&self) {}
+
+    fn fetch_metadata(&self) -> Vec<ExchangeInstrumentInfo> {
+        let asset_pairs = tokio::task::block_in_place(Self::metadata).unwrap_or_default();
+
+        let instruments_info = asset_pairs.iter().map(Self::instrument_info).collect();
+
+        *self.pairs.write().unwrap() = asset_pairs
+            .into_iter()
+            .map(|asset_pair| (asset_pair.symbol(), asset_pair))
+            .collect();
+
+        instruments_info
+    }
+
+    fn fetch_depth(&self, symbol: &str) -> Result<Depth, &'static str> {
+        let asset_pair = self.pair(symbol)?;
+
+        let order_book = match KrakenApi::depth(&asset_pair.altname, KRAKEN_DEPTH_COUNT) {
+            Ok(order_book) => order_book,
+            Err(e) => {
+                error!("Kraken depth error: {}", e);
+                return Err("Kraken depth error");
+            }
+        };
+
+        let tickers = |levels: &Vec<(String, String, u64)>| {
+            levels
+                .iter()
+                .map(|(price, volume, _)| gateway::Ticker {
+                    price: price.parse().unwrap_or_default(),
+                    qty: volume.parse().unwrap_or_default(),
+                })
+                .collect()
+        };
+
+        Ok(Depth {
+            exchange: "Kraken".to_string(),
+            bids: tickers(&order_book.bids),
+            asks: tickers(&order_book.asks),
+        })
+    }
+
+    fn fetch_balances(
+        &self,
+        instruments: Vec<Instrument>,
+    ) -> Result<HashMap<String, f64>, &'static str> {
+        let balances = match self.kraken_account.get_balances() {
+            Ok(balances) => balances,
+            Err(e) => {
+                error!("Kraken balances error: {}", e);
+                return Err("Kraken balances error");
+            }
+        };
+
+        info!("[Gateway] Got balance for Kraken account");
+
+        // Staked and reward balances have the same platform asset, they aren't available
+        let available = balances
+            .iter()
+            .filter(|(asset, _)| !asset.contains('.'))
+            .map(|(asset, balance)| (platform_asset(asset), balance.available()))
+            .collect::<HashMap<String, f64>>();
+
+        let mut instrument_balances = HashMap::new();
+
+        for instrument in instruments {
+            for asset in vec![&instrument.base, &instrument.quote] {
+                instrument_balances.insert(
+                    asset.clone(),
+                    available.get(asset).cloned().unwrap_or_default(),
+                );
+            }
+        }
+
+        Ok(instrument_balances)
+    }
+
+    fn limit_buy(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        let asset_pair = self.pair(symbol)?;
+
+        match self
+            .kraken_account
+            .limit_buy(&asset_pair.altname, amount, price, custom_order_id)
+        {
+            Ok(transaction) => {
+                debug!("[Kraken] Ok. Limit Buy order was placed");
+
+                Ok(Self::transaction(symbol, transaction))
+            }
+            Err(error) => {
+                error!("Kraken Limit Buy error: {}", error);
+                Err(error)
+            }
+        }
+    }
+
+    fn limit_sell(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        let asset_pair = self.pair(symbol)?;
+
+        match self
+            .kraken_account
+            .limit_sell(&asset_pair.altname, amount, price, custom_order_id)
+        {
+            Ok(transaction) => {
+                debug!("[Kraken] Ok. Limit Sell order was placed");
+
+                Ok(Self::transaction(symbol, transaction))
+            }
+            Err(error) => {
+                error!("Kraken Limit Sell error: {}", error);
+                Err(error)
+            }
+        }
+    }
+
+    fn limit_order(&self, limit_order: &LimitOrder) -> ExchangeApiResult<PlatformTransaction> {
+        let params = match Self::limit_params(limit_order) {
+            Some(params) => params,
+            None => return Err("Time in force isn't supported"),
+        };
+
+        let asset_pair = self.pair(&limit_order.symbol)?;
+
+        let transaction = self.kraken_account.custom_order(
+            &asset_pair.altname,
+            Self::side(&limit_order.order_side),
+            "limit",
+            limit_order.amount,
+            params,
+        )?;
+
+        debug!(
+            "[Kraken] Ok. Limit {:?} order was placed",
+            limit_order.time_in_force
+        );
+
+        Ok(Self::transaction(&limit_order.symbol, transaction))
+    }
+
+    fn stop_order(&self, stop_order: &StopOrder) -> ExchangeApiResult<PlatformTransaction> {
+        let asset_pair = self.pair(&stop_order.symbol)?;
+        let (order_type, params) = Self::stop_params(stop_order);
+
+        let transaction = self.kraken_account.custom_order(
+            &asset_pair.altname,
+            Self::side(&stop_order.order_side),
+            order_type,
+            stop_order.amount,
+            params,
+        )?;
+
+        debug!("[Kraken] Ok. Stop order was placed");
+
+        Ok(Self::transaction(&stop_order.symbol, transaction))
+    }
+
+    fn native_time_in_force(&self, time_in_force: &TimeInForce) -> bool {
+        !matches!(time_in_force, TimeInForce::Fok)
+    }
+
+    fn native_stop_order(&self, _stop_order: &StopOrder) -> bool {
+        true
+    }
+
+    fn market_buy(&self, symbol: &str, amount: f64) -> ExchangeApiResult<PlatformTransaction> {
+        let asset_pair = self.pair(symbol)?;
+
+        match self.kraken_account.market_buy(&asset_pair.altname, amount) {
+            Ok(transaction) => Ok(Self::transaction(symbol, transaction)),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn market_sell(&self, symbol: &str, amount: f64) -> ExchangeApiResult<PlatformTransaction> {
+        let asset_pair = self.pair(symbol)?;
+
+        match self.kraken_account.market_sell(&asset_pair.altname, amount) {
+            Ok(transaction) => Ok(Self::transaction(symbol, transaction)),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn cancel_order(
+        &self,
+        symbol: &str,
+        custom_order_id: &str,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        match self
+            .kraken_account
+            .cancel_order_with_custom_id(symbol, custom_order_id)
+        {
+            Ok(transaction) => Ok(Self::transaction(symbol, transaction)),
+            Err(error) => {
+                // Do not throw error
+                // Cancel order could be filled
+
+                warn!("Can't cancel order: {}. It could be filled", error);
+
+                Ok(PlatformTransaction {
+                    symbol: symbol.to_string(),
+                    order_id: 0,
+                })
+            }
+        }
+    }
+
+    fn fetch_open_orders(&self, symbols: &[String]) -> ExchangeApiResult<Vec<OpenOrder>> {
+        let symbols_by_pair = self.symbols_by_pair(symbols, false);
+
+        let orders = match self.kraken_account.get_open_orders() {
+            Ok(orders) => orders,
+            Err(e) => {
+                error!("Kraken open orders error: {}", e);
+                return Err("Kraken open orders error");
+            }
+        };
+
+        let open_orders = orders
+            .into_iter()
+            .filter_map(|(txid, order)| {
+                let symbol = symbols_by_pair.get(&order.descr.pair)?;
+
+                let amount = order.vol.parse::<f64>().unwrap_or_default()
+                    - order.vol_exec.parse::<f64>().unwrap_or_default();
+
+                Some(OpenOrder {
+                    symbol: symbol.clone(),
+                    order_id: order_id_from_txid(&txid),
+                    // Orders placed outside the platform have no custom id
+                    custom_order_id: order.cl_ord_id.unwrap_or_default(),
+                    order_side: if order.descr.side == "buy" {
+                        OrderSide::Buy
+                    } else {
+                        OrderSide::Sell
+                    },
+                    price: order.descr.price.parse().unwrap_or_default(),
+                    amount,
+                })
+            })
+            .collect();
+
+        Ok(open_orders)
+    }
+
+    fn depth_stream(
+        &self,
+        symbols: &[String],
+        _rate_limit: &dyn Fn(RequestClass),
+        handler: &mut dyn FnMut(String, Depth),
+    ) -> ExchangeApiResult<()> {
+        let symbols_by_pair = self.symbols_by_pair(symbols, true);
+        let pairs = symbols_by_pair
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+
+        let mut kraken_ws = KrakenWS::connect(&pairs)?;
+
+        loop {
+            let (pair, kraken_depth) = kraken_ws.get_depth()?;
+
+            if let Some(symbol) = symbols_by_pair.get(&pair) {
+                handler(symbol.clone(), Self::get_depth(&kraken_depth));
+            }
+        }
+    }
+
+    fn has_server_time(&self) -> bool {
+        true
+    }
+
+    fn server_time(&self) -> ExchangeApiResult<u64> {
+        KrakenApi::server_time().map_err(|e| {
+            error!("Kraken server time error: {}", e);
+            "Kraken server time error"
+        })
+    }
+
+    fn user_stream(
+        &self,
+        symbols: &[String],
+        handler: &mut dyn FnMut(UserEvent),
+    ) -> ExchangeApiResult<()> {
+        let account = self.config_account.name.clone();
+        let symbols_by_pair = self.symbols_by_pair(symbols, true);
+
+        // <Order transaction id, (Custom order id, Symbol)>, status updates and trades have txid only
+        let mut orders: HashMap<String, (String, String)> = HashMap::new();
+
+        let user_handler = |event: KrakenWebsocketEvent| {
+            match event {
+                KrakenWebsocketEvent::OpenOrders(order_updates) => {
+                    for (txid, order_update) in order_updates {
+                        if let Some(descr) = &order_update.descr {
+                            if let Some(symbol) = symbols_by_pair.get(&descr.pair) {
+                                orders.insert(
+                                    txid.clone(),
+                                    (
+                                        order_update.cl_ord_id.clone().unwrap_or_default(),
+                                        symbol.clone(),
+                                    ),
+                                );
+                            }
+                        }
+
+                        let (custom_order_id, symbol) = match orders.get(&txid) {
+                            Some(order) => order.clone(),
+                            None => continue,
+                        };
+
+                        let event = order_update.status.as_deref().and_then(Self::order_event);
+
+                        if let Some(event) = event {
+                            handler(UserEvent::OrderUpdate(OrderUpdate {
+                                custom_order_id,
+                                order_id: order_id_from_txid(&txid),
+                                symbol,
+                                event,
+                            }));
+                        }
+
+                        // Closed order gets no more trades after its last one
+                        if matches!(
+                            order_update.status.as_deref(),
+                            Some("canceled") | Some("expired")
+                        ) {
+                            orders.remove(&txid);
+                        }
+                    }
+                }
+                KrakenWebsocketEvent::OwnTrades(trades) => {
+                    for (_, trade) in trades {
+                        let symbol = match symbols_by_pair.get(&trade.pair) {
+                            Some(symbol) => symbol.clone(),
+                            None => continue,
+                        };
+
+                        let custom_order_id = orders
+                            .get(&trade.ordertxid)
+                            .map(|(custom_order_id, _)| custom_order_id.clone())
+                            .unwrap_or_default();
+
+                        let amount = trade.vol.parse().unwrap_or_default();
+
+                        info!("[Gateway] Kraken order was filled: {} {}", symbol, amount);
+
+                        // Kraken charges the fee in the quote asset by default
+                        let quote = trade.pair.split('/').nth(1).unwrap_or_default();
+
+                        handler(UserEvent::FilledOrder(FilledOrder {
+                            order_id: order_id_from_txid(&trade.ordertxid),
+                            custom_order_id,
+                            symbol,
+                            amount,
+                            price: trade.price.parse().unwrap_or_default(),
+                            commission: Some(Commission {
+                                amount: trade.fee.parse().unwrap_or_default(),
+                                asset: platform_asset(quote),
+                                // It's converted by the gateway
+                                quote_amount: 0.,
+                            }),
+                            account: account.clone(),
+                            instrument_id: None,
+                        }));
+                    }
+                }
+            }
+
+            Ok(())
+        };
+
+        Self::user_stream_ws(&self.kraken_account, user_handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Kraken;
+    use crate::api::kraken::models::AssetPair;
+    use crate::order_manager::{LimitOrder, OrderEvent, StopOrder, TimeInForce};
+
+    fn asset_pair() -> AssetPair {
+        AssetPair {
+            altname: "XBTEUR".to_string(),
+            wsname: Some("XBT/EUR".to_string()),
+            base: "XXBT".to_string(),
+            quote: "ZEUR".to_string(),
+            pair_decimals: 1,
+            lot_decimals: 8,
+            ordermin: Some("0.0001".to_string()),
+            costmin: Some("0.5".to_string()),
+        }
+    }
+
+    #[test]
+    fn instrument_info() {
+        let instrument_info = Kraken::instrument_info(&asset_pair());
+
+        assert_eq!(instrument_info.symbol, "BTCEUR");
+        assert_eq!(instrument_info.base, "BTC");
+        assert_eq!(instrument_info.quote, "EUR");
+        assert_eq!(instrument_info.precision, 1);
+        assert_eq!(instrument_info.qty_step, 0.00000001);
+        assert_eq!(instrument_info.min_qty, 0.0001);
+        assert_eq!(instrument_info.min_notional, 0.5);
+    }
+
+    #[test]
+    fn order_event() {
+        assert_eq!(Kraken::order_event("open"), Some(OrderEvent::Accepted));
+        assert_eq!(Kraken::order_event("canceled"), Some(OrderEvent::Cancelled));
+        assert_eq!(Kraken::order_event("expired"), Some(OrderEvent::Expired));
+        assert_eq!(Kraken::order_event("pending"), None);
+    }
+
+    #[test]
+    fn order_params() {
+        let post_only = LimitOrder {
+            time_in_force: TimeInForce::PostOnly,
+            ..LimitOrder::default()
+        };
+        let params = Kraken::limit_params(&post_only).unwrap();
+        assert_eq!(params["oflags"], "post");
+
+        let gtd = LimitOrder {
+            time_in_force: TimeInForce::Gtd(1_688_666_559_000),
+            ..LimitOrder::default()
+        };
+        let params = Kraken::limit_params(&gtd).unwrap();
+        assert_eq!(params["timeinforce"], "GTD");
+        assert_eq!(params["expiretm"], "1688666559");
+
+        let fill_or_kill = LimitOrder {
+            time_in_force: TimeInForce::Fok,
+            ..LimitOrder::default()
+        };
+        assert!(Kraken::limit_params(&fill_or_kill).is_none());
+
+        let stop_limit = StopOrder {
+            limit_price: Some(9.),
+            ..StopOrder::default()
+        };
+        let (order_type, params) = Kraken::stop_params(&stop_limit);
+        assert_eq!(order_type, "stop-loss-limit");
+        assert_eq!(params["price"], "10");
+        assert_eq!(params["price2"], "9");
+    }
+
+    #[test]
+    #[ignore]
+    // For local testing
+    fn metadata() {
+        println!("{:?}", Kraken::metadata().unwrap());
+    }
+}
@@ -1,4 +1,7 @@
 pub mod binance;
 pub mod bitmex;
+pub mod fix;
 pub mod huobi;
+pub mod kraken;
+pub mod replay;
 pub mod stub;
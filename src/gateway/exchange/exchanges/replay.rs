@@ -0,0 +1,402 @@
+use super::stub::Stub;
+use crate::context_manager::{MarketRecorder, RecordEvent};
+use crate::gateway::{
+    exchange::{ExchangeAction, ExchangeApiResult, OpenOrder, PlatformTransaction, UserEvent},
+    gateway::ExchangeInstrumentInfo,
+    Depth, Instrument, ReplayParams, RequestClass, Ticker,
+};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+// Number of the book levels published after every replayed snapshot
+const REPLAY_DEPTH_LEVELS: usize = 20;
+
+// Depth snapshot of the symbol at unix time in milliseconds
+#[derive(Debug, Clone)]
+struct Snapshot {
+    timestamp: u64,
+    symbol: String,
+    depth: Depth,
+}
+
+// Exchange which replays recorded market data instead of connecting to the exchange.
+// Replayed depths replace the liquidity of the simulated Stub book, so orders of the robots
+// are filled against the historical market. Depth stream is closed at the end of the data.
+pub struct Replay {
+    stub: Stub,
+    params: ReplayParams,
+}
+
+impl Replay {
+    pub fn new(instruments: &[Instrument], params: ReplayParams) -> Self {
+        Replay {
+            stub: Stub::new(instruments),
+            params,
+        }
+    }
+
+    // Snapshots of the symbols in time order from the records of Market Recorder
+    // or from the CSV file
+    fn load(&self, symbols: &[String]) -> Result<Vec<Snapshot>, &'static str> {
+        let source = Path::new(&self.params.source);
+
+        let mut snapshots = if source.extension().map_or(false, |ext| ext == "csv") {
+            let csv = fs::read_to_string(source).map_err(|_| "Can't read replay CSV file")?;
+            Self::parse_csv(&csv)?
+        } else {
+            Self::read_records(source, symbols)
+        };
+
+        snapshots.retain(|snapshot| symbols.contains(&snapshot.symbol));
+        // Stable sort keeps the order of the snapshots with the same time
+        snapshots.sort_by_key(|snapshot| snapshot.timestamp);
+
+        info!(
+            "[Replay] {} depth snapshots are loaded from {:?}",
+            snapshots.len(),
+            source
+        );
+
+        Ok(snapshots)
+    }
+
+    // {source}/{symbol}/{YYYY-MM-DD}.bin.gz and the next parts of the day, files are sorted by
+    // their day and part. File which can't be read is skipped.
+    fn read_records(source: &Path, symbols: &[String]) -> Vec<Snapshot> {
+        let mut snapshots = Vec::new();
+
+        for symbol in symbols {
+            let paths = match MarketRecorder::record_files(&source.join(symbol)) {
+                Ok(paths) => paths,
+                Err(_) => {
+                    error!("[Replay] No records of {} in {:?}", symbol, source);
+                    continue;
+                }
+            };
+
+            for path in paths {
+                let records = match MarketRecorder::read(&path) {
+                    Ok(records) => records,
+                    Err(e) => {
+                        error!("[Replay] Records file {:?} is skipped: {}", path, e);
+                        continue;
+                    }
+                };
+
+                for record in records {
+                    // Recorded fills are ours, they're made again by the replayed orders
+                    if let RecordEvent::Depth { bids, asks } = record.event {
+                        snapshots.push(Snapshot {
+                            timestamp: record.timestamp,
+                            symbol: symbol.clone(),
+                            depth: Self::depth(&bids, &asks),
+                        });
+                    }
+                }
+            }
+        }
+
+        snapshots
+    }
+
+    // timestamp,symbol,side,price,qty with unix time in milliseconds and bid or ask side.
+    // Levels of the same time and symbol are the snapshot, header line is optional.
+    fn parse_csv(csv: &str) -> Result<Vec<Snapshot>, &'static str> {
+        let mut snapshots: Vec<Snapshot> = Vec::new();
+
+        for (index, line) in csv.lines().enumerate() {
+            let fields = line.split(',').map(str::trim).collect::<Vec<&str>>();
+
+            if line.trim().is_empty() || (index == 0 && fields[0].parse::<u64>().is_err()) {
+                continue;
+            }
+
+            let (timestamp, symbol, side, price, qty) = match fields.as_slice() {
+                [timestamp, symbol, side, price, qty] => (
+                    timestamp.parse::<u64>(),
+                    *symbol,
+                    *side,
+                    price.parse::<f64>(),
+                    qty.parse::<f64>(),
+                ),
+                _ => return Err("Wrong replay CSV line"),
+            };
+
+            let (timestamp, ticker) = match (timestamp, price, qty) {
+                (Ok(timestamp), Ok(price), Ok(qty)) => (timestamp, Ticker { price, qty }),
+                _ => return Err("Wrong replay CSV line"),
+            };
+
+            let is_same = snapshots.last().map_or(false, |snapshot| {
+                snapshot.timestamp == timestamp && snapshot.symbol == symbol
+            });
+
+            if !is_same {
+                snapshots.push(Snapshot {
+                    timestamp,
+                    symbol: symbol.to_string(),
+                    depth: Self::depth(&[], &[]),
+                });
+            }
+
+            let depth = &mut snapshots.last_mut().unwrap().depth;
+
+            match side {
+                "bid" => depth.bids.push(ticker),
+                "ask" => depth.asks.push(ticker),
+                _ => return Err("Wrong replay CSV side"),
+            }
+        }
+
+        Ok(snapshots)
+    }
+
+    fn depth(bids: &[(f64, f64)], asks: &[(f64, f64)]) -> Depth {
+        let tickers = |levels: &[(f64, f64)]| {
+            levels
+                .iter()
+                .map(|(price, qty)| Ticker {
+                    price: *price,
+                    qty: *qty,
+                })
+                .collect()
+        };
+
+        Depth {
+            exchange: "Replay".to_string(),
+            bids: tickers(bids),
+            asks: tickers(asks),
+        }
+    }
+
+    // Time to wait from the replay start until the snapshot, None if it's as fast as possible
+    fn delay(&self, started_at: u64, timestamp: u64) -> Option<Duration> {
+        if self.params.speed <= 0. {
+            return None;
+        }
+
+        let elapsed = timestamp.saturating_sub(started_at) as f64 / self.params.speed;
+
+        Some(Duration::from_micros((elapsed * 1000.) as u64))
+    }
+}
+
+impl ExchangeAction for Replay {
+    fn inti(&self) {}
+
+    fn fetch_metadata(&self) -> Vec<ExchangeInstrumentInfo> {
+        self.stub.fetch_metadata()
+    }
+
+    fn fetch_depth(&self, symbol: &str) -> Result<Depth, &'static str> {
+        self.stub.fetch_depth(symbol)
+    }
+
+    fn fetch_balances(
+        &self,
+        instruments: Vec<Instrument>,
+    ) -> Result<HashMap<String, f64>, &'static str> {
+        self.stub.fetch_balances(instruments)
+    }
+
+    fn limit_buy(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        self.stub.limit_buy(symbol, amount, price, custom_order_id)
+    }
+
+    fn limit_sell(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        self.stub.limit_sell(symbol, amount, price, custom_order_id)
+    }
+
+    fn market_buy(&self, symbol: &str, amount: f64) -> ExchangeApiResult<PlatformTransaction> {
+        self.stub.market_buy(symbol, amount)
+    }
+
+    fn market_sell(&self, symbol: &str, amount: f64) -> ExchangeApiResult<PlatformTransaction> {
+        self.stub.market_sell(symbol, amount)
+    }
+
+    fn cancel_order(
+        &self,
+        symbol: &str,
+        custom_order_id: &str,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        self.stub.cancel_order(symbol, custom_order_id)
+    }
+
+    fn fetch_open_orders(&self, symbols: &[String]) -> ExchangeApiResult<Vec<OpenOrder>> {
+        self.stub.fetch_open_orders(symbols)
+    }
+
+    // Snapshots are published with their original intervals scaled by the speed
+    fn depth_stream(
+        &self,
+        symbols: &[String],
+        _rate_limit: &dyn Fn(RequestClass),
+        handler: &mut dyn FnMut(String, Depth),
+    ) -> ExchangeApiResult<()> {
+        let snapshots = self.load(symbols)?;
+
+        let started_at = match snapshots.first() {
+            Some(snapshot) => snapshot.timestamp,
+            None => return Err("No market data to replay"),
+        };
+        let replay_started_at = Instant::now();
+
+        for snapshot in snapshots {
+            if let Some(delay) = self.delay(started_at, snapshot.timestamp) {
+                if let Some(wait) = delay.checked_sub(replay_started_at.elapsed()) {
+                    thread::sleep(wait);
+                }
+            }
+
+            self.stub.set_depth(&snapshot.symbol, &snapshot.depth)?;
+
+            handler(
+                snapshot.symbol.clone(),
+                self.stub.get_depth(&snapshot.symbol, REPLAY_DEPTH_LEVELS)?,
+            );
+        }
+
+        info!("[Replay] Market data of {:?} is replayed", symbols);
+
+        Ok(())
+    }
+
+    fn user_stream(
+        &self,
+        symbols: &[String],
+        handler: &mut dyn FnMut(UserEvent),
+    ) -> ExchangeApiResult<()> {
+        self.stub.user_stream(symbols, handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Replay;
+    use crate::context_manager::{MarketRecorder, RecordEvent};
+    use crate::gateway::exchange::{ExchangeAction, UserEvent};
+    use crate::gateway::{GatewayParams, ReplayParams};
+    use std::env;
+    use std::fs;
+    use std::time::{Duration, Instant};
+
+    const CSV: &str = "timestamp,symbol,side,price,qty
+1000,BTCUSDT,bid,99,1
+1000,BTCUSDT,ask,101,1
+1000,ETHUSDT,bid,9,1
+1200,BTCUSDT,bid,100,1
+1200,BTCUSDT,ask,101.5,2
+";
+
+    fn replay(source: &str, speed: f64) -> Replay {
+        Replay::new(
+            &GatewayParams::default().instruments,
+            ReplayParams {
+                source: source.to_string(),
+                speed,
+            },
+        )
+    }
+
+    #[test]
+    fn parse_csv() {
+        let snapshots = Replay::parse_csv(CSV).unwrap();
+
+        assert_eq!(snapshots.len(), 3);
+        assert_eq!(snapshots[0].depth.bids[0].price, 99.);
+        assert_eq!(snapshots[0].depth.asks[0].price, 101.);
+        assert_eq!(snapshots[1].symbol, "ETHUSDT");
+        assert_eq!(snapshots[2].timestamp, 1200);
+
+        assert!(Replay::parse_csv("1000,BTCUSDT,bid,99").is_err());
+    }
+
+    #[test]
+    fn csv_with_scaled_timing() {
+        let path = env::temp_dir().join(format!("mx_replay_{}.csv", std::process::id()));
+        fs::write(&path, CSV).unwrap();
+
+        // 200 milliseconds of the market are replayed twice faster
+        let replay = replay(path.to_str().unwrap(), 2.);
+        let started_at = Instant::now();
+        let mut depths = Vec::new();
+
+        replay
+            .depth_stream(&["BTCUSDT".to_string()], &|_| {}, &mut |symbol, depth| {
+                depths.push((symbol, depth))
+            })
+            .unwrap();
+
+        assert!(started_at.elapsed() >= Duration::from_millis(100));
+        assert_eq!(depths.len(), 2);
+        assert_eq!(depths[1].1.bids[0].price, 100.);
+        assert_eq!(depths[1].1.asks[0].qty, 2.);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn recorded_market_fills_orders() {
+        let dir = env::temp_dir().join(format!("mx_replay_records_{}", std::process::id()));
+
+        let recorder = MarketRecorder::new(dir.to_str().unwrap(), vec!["Binance".to_string()]);
+        for (bid, ask) in vec![(99., 101.), (97., 98.)] {
+            recorder
+                .record(
+                    "Binance",
+                    "BTCUSDT",
+                    RecordEvent::Depth {
+                        bids: vec![(bid, 1.)],
+                        asks: vec![(ask, 1.)],
+                    },
+                )
+                .unwrap();
+        }
+        recorder.finish_all();
+
+        let replay = replay(dir.join("Binance").to_str().unwrap(), 0.);
+        let mut depths = Vec::new();
+
+        replay
+            .depth_stream(&["BTCUSDT".to_string()], &|_| {}, &mut |_, depth| {
+                depths.push(depth);
+
+                // Resting bid is crossed by the next snapshot
+                if depths.len() == 1 {
+                    replay
+                        .limit_buy("BTCUSDT", 0.5, 99.5, Some("Custom123".to_string()))
+                        .unwrap();
+                }
+            })
+            .unwrap();
+
+        assert_eq!(depths.len(), 2);
+        assert_eq!(depths[1].asks[0].qty, 0.5);
+
+        let filled = replay.stub.events().try_iter().any(|event| match event {
+            UserEvent::FilledOrder(filled_order) => filled_order.custom_order_id == "Custom123",
+            _ => false,
+        });
+        assert!(filled);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
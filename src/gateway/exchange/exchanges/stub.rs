@@ -1,10 +1,976 @@
-// use crate::gateway::{
-//     exchange::{ExchangeAction, ExchangeApiResult, Transaction},
-//     Depth,
-// };
-// use std::collections::HashMap;
+use crate::context_manager::FilledOrder;
+use crate::gateway::{
+    self,
+    exchange::{
+        ExchangeAction, ExchangeApiResult, OpenOrder, OrderUpdate, PlatformTransaction, UserEvent,
+    },
+    gateway::ExchangeInstrumentInfo,
+    Depth, Instrument, InstrumentType, RequestClass,
+};
+use crate::order_manager::{OrderEvent, OrderSide};
+use crossbeam::channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
 
-struct Stub;
+// Initial mid price of every simulated instrument
+const STUB_INITIAL_PRICE: f64 = 100.;
 
-// impl ExchangeAction for Stub {
-// }
+// Initial balances of the simulated account
+const STUB_INITIAL_BASE_BALANCE: f64 = 100.;
+const STUB_INITIAL_QUOTE_BALANCE: f64 = 150.;
+
+const STUB_PRICE_PRECISION: u8 = 2;
+const STUB_TICK_SIZE: f64 = 0.01;
+
+// Number of synthetic levels on each side of the book
+const STUB_DEPTH_LEVELS: usize = 10;
+
+// Interval between synthetic depth updates in milliseconds
+const STUB_DEPTH_INTERVAL: u64 = 100;
+
+// In-process simulated exchange.
+// It keeps a price-time priority book per instrument, fills our orders against
+// synthetic liquidity and reports fills and order events through its own channel.
+pub struct Stub {
+    state: Mutex<StubState>,
+    instruments: Vec<Instrument>,
+    events: (Sender<UserEvent>, Receiver<UserEvent>),
+}
+
+struct StubState {
+    books: HashMap<String, StubBook>,
+    // <asset, balance>
+    balances: HashMap<String, StubBalance>,
+    next_order_id: u64,
+    next_seq: u64,
+    rng: XorShift,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StubBalance {
+    pub free: f64,
+    pub locked: f64,
+}
+
+#[derive(Debug, Clone)]
+struct RestingOrder {
+    order_id: u64,
+    // None for synthetic liquidity
+    custom_order_id: Option<String>,
+    price: f64,
+    amount: f64,
+    seq: u64,
+}
+
+// Bids are sorted from the best (highest) price, asks from the best (lowest) price.
+// Orders on the same price are sorted by arrival.
+#[derive(Debug)]
+struct StubBook {
+    mid_price: f64,
+    bids: Vec<RestingOrder>,
+    asks: Vec<RestingOrder>,
+}
+
+// Our side of a trade, used for balance settlement and fill reporting
+#[derive(Debug, Clone)]
+struct Owner {
+    order_id: u64,
+    custom_order_id: String,
+    order_side: OrderSide,
+    // None for market orders
+    limit_price: Option<f64>,
+}
+
+#[derive(Debug)]
+struct Trade {
+    price: f64,
+    amount: f64,
+    maker: Option<Owner>,
+}
+
+impl Stub {
+    pub fn new(instruments: &[Instrument]) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(1);
+
+        Stub::with_seed(instruments, seed)
+    }
+
+    pub fn with_seed(instruments: &[Instrument], seed: u64) -> Self {
+        let mut books = HashMap::new();
+        let mut balances = HashMap::new();
+
+        for instrument in instruments {
+            books.insert(instrument.name.clone(), StubBook::new(STUB_INITIAL_PRICE));
+
+            balances.insert(
+                instrument.base.clone(),
+                StubBalance {
+                    free: STUB_INITIAL_BASE_BALANCE,
+                    locked: 0.,
+                },
+            );
+            balances.insert(
+                instrument.quote.clone(),
+                StubBalance {
+                    free: STUB_INITIAL_QUOTE_BALANCE,
+                    locked: 0.,
+                },
+            );
+        }
+
+        Stub {
+            state: Mutex::new(StubState {
+                books,
+                balances,
+                next_order_id: 1,
+                next_seq: 1,
+                rng: XorShift::new(seed),
+            }),
+            instruments: instruments.to_vec(),
+            events: unbounded(),
+        }
+    }
+
+    // Receiver of our filled orders and order events
+    pub fn events(&self) -> &Receiver<UserEvent> {
+        &self.events.1
+    }
+
+    fn send_order_event(
+        &self,
+        symbol: &str,
+        order_id: u64,
+        custom_order_id: &str,
+        event: OrderEvent,
+    ) {
+        let _ = self.events.0.send(UserEvent::OrderUpdate(OrderUpdate {
+            custom_order_id: custom_order_id.to_string(),
+            order_id,
+            symbol: symbol.to_string(),
+            event,
+        }));
+    }
+
+    pub fn metadata(&self) -> Vec<ExchangeInstrumentInfo> {
+        self.instruments
+            .iter()
+            .map(|instrument| ExchangeInstrumentInfo {
+                base: instrument.base.clone(),
+                quote: instrument.quote.clone(),
+                symbol: instrument.name.clone(),
+                instrument_type: InstrumentType::Spot,
+                precision: STUB_PRICE_PRECISION,
+                qty_step: instrument.lot_size,
+                min_qty: instrument.min_order_size,
+                min_notional: 0.,
+            })
+            .collect()
+    }
+
+    pub fn balance(&self, asset: &str) -> StubBalance {
+        let state = self.state.lock().unwrap();
+        state.balances.get(asset).cloned().unwrap_or_default()
+    }
+
+    // Moves the mid price and replaces synthetic liquidity around it.
+    // Our resting orders crossed by the new liquidity are filled.
+    pub fn tick(&self, symbol: &str) -> Result<(), &'static str> {
+        let mut state = self.state.lock().unwrap();
+
+        let step = (state.rng.next_f64() - 0.5) * 0.002;
+        let sizes = (0..STUB_DEPTH_LEVELS * 2)
+            .map(|_| 0.1 + state.rng.next_f64() * 2.)
+            .collect::<Vec<f64>>();
+
+        let book = state.books.get_mut(symbol).ok_or("Unknown stub symbol")?;
+        book.mid_price = round_to_tick(book.mid_price * (1. + step)).max(STUB_TICK_SIZE);
+        book.remove_synthetic();
+
+        let mid_price = book.mid_price;
+        let mut liquidity = Vec::with_capacity(STUB_DEPTH_LEVELS * 2);
+        for level in 0..STUB_DEPTH_LEVELS {
+            let offset = STUB_TICK_SIZE * (level + 1) as f64;
+            liquidity.push((
+                OrderSide::Buy,
+                round_to_tick(mid_price - offset),
+                sizes[level * 2],
+            ));
+            liquidity.push((
+                OrderSide::Sell,
+                round_to_tick(mid_price + offset),
+                sizes[level * 2 + 1],
+            ));
+        }
+
+        drop(state);
+
+        for (order_side, price, amount) in liquidity {
+            self.add_liquidity(symbol, order_side, price, amount)?;
+        }
+
+        Ok(())
+    }
+
+    // Replaces synthetic liquidity by the given depth, e.g. by the recorded market.
+    // Our resting orders crossed by it are filled.
+    pub fn set_depth(&self, symbol: &str, depth: &Depth) -> Result<(), &'static str> {
+        {
+            let mut state = self.state.lock().unwrap();
+            let book = state.books.get_mut(symbol).ok_or("Unknown stub symbol")?;
+            book.remove_synthetic();
+
+            if let (Some(bid), Some(ask)) = (depth.bids.first(), depth.asks.first()) {
+                book.mid_price = (bid.price + ask.price) / 2.;
+            }
+        }
+
+        for bid in &depth.bids {
+            self.add_liquidity(symbol, OrderSide::Buy, bid.price, bid.qty)?;
+        }
+
+        for ask in &depth.asks {
+            self.add_liquidity(symbol, OrderSide::Sell, ask.price, ask.qty)?;
+        }
+
+        Ok(())
+    }
+
+    // Aggregated snapshot of the book
+    pub fn get_depth(&self, symbol: &str, levels: usize) -> Result<Depth, &'static str> {
+        let state = self.state.lock().unwrap();
+        let book = state.books.get(symbol).ok_or("Unknown stub symbol")?;
+
+        Ok(Depth {
+            exchange: "StubExchange".to_string(),
+            bids: aggregate(&book.bids, levels),
+            asks: aggregate(&book.asks, levels),
+        })
+    }
+
+    // Adds synthetic (foreign) order, it trades against our resting orders first
+    fn add_liquidity(
+        &self,
+        symbol: &str,
+        order_side: OrderSide,
+        price: f64,
+        amount: f64,
+    ) -> Result<(), &'static str> {
+        let mut state = self.state.lock().unwrap();
+        let seq = state.next_seq();
+
+        let book = state.books.get_mut(symbol).ok_or("Unknown stub symbol")?;
+        let (trades, left) = book.match_order(&order_side, Some(price), amount);
+
+        if left > 0. {
+            book.insert(
+                &order_side,
+                RestingOrder {
+                    order_id: 0,
+                    custom_order_id: None,
+                    price,
+                    amount: left,
+                    seq,
+                },
+            );
+        }
+
+        self.settle(&mut state, symbol, None, trades);
+
+        Ok(())
+    }
+
+    fn place_order(
+        &self,
+        symbol: &str,
+        order_side: OrderSide,
+        amount: f64,
+        price: Option<f64>,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        if amount <= 0. {
+            return Err("Order amount must be positive");
+        }
+
+        let instrument = self
+            .instruments
+            .iter()
+            .find(|instrument| instrument.name == symbol)
+            .ok_or("Unknown stub symbol")?;
+
+        let mut state = self.state.lock().unwrap();
+
+        // Reserves funds for the limit orders, market orders are checked by the reference price
+        let reference_price = match price {
+            Some(price) => price,
+            None => {
+                let book = state.books.get(symbol).ok_or("Unknown stub symbol")?;
+                match order_side {
+                    OrderSide::Buy => book.asks.last().map(|o| o.price).unwrap_or(book.mid_price),
+                    OrderSide::Sell => book.mid_price,
+                }
+            }
+        };
+
+        let (asset, required) = match order_side {
+            OrderSide::Buy => (&instrument.quote, amount * reference_price),
+            OrderSide::Sell => (&instrument.base, amount),
+        };
+
+        {
+            let balance = state.balances.entry(asset.clone()).or_default();
+            if balance.free < required {
+                warn!(
+                    "[Stub] Not enough {} balance: {} < {}",
+                    asset, balance.free, required
+                );
+                return Err("Not enough balance");
+            }
+
+            if price.is_some() {
+                balance.free -= required;
+                balance.locked += required;
+            }
+        }
+
+        let order_id = state.next_order_id();
+        let seq = state.next_seq();
+
+        let owner = Owner {
+            order_id,
+            custom_order_id: custom_order_id.clone().unwrap_or_default(),
+            order_side: order_side.clone(),
+            limit_price: price,
+        };
+
+        // Limit order is acknowledged before its fills
+        if price.is_some() {
+            self.send_order_event(
+                symbol,
+                order_id,
+                &owner.custom_order_id,
+                OrderEvent::Accepted,
+            );
+        }
+
+        let book = state.books.get_mut(symbol).ok_or("Unknown stub symbol")?;
+        let (trades, left) = book.match_order(&order_side, price, amount);
+
+        match price {
+            Some(price) if left > 0. => book.insert(
+                &order_side,
+                RestingOrder {
+                    order_id,
+                    custom_order_id: Some(owner.custom_order_id.clone()),
+                    price,
+                    amount: left,
+                    seq,
+                },
+            ),
+            // Unfilled part of a market order is dropped
+            _ => {}
+        }
+
+        self.settle(&mut state, symbol, Some(owner), trades);
+
+        debug!("[Stub] Order {} was placed on {}", order_id, symbol);
+
+        Ok(PlatformTransaction {
+            symbol: symbol.to_string(),
+            order_id,
+        })
+    }
+
+    fn cancel(
+        &self,
+        symbol: &str,
+        custom_order_id: &str,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        let instrument = self
+            .instruments
+            .iter()
+            .find(|instrument| instrument.name == symbol)
+            .ok_or("Unknown stub symbol")?;
+
+        let mut state = self.state.lock().unwrap();
+        let book = state.books.get_mut(symbol).ok_or("Unknown stub symbol")?;
+
+        let canceled = book.remove(custom_order_id);
+
+        match canceled {
+            Some((order_side, order)) => {
+                // Releases reserved funds
+                let (asset, reserved) = match order_side {
+                    OrderSide::Buy => (&instrument.quote, order.amount * order.price),
+                    OrderSide::Sell => (&instrument.base, order.amount),
+                };
+
+                let balance = state.balances.entry(asset.clone()).or_default();
+                balance.locked -= reserved;
+                balance.free += reserved;
+
+                self.send_order_event(
+                    symbol,
+                    order.order_id,
+                    custom_order_id,
+                    OrderEvent::Cancelled,
+                );
+
+                Ok(PlatformTransaction {
+                    symbol: symbol.to_string(),
+                    order_id: order.order_id,
+                })
+            }
+            None => {
+                warn!(
+                    "Can't cancel order: {}. It could be filled",
+                    custom_order_id
+                );
+
+                Ok(PlatformTransaction {
+                    symbol: symbol.to_string(),
+                    order_id: 0,
+                })
+            }
+        }
+    }
+
+    // Updates balances and reports fills for our side of the trades
+    fn settle(
+        &self,
+        state: &mut StubState,
+        symbol: &str,
+        taker: Option<Owner>,
+        trades: Vec<Trade>,
+    ) {
+        let instrument = match self.instruments.iter().find(|i| i.name == symbol) {
+            Some(instrument) => instrument,
+            None => return,
+        };
+
+        for trade in trades {
+            let owners = trade.maker.iter().chain(taker.iter());
+
+            for owner in owners {
+                let notional = trade.amount * trade.price;
+
+                match owner.order_side {
+                    OrderSide::Buy => {
+                        let quote = state.balances.entry(instrument.quote.clone()).or_default();
+                        match owner.limit_price {
+                            Some(limit_price) => {
+                                let reserved = trade.amount * limit_price;
+                                quote.locked -= reserved;
+                                quote.free += reserved - notional;
+                            }
+                            None => quote.free -= notional,
+                        }
+
+                        state
+                            .balances
+                            .entry(instrument.base.clone())
+                            .or_default()
+                            .free += trade.amount;
+                    }
+                    OrderSide::Sell => {
+                        let base = state.balances.entry(instrument.base.clone()).or_default();
+                        match owner.limit_price {
+                            Some(_) => base.locked -= trade.amount,
+                            None => base.free -= trade.amount,
+                        }
+
+                        state
+                            .balances
+                            .entry(instrument.quote.clone())
+                            .or_default()
+                            .free += notional;
+                    }
+                }
+
+                let filled_order = FilledOrder {
+                    order_id: owner.order_id,
+                    custom_order_id: owner.custom_order_id.clone(),
+                    symbol: symbol.to_string(),
+                    amount: trade.amount,
+                    price: trade.price,
+                    // Stub has no fees, the configured one is charged by the gateway
+                    commission: None,
+                    // Stub doesn't know its account, the fill is tagged by the gateway
+                    account: String::new(),
+                    instrument_id: None,
+                };
+
+                debug!("[Stub] Order was filled: {:?}", filled_order);
+
+                let _ = self.events.0.send(UserEvent::FilledOrder(filled_order));
+            }
+        }
+    }
+}
+
+impl StubState {
+    fn next_order_id(&mut self) -> u64 {
+        let order_id = self.next_order_id;
+        self.next_order_id += 1;
+        order_id
+    }
+
+    fn next_seq(&mut self) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        seq
+    }
+}
+
+impl StubBook {
+    fn new(mid_price: f64) -> Self {
+        StubBook {
+            mid_price,
+            bids: vec![],
+            asks: vec![],
+        }
+    }
+
+    fn insert(&mut self, order_side: &OrderSide, order: RestingOrder) {
+        match order_side {
+            OrderSide::Buy => {
+                let index = self
+                    .bids
+                    .iter()
+                    .position(|o| o.price < order.price)
+                    .unwrap_or(self.bids.len());
+                self.bids.insert(index, order);
+            }
+            OrderSide::Sell => {
+                let index = self
+                    .asks
+                    .iter()
+                    .position(|o| o.price > order.price)
+                    .unwrap_or(self.asks.len());
+                self.asks.insert(index, order);
+            }
+        }
+    }
+
+    // Matches incoming order against the opposite side.
+    // It returns trades and not filled amount.
+    fn match_order(
+        &mut self,
+        order_side: &OrderSide,
+        price: Option<f64>,
+        mut amount: f64,
+    ) -> (Vec<Trade>, f64) {
+        let mut trades = vec![];
+
+        let opposite = match order_side {
+            OrderSide::Buy => &mut self.asks,
+            OrderSide::Sell => &mut self.bids,
+        };
+
+        while amount > 0. && !opposite.is_empty() {
+            let best = &mut opposite[0];
+
+            let crossed = match (order_side, price) {
+                (_, None) => true,
+                (OrderSide::Buy, Some(price)) => best.price <= price,
+                (OrderSide::Sell, Some(price)) => best.price >= price,
+            };
+
+            if !crossed {
+                break;
+            }
+
+            let traded = amount.min(best.amount);
+            amount -= traded;
+            best.amount -= traded;
+
+            trades.push(Trade {
+                price: best.price,
+                amount: traded,
+                maker: best.custom_order_id.as_ref().map(|custom_order_id| Owner {
+                    order_id: best.order_id,
+                    custom_order_id: custom_order_id.clone(),
+                    order_side: match order_side {
+                        OrderSide::Buy => OrderSide::Sell,
+                        OrderSide::Sell => OrderSide::Buy,
+                    },
+                    limit_price: Some(best.price),
+                }),
+            });
+
+            if best.amount <= f64::EPSILON {
+                opposite.remove(0);
+            }
+        }
+
+        (trades, amount)
+    }
+
+    fn remove(&mut self, custom_order_id: &str) -> Option<(OrderSide, RestingOrder)> {
+        let is_ours = |o: &RestingOrder| o.custom_order_id.as_deref() == Some(custom_order_id);
+
+        if let Some(index) = self.bids.iter().position(is_ours) {
+            return Some((OrderSide::Buy, self.bids.remove(index)));
+        }
+
+        if let Some(index) = self.asks.iter().position(is_ours) {
+            return Some((OrderSide::Sell, self.asks.remove(index)));
+        }
+
+        None
+    }
+
+    fn remove_synthetic(&mut self) {
+        self.bids.retain(|o| o.custom_order_id.is_some());
+        self.asks.retain(|o| o.custom_order_id.is_some());
+    }
+}
+
+fn aggregate(orders: &[RestingOrder], levels: usize) -> Vec<gateway::Ticker> {
+    let mut tickers: Vec<gateway::Ticker> = vec![];
+
+    for order in orders {
+        match tickers.last_mut() {
+            Some(ticker) if ticker.price == order.price => ticker.qty += order.amount,
+            _ => {
+                if tickers.len() == levels {
+                    break;
+                }
+                tickers.push(gateway::Ticker {
+                    price: order.price,
+                    qty: order.amount,
+                });
+            }
+        }
+    }
+
+    tickers
+}
+
+fn round_to_tick(price: f64) -> f64 {
+    (price / STUB_TICK_SIZE).round() * STUB_TICK_SIZE
+}
+
+// Small pseudo random generator, enough for the synthetic market
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        XorShift(seed.max(1))
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl ExchangeAction for Stub {
+    fn inti(&self) {}
+
+    fn fetch_metadata(&self) -> Vec<ExchangeInstrumentInfo> {
+        self.metadata()
+    }
+
+    fn fetch_depth(&self, symbol: &str) -> Result<Depth, &'static str> {
+        self.get_depth(symbol, STUB_DEPTH_LEVELS)
+    }
+
+    fn fetch_balances(
+        &self,
+        instruments: Vec<Instrument>,
+    ) -> Result<HashMap<String, f64>, &'static str> {
+        let mut instrument_balances = HashMap::new();
+
+        for instrument in instruments {
+            instrument_balances
+                .insert(instrument.base.clone(), self.balance(&instrument.base).free);
+            instrument_balances.insert(
+                instrument.quote.clone(),
+                self.balance(&instrument.quote).free,
+            );
+        }
+
+        Ok(instrument_balances)
+    }
+
+    fn limit_buy(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        self.place_order(symbol, OrderSide::Buy, amount, Some(price), custom_order_id)
+    }
+
+    fn limit_sell(
+        &self,
+        symbol: &str,
+        amount: f64,
+        price: f64,
+        custom_order_id: Option<String>,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        self.place_order(
+            symbol,
+            OrderSide::Sell,
+            amount,
+            Some(price),
+            custom_order_id,
+        )
+    }
+
+    fn market_buy(&self, symbol: &str, amount: f64) -> ExchangeApiResult<PlatformTransaction> {
+        self.place_order(symbol, OrderSide::Buy, amount, None, None)
+    }
+
+    fn market_sell(&self, symbol: &str, amount: f64) -> ExchangeApiResult<PlatformTransaction> {
+        self.place_order(symbol, OrderSide::Sell, amount, None, None)
+    }
+
+    fn cancel_order(
+        &self,
+        symbol: &str,
+        custom_order_id: &str,
+    ) -> ExchangeApiResult<PlatformTransaction> {
+        self.cancel(symbol, custom_order_id)
+    }
+
+    fn fetch_open_orders(&self, symbols: &[String]) -> ExchangeApiResult<Vec<OpenOrder>> {
+        let state = self.state.lock().unwrap();
+
+        let mut open_orders = Vec::new();
+
+        for symbol in symbols {
+            let book = match state.books.get(symbol) {
+                Some(book) => book,
+                None => continue,
+            };
+
+            let sides = vec![(OrderSide::Buy, &book.bids), (OrderSide::Sell, &book.asks)];
+
+            for (order_side, orders) in sides {
+                // Synthetic liquidity isn't ours
+                for order in orders {
+                    if let Some(custom_order_id) = &order.custom_order_id {
+                        open_orders.push(OpenOrder {
+                            symbol: symbol.clone(),
+                            order_id: order.order_id,
+                            custom_order_id: custom_order_id.clone(),
+                            order_side: order_side.clone(),
+                            price: order.price,
+                            amount: order.amount,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(open_orders)
+    }
+
+    fn depth_stream(
+        &self,
+        symbols: &[String],
+        _rate_limit: &dyn Fn(RequestClass),
+        handler: &mut dyn FnMut(String, Depth),
+    ) -> ExchangeApiResult<()> {
+        loop {
+            // Moves synthetic market and publishes its snapshot
+            for symbol in symbols {
+                self.tick(symbol)?;
+
+                handler(symbol.clone(), self.get_depth(symbol, STUB_DEPTH_LEVELS)?);
+            }
+
+            thread::sleep(Duration::from_millis(STUB_DEPTH_INTERVAL));
+        }
+    }
+
+    fn user_stream(
+        &self,
+        _symbols: &[String],
+        handler: &mut dyn FnMut(UserEvent),
+    ) -> ExchangeApiResult<()> {
+        for event in self.events().iter() {
+            handler(event);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::gateway::GatewayParams;
+
+    fn stub() -> Stub {
+        Stub::with_seed(&GatewayParams::default().instruments, 42)
+    }
+
+    // Order events are skipped
+    fn next_fill(stub: &Stub) -> Option<FilledOrder> {
+        stub.events().try_iter().find_map(|event| match event {
+            UserEvent::FilledOrder(filled_order) => Some(filled_order),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn limit_buy_crossing_ask_is_filled() {
+        let stub = stub();
+        stub.add_liquidity("BTCUSDT", OrderSide::Sell, 100., 1.)
+            .unwrap();
+
+        stub.limit_buy("BTCUSDT", 0.5, 101., Some("Order1".to_string()))
+            .unwrap();
+
+        let filled_order = next_fill(&stub).unwrap();
+        assert_eq!(filled_order.custom_order_id, "Order1");
+        assert_eq!(filled_order.amount, 0.5);
+
+        // Price improvement is returned to the account
+        assert_eq!(stub.balance("BTC").free, 100.5);
+        assert_eq!(stub.balance("USDT").free, 100.);
+        assert_eq!(stub.balance("USDT").locked, 0.);
+    }
+
+    #[test]
+    fn limit_order_rests_and_keeps_time_priority() {
+        let stub = stub();
+
+        stub.limit_buy("BTCUSDT", 0.5, 99., Some("First".to_string()))
+            .unwrap();
+        stub.limit_buy("BTCUSDT", 0.5, 99., Some("Second".to_string()))
+            .unwrap();
+
+        assert!(next_fill(&stub).is_none());
+        assert_eq!(stub.balance("USDT").locked, 99.);
+
+        stub.add_liquidity("BTCUSDT", OrderSide::Sell, 99., 0.5)
+            .unwrap();
+
+        let filled_order = next_fill(&stub).unwrap();
+        assert_eq!(filled_order.custom_order_id, "First");
+        assert!(next_fill(&stub).is_none());
+    }
+
+    #[test]
+    fn cancel_releases_funds() {
+        let stub = stub();
+
+        stub.limit_sell("BTCUSDT", 2., 120., Some("Order1".to_string()))
+            .unwrap();
+        assert_eq!(stub.balance("BTC").locked, 2.);
+
+        stub.cancel_order("BTCUSDT", "Order1").unwrap();
+        assert_eq!(stub.balance("BTC").locked, 0.);
+        assert_eq!(stub.balance("BTC").free, 100.);
+
+        let depth = stub.get_depth("BTCUSDT", 10).unwrap();
+        assert!(depth.asks.is_empty());
+
+        let events = stub
+            .events()
+            .try_iter()
+            .filter_map(|event| match event {
+                UserEvent::OrderUpdate(order_update) => Some(order_update.event),
+                _ => None,
+            })
+            .collect::<Vec<OrderEvent>>();
+
+        assert_eq!(events, vec![OrderEvent::Accepted, OrderEvent::Cancelled]);
+    }
+
+    #[test]
+    fn open_orders_are_ours_only() {
+        let stub = stub();
+        stub.add_liquidity("BTCUSDT", OrderSide::Sell, 130., 1.)
+            .unwrap();
+
+        stub.limit_buy("BTCUSDT", 0.5, 99., Some("Order1".to_string()))
+            .unwrap();
+        stub.limit_sell("BTCUSDT", 1., 120., Some("Order2".to_string()))
+            .unwrap();
+
+        let open_orders = stub.fetch_open_orders(&["BTCUSDT".to_string()]).unwrap();
+
+        assert_eq!(open_orders.len(), 2);
+        assert_eq!(open_orders[0].custom_order_id, "Order1");
+        assert_eq!(open_orders[0].order_side, OrderSide::Buy);
+        assert_eq!(open_orders[1].custom_order_id, "Order2");
+        assert_eq!(open_orders[1].amount, 1.);
+    }
+
+    #[test]
+    fn not_enough_balance() {
+        let stub = stub();
+
+        assert!(stub
+            .limit_buy("BTCUSDT", 2., 100., Some("Order1".to_string()))
+            .is_err());
+    }
+
+    #[test]
+    fn market_order_takes_best_levels() {
+        let stub = stub();
+        stub.add_liquidity("BTCUSDT", OrderSide::Buy, 99., 1.)
+            .unwrap();
+        stub.add_liquidity("BTCUSDT", OrderSide::Buy, 98., 1.)
+            .unwrap();
+
+        stub.market_sell("BTCUSDT", 1.5).unwrap();
+
+        assert_eq!(stub.balance("BTC").free, 98.5);
+        assert_eq!(stub.balance("USDT").free, 150. + 99. + 49.);
+
+        let depth = stub.get_depth("BTCUSDT", 10).unwrap();
+        assert_eq!(depth.bids.len(), 1);
+        assert_eq!(depth.bids[0].qty, 0.5);
+    }
+
+    #[test]
+    fn tick_generates_depth() {
+        let stub = stub();
+
+        stub.tick("BTCUSDT").unwrap();
+
+        let depth = stub.get_depth("BTCUSDT", STUB_DEPTH_LEVELS).unwrap();
+        assert_eq!(depth.bids.len(), STUB_DEPTH_LEVELS);
+        assert_eq!(depth.asks.len(), STUB_DEPTH_LEVELS);
+        assert!(depth.bids[0].price < depth.asks[0].price);
+    }
+
+    #[test]
+    fn depth_stream_publishes_every_symbol() {
+        let mut instruments = GatewayParams::default().instruments;
+        instruments.push(Instrument {
+            name: "ETHUSDT".to_string(),
+            base: "ETH".to_string(),
+            quote: "USDT".to_string(),
+            ..instruments[0].clone()
+        });
+
+        let stub: &'static Stub = Box::leak(Box::new(Stub::with_seed(&instruments, 42)));
+        let symbols = vec!["BTCUSDT".to_string(), "ETHUSDT".to_string()];
+        let (sender, receiver) = unbounded();
+
+        thread::spawn(move || {
+            stub.depth_stream(&symbols, &|_| {}, &mut |symbol, _depth| {
+                let _ = sender.send(symbol);
+            })
+        });
+
+        let received = receiver.iter().take(2).collect::<Vec<String>>();
+        assert_eq!(received, vec!["BTCUSDT", "ETHUSDT"]);
+    }
+}
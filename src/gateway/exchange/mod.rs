@@ -3,7 +3,12 @@ pub mod exchanges;
 
 mod admin;
 mod exchange;
+mod registry;
 
-pub use exchange::{ExchangeAction, ExchangeApiResult, PlatformTransaction};
+pub use exchange::{
+    BalanceUpdate, ExchangeAction, ExchangeApiResult, OpenOrder, OrderUpdate, PlatformTransaction,
+    UserEvent,
+};
+pub use registry::{ExchangeAccounts, ExchangeRegistry};
 
-pub use exchanges::{binance, huobi};
+pub use exchanges::{binance, bitmex, fix, huobi, kraken, replay, stub};
@@ -0,0 +1,189 @@
+use super::{
+    binance::Binance, bitmex::BitMEX, fix::Fix, huobi::Huobi, kraken::Kraken, replay::Replay,
+    stub::Stub, ExchangeAction,
+};
+use crate::gateway::{ExchangeName, GatewayParams, GatewayParamsAccount};
+use std::sync::Arc;
+use tracing::info;
+
+// Builds exchange adapter for the gateway parameters.
+// New exchange needs its ExchangeAction implementation and one arm here.
+pub struct ExchangeRegistry;
+
+impl ExchangeRegistry {
+    // Adapter of the first (default) account
+    pub fn build(gateway_params: &GatewayParams) -> Result<Arc<dyn ExchangeAction>, &'static str> {
+        Ok(Self::build_accounts(gateway_params)?.default_exchange())
+    }
+
+    // Signed adapter for every gateway account
+    pub fn build_accounts(
+        gateway_params: &GatewayParams,
+    ) -> Result<ExchangeAccounts, &'static str> {
+        info!(
+            "Building {} exchange for {} accounts",
+            gateway_params.exchange,
+            gateway_params.accounts.len()
+        );
+
+        if gateway_params.accounts.is_empty() {
+            return Err("Gateway has no exchange account");
+        }
+
+        let mut accounts: Vec<(String, Arc<dyn ExchangeAction>)> = Vec::new();
+
+        for config_account in &gateway_params.accounts {
+            if accounts
+                .iter()
+                .any(|(name, _)| *name == config_account.name)
+            {
+                return Err("Duplicate exchange account name");
+            }
+
+            accounts.push((
+                config_account.name.clone(),
+                Self::build_account(gateway_params, config_account),
+            ));
+        }
+
+        Ok(ExchangeAccounts { accounts })
+    }
+
+    fn build_account(
+        gateway_params: &GatewayParams,
+        config_account: &GatewayParamsAccount,
+    ) -> Arc<dyn ExchangeAction> {
+        match gateway_params.exchange {
+            ExchangeName::Binance => Arc::new(Binance::new(config_account)),
+            ExchangeName::Huobi => Arc::new(Huobi::new(config_account)),
+            ExchangeName::BitMEX => Arc::new(BitMEX::new(config_account)),
+            ExchangeName::Kraken => Arc::new(Kraken::new(config_account)),
+            ExchangeName::Fix => Arc::new(Fix::new(config_account, &gateway_params.instruments)),
+            // Market is replayed by the default account, so replay gateway needs only one
+            ExchangeName::Replay => Arc::new(Replay::new(
+                &gateway_params.instruments,
+                gateway_params.replay.clone().unwrap_or_default(),
+            )),
+            // Every stub account is a separate simulated exchange with its own balances
+            ExchangeName::StubExchange => Arc::new(Stub::new(&gateway_params.instruments)),
+        }
+    }
+}
+
+// Exchange adapters of the gateway accounts in config order.
+// The first account is the default one, it's used for market data and for orders without account.
+#[derive(Clone)]
+pub struct ExchangeAccounts {
+    accounts: Vec<(String, Arc<dyn ExchangeAction>)>,
+}
+
+impl ExchangeAccounts {
+    pub fn default_account(&self) -> &str {
+        &self.accounts[0].0
+    }
+
+    pub fn default_exchange(&self) -> Arc<dyn ExchangeAction> {
+        self.accounts[0].1.clone()
+    }
+
+    // It returns account name with its adapter, None means the default account
+    pub fn get(
+        &self,
+        account: Option<&str>,
+    ) -> Result<(String, Arc<dyn ExchangeAction>), &'static str> {
+        let account = account.unwrap_or_else(|| self.default_account());
+
+        self.accounts
+            .iter()
+            .find(|(name, _)| name == account)
+            .map(|(name, exchange)| (name.clone(), exchange.clone()))
+            .ok_or("Unknown exchange account")
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.accounts.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Arc<dyn ExchangeAction>)> {
+        self.accounts
+            .iter()
+            .map(|(name, exchange)| (name, exchange))
+    }
+
+    // Accounts with the given adapters, e.g. with test doubles of the exchange
+    #[cfg(test)]
+    pub fn from_accounts(accounts: Vec<(String, Arc<dyn ExchangeAction>)>) -> Self {
+        ExchangeAccounts { accounts }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::ExchangeRegistry;
+    use crate::gateway::{ExchangeName, GatewayParams, GatewayParamsAccount};
+
+    #[test]
+    fn build_stub() {
+        let gateway_params = GatewayParams {
+            exchange: ExchangeName::StubExchange,
+            ..GatewayParams::default()
+        };
+
+        let exchange = ExchangeRegistry::build(&gateway_params).unwrap();
+
+        assert_eq!(
+            exchange.fetch_metadata().len(),
+            gateway_params.instruments.len()
+        );
+    }
+
+    #[test]
+    fn build_without_account() {
+        let gateway_params = GatewayParams {
+            exchange: ExchangeName::Huobi,
+            accounts: vec![],
+            ..GatewayParams::default()
+        };
+
+        assert!(ExchangeRegistry::build(&gateway_params).is_err());
+    }
+
+    #[test]
+    fn build_accounts() {
+        let gateway_params = GatewayParams {
+            exchange: ExchangeName::StubExchange,
+            accounts: vec![
+                GatewayParamsAccount::default(),
+                GatewayParamsAccount {
+                    name: "SubAccount".to_string(),
+                    ..GatewayParamsAccount::default()
+                },
+            ],
+            ..GatewayParams::default()
+        };
+
+        let accounts = ExchangeRegistry::build_accounts(&gateway_params).unwrap();
+
+        assert_eq!(accounts.names(), vec!["StubAccount", "SubAccount"]);
+        assert_eq!(accounts.default_account(), "StubAccount");
+
+        assert_eq!(accounts.get(None).unwrap().0, "StubAccount");
+        assert_eq!(accounts.get(Some("SubAccount")).unwrap().0, "SubAccount");
+        assert!(accounts.get(Some("Unknown")).is_err());
+    }
+
+    #[test]
+    fn build_duplicate_accounts() {
+        let gateway_params = GatewayParams {
+            exchange: ExchangeName::StubExchange,
+            accounts: vec![
+                GatewayParamsAccount::default(),
+                GatewayParamsAccount::default(),
+            ],
+            ..GatewayParams::default()
+        };
+
+        assert!(ExchangeRegistry::build_accounts(&gateway_params).is_err());
+    }
+}
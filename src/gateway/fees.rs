@@ -0,0 +1,127 @@
+use super::{Fee, Instrument};
+use crate::context_manager::{Commission, FilledOrder};
+
+// Configured fee is in basis points of the fill notional, e.g. 2.5 is 0.025%
+const BASIS_POINT: f64 = 0.0001;
+
+// Fee rate of the account, account without configured fee isn't charged
+pub fn fee_rate(fees: &[Fee], account: &str) -> f64 {
+    fees.iter()
+        .find(|fee| fee.account_name == account)
+        .map_or(0., |fee| fee.amount_fee * BASIS_POINT)
+}
+
+// Exchange-reported commission is used where the stream has it, the configured fee otherwise.
+// Commission in the base asset is converted by the fill price.
+pub fn fill_commission(
+    filled_order: &FilledOrder,
+    instrument: Option<&Instrument>,
+    fee_rate: f64,
+) -> Commission {
+    let configured_fee = filled_order.amount * filled_order.price * fee_rate;
+
+    let quote = instrument.map_or(String::new(), |instrument| instrument.quote.clone());
+
+    let reported = match &filled_order.commission {
+        Some(reported) => reported,
+        None => {
+            return Commission {
+                amount: configured_fee,
+                asset: quote,
+                quote_amount: configured_fee,
+            }
+        }
+    };
+
+    let quote_amount = match instrument {
+        Some(instrument) if reported.asset == instrument.quote => reported.amount,
+        Some(instrument) if reported.asset == instrument.base => {
+            reported.amount * filled_order.price
+        }
+        // Commission in the other asset, e.g. BNB, has no price here
+        _ => configured_fee,
+    };
+
+    Commission {
+        quote_amount,
+        ..reported.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{fee_rate, fill_commission};
+    use crate::context_manager::{Commission, FilledOrder};
+    use crate::gateway::{Fee, Instrument};
+
+    fn instrument() -> Instrument {
+        Instrument {
+            name: "BTCUSDT".to_string(),
+            base: "BTC".to_string(),
+            quote: "USDT".to_string(),
+            lot_size: 0.00001,
+            min_order_size: 0.00001,
+        }
+    }
+
+    fn filled_order(commission: Option<Commission>) -> FilledOrder {
+        FilledOrder {
+            amount: 2.,
+            price: 10000.,
+            commission,
+            ..FilledOrder::default()
+        }
+    }
+
+    #[test]
+    fn account_fee_rate() {
+        let fees = vec![Fee {
+            account_name: "Account1".to_string(),
+            amount_fee: 2.5,
+        }];
+
+        assert_eq!(fee_rate(&fees, "Account1"), 2.5 * 0.0001);
+        assert_eq!(fee_rate(&fees, "Account2"), 0.);
+    }
+
+    #[test]
+    fn configured_commission() {
+        let commission = fill_commission(&filled_order(None), Some(&instrument()), 0.001);
+
+        assert_eq!(
+            commission,
+            Commission {
+                amount: 20.,
+                asset: "USDT".to_string(),
+                quote_amount: 20.,
+            }
+        );
+    }
+
+    #[test]
+    fn reported_commission() {
+        let reported_in_base = filled_order(Some(Commission {
+            amount: 0.002,
+            asset: "BTC".to_string(),
+            quote_amount: 0.,
+        }));
+
+        let commission = fill_commission(&reported_in_base, Some(&instrument()), 0.001);
+
+        assert_eq!(commission.amount, 0.002);
+        assert_eq!(commission.asset, "BTC");
+        assert_eq!(commission.quote_amount, 20.);
+
+        let reported_in_other = filled_order(Some(Commission {
+            amount: 0.05,
+            asset: "BNB".to_string(),
+            quote_amount: 0.,
+        }));
+
+        let commission = fill_commission(&reported_in_other, Some(&instrument()), 0.0005);
+
+        assert_eq!(commission.asset, "BNB");
+        assert_eq!(commission.quote_amount, 10.);
+    }
+}
@@ -1,28 +1,32 @@
-use super::exchange::{
-    self,
-    account::{Accounts, WebSocket},
-    ExchangeAction,
-};
+use super::exchange::{ExchangeAccounts, ExchangeAction, ExchangeRegistry, UserEvent};
 use super::{
-    ExchangeName, Fee, GatewayConfig, GatewayParams, GatewayParamsAccount, GatewayParamsActions,
-    Instrument, TimeLimit,
+    fee_rate, fill_commission, Balance, BalanceCache, ClockSync, ConnectionStatus, ExchangeName,
+    Fee, GatewayConfig, GatewayParams, GatewayParamsActions, Instrument, InstrumentType,
+    OrderEmulator, OrderRules, OrderTracker, RateLimiter, ReconcilePolicy, RejectReason,
+    ReplayParams, RequestClass, StreamKind, StreamSupervisor, TimeLimit, TrackedOrder,
+    CLOCK_SYNC_INTERVAL, INSTRUMENT_REGISTRY,
 };
 use crate::{
-    api::huobi::websocket_data::HuobiWS,
+    api::clock,
     config::ParseConfig,
-    context_manager::{ActiveOrder, DepthInfo, DepthMsg, FilledOrder, GatewayMsg},
+    context_manager::{
+        ActiveOrder, CandleHistoryMsg, CandleSource, Commission, DepthInfo, DepthMsg, FilledOrder,
+        GatewayMsg, OrderStateMsg, PublicTradeMsg, RejectedOrder, StreamHealth, CANDLES_HISTORY,
+    },
     gateway::exchange::PlatformTransaction,
     order_manager::{
-        ActiveOrderMsg, CancelOrder, LimitOrder, Order, OrderContainer, OrderMsg, OrderSide,
+        AccountOpenOrders, ActiveOrderMsg, CancelOrder, LimitOrder, MarketOrder, Order,
+        OrderContainer, OrderEvent, OrderMsg, OrderSide, OrderState, OrderStatus, ReplaceOrder,
+        StopOrder, TimeInForce,
     },
-    platform::{self, ROBOT_TO_GATEWAY_TIMES},
+    platform::{self, EXCHANGE_TO_GATEWAY_TIMES, ROBOT_TO_GATEWAY_TIMES},
     robot::RobotParamsActions,
     robot::{strategy::StrategyParams, RobotParams},
 };
-use binance;
+use chrono::Utc;
 use crossbeam::channel::{bounded, Receiver, Sender};
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     fmt,
     str::FromStr,
     string::ToString,
@@ -58,9 +62,26 @@ pub struct Gateway {
 
     stop_channel: (Sender<()>, Receiver<()>),
 
-    exchange: Arc<Vec<Box<dyn ExchangeAction>>>,
-    account: Accounts,
-    websocket: Arc<WebSocket>,
+    // Exchange adapter of every account built from gateway params, they're rebuilt on set config
+    exchanges: Arc<RwLock<ExchangeAccounts>>,
+
+    // Last connection status of exchange streams by account
+    streams_health: Arc<RwLock<HashMap<(StreamKind, String), ConnectionStatus>>>,
+
+    // Exchange requests limit per account from exchange_time_limit
+    rate_limiter: Arc<RateLimiter>,
+
+    // Balances of every account and funds reserved by open orders
+    balance_cache: Arc<BalanceCache>,
+
+    // Lifecycle of the sent limit orders
+    order_tracker: Arc<OrderTracker>,
+
+    // Order types which the exchange doesn't support natively
+    order_emulator: Arc<OrderEmulator>,
+
+    // Exchange clock offset estimated by its server time
+    clock_sync: Arc<ClockSync>,
 }
 
 impl fmt::Debug for Gateway {
@@ -70,6 +91,7 @@ impl fmt::Debug for Gateway {
             .field("status", &self.status)
             .field("orders", &self.order_containers)
             .field("metadata", &self.metadata)
+            .field("streams_health", &self.streams_health)
             .finish()
     }
 }
@@ -85,7 +107,13 @@ pub struct ExchangeInstrumentInfo {
     pub base: String,
     pub quote: String,
     pub symbol: String,
+    pub instrument_type: InstrumentType,
     pub precision: u8,
+    // Order amount step, zero means no limit
+    pub qty_step: f64,
+    pub min_qty: f64,
+    // Minimum price * amount of the order
+    pub min_notional: f64,
 }
 
 // General Depth struct for all exchanges
@@ -121,6 +149,18 @@ impl Default for Ticker {
     }
 }
 
+// Public trade of the exchange market, not only ours
+#[derive(Clone, Debug, PartialEq)]
+pub struct PublicTrade {
+    pub trade_id: u64,
+    pub price: f64,
+    pub qty: f64,
+    // Side of the taker order
+    pub aggressor: OrderSide,
+    // Exchange time in unix milliseconds
+    pub timestamp: u64,
+}
+
 impl Gateway {
     // Initialize gateway with channels and loads its parameters from config file
     pub fn load(
@@ -143,9 +183,15 @@ impl Gateway {
             active_order_sender,
             metadata: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
             stop_channel: bounded(0),
-            exchange: Arc::new(vec![]),
-            account: Accounts::get(&gateway_params),
-            websocket: Arc::new(WebSocket::get(&gateway_params)),
+            exchanges: Arc::new(RwLock::new(ExchangeRegistry::build_accounts(
+                &gateway_params,
+            )?)),
+            streams_health: Arc::new(RwLock::new(HashMap::new())),
+            rate_limiter: Arc::new(RateLimiter::new(gateway_params.exchange_time_limit.rpc)),
+            balance_cache: Arc::new(BalanceCache::new()),
+            order_tracker: Arc::new(OrderTracker::new()),
+            order_emulator: Arc::new(OrderEmulator::new()),
+            clock_sync: Arc::new(ClockSync::new()),
         })
     }
 
@@ -155,7 +201,6 @@ impl Gateway {
         let gateway_params_lock = self.gateway_params.read().unwrap();
 
         let gateway_name = gateway_params_lock.name.clone();
-        let exchange = gateway_params_lock.exchange.clone();
 
         let symbols = gateway_params_lock
             .instruments
@@ -163,8 +208,6 @@ impl Gateway {
             .map(|instrument| instrument.name.clone())
             .collect::<Vec<String>>();
 
-        let symbol = symbols.first().unwrap().clone();
-
         match gateway_status_lock {
             Ok(mut status) => match *status {
                 GatewayStatus::Active => Err(Box::leak(Box::new(format!(
@@ -178,10 +221,6 @@ impl Gateway {
                     // Creates static variable before move in thread for logging
                     let gateway_name_log: &'static str = Box::leak(Box::new(gateway_name));
 
-                    let current_exchange: &'static ExchangeName = Box::leak(Box::new(exchange));
-
-                    let current_symbol: &'static str = Box::leak(Box::new(symbol));
-
                     // Working with actix
                     // let rt = tokio::runtime::Builder::new_current_thread()
                     //     .build()
@@ -191,7 +230,7 @@ impl Gateway {
                     let rt = Handle::current();
 
                     // Fetches metadata at once from exchange
-                    rt.spawn(async move { self.fetch_metadata(current_exchange).await });
+                    rt.spawn(async move { self.fetch_metadata().await });
 
                     rt.spawn(async move {
                         let log_items = format!(
@@ -224,7 +263,7 @@ impl Gateway {
                         );
 
                         loop {
-                            self.send_order(current_exchange).unwrap();
+                            self.send_order().unwrap();
 
                             match self.stop_channel.1.try_recv() {
                                 Ok(_) => break,
@@ -240,15 +279,25 @@ impl Gateway {
                             gateway_name_log
                         );
 
-                        self.send_info(current_symbol, current_exchange).unwrap();
+                        self.send_info(symbols).unwrap();
                     });
 
-                    // Runs thread for receiving filled orders from exchange
-                    // and send them to Context Manager
+                    // Runs user stream of every account for receiving filled orders
+                    // from exchange and send them to Context Manager
                     let _ = thread::spawn(move || {
                         self.receive_filled_orders().unwrap();
                     });
 
+                    // Reconciles orders left open on exchange by the previous run
+                    let _ = thread::spawn(move || {
+                        self.reconcile_open_orders().unwrap();
+                    });
+
+                    // Keeps signed requests and latency stamps in the exchange time
+                    let _ = thread::spawn(move || {
+                        self.sync_clock().unwrap();
+                    });
+
                     *status = GatewayStatus::Active;
 
                     Ok(handle)
@@ -258,187 +307,469 @@ impl Gateway {
         }
     }
 
-    // Receives filled orders from exchanges
-    fn receive_filled_orders(&self) -> Result<(), &'static str> {
-        // todo reads gateway params once and then pass as function argument
-        let gateway_params_lock = self.gateway_params.read().unwrap();
-        let config_account = gateway_params_lock.accounts.first().unwrap();
+    // Receives filled orders and balances from exchanges, every account has its own user stream.
+    // Fills are tagged with the account they came from.
+    // Broken stream is reconnected until gateway is stopped, balances are fetched again on connect.
+    fn receive_filled_orders(&'static self) -> Result<(), &'static str> {
+        let symbols = self
+            .get_gateway_params()?
+            .instruments
+            .iter()
+            .map(|instrument| instrument.name.clone())
+            .collect::<Vec<String>>();
 
-        match gateway_params_lock.exchange {
-            ExchangeName::Binance => {
-                // Binance api is listening all instruments
-                self.binance_ws(config_account);
-            }
-            ExchangeName::Huobi => {
-                let symbols = gateway_params_lock
-                    .instruments
-                    .iter()
-                    .map(|instrument| instrument.name.as_str())
-                    .collect::<Vec<&str>>();
+        for (account, exchange) in self.exchange_accounts().iter() {
+            let account = account.clone();
+            let exchange = exchange.clone();
+            let symbols = symbols.clone();
 
-                self.huobi_ws(config_account, symbols);
-            }
-            ExchangeName::BitMEX => {
-                // TODO
-            }
-            ExchangeName::StubExchange => {
-                info!("[Gateway] Stub Exchange: receive filled orders");
-            }
+            thread::spawn(move || {
+                StreamSupervisor::new(StreamKind::User).run(
+                    &mut |connected| {
+                        // User stream is silent without orders, so it's connected once it runs
+                        connected();
+
+                        if let Err(e) = self.fetch_account_balances(&account, &exchange) {
+                            warn!("[Gateway] Can't fetch {} balances: {}", account, e);
+                        }
+
+                        exchange.user_stream(&symbols, &mut |event| match event {
+                            UserEvent::FilledOrder(filled_order) => {
+                                self.filled_order_sender(&account, filled_order)
+                            }
+                            UserEvent::OrderUpdate(order_update) => self.order_event(
+                                &order_update.custom_order_id,
+                                order_update.order_id,
+                                order_update.event,
+                            ),
+                            UserEvent::BalanceUpdate(balance_update) => self.balance_cache.update(
+                                &account,
+                                &balance_update.asset,
+                                Balance {
+                                    free: balance_update.free,
+                                    locked: balance_update.locked,
+                                },
+                            ),
+                        })
+                    },
+                    &mut |stream, status| self.health_sender(&account, stream, status),
+                    &|| self.is_active(),
+                );
+            });
         }
 
         Ok(())
     }
 
-    fn binance_ws(&self, config_account: &GatewayParamsAccount) {
-        use binance::websockets::WebsocketEvent;
-        use exchange::binance::Binance;
+    // Sends open orders of every account to Order Manager, it adopts or cancels them.
+    // Account which open orders can't be fetched is skipped.
+    fn reconcile_open_orders(&self) -> Result<(), &'static str> {
+        let gateway_params = self.get_gateway_params()?;
 
-        // Binance handler
-        let handler = |event: WebsocketEvent| {
-            match event {
-                WebsocketEvent::OrderTrade(trade) => {
-                    if trade.execution_type == "TRADE" {
-                        info!(
-                            "[Gateway] Binance order was filled: {} {}",
-                            trade.symbol, trade.qty
-                        );
+        let symbols = gateway_params
+            .instruments
+            .iter()
+            .map(|instrument| instrument.name.clone())
+            .collect::<Vec<String>>();
 
-                        match self.info_sender.send(GatewayMsg::FilledOrder(FilledOrder {
-                            order_id: trade.order_id,
-                            custom_order_id: trade.new_client_order_id,
-                            symbol: trade.symbol.clone(),
-                            amount: trade.qty.clone(),
-                        })) {
-                            Ok(_) => {
-                                info!("[Gateway] Binance Filled Order info was sent to Context Manager");
-
-                                // debug!(
-                                //     "[Gateway] Gateway {} sent Filled Order (Binance Exchange) info {} symbol",
-                                //     self.get_gateway_name().unwrap(),
-                                //     trade.symbol,
-                                // );
-                            }
-                            Err(_e) => {}
-                        }
-                    }
+        for (account, exchange) in self.exchange_accounts().iter() {
+            self.rate_limiter.acquire(account, RequestClass::Info);
+
+            let orders = match exchange.fetch_open_orders(&symbols) {
+                Ok(orders) => orders,
+                Err(e) => {
+                    warn!("[Gateway] Can't fetch {} open orders: {}", account, e);
+                    continue;
                 }
-                _ => {}
+            };
+
+            info!(
+                "[Gateway] {} open orders on {} account",
+                orders.len(),
+                account
+            );
+
+            let open_orders = AccountOpenOrders {
+                gateway: gateway_params.name.clone(),
+                account: account.clone(),
+                policy: gateway_params.reconcile_policy.clone(),
+                orders,
+            };
+
+            if let Err(e) = self
+                .active_order_sender
+                .send(ActiveOrderMsg::OpenOrders(open_orders))
+            {
+                error!("[Gateway] Error to send open orders: {}", e);
             }
-            Ok(())
-        };
+        }
 
-        // Run binance websockets listening
-        Binance::user_stream_ws(config_account, handler);
+        Ok(())
     }
 
-    fn huobi_ws(&self, config_account: &GatewayParamsAccount, symbols: Vec<&str>) {
-        use crate::api::huobi::models::EventType;
-        use crate::api::huobi::websocket_account::WebsocketEvent;
-        use exchange::huobi::Huobi;
+    // Sends filled order tagged with its account and commission to Context Manager
+    fn filled_order_sender(&self, account: &str, mut filled_order: FilledOrder) {
+        filled_order.account = account.to_string();
+        filled_order.commission = Some(self.fill_commission(&filled_order));
+        filled_order.instrument_id = INSTRUMENT_REGISTRY.canonical_id(
+            &self.get_gateway_name().unwrap_or_default(),
+            &filled_order.symbol,
+        );
 
-        let handler = |event: WebsocketEvent| {
-            match event {
-                WebsocketEvent::OrderUpdate(order_subscription) => {
-                    match order_subscription.data {
-                        EventType::Trade(trade) => {
-                            info!(
-                                "[Gateway] Huobi order was filled: {} {}",
-                                trade.symbol, trade.order_size
-                            );
+        self.balance_cache
+            .release(&filled_order.custom_order_id, filled_order.amount);
 
-                            match self.info_sender.send(GatewayMsg::FilledOrder(FilledOrder {
-                                order_id: trade.order_id,
-                                custom_order_id: trade.client_order_id,
-                                symbol: trade.symbol.clone(),
-                                amount: trade.order_size.clone(),
-                            })) {
-                                Ok(_) => {
-                                    info!(
-                                        "[Gateway] Huobi Filled Order info was sent to Context Manager"
-                                    );
-                                    // debug!(
-                                    //     "[Gateway] Gateway {} sent Filled Order (Huobi Exchange) info {} symbol",
-                                    //     self.get_gateway_name().unwrap(),
-                                    //     trade.symbol,
-                                    // );
-                                }
-                                Err(_e) => {}
-                            }
-                        }
-                        EventType::Creation(_order) => {}
+        info!(
+            "[Gateway] Order was filled on {} account: {} {}",
+            filled_order.account, filled_order.symbol, filled_order.amount
+        );
 
-                        EventType::Cancellation(_order) => {}
-                    };
-                }
+        let fill = OrderEvent::Fill {
+            amount: filled_order.amount,
+            price: filled_order.price,
+        };
+        let custom_order_id = filled_order.custom_order_id.clone();
+        let order_id = filled_order.order_id;
+
+        match self.info_sender.send(GatewayMsg::FilledOrder(filled_order)) {
+            Ok(_) => {
+                info!("[Gateway] Filled Order info was sent to Context Manager");
             }
+            Err(e) => error!("[Gateway] Error to send Filled Order: {:?}", e),
+        }
 
-            Ok(())
+        // State is sent after the fill, so the fill is counted before the order is final
+        self.order_event(&custom_order_id, order_id, fill);
+    }
+
+    // Applies the event to the tracked order and sends its new state
+    // to Context Manager and Order Manager. Unknown orders and stale events are skipped.
+    fn order_event(&self, custom_order_id: &str, order_id: u64, event: OrderEvent) {
+        let tracked_order = match self.order_tracker.apply(custom_order_id, order_id, &event) {
+            Some(tracked_order) => tracked_order,
+            None => {
+                debug!(
+                    "[Gateway] Order {} {} wasn't changed by {:?}",
+                    custom_order_id, order_id, event
+                );
+                return;
+            }
         };
 
-        Huobi::user_stream_ws(config_account, handler, symbols);
+        match tracked_order.order_state.status {
+            OrderStatus::Cancelled | OrderStatus::Rejected | OrderStatus::Expired => self
+                .balance_cache
+                .release_all(&tracked_order.order_state.custom_order_id),
+            _ => {}
+        }
+
+        self.order_state_sender(tracked_order);
     }
 
-    fn fetch_depth(
-        &'static self,
-        symbol: String,
-        exchange: &ExchangeName,
-    ) -> Result<(), &'static str> {
-        let symbols: Vec<_> = vec![symbol.as_str()];
-        let gateway = self.get_gateway_name().unwrap();
+    fn order_state_sender(&self, tracked_order: TrackedOrder) {
+        debug!(
+            "[Gateway] Order {} is {}",
+            tracked_order.order_state.custom_order_id, tracked_order.order_state.status
+        );
+
+        let gateway_name = self.get_gateway_name().unwrap_or_default();
+        let instrument_id =
+            INSTRUMENT_REGISTRY.canonical_id(&gateway_name, &tracked_order.order_state.symbol);
+
+        let order_state_msg = OrderStateMsg {
+            robot_id: tracked_order.robot_id,
+            gateway_name,
+            account: tracked_order.account,
+            order_state: tracked_order.order_state,
+            instrument_id,
+            created_at: Instant::now(),
+        };
+
+        if let Err(e) = self
+            .active_order_sender
+            .send(ActiveOrderMsg::OrderState(order_state_msg.clone()))
+        {
+            error!("[Gateway] Error to send order state: {}", e);
+        }
+
+        if let Err(e) = self
+            .info_sender
+            .send(GatewayMsg::OrderState(order_state_msg))
+        {
+            error!("[Gateway] Error to send order state: {:?}", e);
+        }
+    }
+
+    // New order is pending until the exchange acknowledges it.
+    // Context Manager gets the order before the request, so it knows the order on its fills.
+    fn track_order(&self, limit_order: &LimitOrder, active_order: ActiveOrder) {
+        let robot_id = active_order.robot_id.clone();
+
+        if let Err(e) = self.info_sender.send(GatewayMsg::ActiveOrder(active_order)) {
+            error!("[Gateway] Error to send Active Order: {:?}", e);
+        }
+
+        let account = self.order_account(&Order::LimitOrder(limit_order.clone()));
+
+        let order_state = OrderState::new(
+            &limit_order.custom_order_id,
+            &limit_order.symbol,
+            limit_order.order_side.clone(),
+            limit_order.amount,
+            limit_order.price,
+        );
 
-        match exchange {
-            ExchangeName::Binance => {
-                use binance::websockets::WebsocketEvent;
-                use exchange::binance::Binance;
+        self.order_tracker
+            .track(&robot_id, &account, order_state.clone());
+
+        self.order_state_sender(TrackedOrder {
+            robot_id,
+            account,
+            order_state,
+        });
+    }
+
+    // Subscribes to depth of all symbols, every update is sent with its own symbol.
+    // Broken stream is reconnected and resubscribed until gateway is stopped.
+    fn fetch_depth(&'static self, symbols: Vec<String>) -> Result<(), &'static str> {
+        let gateway = self.get_gateway_name()?;
+        let exchange_name = self.exchange_name().to_string();
+
+        // Market data is the same for all accounts, it's streamed by the default one
+        let (account, exchange) = self.account_exchange(None)?;
+
+        thread::spawn(move || {
+            StreamSupervisor::new(StreamKind::Depth).run(
+                &mut |connected| {
+                    // Book snapshots share the account limit with other requests
+                    let rate_limit = |class| {
+                        self.rate_limiter.acquire(&account, class);
+                    };
+
+                    exchange.depth_stream(&symbols, &rate_limit, &mut |symbol, depth| {
+                        connected();
 
-                let handler = |event: WebsocketEvent| {
-                    if let WebsocketEvent::OrderBook(order_book) = event {
                         // Orderbook has delivered, start estimate time from now
                         let created_at = Instant::now();
 
-                        let depth = Binance::get_depth(&order_book);
+                        self.trigger_stop_orders(&symbol, &depth);
 
-                        self.info_sender(symbol.clone(), &gateway, "Binance", depth, created_at);
-                    }
-                    Ok(())
-                };
+                        self.info_sender(symbol, &gateway, &exchange_name, depth, created_at);
+                    })
+                },
+                &mut |stream, status| self.health_sender(&account, stream, status),
+                &|| self.is_active(),
+            );
+        });
 
-                Binance::depth_ws(handler, symbols);
-            }
+        Ok(())
+    }
+
+    // Subscribes to public trades of all symbols if the exchange streams them.
+    // Broken stream is reconnected and resubscribed until gateway is stopped.
+    fn fetch_trades(&'static self, symbols: Vec<String>) -> Result<(), &'static str> {
+        let gateway = self.get_gateway_name()?;
+        let exchange_name = self.exchange_name().to_string();
+
+        // Market data is the same for all accounts, it's streamed by the default one
+        let (account, exchange) = self.account_exchange(None)?;
+
+        if !exchange.has_trade_stream() {
+            return Ok(());
+        }
+
+        thread::spawn(move || {
+            StreamSupervisor::new(StreamKind::Trade).run(
+                &mut |connected| {
+                    exchange.trade_stream(&symbols, &mut |symbol, trade| {
+                        connected();
 
-            ExchangeName::Huobi => {
-                // info!("Depth was fetched from Huobi successfully");
+                        self.trade_sender(symbol, &gateway, &exchange_name, trade);
+                    })
+                },
+                &mut |stream, status| self.health_sender(&account, stream, status),
+                &|| self.is_active(),
+            );
+        });
 
-                let mut huobi_ws = HuobiWS::connect(&symbol);
+        Ok(())
+    }
 
-                thread::spawn(move || loop {
-                    let huobi_depth = huobi_ws.get_depth();
+    // Backfills candles of the configured intervals, Context Manager builds them further
+    // from public trades or from mid prices if the exchange doesn't stream trades.
+    // Series without exchange candles is sent empty to be built from now.
+    fn fetch_candles(&'static self, symbols: Vec<String>) -> Result<(), &'static str> {
+        let gateway = self.get_gateway_name()?;
+        let exchange_name = self.exchange_name().to_string();
+        let intervals = self.get_gateway_params()?.candle_intervals;
 
-                    let depth = exchange::huobi::Huobi::get_depth(&huobi_depth);
+        if intervals.is_empty() {
+            return Ok(());
+        }
 
-                    let created_at = Instant::now();
+        let (_, exchange) = self.account_exchange(None)?;
 
-                    self.info_sender(symbol.clone(), &gateway, "Huobi", depth, created_at);
-                });
+        let source = if exchange.has_trade_stream() {
+            CandleSource::Trades
+        } else {
+            CandleSource::MidPrice
+        };
+
+        thread::spawn(move || {
+            for symbol in symbols.iter() {
+                for interval in intervals.iter() {
+                    let candles = exchange
+                        .fetch_candles(symbol, *interval, CANDLES_HISTORY)
+                        .unwrap_or_else(|e| {
+                            warn!(
+                                "[Gateway] {} {} candles aren't backfilled: {}",
+                                symbol, interval, e
+                            );
+                            Vec::new()
+                        });
+
+                    let candle_history = CandleHistoryMsg {
+                        gateway_name: gateway.clone(),
+                        exchange_name: exchange_name.clone(),
+                        symbol: symbol.clone(),
+                        interval: *interval,
+                        source,
+                        candles,
+                        created_at: Instant::now(),
+                    };
+
+                    if let Err(e) = self
+                        .info_sender
+                        .send(GatewayMsg::CandleHistory(candle_history))
+                    {
+                        error!("[Gateway] Error to send candles: {:?}", e);
+                    }
+                }
             }
+        });
+
+        Ok(())
+    }
+
+    // Samples the exchange server time until gateway is stopped.
+    // Offset is shared by all gateways of the exchange, the last estimate is applied.
+    fn sync_clock(&'static self) -> Result<(), &'static str> {
+        let exchange_name = self.exchange_name().to_string();
+
+        // Server time is the same for all accounts, it's requested by the default one
+        let (_, exchange) = self.account_exchange(None)?;
+
+        if !exchange.has_server_time() {
+            return Ok(());
+        }
 
-            ExchangeName::BitMEX => {
-                info!("Depth was fetched from BitMEX successfully");
+        while self.is_active() {
+            match self.clock_sync.sample(|| exchange.server_time()) {
+                Ok(estimate) => {
+                    clock::set_clock_offset(&exchange_name, estimate.offset);
+
+                    debug!(
+                        "[Gateway] {} clock offset is {} ms, rtt {} ms",
+                        exchange_name, estimate.offset, estimate.rtt
+                    );
+                }
+                Err(e) => warn!("[Gateway] {} clock isn't synced: {}", exchange_name, e),
             }
 
-            ExchangeName::StubExchange => {
-                info!("Depth was fetched from StubExchange successfully");
-                // Do nothing
-                let _depth = Depth {
-                    exchange: "StubExchange".to_string(),
-                    bids: vec![],
-                    asks: vec![],
-                };
+            let started_at = Instant::now();
+
+            while self.is_active() && started_at.elapsed() < CLOCK_SYNC_INTERVAL {
+                thread::sleep(Duration::from_secs(1));
             }
         }
+
         Ok(())
     }
 
+    // Emulated stop orders triggered by the depth are queued as market or limit orders
+    fn trigger_stop_orders(&self, symbol: &str, depth: &Depth) {
+        let triggered_orders = self.order_emulator.update_depth(symbol, depth.clone());
+
+        if !triggered_orders.is_empty() {
+            info!(
+                "[Gateway] {} stop orders of {} were triggered",
+                triggered_orders.len(),
+                symbol
+            );
+
+            self.queue_orders(triggered_orders);
+        }
+    }
+
+    // Emulated GTD orders are canceled on expiration
+    fn queue_expired_orders(&self) {
+        let expired_orders = self
+            .order_emulator
+            .expired(Utc::now().timestamp_millis() as u64);
+
+        if !expired_orders.is_empty() {
+            info!("[Gateway] {} orders were expired", expired_orders.len());
+
+            self.queue_orders(expired_orders);
+        }
+    }
+
+    fn queue_orders(&self, order_containers: Vec<OrderContainer>) {
+        match self.order_containers.write() {
+            Ok(mut order_containers_lock) => order_containers_lock.extend(order_containers),
+            Err(e) => error!("Poison error {}", e),
+        }
+    }
+
+    // Sends connection status change of the account stream to Context Manager
+    fn health_sender(&self, account: &str, stream: StreamKind, status: ConnectionStatus) {
+        let gateway_name = self.get_gateway_name().unwrap_or_default();
+
+        match &status {
+            ConnectionStatus::Disconnected(reason) => {
+                warn!(
+                    "[Gateway] {} {} {} stream is disconnected: {}",
+                    gateway_name, account, stream, reason
+                )
+            }
+            _ => info!(
+                "[Gateway] {} {} {} stream is {}",
+                gateway_name, account, stream, status
+            ),
+        }
+
+        match self.streams_health.write() {
+            Ok(mut streams_health) => {
+                streams_health.insert((stream, account.to_string()), status.clone());
+            }
+            Err(e) => error!("Poison error {}", e),
+        }
+
+        let stream_health = StreamHealth {
+            gateway_name,
+            exchange_name: self.exchange_name().to_string(),
+            account: account.to_string(),
+            stream,
+            status,
+            created_at: Instant::now(),
+        };
+
+        match self
+            .info_sender
+            .send(GatewayMsg::StreamHealth(stream_health))
+        {
+            Ok(_) => {}
+            Err(e) => error!("[Gateway] Error to send stream health: {:?}", e),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        match self.status.read() {
+            Ok(status) => *status == GatewayStatus::Active,
+            Err(_) => false,
+        }
+    }
+
     fn info_sender(
         &self,
         symbol: String,
@@ -452,6 +783,7 @@ impl Gateway {
             depth_info: DepthInfo {
                 gateway_name: gateway.to_string(),
                 exchange_name: exchange.to_string(),
+                instrument_id: INSTRUMENT_REGISTRY.canonical_id(gateway, &symbol),
                 symbol: symbol.clone(),
                 depth,
             },
@@ -471,6 +803,34 @@ impl Gateway {
         }
     }
 
+    fn trade_sender(&self, symbol: String, gateway: &str, exchange: &str, trade: PublicTrade) {
+        // Trade is stamped by the exchange clock, so the offset is applied to the local one
+        let latency = clock::exchange_now(exchange) - trade.timestamp as i64;
+
+        if latency > 0 {
+            EXCHANGE_TO_GATEWAY_TIMES
+                .lock()
+                .unwrap()
+                .push(Duration::from_millis(latency as u64));
+        }
+
+        let public_trade_msg = PublicTradeMsg {
+            gateway_name: gateway.to_string(),
+            exchange_name: exchange.to_string(),
+            instrument_id: INSTRUMENT_REGISTRY.canonical_id(gateway, &symbol),
+            symbol,
+            trade,
+            created_at: Instant::now(),
+        };
+
+        if let Err(e) = self
+            .info_sender
+            .send(GatewayMsg::PublicTrade(public_trade_msg))
+        {
+            error!("[Gateway] Error to send public trade: {:?}", e);
+        }
+    }
+
     /// Receives order from Order Manager
     fn receive_order(&self) -> Result<(), &'static str> {
         match self.orders_receiver.try_recv() {
@@ -499,8 +859,23 @@ impl Gateway {
                                 }
                                 Order::MarketOrder(_market) => {}
                                 Order::CancelOrder(_cancel) => {}
+                                Order::StopOrder(_stop) => {}
+                                Order::ReplaceOrder(_replace) => {}
                             });
 
+                        // Robots could address instruments by their canonical ids
+                        let gateway_name = self.get_gateway_name()?;
+
+                        let received_order_containers = received_order_containers
+                            .into_iter()
+                            .map(|mut order_container| {
+                                order_container.order.map_symbols(|symbol| {
+                                    INSTRUMENT_REGISTRY.exchange_symbol(&gateway_name, symbol)
+                                });
+                                order_container
+                            })
+                            .collect::<Vec<OrderContainer>>();
+
                         match self.order_containers.write() {
                             Ok(mut order_containers) => {
                                 order_containers.extend(received_order_containers);
@@ -525,355 +900,739 @@ impl Gateway {
     }
 
     /// Send order to exchanges
-    fn send_order(&'static self, exchange: &'static ExchangeName) -> Result<(), &'static str> {
+    fn send_order(&'static self) -> Result<(), &'static str> {
         // let gateway_params_lock = self.gateway_params.read().unwrap();
 
-        match self.order_containers.write() {
-            Ok(mut order_containers) => {
-                // crate::order_manager::utils::OrderUtils::inspect_orders(&Vec::from(
-                //     order_containers.clone(),
-                // ));
+        self.queue_expired_orders();
+
+        // Accounts over their exchange limit in this pass, their orders stay queued in order
+        let mut throttled_accounts = HashSet::new();
+
+        loop {
+            // Lock is released before sending, so new orders are queued meanwhile
+            let order_container = match self.order_containers.write() {
+                Ok(mut order_containers) => {
+                    match self.next_order(&mut order_containers, &mut throttled_accounts) {
+                        Some(order_container) => order_container,
+                        // No orders or all of them wait for their accounts
+                        None => return Ok(()),
+                    }
+                }
+                Err(e) => {
+                    error!("Gateway poison error: {}", e);
+                    return Ok(());
+                }
+            };
 
-                while !order_containers.is_empty() {
-                    // info!(
-                    //     "[Send] {} order_containers len {}",
-                    //     self.get_gateway_name().unwrap(),
-                    //     order_containers.len()
-                    // );
+            let send_res = self.order_sender(order_container);
+            if let Err(e) = send_res {
+                error!("Error on order send {:?}", e);
+            }
+        }
+    }
 
-                    let order_container = order_containers.pop_front().unwrap();
+    // The first queued order which is allowed by the exchange limit of its account.
+    // Throttled account doesn't hold orders of the other accounts.
+    fn next_order(
+        &self,
+        order_containers: &mut VecDeque<OrderContainer>,
+        throttled_accounts: &mut HashSet<String>,
+    ) -> Option<OrderContainer> {
+        let mut allowed = None;
 
-                    // let _order_metainfo = order_container.metainfo;
+        for (index, order_container) in order_containers.iter().enumerate() {
+            let account = self.order_account(&order_container.order);
 
-                    // let _account = gateway_params_lock.accounts.first().unwrap();
+            if throttled_accounts.contains(&account) {
+                continue;
+            }
 
-                    let send_res = self.order_sender(order_container, exchange);
-                    if let Err(e) = send_res {
-                        error!("Error on order send {:?}", e);
-                    }
+            let request_class = Self::request_class(&order_container.order);
+
+            match self.rate_limiter.try_acquire(&account, request_class) {
+                Ok(_) => {
+                    allowed = Some(index);
+                    break;
+                }
+                Err(wait) => {
+                    debug!(
+                        "[Gateway] {} {} request waits {:?}",
+                        account, request_class, wait
+                    );
+
+                    throttled_accounts.insert(account);
                 }
-                // No orders
-                Ok(())
-            }
-            Err(e) => {
-                error!("Gateway poison error: {}", e);
-                Ok(())
             }
         }
+
+        order_containers.remove(allowed?)
     }
 
-    fn order_sender(
-        &'static self,
-        order_container: OrderContainer,
-        exchange: &'static ExchangeName,
-    ) -> Result<(), &'static str> {
+    fn request_class(order: &Order) -> RequestClass {
+        match order {
+            Order::LimitOrder(_)
+            | Order::MarketOrder(_)
+            | Order::StopOrder(_)
+            | Order::ReplaceOrder(_) => RequestClass::Order,
+            Order::CancelOrder(_) => RequestClass::Cancel,
+        }
+    }
+
+    // Waits until the order request is allowed by the exchange limit
+    fn throttle(&self, order: &Order) {
+        let request_class = Self::request_class(order);
+
+        let account = self.order_account(order);
+
+        let delay = self.rate_limiter.acquire(&account, request_class);
+
+        if delay > Duration::from_secs(0) {
+            debug!(
+                "[Gateway] {} {} request was throttled for {:?}",
+                account, request_class, delay
+            );
+        }
+    }
+
+    // Account which the order is sent from
+    fn order_account(&self, order: &Order) -> String {
+        let account = match order {
+            Order::LimitOrder(limit_order) => limit_order.account.as_deref(),
+            Order::MarketOrder(market_order) => market_order.account.as_deref(),
+            Order::CancelOrder(cancel_order) => cancel_order.account.as_deref(),
+            Order::StopOrder(stop_order) => stop_order.account.as_deref(),
+            Order::ReplaceOrder(replace_order) => replace_order.limit_order.account.as_deref(),
+        };
+
+        match account {
+            Some(account) => account.to_string(),
+            None => self.exchange_accounts().default_account().to_string(),
+        }
+    }
+
+    fn order_sender(&'static self, order_container: OrderContainer) -> Result<(), &'static str> {
         let robot_id = order_container.robot_id;
         let strategy_params = order_container.metainfo;
         let order = order_container.order;
 
         return match order {
             Order::LimitOrder(limit_order) => {
-                // match self.check_balance(limit_order.clone(), robot_id.clone(), order_metainfo)
-                // {
-                // Ok(_) => {
+                if let Err(error) = self.check_balance(
+                    Order::LimitOrder(limit_order.clone()),
+                    robot_id.clone(),
+                    strategy_params.clone(),
+                ) {
+                    info!("Wrong balance: {}", error);
+                    info!("Order will send again after moratorium time");
+
+                    // Do not throw error, order will send again after moratorium time
+                    return Ok(());
+                }
 
                 tokio::spawn(async move {
-                    let precision = self
-                        .metadata
-                        .read()
-                        .await
-                        .get(&limit_order.symbol)
-                        .unwrap()
-                        .precision as usize;
-
-                    // Convert price to two decimal points
-                    let converted_price = format!("{:.1$}", limit_order.price, precision)
-                        .parse::<f64>()
-                        .unwrap();
-
-                    let prepared_order = LimitOrder {
-                        price: converted_price,
-                        ..limit_order.clone()
+                    // Price and amount are rounded to the instrument filters
+                    let prepared_order = match self.order_rules(&limit_order.symbol).await {
+                        Some(order_rules) => order_rules.normalize_limit(&limit_order),
+                        None => Err(RejectReason::UnknownInstrument),
                     };
 
-                    let order_responce = match prepared_order.order_side {
-                        OrderSide::Buy => self.limit_buy(&prepared_order, exchange),
+                    let prepared_order = match prepared_order {
+                        Ok(prepared_order) => prepared_order,
+                        Err(reason) => {
+                            self.balance_cache.release_all(&limit_order.custom_order_id);
+                            self.reject_order(&robot_id, Order::LimitOrder(limit_order), reason);
+                            return;
+                        }
+                    };
 
-                        OrderSide::Sell => self.limit_sell(&prepared_order, exchange),
+                    // Time in force which the exchange doesn't support is emulated
+                    let exchange_order = match self.exchange_limit_order(&prepared_order) {
+                        Ok(exchange_order) => exchange_order,
+                        Err(reason) => {
+                            self.balance_cache.release_all(&limit_order.custom_order_id);
+                            self.reject_order(&robot_id, Order::LimitOrder(prepared_order), reason);
+                            return;
+                        }
+                    };
+
+                    // Rounded order holds less funds
+                    self.reserve_balance(&prepared_order);
+
+                    let active_order = ActiveOrder {
+                        robot_id: robot_id.to_string(),
+                        custom_order_id: prepared_order.custom_order_id.clone(),
+                        symbol: prepared_order.symbol.to_string(),
+                        amount: prepared_order.amount,
+                        price: prepared_order.price,
+                        gateway: prepared_order.gateway.clone(),
+                        order_side: prepared_order.order_side.clone(),
+                        strategy_params: strategy_params.clone(),
                     };
 
+                    self.track_order(&prepared_order, active_order.clone());
+
+                    let order_responce = self.limit_order(&exchange_order);
+
                     match order_responce {
-                        Ok(_platform_transaction) => {
-                            let active_order = ActiveOrder {
-                                robot_id: robot_id.to_string(),
-                                custom_order_id: limit_order.custom_order_id.clone(),
-                                symbol: limit_order.symbol.to_string(),
-                                amount: limit_order.amount,
-                                price: limit_order.price,
-                                gateway: limit_order.gateway,
-                                order_side: limit_order.order_side,
-                                strategy_params: strategy_params.clone(),
-                            };
+                        Ok(platform_transaction) => {
+                            self.order_tracker.set_order_id(
+                                &prepared_order.custom_order_id,
+                                platform_transaction.order_id,
+                            );
+
+                            self.order_event(
+                                &prepared_order.custom_order_id,
+                                platform_transaction.order_id,
+                                OrderEvent::Accepted,
+                            );
 
                             // Send active order to Order Manager
                             self.save_active_order(active_order);
-                        }
-                        Err(e) => error!(e),
-                    }
-                });
-
-                Ok(())
 
-                // }
+                            self.emulate_time_in_force(
+                                &robot_id,
+                                &prepared_order,
+                                platform_transaction.order_id,
+                            );
+                        }
+                        Err(e) => {
+                            error!(e);
 
-                //     Err(error) => {
-                //         info!("Wrong balance: {}", error);
-                //         info!("Order will send again after moratorium time");
+                            self.order_event(
+                                &prepared_order.custom_order_id,
+                                0,
+                                OrderEvent::Rejected(e.to_string()),
+                            );
 
-                //         // Do not throw error, order will send again after moratorium time
-                //         Ok(())
-                //     }
-                // }
-            }
+                            self.balance_cache
+                                .release_all(&prepared_order.custom_order_id);
 
-            Order::MarketOrder(market_order) => {
-                match market_order.order_side {
-                    OrderSide::Buy => {
-                        self.market_buy(&market_order.symbol, market_order.amount, exchange)?
-                    }
-                    OrderSide::Sell => {
-                        self.market_sell(&market_order.symbol, market_order.amount, exchange)?
+                            self.reject_order(
+                                &robot_id,
+                                Order::LimitOrder(prepared_order),
+                                RejectReason::Exchange(e.to_string()),
+                            );
+                        }
                     }
-                }
+                });
 
                 Ok(())
             }
 
-            Order::CancelOrder(cancel_order) => {
-                self.cancel_order(&cancel_order, exchange)?;
+            Order::MarketOrder(market_order) => {
+                tokio::spawn(async move {
+                    let prepared_order = match self.order_rules(&market_order.symbol).await {
+                        Some(order_rules) => order_rules.normalize_market(&market_order),
+                        None => Err(RejectReason::UnknownInstrument),
+                    };
+
+                    let prepared_order = match prepared_order {
+                        Ok(prepared_order) => prepared_order,
+                        Err(reason) => {
+                            self.reject_order(&robot_id, Order::MarketOrder(market_order), reason);
+                            return;
+                        }
+                    };
+
+                    let order_responce = match prepared_order.order_side {
+                        OrderSide::Buy => self.market_buy(&prepared_order),
+                        OrderSide::Sell => self.market_sell(&prepared_order),
+                    };
+
+                    if let Err(e) = order_responce {
+                        error!(e);
+
+                        self.reject_order(
+                            &robot_id,
+                            Order::MarketOrder(prepared_order),
+                            RejectReason::Exchange(e.to_string()),
+                        );
+                    }
+                });
 
                 Ok(())
             }
-        };
-    }
 
-    // TODO split thread from function, and make accept one parameter(OrderContainer or Order)
-    fn check_balance(
-        &'static self,
-        limit_order: LimitOrder,
-        robot_id: String,
-        order_metainfo: StrategyParams,
-    ) -> Result<(), &'static str> {
-        let gateway_params_lock = self.gateway_params.read().unwrap();
+            Order::CancelOrder(cancel_order) => {
+                self.cancel_order(&cancel_order)?;
 
-        let account_params = gateway_params_lock.accounts.first().unwrap();
+                Ok(())
+            }
+
+            Order::StopOrder(stop_order) => {
+                // Stop order which the exchange doesn't support waits for its trigger here
+                if !self.is_native_stop_order(&stop_order) {
+                    info!(
+                        "[Gateway] Stop order {} is emulated, stop price {}",
+                        stop_order.custom_order_id, stop_order.stop_price
+                    );
 
-        let balances = self.fetch_balances()?;
+                    self.order_emulator.add_stop(OrderContainer {
+                        robot_id,
+                        order: Order::StopOrder(stop_order),
+                        metainfo: strategy_params,
+                        created_at: Instant::now(),
+                    });
 
-        // Working with first only instrument
-        let instrument = gateway_params_lock.instruments.first().unwrap();
+                    return Ok(());
+                }
 
-        let total_price = limit_order.amount * limit_order.price;
+                tokio::spawn(async move {
+                    let prepared_order = match self.order_rules(&stop_order.symbol).await {
+                        Some(order_rules) => order_rules.normalize_stop(&stop_order),
+                        None => Err(RejectReason::UnknownInstrument),
+                    };
 
-        match balances.get(&account_params.name) {
-            Some(account) => {
-                let symbol = match limit_order.order_side {
-                    OrderSide::Buy => instrument.quote.clone(),
-                    OrderSide::Sell => instrument.base.clone(),
-                };
+                    let prepared_order = match prepared_order {
+                        Ok(prepared_order) => prepared_order,
+                        Err(reason) => {
+                            self.reject_order(&robot_id, Order::StopOrder(stop_order), reason);
+                            return;
+                        }
+                    };
 
-                let balance = *account.get(symbol.as_str()).unwrap();
+                    if let Err(e) = self.stop_order(&prepared_order) {
+                        error!(e);
 
-                // If total price of order more than account balance then set moratorium time for that
-                if total_price > balance {
-                    thread::spawn(move || {
-                        thread::sleep(Duration::from_secs(EXCHANGE_MORATORIUM_TIME));
-                        let mut order_containers_lock = self.order_containers.write().unwrap();
-                        order_containers_lock.push_back(OrderContainer {
-                            robot_id: robot_id.to_string(),
-                            order: Order::LimitOrder(limit_order),
-                            metainfo: order_metainfo,
-                            created_at: Instant::now(),
-                        });
+                        self.reject_order(
+                            &robot_id,
+                            Order::StopOrder(prepared_order),
+                            RejectReason::Exchange(e.to_string()),
+                        );
+                    }
+                });
+
+                Ok(())
+            }
+
+            Order::ReplaceOrder(replace_order) => {
+                // Without amend the order is canceled first and the new one is sent after that
+                if !self.is_native_replace_order(&replace_order) {
+                    let cancel_order = replace_order.cancel_order;
+
+                    // Throttled as the order request, cancel is the extra one
+                    self.throttle(&Order::CancelOrder(cancel_order.clone()));
+
+                    if let Err(e) = self.cancel_order(&cancel_order) {
+                        self.reject_order(
+                            &robot_id,
+                            Order::LimitOrder(replace_order.limit_order),
+                            RejectReason::Exchange(e.to_string()),
+                        );
+
+                        return Ok(());
+                    }
+
+                    return self.order_sender(OrderContainer {
+                        robot_id,
+                        order: Order::LimitOrder(replace_order.limit_order),
+                        metainfo: strategy_params,
+                        created_at: Instant::now(),
                     });
-                    error!("Not enough balance on account {}", account_params.name);
-                    return Err("Not enough balance");
                 }
-            }
 
-            None => {
-                return Err("Account not found");
+                if let Err(error) = self.check_balance(
+                    Order::ReplaceOrder(replace_order.clone()),
+                    robot_id.clone(),
+                    strategy_params.clone(),
+                ) {
+                    info!("Wrong balance: {}", error);
+                    info!("Order will send again after moratorium time");
+
+                    // Do not throw error, order will send again after moratorium time
+                    return Ok(());
+                }
+
+                tokio::spawn(async move {
+                    let limit_order = &replace_order.limit_order;
+
+                    let prepared_order = match self.order_rules(&limit_order.symbol).await {
+                        Some(order_rules) => order_rules.normalize_limit(limit_order),
+                        None => Err(RejectReason::UnknownInstrument),
+                    };
+
+                    let prepared_order = match prepared_order {
+                        Ok(prepared_order) => prepared_order,
+                        Err(reason) => {
+                            self.balance_cache.release_all(&limit_order.custom_order_id);
+                            self.reject_order(
+                                &robot_id,
+                                Order::ReplaceOrder(replace_order),
+                                reason,
+                            );
+                            return;
+                        }
+                    };
+
+                    // Time in force which the exchange doesn't support is emulated
+                    let exchange_order = match self.exchange_limit_order(&prepared_order) {
+                        Ok(exchange_order) => exchange_order,
+                        Err(reason) => {
+                            self.balance_cache.release_all(&limit_order.custom_order_id);
+                            self.reject_order(&robot_id, Order::LimitOrder(prepared_order), reason);
+                            return;
+                        }
+                    };
+
+                    let cancel_order = &replace_order.cancel_order;
+
+                    let active_order = ActiveOrder {
+                        robot_id: robot_id.to_string(),
+                        custom_order_id: prepared_order.custom_order_id.clone(),
+                        symbol: prepared_order.symbol.to_string(),
+                        amount: prepared_order.amount,
+                        price: prepared_order.price,
+                        gateway: prepared_order.gateway.clone(),
+                        order_side: prepared_order.order_side.clone(),
+                        strategy_params: strategy_params.clone(),
+                    };
+
+                    self.track_order(&prepared_order, active_order.clone());
+
+                    match self.replace_order(cancel_order, &exchange_order) {
+                        Ok(platform_transaction) => {
+                            // Amended order is the new order from now on
+                            self.order_event(
+                                &cancel_order.custom_order_id,
+                                cancel_order.order_id,
+                                OrderEvent::Cancelled,
+                            );
+
+                            self.reserve_balance(&prepared_order);
+
+                            self.order_tracker.set_order_id(
+                                &prepared_order.custom_order_id,
+                                platform_transaction.order_id,
+                            );
+
+                            self.order_event(
+                                &prepared_order.custom_order_id,
+                                platform_transaction.order_id,
+                                OrderEvent::Accepted,
+                            );
+
+                            // Send active order to Order Manager
+                            self.save_active_order(active_order);
+                        }
+                        Err(e) => {
+                            error!(e);
+
+                            // Replaced order stays on the exchange as it was
+                            self.order_event(
+                                &prepared_order.custom_order_id,
+                                0,
+                                OrderEvent::Rejected(e.to_string()),
+                            );
+
+                            self.balance_cache
+                                .release_all(&prepared_order.custom_order_id);
+
+                            self.reject_order(
+                                &robot_id,
+                                Order::LimitOrder(prepared_order),
+                                RejectReason::Exchange(e.to_string()),
+                            );
+                        }
+                    }
+                });
+
+                Ok(())
             }
+        };
+    }
+
+    // Limit order which is sent to the exchange, emulated time in force is sent as GTC.
+    // Post-only and FOK orders are checked against the last depth.
+    fn exchange_limit_order(&self, limit_order: &LimitOrder) -> Result<LimitOrder, RejectReason> {
+        if self.is_native_time_in_force(limit_order) {
+            return Ok(limit_order.clone());
         }
 
-        Ok(())
+        self.order_emulator.check_limit(limit_order)?;
+
+        Ok(LimitOrder {
+            time_in_force: TimeInForce::Gtc,
+            ..limit_order.clone()
+        })
     }
 
-    // For next version, without base and quote fields in config
-    // balance <symbol, available_balance>
-    async fn _check_balance(&self, _balances: HashMap<String, f64>, limit_order: &LimitOrder) {
-        // fn _check_balance(&self, _balances: HashMap<String, f64>, limit_order: &LimitOrder) {
-        let exchange_instruments_info = self.metadata.read().await;
-        for (_symbol, instrument_info) in exchange_instruments_info.iter() {
-            if instrument_info.base == limit_order.symbol {
-                match limit_order.order_side {
-                    OrderSide::Buy => {
-                        // limit_order.symbol
-                    }
-                    OrderSide::Sell => {}
-                };
+    // Unfilled part of the emulated IOC and FOK order is canceled at once,
+    // GTD order is canceled on its expiration
+    fn emulate_time_in_force(&self, robot_id: &str, limit_order: &LimitOrder, order_id: u64) {
+        if self.is_native_time_in_force(limit_order) {
+            return;
+        }
+
+        let cancel_order = CancelOrder {
+            order_id,
+            gateway: limit_order.gateway.clone(),
+            symbol: limit_order.symbol.clone(),
+            price: limit_order.price,
+            amount: limit_order.amount,
+            order_side: limit_order.order_side.clone(),
+            custom_order_id: limit_order.custom_order_id.clone(),
+            account: limit_order.account.clone(),
+        };
+
+        match limit_order.time_in_force {
+            TimeInForce::Ioc | TimeInForce::Fok => {
+                self.throttle(&Order::CancelOrder(cancel_order.clone()));
+
+                if let Err(e) = self.cancel_order(&cancel_order) {
+                    error!("[Gateway] Can't cancel the rest of the order: {}", e);
+                }
+            }
+            TimeInForce::Gtd(expire_at) => {
+                self.order_emulator
+                    .expire_at(expire_at, cancel_order, robot_id)
             }
+            TimeInForce::Gtc | TimeInForce::PostOnly => {}
         }
     }
 
-    /// Fetch metadata once when gateway starts
-    async fn fetch_metadata(
-        &'static self,
-        exchange: &'static ExchangeName,
-    ) -> Result<(), &'static str> {
-        info!("Fetching metadata from exchange");
+    // Order of unknown account is treated as native, it's rejected on sending
+    fn is_native_time_in_force(&self, limit_order: &LimitOrder) -> bool {
+        match self.account_exchange(limit_order.account.as_deref()) {
+            Ok((_, exchange)) => exchange.native_time_in_force(&limit_order.time_in_force),
+            Err(_) => true,
+        }
+    }
 
-        let mut metadata_lock = self.metadata.write().await;
+    // Amend keeps the order on its account, so the replacement from another account is sent anew
+    fn is_native_replace_order(&self, replace_order: &ReplaceOrder) -> bool {
+        let limit_order = &replace_order.limit_order;
 
-        match exchange {
-            // Fetch metadata from Binance exchange
-            ExchangeName::Binance => {
-                tokio::task::block_in_place(|| {
-                    let binance_metadata = exchange::binance::Binance::metadata().unwrap();
-
-                    for symbol_info in binance_metadata.symbols {
-                        metadata_lock.insert(
-                            symbol_info.symbol.clone(),
-                            ExchangeInstrumentInfo {
-                                base: symbol_info.base_asset,
-                                quote: symbol_info.quote_asset,
-                                symbol: symbol_info.symbol,
-                                precision: exchange::binance::Binance::price_precision(
-                                    symbol_info.filters,
-                                ),
-                            },
-                        );
-                    }
+        if replace_order.cancel_order.account != limit_order.account {
+            return false;
+        }
 
-                    debug!("Binance metadata {:?}", self.metadata);
-                });
+        match self.account_exchange(limit_order.account.as_deref()) {
+            Ok((_, exchange)) => {
+                exchange.native_replace_order()
+                    && exchange.native_time_in_force(&limit_order.time_in_force)
             }
-            // Fetch metadata from Huobi exchange
-            ExchangeName::Huobi => {
-                let huobi_metadata = exchange::huobi::Huobi::metadata().await.unwrap();
-
-                for symbol_info in huobi_metadata.data {
-                    metadata_lock.insert(
-                        symbol_info.symbol.clone(),
-                        ExchangeInstrumentInfo {
-                            base: symbol_info.base,
-                            quote: symbol_info.quote,
-                            symbol: symbol_info.symbol,
-                            precision: symbol_info.price_precision,
-                        },
-                    );
-                }
+            Err(_) => true,
+        }
+    }
 
-                debug!("Huobi metadata {:?}", self.metadata);
-            }
+    fn is_native_stop_order(&self, stop_order: &StopOrder) -> bool {
+        match self.account_exchange(stop_order.account.as_deref()) {
+            Ok((_, exchange)) => exchange.native_stop_order(stop_order),
+            Err(_) => true,
+        }
+    }
 
-            // Fetch metadata from BitMEX exchange
-            ExchangeName::BitMEX => {}
+    // Size and price filters of the symbol from exchange metadata and gateway config
+    async fn order_rules(&self, symbol: &str) -> Option<OrderRules> {
+        let metadata = self.metadata.read().await;
+        let instrument_info = metadata.get(symbol)?;
 
-            // Other exchanges here
+        let gateway_params = self.gateway_params.read().unwrap();
+        let instrument = gateway_params
+            .instruments
+            .iter()
+            .find(|instrument| instrument.name == symbol);
 
-            // Stub exchange for the local testing
-            ExchangeName::StubExchange => {
-                // Do nothing
-            }
+        Some(OrderRules::new(instrument_info, instrument))
+    }
+
+    fn fill_commission(&self, filled_order: &FilledOrder) -> Commission {
+        let gateway_params = self.gateway_params.read().unwrap();
+
+        let instrument = gateway_params
+            .instruments
+            .iter()
+            .find(|instrument| instrument.name == filled_order.symbol);
+
+        fill_commission(
+            filled_order,
+            instrument,
+            fee_rate(&gateway_params.fees, &filled_order.account),
+        )
+    }
+
+    // Returns the order to its robot with the reason
+    fn reject_order(&self, robot_id: &str, order: Order, reason: RejectReason) {
+        warn!(
+            "[Gateway] Order of {} robot was rejected: {}",
+            robot_id, reason
+        );
+
+        let rejected_order = RejectedOrder {
+            robot_id: robot_id.to_string(),
+            gateway_name: self.get_gateway_name().unwrap_or_default(),
+            order,
+            reason,
+            created_at: Instant::now(),
+        };
+
+        match self
+            .info_sender
+            .send(GatewayMsg::RejectedOrder(rejected_order))
+        {
+            Ok(_) => {}
+            Err(e) => error!("[Gateway] Error to send Rejected Order: {:?}", e),
         }
-        Ok(())
     }
 
-    // Sends info (depth) to Context Manager
-    fn send_info(
+    // Limit order is checked against available balance of its account minus reserved funds,
+    // amend can also use the funds of the order it replaces.
+    // Order without enough balance is queued again after moratorium time,
+    // otherwise its funds are reserved until it's filled or canceled.
+    // Order with unknown account or instrument passes, it's rejected on sending.
+    fn check_balance(
         &'static self,
-        symbol: &str,
-        current_exchange: &ExchangeName,
+        order: Order,
+        robot_id: String,
+        order_metainfo: StrategyParams,
     ) -> Result<(), &'static str> {
-        // Fetchs depth from exchange and send to Context Manager
-        self.fetch_depth(symbol.to_string(), current_exchange)?;
+        let (limit_order, replaced_order_id) = match &order {
+            Order::LimitOrder(limit_order) => (limit_order.clone(), None),
+            Order::ReplaceOrder(replace_order) => (
+                replace_order.limit_order.clone(),
+                Some(replace_order.cancel_order.custom_order_id.clone()),
+            ),
+            _ => return Ok(()),
+        };
+
+        let (account_name, exchange) = match self.account_exchange(limit_order.account.as_deref()) {
+            Ok(account_exchange) => account_exchange,
+            Err(_) => return Ok(()),
+        };
+
+        let (asset, per_unit) = match self.order_funds(&limit_order) {
+            Ok(order_funds) => order_funds,
+            Err(_) => return Ok(()),
+        };
+
+        // Balances are fetched once and then updated by the user stream
+        if !self.balance_cache.is_seeded(&account_name) {
+            if let Err(e) = self.fetch_account_balances(&account_name, &exchange) {
+                warn!("[Gateway] Balance isn't checked: {}", e);
+                return Ok(());
+            }
+        }
+
+        let released = replaced_order_id.map_or(0., |order_id| {
+            self.balance_cache
+                .reserved_by(&order_id, &account_name, &asset)
+        });
+
+        let required = limit_order.amount * per_unit - released;
+
+        let available = self
+            .balance_cache
+            .available(&account_name, &asset)
+            .unwrap_or_default();
+
+        // If total price of order more than account balance then set moratorium time for that
+        if required > available {
+            thread::spawn(move || {
+                thread::sleep(Duration::from_secs(EXCHANGE_MORATORIUM_TIME));
+                let mut order_containers_lock = self.order_containers.write().unwrap();
+                order_containers_lock.push_back(OrderContainer {
+                    robot_id: robot_id.to_string(),
+                    order,
+                    metainfo: order_metainfo,
+                    created_at: Instant::now(),
+                });
+            });
+            error!(
+                "Not enough {} on account {}: {} of {}",
+                asset, account_name, available, required
+            );
+            return Err("Not enough balance");
+        }
+
+        self.reserve_balance(&limit_order);
 
         Ok(())
     }
 
-    // It returns <account, <instrument, balance>>
-    fn fetch_balances(&self) -> Result<HashMap<String, HashMap<String, f64>>, &'static str> {
-        let gateway_params_lock = self.gateway_params.read().unwrap();
-        // info!("[Gateway] Fetching accounts balance");
+    fn reserve_balance(&self, limit_order: &LimitOrder) {
+        let account = self.order_account(&Order::LimitOrder(limit_order.clone()));
 
-        let mut balances = HashMap::new();
-
-        let instruments = &gateway_params_lock.instruments;
-        let accounts = &gateway_params_lock.accounts;
+        if let Ok((asset, per_unit)) = self.order_funds(limit_order) {
+            self.balance_cache.reserve(
+                &limit_order.custom_order_id,
+                &account,
+                &asset,
+                limit_order.amount * per_unit,
+                per_unit,
+            );
+        }
+    }
 
-        for account in accounts {
-            let mut instrument_balances = HashMap::new();
-            for instrument in instruments {
-                match gateway_params_lock.exchange {
-                    // Fetch balacnes from Binance exchange
-                    ExchangeName::Binance => {
-                        let binance_account: &binance::account::Account =
-                            self.account.binance.as_ref().unwrap();
+    // Asset the order is paid with and its amount per unit of the base asset
+    fn order_funds(&self, limit_order: &LimitOrder) -> Result<(String, f64), &'static str> {
+        let (base, quote) = self.symbol_assets(&limit_order.symbol)?;
 
-                        let balance_base = binance_account.get_balance(&instrument.base);
-                        let balance_quote = binance_account.get_balance(&instrument.quote);
+        Ok(match limit_order.order_side {
+            OrderSide::Buy => (quote, limit_order.price),
+            OrderSide::Sell => (base, 1.),
+        })
+    }
 
-                        info!("[Gateway] Got balance for Binance account");
+    // Base and quote assets from gateway config or exchange metadata
+    fn symbol_assets(&self, symbol: &str) -> Result<(String, String), &'static str> {
+        let gateway_params = self.gateway_params.read().unwrap();
 
-                        instrument_balances.insert(
-                            instrument.base.clone(),
-                            balance_base.unwrap().free.parse().unwrap(),
-                        );
+        if let Some(instrument) = gateway_params
+            .instruments
+            .iter()
+            .find(|instrument| instrument.name == symbol)
+        {
+            return Ok((instrument.base.clone(), instrument.quote.clone()));
+        }
 
-                        instrument_balances.insert(
-                            instrument.quote.clone(),
-                            balance_quote.unwrap().free.parse().unwrap(),
-                        );
+        match self.metadata.try_read() {
+            Ok(metadata) => match metadata.get(symbol) {
+                Some(info) => Ok((info.base.clone(), info.quote.clone())),
+                None => Err("Unknown instrument"),
+            },
+            Err(_) => Err("Metadata lock error"),
+        }
+    }
 
-                        balances.insert(account.name.clone(), instrument_balances.clone());
-                    }
+    /// Fetch metadata once when gateway starts
+    async fn fetch_metadata(&'static self) -> Result<(), &'static str> {
+        info!("Fetching metadata from exchange");
 
-                    // Fetch balacnes from Huobi exchange
-                    ExchangeName::Huobi => {
-                        let huobi_account = self.account.huobi.as_ref().unwrap();
+        let exchange = self.exchange();
+        let instruments_info = exchange.fetch_metadata();
 
-                        let balance_base =
-                            huobi_account.get_balance(&instrument.base).unwrap().balance;
+        INSTRUMENT_REGISTRY.register(&self.get_gateway_name()?, &instruments_info);
 
-                        let balance_quote = huobi_account
-                            .get_balance(&instrument.quote)
-                            .unwrap()
-                            .balance;
+        let mut metadata_lock = self.metadata.write().await;
 
-                        info!("[Gateway] Got balance for Huobi account");
+        for instrument_info in instruments_info {
+            metadata_lock.insert(instrument_info.symbol.clone(), instrument_info);
+        }
 
-                        instrument_balances.insert(instrument.base.clone(), balance_base);
-                        instrument_balances.insert(instrument.quote.clone(), balance_quote);
+        debug!("{} metadata {:?}", self.exchange_name(), metadata_lock);
 
-                        balances.insert(account.name.clone(), instrument_balances.clone());
-                    }
+        Ok(())
+    }
 
-                    ExchangeName::BitMEX => {}
+    // Sends info (depth) to Context Manager
+    fn send_info(&'static self, symbols: Vec<String>) -> Result<(), &'static str> {
+        // Fetchs candles, public trades and depth from exchange and send to Context Manager
+        self.fetch_candles(symbols.clone())?;
+        self.fetch_trades(symbols.clone())?;
+        self.fetch_depth(symbols)?;
 
-                    // Stub Exchange for the local testing
-                    ExchangeName::StubExchange => {
-                        instrument_balances.insert("BTC".to_string(), 100f64);
+        Ok(())
+    }
 
-                        instrument_balances.insert("USDT".to_string(), 150f64);
+    // It returns <account, <instrument, balance>>
+    fn fetch_balances(&self) -> Result<HashMap<String, HashMap<String, f64>>, &'static str> {
+        let mut balances = HashMap::new();
 
-                        balances.insert(
-                            GatewayParamsAccount::default().name,
-                            instrument_balances.clone(),
-                        );
-                    }
-                }
-            }
+        for (account, exchange) in self.exchange_accounts().iter() {
+            balances.insert(
+                account.clone(),
+                self.fetch_account_balances(account, exchange)?,
+            );
         }
 
         info!("[Gateway] Balances {:?} ", balances);
@@ -881,260 +1640,251 @@ impl Gateway {
         Ok(balances)
     }
 
-    // Send a limit buy request to buy an instrument on exchange
-    fn limit_buy(
+    // Fetches balances of the account from exchange and seeds the balance cache with them
+    fn fetch_account_balances(
         &self,
-        limit_order: &LimitOrder,
-        exchange: &ExchangeName,
-    ) -> Result<PlatformTransaction, &'static str> {
-        info!(
-            "[Gateway] Limit Buy: {} {} by {:?} on {}",
-            limit_order.symbol, limit_order.amount, limit_order.price, exchange
-        );
+        account: &str,
+        exchange: &Arc<dyn ExchangeAction>,
+    ) -> Result<HashMap<String, f64>, &'static str> {
+        let instruments = self.get_gateway_params()?.instruments;
 
-        let active_order = match exchange {
-            // Send an order to Binance exchange
-            ExchangeName::Binance => {
-                let binance_account: &binance::account::Account =
-                    self.account.binance.as_ref().unwrap();
-
-                // match binance_account.limit_buy(symbol, qty, price) {
-                match binance_account.custom_order(
-                    limit_order.symbol.clone(),
-                    limit_order.amount,
-                    limit_order.price,
-                    None,
-                    binance::account::OrderSide::Buy,
-                    binance::account::OrderType::Limit,
-                    binance::account::TimeInForce::GTC,
-                    Some(limit_order.custom_order_id.clone()),
-                ) {
-                    Ok(transaction) => {
-                        debug!("[Binance] Ok. Limit Buy order was placed");
+        self.rate_limiter.acquire(account, RequestClass::Info);
 
-                        Ok(PlatformTransaction {
-                            symbol: transaction.symbol,
-                            order_id: transaction.order_id,
-                        })
-                    }
-                    Err(e) => Err(Box::leak(Box::new(e)).description()),
-                }
-            }
+        let balances = exchange.fetch_balances(instruments)?;
 
-            // Send an order to Huobi exchange
-            ExchangeName::Huobi => {
-                let huobi_account = self.account.huobi.as_ref().unwrap();
+        self.balance_cache.seed(account, balances.clone());
 
-                match huobi_account.limit_buy(
-                    &limit_order.symbol,
-                    limit_order.amount,
-                    limit_order.price,
-                    Some(limit_order.custom_order_id.clone()),
-                ) {
-                    Ok(transaction) => {
-                        debug!("[Huobi] Ok. Limit Buy order was placed");
+        Ok(balances)
+    }
 
-                        Ok(PlatformTransaction {
-                            symbol: transaction.symbol,
-                            order_id: transaction.order_id,
-                        })
-                    }
-                    Err(e) => Err(e),
-                }
-            }
+    // Cached balances of all accounts in readable form
+    pub fn balances_info(&self) -> Result<String, &'static str> {
+        Ok(format!(
+            "Balances of {} gateway:\n{}",
+            self.get_gateway_name()?,
+            self.balance_cache.info()
+        ))
+    }
 
-            // Send an order to BitMEX exchange
-            ExchangeName::BitMEX => {
-                let _bitmex_account = self.account.bitmex.unwrap();
+    // Kill switch, it cancels every open order of the symbol and the account on exchange,
+    // all symbols and accounts by default. Emulated stops and queued orders are dropped.
+    // Account which open orders can't be fetched is skipped, the others are still flattened.
+    // It returns the number of canceled orders or an error if some account was skipped.
+    pub fn flatten(
+        &self,
+        symbol: Option<&str>,
+        account: Option<&str>,
+    ) -> Result<usize, &'static str> {
+        let symbols = match symbol {
+            Some(symbol) => vec![symbol.to_string()],
+            None => self
+                .get_gateway_params()?
+                .instruments
+                .iter()
+                .map(|instrument| instrument.name.clone())
+                .collect::<Vec<String>>(),
+        };
 
-                Ok(PlatformTransaction::default())
-            }
+        let accounts = match account {
+            Some(account) => vec![self.account_exchange(Some(account))?],
+            None => self
+                .exchange_accounts()
+                .iter()
+                .map(|(account, exchange)| (account.clone(), exchange.clone()))
+                .collect::<Vec<_>>(),
+        };
 
-            ExchangeName::StubExchange => Ok(PlatformTransaction::default()),
+        let is_flattened = |order: &Order| {
+            symbols
+                .iter()
+                .any(|symbol| symbol == Self::order_symbol(order))
+                && accounts
+                    .iter()
+                    .any(|(account, _)| *account == self.order_account(order))
         };
 
-        active_order
-    }
+        // Orders which aren't on the exchange yet
+        let dropped_stops = self
+            .order_emulator
+            .remove_stops(|order_container| is_flattened(&order_container.order));
 
-    // Send a market buy request to buy an instrument on exchange
-    fn market_buy(
-        &self,
-        symbol: &str,
-        qty: f64,
-        exchange: &ExchangeName,
-    ) -> Result<(), &'static str> {
-        info!("[Gateway] Market Buy: {} {} on {}", symbol, qty, exchange);
+        let mut dropped_orders = 0;
+        if let Ok(mut order_containers) = self.order_containers.write() {
+            let order_containers_len = order_containers.len();
 
-        return match exchange {
-            // Send a market order to Binance exchange
-            ExchangeName::Binance => {
-                let binance_account: &binance::account::Account =
-                    self.account.binance.as_ref().unwrap();
+            order_containers.retain(|order_container| !is_flattened(&order_container.order));
 
-                match binance_account.market_buy(symbol, qty) {
-                    Ok(_transaction) => {
-                        // info!("[Binance] Ok. Market Buy order");
+            dropped_orders = order_containers_len - order_containers.len();
+        }
 
-                        Ok(())
-                    }
-                    Err(e) => {
-                        dbg!(&e);
-                        Err(Box::leak(Box::new(e)).description())
-                    }
+        info!(
+            "[Gateway] Flatten dropped {} emulated stops and {} queued orders",
+            dropped_stops, dropped_orders
+        );
+
+        let mut canceled_orders = 0;
+        let mut failed_accounts = Vec::new();
+
+        for (account, exchange) in accounts.iter() {
+            self.rate_limiter.acquire(account, RequestClass::Info);
+
+            let open_orders = match exchange.fetch_open_orders(&symbols) {
+                Ok(open_orders) => open_orders,
+                Err(e) => {
+                    error!(
+                        "[Gateway] Flatten can't fetch open orders of {} account: {}",
+                        account, e
+                    );
+                    failed_accounts.push(account.clone());
+                    continue;
                 }
-            }
+            };
 
-            // Send a market order to Huobi exchange
-            ExchangeName::Huobi => {
-                let huobi_account = self.account.huobi.as_ref().unwrap();
+            for open_order in open_orders {
+                self.rate_limiter.acquire(account, RequestClass::Cancel);
 
-                match huobi_account.market_buy(symbol, qty) {
-                    Ok(_transaction) => {
-                        // info!("[Huobi] Ok. Market Buy order");
+                self.order_event(
+                    &open_order.custom_order_id,
+                    open_order.order_id,
+                    OrderEvent::CancelRequested,
+                );
 
-                        Ok(())
+                match exchange.cancel_order(&open_order.symbol, &open_order.custom_order_id) {
+                    Ok(_) => {
+                        self.order_emulator
+                            .remove_expiring(&open_order.custom_order_id);
+                        self.balance_cache.release_all(&open_order.custom_order_id);
+
+                        canceled_orders += 1;
                     }
-                    Err(e) => Err(e),
+                    Err(e) => warn!(
+                        "[Gateway] Order {} wasn't canceled on {} account: {}",
+                        open_order.custom_order_id, account, e
+                    ),
                 }
             }
+        }
+
+        info!(
+            "[Gateway] Flatten canceled {} open orders of {:?} on {:?}",
+            canceled_orders, symbols, account
+        );
 
-            // Send a market order to BitMEX exchange
-            ExchangeName::BitMEX => {
-                let _bitmex_account = self.account.bitmex.unwrap();
+        if !failed_accounts.is_empty() {
+            error!(
+                "[Gateway] Flatten failed on {} of {} accounts: {:?}",
+                failed_accounts.len(),
+                accounts.len(),
+                failed_accounts
+            );
 
-                Ok(())
-            }
+            return Err("Open orders of some accounts weren't canceled");
+        }
 
-            ExchangeName::StubExchange => Ok(()),
-        };
+        Ok(canceled_orders)
     }
 
-    // Send a limit sell request to sell an instrument on exchange
-    fn limit_sell(
-        &self,
-        limit_order: &LimitOrder,
-        exchange: &ExchangeName,
-    ) -> Result<PlatformTransaction, &'static str> {
-        // info!(
-        //     "[Gateway] Limit Sell: {} {} by {:?} on {} Robot {}",
-        //     symbol, qty, price, exchange, robot_id,
-        // );
+    fn order_symbol(order: &Order) -> &str {
+        match order {
+            Order::LimitOrder(limit_order) => &limit_order.symbol,
+            Order::MarketOrder(market_order) => &market_order.symbol,
+            Order::CancelOrder(cancel_order) => &cancel_order.symbol,
+            Order::StopOrder(stop_order) => &stop_order.symbol,
+            Order::ReplaceOrder(replace_order) => &replace_order.limit_order.symbol,
+        }
+    }
+
+    // Send a limit request with its time in force to exchange
+    fn limit_order(&self, limit_order: &LimitOrder) -> Result<PlatformTransaction, &'static str> {
+        let (account, exchange) = self.account_exchange(limit_order.account.as_deref())?;
 
         info!(
-            "[Gateway] Limit Sell: {} {} by {:?} on {}",
-            limit_order.symbol, limit_order.amount, limit_order.price, exchange
+            "[Gateway] Limit {:?} {:?}: {} {} by {:?} on {} {}",
+            limit_order.order_side,
+            limit_order.time_in_force,
+            limit_order.symbol,
+            limit_order.amount,
+            limit_order.price,
+            self.exchange_name(),
+            account
         );
 
-        return match exchange {
-            // Send an order to Binance exchange
-            ExchangeName::Binance => {
-                let binance_account: &binance::account::Account =
-                    self.account.binance.as_ref().unwrap();
-
-                // match binance_account.limit_sell(symbol, qty, price) {
-
-                match binance_account.custom_order(
-                    limit_order.symbol.clone(),
-                    limit_order.amount,
-                    limit_order.price,
-                    None,
-                    binance::account::OrderSide::Sell,
-                    binance::account::OrderType::Limit,
-                    binance::account::TimeInForce::GTC,
-                    Some(limit_order.custom_order_id.clone()),
-                ) {
-                    Ok(transaction) => {
-                        // info!("[Binance] Ok. Limit Sell order was placed");
-
-                        Ok(PlatformTransaction {
-                            symbol: transaction.symbol,
-                            order_id: transaction.order_id,
-                        })
-                    }
-                    Err(error) => {
-                        error!("Binance Limit Sell error: {}", error);
-                        Err(Box::leak(Box::new(error)).description())
-                    }
-                }
-            }
+        exchange.limit_order(limit_order)
+    }
 
-            // Send an order to Huobi exchange
-            ExchangeName::Huobi => {
-                let huobi_account = self.account.huobi.as_ref().unwrap();
+    // Send a market buy request to buy an instrument on exchange
+    fn market_buy(&self, market_order: &MarketOrder) -> Result<(), &'static str> {
+        let (account, exchange) = self.account_exchange(market_order.account.as_deref())?;
 
-                match huobi_account.limit_sell(
-                    &limit_order.symbol,
-                    limit_order.amount,
-                    limit_order.price,
-                    Some(limit_order.custom_order_id.clone()),
-                ) {
-                    Ok(transaction) => {
-                        // info!("[Huobi] Ok. Limit Sell order was placed");
+        info!(
+            "[Gateway] Market Buy: {} {} on {} {}",
+            market_order.symbol,
+            market_order.amount,
+            self.exchange_name(),
+            account
+        );
 
-                        Ok(PlatformTransaction {
-                            symbol: transaction.symbol,
-                            order_id: transaction.order_id,
-                        })
-                    }
-                    Err(error) => {
-                        error!("Huobi Limit Sell error: {}", error);
+        exchange.market_buy(&market_order.symbol, market_order.amount)?;
 
-                        Err(error)
-                    }
-                }
-            }
+        Ok(())
+    }
 
-            // Send an order to BitMEX exchange
-            ExchangeName::BitMEX => {
-                let _bitmex_account = self.account.bitmex.unwrap();
+    // Send a stop request which waits for the stop price on exchange
+    fn stop_order(&self, stop_order: &StopOrder) -> Result<PlatformTransaction, &'static str> {
+        let (account, exchange) = self.account_exchange(stop_order.account.as_deref())?;
 
-                Ok(PlatformTransaction::default())
-            }
+        info!(
+            "[Gateway] Stop {:?}: {} {} by {} limit {:?} on {} {}",
+            stop_order.order_side,
+            stop_order.symbol,
+            stop_order.amount,
+            stop_order.stop_price,
+            stop_order.limit_price,
+            self.exchange_name(),
+            account
+        );
 
-            ExchangeName::StubExchange => Ok(PlatformTransaction::default()),
-        };
+        exchange.stop_order(stop_order)
     }
 
-    // Send a market sell request to sell an instrument on exchange
-    fn market_sell(
+    // Send an amend request which moves the open order to the new price and amount
+    fn replace_order(
         &self,
-        symbol: &str,
-        qty: f64,
-        exchange: &ExchangeName,
-    ) -> Result<(), &'static str> {
-        info!("[Gateway] Market Sell: {} {} on {}", symbol, qty, exchange);
-
-        return match exchange {
-            // Send a market order to Binance exchange
-            ExchangeName::Binance => {
-                let binance_account: &binance::account::Account =
-                    self.account.binance.as_ref().unwrap();
+        cancel_order: &CancelOrder,
+        limit_order: &LimitOrder,
+    ) -> Result<PlatformTransaction, &'static str> {
+        let (account, exchange) = self.account_exchange(limit_order.account.as_deref())?;
 
-                match binance_account.market_sell(symbol, qty) {
-                    Ok(_transaction) => Ok(()),
-                    Err(e) => Err(Box::leak(Box::new(e)).description()),
-                }
-            }
+        info!(
+            "[Gateway] Replace {:?} {} by {:?}: {} {} by {:?} on {} {}",
+            limit_order.order_side,
+            cancel_order.custom_order_id,
+            limit_order.custom_order_id,
+            limit_order.symbol,
+            limit_order.amount,
+            limit_order.price,
+            self.exchange_name(),
+            account
+        );
 
-            // Send a market order to Huobi exchange
-            ExchangeName::Huobi => {
-                let huobi_account = self.account.huobi.as_ref().unwrap();
+        exchange.replace_order(cancel_order, limit_order)
+    }
 
-                match huobi_account.market_sell(symbol, qty) {
-                    Ok(_transaction) => Ok(()),
-                    Err(e) => Err(e),
-                }
-            }
+    // Send a market sell request to sell an instrument on exchange
+    fn market_sell(&self, market_order: &MarketOrder) -> Result<(), &'static str> {
+        let (account, exchange) = self.account_exchange(market_order.account.as_deref())?;
 
-            // Send a market order to BitMEX exchange
-            ExchangeName::BitMEX => {
-                let _bitmex_account = self.account.bitmex.unwrap();
+        info!(
+            "[Gateway] Market Sell: {} {} on {} {}",
+            market_order.symbol,
+            market_order.amount,
+            self.exchange_name(),
+            account
+        );
 
-                Ok(())
-            }
+        exchange.market_sell(&market_order.symbol, market_order.amount)?;
 
-            ExchangeName::StubExchange => Ok(()),
-        };
+        Ok(())
     }
 
     // Sends active order metainfo to Order Manager
@@ -1150,82 +1900,69 @@ impl Gateway {
         }
     }
 
-    fn cancel_order(
-        &self,
-        cancel_order: &CancelOrder,
-        exchange: &ExchangeName,
-    ) -> Result<(), &'static str> {
-        // info!(
-        //     "[Gateway] Canceling order: Cancel Order {:#?} exchange {}",
-        //     cancel_order, exchange
-        // );
-
+    fn cancel_order(&self, cancel_order: &CancelOrder) -> Result<(), &'static str> {
         let symbol = cancel_order.symbol.clone();
         let custom_order_id = cancel_order.custom_order_id.clone();
         let price = format!("{:.2}", cancel_order.price).parse::<f64>().unwrap();
         let amount = cancel_order.amount;
         let order_side = cancel_order.order_side.clone();
+        let exchange_name = self.exchange_name();
+
+        // Emulated stop order isn't on the exchange yet
+        if self.order_emulator.remove_stop(&custom_order_id) {
+            info!("[Gateway] Stop order {} was canceled", custom_order_id);
+            return Ok(());
+        }
+
+        self.order_emulator.remove_expiring(&custom_order_id);
+
+        // Order is canceled on the account it was sent from
+        let (account, exchange) = self.account_exchange(cancel_order.account.as_deref())?;
 
         info!(
-            "[Gateway] Cancel Order: {:?} {} {} by {} on {:?}",
-            order_side, symbol, amount, price, exchange
+            "[Gateway] Cancel Order: {:?} {} {} by {} on {:?} {}",
+            order_side, symbol, amount, price, exchange_name, account
         );
 
-        let debug_log = format!(
-            "[Gateway] Canceled order: {:?} {} {} by {} on {:?}",
-            order_side, symbol, amount, price, exchange
+        // Order is cancelled by the exchange event
+        self.order_event(
+            &custom_order_id,
+            cancel_order.order_id,
+            OrderEvent::CancelRequested,
         );
 
-        match exchange {
-            ExchangeName::Binance => {
-                let binance_account: &binance::account::Account =
-                    self.account.binance.as_ref().unwrap();
+        // Adapters do not throw error if order could be filled
+        exchange.cancel_order(&symbol, &custom_order_id)?;
 
-                match binance_account.cancel_order_with_client_id(&symbol, custom_order_id) {
-                    Ok(_order_canceled) => {
-                        debug!("{}", debug_log);
+        self.balance_cache.release_all(&custom_order_id);
 
-                        Ok(())
-                    }
-                    Err(error) => {
-                        // Do not throw error
-                        // Cancel order could be filled
-                        warn!("Can't cancel order: {}. It could be filled", error);
-                        // warn!("Cancel Order {:#?}", cancel_order);
-
-                        Ok(())
-                    }
-                }
-            }
+        debug!(
+            "[Gateway] Canceled order: {:?} {} {} by {} on {:?} {}",
+            order_side, symbol, amount, price, exchange_name, account
+        );
 
-            ExchangeName::Huobi => {
-                let huobi_account = self.account.huobi.as_ref().unwrap();
+        Ok(())
+    }
 
-                match huobi_account.cancel_order_with_custom_id(&symbol, &custom_order_id) {
-                    Ok(_transaction) => {
-                        debug!("{}", debug_log);
+    // Exchange adapter of the default account
+    fn exchange(&self) -> Arc<dyn ExchangeAction> {
+        self.exchanges.read().unwrap().default_exchange()
+    }
 
-                        Ok(())
-                    }
-                    Err(error) => {
-                        warn!("Can't cancel order: {}. It could be filled", error);
-                        // warn!("Cancel Order {:#?}", cancel_order);
+    fn exchange_accounts(&self) -> ExchangeAccounts {
+        self.exchanges.read().unwrap().clone()
+    }
 
-                        Ok(())
-                    }
-                }
-            }
+    // It returns account name with its adapter, None means the default account
+    fn account_exchange(
+        &self,
+        account: Option<&str>,
+    ) -> Result<(String, Arc<dyn ExchangeAction>), &'static str> {
+        self.exchanges.read().unwrap().get(account)
+    }
 
-            ExchangeName::BitMEX => {
-                let _bitmex_account = self.account.bitmex.as_ref().unwrap();
-                Ok(())
-            }
-            ExchangeName::StubExchange => {
-                // Stub exchange
-                // Order canceled
-                Ok(())
-            }
-        }
+    fn exchange_name(&self) -> ExchangeName {
+        self.gateway_params.read().unwrap().exchange.clone()
     }
 
     // Stops gateway and its all dependent threads
@@ -1271,13 +2008,42 @@ impl Gateway {
     pub fn info(&self) -> Result<String, &'static str> {
         let gateway_params_lock = self.gateway_params.read().unwrap();
         info!("Getting info for {} Gateway", gateway_params_lock.name);
+        let streams_health = self
+            .streams_health
+            .read()
+            .unwrap()
+            .iter()
+            .map(|((stream, account), status)| format!("{} {}: {:?}", account, stream, status))
+            .collect::<Vec<String>>();
+
+        let queued_orders =
+            self.order_containers.read().unwrap().len() + self.rate_limiter.waiting();
+
+        let clock_offset = self
+            .clock_sync
+            .estimate()
+            .map_or("unknown".to_string(), |estimate| {
+                format!("{} ms (rtt {} ms)", estimate.offset, estimate.rtt)
+            });
+
         Ok(format!(
             r#"Gateway
 name: {}
 status: {:?}
+accounts: {}
+streams: {}
+queued orders: {}
+clock offset: {}
+throttling:
+{}
 "#,
             gateway_params_lock.name,
             *self.status.read().unwrap(),
+            self.exchange_accounts().names().join(", "),
+            streams_health.join(", "),
+            queued_orders,
+            clock_offset,
+            self.rate_limiter.info(),
         ))
     }
 
@@ -1347,10 +2113,7 @@ status: {:?}
                 info!("Setting config for Gateway");
                 match GatewayConfig::from_file(config_file_path) {
                     Ok(gateway_config) => match GatewayParams::validate_config(&gateway_config) {
-                        Ok(_) => {
-                            self._set_config(gateway_config);
-                            Ok(())
-                        }
+                        Ok(_) => self._set_config(gateway_config),
                         Err(_error) => Err("Config validation error"),
                     },
                     Err(_e) => Err("No gateway config"),
@@ -1359,23 +2122,17 @@ status: {:?}
         }
     }
 
-    fn _set_config(&self, gateway_config: GatewayConfig) {
+    fn _set_config(&self, gateway_config: GatewayConfig) -> Result<(), &'static str> {
+        // Config with missing secrets doesn't change the gateway
+        let accounts = GatewayParams::accounts(&gateway_config)?;
+
         let mut gateway_params_lock = self.gateway_params.write().unwrap();
 
         gateway_params_lock.name = gateway_config.gateway_name;
 
         gateway_params_lock.exchange = ExchangeName::from_str(&gateway_config.exchange).unwrap();
 
-        gateway_params_lock.accounts = gateway_config
-            .accounts
-            .iter()
-            .map(|a| GatewayParamsAccount {
-                name: a.name.clone(),
-                account_id: a.account_id.clone(),
-                api_key: a.api_key.clone(),
-                secret_key: a.secret_key.clone(),
-            })
-            .collect();
+        gateway_params_lock.accounts = accounts;
 
         gateway_params_lock.instruments = gateway_config
             .instruments
@@ -1401,6 +2158,26 @@ status: {:?}
         gateway_params_lock.exchange_time_limit = TimeLimit {
             rpc: gateway_config.limit.rps,
         };
+
+        self.rate_limiter
+            .set_rps(gateway_params_lock.exchange_time_limit.rpc);
+
+        gateway_params_lock.reconcile_policy = ReconcilePolicy::from_config(&gateway_config);
+
+        gateway_params_lock.replay = gateway_config
+            .replay
+            .as_ref()
+            .map(ReplayParams::from_config);
+
+        gateway_params_lock.candle_intervals = GatewayParams::candle_intervals(&gateway_config);
+
+        // Exchange or accounts could be changed
+        *self.exchanges.write().unwrap() = ExchangeRegistry::build_accounts(&gateway_params_lock)?;
+
+        self.balance_cache.clear();
+        self.clock_sync.clear();
+
+        Ok(())
     }
 }
 
@@ -1408,7 +2185,9 @@ status: {:?}
 mod tests {
 
     use super::*;
+    use crate::gateway::exchange::{ExchangeApiResult, OpenOrder};
     use crate::gateway::gateway_params::test_utils::GatewayParamsUtils;
+    use crate::gateway::GatewayParamsAccount;
     use crate::order_manager::OrderContainer;
     use crate::platform::PlatformConfig;
     use crossbeam::channel::unbounded;
@@ -1459,18 +2238,35 @@ mod tests {
 
                 metadata: Arc::new(metadata),
                 stop_channel,
-                exchange: Arc::new(vec![]),
-                account: Accounts::default(),
-                websocket: Arc::new(WebSocket::default()),
+                exchanges: Arc::new(RwLock::new(
+                    ExchangeRegistry::build_accounts(&GatewayParams::default()).unwrap(),
+                )),
+                streams_health: Arc::new(RwLock::new(HashMap::new())),
+                rate_limiter: Arc::new(RateLimiter::new(TimeLimit::default().rpc)),
+                balance_cache: Arc::new(BalanceCache::new()),
+                order_tracker: Arc::new(OrderTracker::new()),
+                order_emulator: Arc::new(OrderEmulator::new()),
+                clock_sync: Arc::new(ClockSync::new()),
             }))
         }
 
         fn from_params(params: GatewayParams) -> &'static Self {
-            let (_s, order_receiver): (Sender<OrderMsg>, Receiver<OrderMsg>) = unbounded();
-            let (info_sender, _r): (Sender<GatewayMsg>, Receiver<GatewayMsg>) = unbounded();
             let (active_order_sender, _r): (Sender<ActiveOrderMsg>, Receiver<ActiveOrderMsg>) =
                 unbounded();
 
+            let _ = Box::leak(Box::new(_r));
+
+            Gateway::with_active_order_sender(params, active_order_sender)
+        }
+
+        // Creates Gateway which sends active orders to the given channel
+        fn with_active_order_sender(
+            params: GatewayParams,
+            active_order_sender: Sender<ActiveOrderMsg>,
+        ) -> &'static Self {
+            let (_s, order_receiver): (Sender<OrderMsg>, Receiver<OrderMsg>) = unbounded();
+            let (info_sender, _r): (Sender<GatewayMsg>, Receiver<GatewayMsg>) = unbounded();
+
             let metadata: tokio::sync::RwLock<HashMap<String, ExchangeInstrumentInfo>> =
                 tokio::sync::RwLock::new(HashMap::new());
 
@@ -1491,9 +2287,15 @@ mod tests {
 
                 metadata: Arc::new(metadata),
                 stop_channel,
-                exchange: Arc::new(vec![]),
-                account: Accounts::get(&params),
-                websocket: Arc::new(WebSocket::default()),
+                exchanges: Arc::new(RwLock::new(
+                    ExchangeRegistry::build_accounts(&params).unwrap(),
+                )),
+                streams_health: Arc::new(RwLock::new(HashMap::new())),
+                rate_limiter: Arc::new(RateLimiter::new(params.exchange_time_limit.rpc)),
+                balance_cache: Arc::new(BalanceCache::new()),
+                order_tracker: Arc::new(OrderTracker::new()),
+                order_emulator: Arc::new(OrderEmulator::new()),
+                clock_sync: Arc::new(ClockSync::new()),
             }))
         }
     }
@@ -1550,15 +2352,46 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn receive_order() {
+    async fn receive_order() {
+        let (order_sender, order_receiver): (Sender<OrderMsg>, Receiver<OrderMsg>) = unbounded();
+
+        let gateway = Gateway::create("Gateway1", order_receiver);
+
+        order_sender
+            .send(OrderMsg::OrderContainers(vec![OrderContainer {
+                robot_id: "Robot1".to_string(),
+                order: Order::default(),
+                metainfo: StrategyParams::Stub,
+                created_at: Instant::now(),
+            }]))
+            .unwrap();
+
+        gateway.receive_order().unwrap();
+
+        assert_eq!(gateway.order_containers.read().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn receive_order_by_canonical_id() {
         let (order_sender, order_receiver): (Sender<OrderMsg>, Receiver<OrderMsg>) = unbounded();
 
-        let gateway = Gateway::create("Gateway1", order_receiver);
+        let gateway = Gateway::create("CanonicalGateway", order_receiver);
+
+        INSTRUMENT_REGISTRY.register_instrument(
+            "CanonicalGateway",
+            "btcusdt",
+            "btc",
+            "usdt",
+            InstrumentType::Spot,
+        );
 
         order_sender
             .send(OrderMsg::OrderContainers(vec![OrderContainer {
                 robot_id: "Robot1".to_string(),
-                order: Order::default(),
+                order: Order::LimitOrder(LimitOrder {
+                    symbol: "BTC-USDT".to_string(),
+                    ..LimitOrder::default()
+                }),
                 metainfo: StrategyParams::Stub,
                 created_at: Instant::now(),
             }]))
@@ -1566,7 +2399,46 @@ mod tests {
 
         gateway.receive_order().unwrap();
 
-        assert_eq!(gateway.order_containers.read().unwrap().len(), 1);
+        match &gateway.order_containers.read().unwrap()[0].order {
+            Order::LimitOrder(limit_order) => assert_eq!(limit_order.symbol, "btcusdt"),
+            order => panic!("Unexpected order {:?}", order),
+        }
+    }
+
+    #[test]
+    fn order_state_with_canonical_id() {
+        let (active_order_sender, active_order_receiver) = unbounded();
+
+        let gateway = Gateway::with_active_order_sender(
+            GatewayParams {
+                name: "CanonicalStateGateway".to_string(),
+                ..GatewayParams::default()
+            },
+            active_order_sender,
+        );
+
+        INSTRUMENT_REGISTRY.register_instrument(
+            "CanonicalStateGateway",
+            "btcusdt",
+            "btc",
+            "usdt",
+            InstrumentType::Spot,
+        );
+
+        gateway.order_state_sender(TrackedOrder {
+            robot_id: "Robot1".to_string(),
+            account: "StubAccount".to_string(),
+            order_state: OrderState::new("Order1", "btcusdt", OrderSide::Buy, 1., 100.),
+        });
+
+        match active_order_receiver.try_recv() {
+            Ok(ActiveOrderMsg::OrderState(order_state_msg)) => {
+                // Symbol stays the exchange one, the canonical id is added
+                assert_eq!(order_state_msg.order_state.symbol, "btcusdt");
+                assert_eq!(order_state_msg.instrument_id.unwrap(), "BTC-USDT");
+            }
+            _ => panic!("Order state wasn't sent"),
+        }
     }
 
     #[test]
@@ -1602,11 +2474,22 @@ mod tests {
 
         let gateway = Gateway::from_params(gatewap_params);
 
-        let symbol = "BTCUSDT";
+        let symbols = vec!["BTCUSDT".to_string()];
 
-        assert!(gateway
-            .send_info(symbol, &ExchangeName::StubExchange)
-            .is_ok());
+        assert!(gateway.send_info(symbols).is_ok());
+    }
+
+    #[test]
+    fn sync_clock_without_server_time() {
+        let gatewap_params = GatewayParams {
+            exchange: ExchangeName::StubExchange,
+            ..GatewayParams::default()
+        };
+
+        let gateway = Gateway::from_params(gatewap_params);
+
+        assert!(gateway.sync_clock().is_ok());
+        assert_eq!(gateway.clock_sync.estimate(), None);
     }
 
     #[test]
@@ -1688,12 +2571,69 @@ mod tests {
 
         assert_eq!(gateway.order_containers.read().unwrap().len(), 2);
 
-        gateway.send_order(&ExchangeName::StubExchange).unwrap();
-        gateway.send_order(&ExchangeName::StubExchange).unwrap();
+        gateway.send_order().unwrap();
+        gateway.send_order().unwrap();
 
         assert_eq!(gateway.order_containers.read().unwrap().len(), 0);
     }
 
+    #[test]
+    fn throttled_account_queue() {
+        let gateway = Gateway::from_params(GatewayParams {
+            accounts: vec![
+                GatewayParamsAccount::default(),
+                GatewayParamsAccount {
+                    name: "SubAccount".to_string(),
+                    ..GatewayParamsAccount::default()
+                },
+            ],
+            exchange_time_limit: TimeLimit { rpc: 1 },
+            ..GatewayParams::default()
+        });
+
+        let order_container = |custom_order_id: &str, account: Option<&str>| OrderContainer {
+            order: Order::LimitOrder(LimitOrder {
+                custom_order_id: custom_order_id.to_string(),
+                account: account.map(|account| account.to_string()),
+                ..LimitOrder::default()
+            }),
+            ..OrderContainer::default()
+        };
+
+        let mut order_containers = VecDeque::from(vec![
+            order_container("Order1", None),
+            order_container("Order2", None),
+            order_container("SubOrder1", Some("SubAccount")),
+        ]);
+        let mut throttled_accounts = HashSet::new();
+
+        let custom_order_id = |order_container: Option<OrderContainer>| match order_container {
+            Some(OrderContainer {
+                order: Order::LimitOrder(limit_order),
+                ..
+            }) => Some(limit_order.custom_order_id),
+            _ => None,
+        };
+
+        assert_eq!(
+            custom_order_id(gateway.next_order(&mut order_containers, &mut throttled_accounts)),
+            Some("Order1".to_string())
+        );
+
+        // Order of the throttled default account doesn't hold the other account
+        assert_eq!(
+            custom_order_id(gateway.next_order(&mut order_containers, &mut throttled_accounts)),
+            Some("SubOrder1".to_string())
+        );
+        assert_eq!(
+            custom_order_id(gateway.next_order(&mut order_containers, &mut throttled_accounts)),
+            None
+        );
+
+        assert_eq!(order_containers.len(), 1);
+        assert!(throttled_accounts.contains("StubAccount"));
+    }
+
     #[test]
     fn moratorium() {
         let moratorium_order = OrderMsg::OrderContainers(vec![OrderContainer {
@@ -1705,6 +2645,8 @@ mod tests {
                 price: 101., // price is more than balance on default account
                 order_side: OrderSide::Buy,
                 custom_order_id: "".to_string(),
+                account: None,
+                time_in_force: TimeInForce::Gtc,
             }),
 
             metainfo: StrategyParams::Stub,
@@ -1733,6 +2675,8 @@ mod tests {
                 price: 151., // price is more than balance on default account
                 order_side: OrderSide::Buy,
                 custom_order_id: "".to_string(),
+                account: None,
+                time_in_force: TimeInForce::Gtc,
             }),
 
             metainfo: StrategyParams::Stub,
@@ -1749,7 +2693,7 @@ mod tests {
 
         gateway.receive_order().unwrap();
 
-        gateway.send_order(&ExchangeName::StubExchange).unwrap();
+        gateway.send_order().unwrap();
 
         assert_eq!(gateway.order_containers.read().unwrap().len(), 0);
 
@@ -1769,10 +2713,20 @@ mod tests {
 
         let gateway = Gateway::from_params(gatewap_params);
 
-        assert!(gateway
-            .fetch_metadata(&ExchangeName::StubExchange)
-            .await
-            .is_ok());
+        assert!(gateway.fetch_metadata().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn order_rules() {
+        let gateway = Gateway::new();
+
+        gateway.fetch_metadata().await.unwrap();
+
+        let order_rules = gateway.order_rules("BTCUSDT").await.unwrap();
+        assert_eq!(order_rules.qty_step, 0.00001);
+        assert_eq!(order_rules.price_precision, 2);
+
+        assert!(gateway.order_rules("UNKNOWN").await.is_none());
     }
 
     #[tokio::test(flavor = "multi_thread")]
@@ -1789,10 +2743,7 @@ mod tests {
 
         let gateway = Gateway::from_params(gatewap_params);
 
-        gateway
-            .fetch_metadata(&ExchangeName::Binance)
-            .await
-            .unwrap();
+        gateway.fetch_metadata().await.unwrap();
 
         println!("Metadata: {:?}", gateway.metadata.read().await);
     }
@@ -1810,7 +2761,7 @@ mod tests {
 
         let gateway = Gateway::from_params(gatewap_params);
 
-        gateway.fetch_metadata(&ExchangeName::Huobi).await.unwrap();
+        gateway.fetch_metadata().await.unwrap();
 
         println!("Metadata: {:?}", gateway.metadata.read().await);
     }
@@ -1824,11 +2775,9 @@ mod tests {
 
         let gateway = Gateway::from_params(gatewap_params);
 
-        let symbol = "BTCUSDT";
+        let symbols = vec!["BTCUSDT".to_string()];
 
-        assert!(gateway
-            .fetch_depth(symbol.to_string(), &ExchangeName::StubExchange)
-            .is_ok());
+        assert!(gateway.fetch_depth(symbols).is_ok());
     }
 
     #[test]
@@ -1845,14 +2794,11 @@ mod tests {
 
         let gateway = Gateway::from_params(gatewap_params);
 
-        let symbol = "BTCUSDT";
+        let symbols = vec!["BTCUSDT".to_string(), "ETHUSDT".to_string()];
 
-        loop {
-            println!(
-                "Orderbook {:?}",
-                gateway.fetch_depth(symbol.to_string(), &ExchangeName::Binance)
-            );
-        }
+        println!("Orderbook {:?}", gateway.fetch_depth(symbols));
+
+        thread::sleep(Duration::from_secs(10));
     }
 
     #[test]
@@ -1869,12 +2815,11 @@ mod tests {
 
         let gateway = Gateway::from_params(gatewap_params);
 
-        let symbol = "BTCUSDT";
+        let symbols = vec!["BTCUSDT".to_string(), "ETHUSDT".to_string()];
 
-        println!(
-            "Orderbook {:?}",
-            gateway.fetch_depth(symbol.to_string(), &ExchangeName::Huobi)
-        );
+        println!("Orderbook {:?}", gateway.fetch_depth(symbols));
+
+        thread::sleep(Duration::from_secs(10));
     }
 
     #[test]
@@ -1946,11 +2891,548 @@ mod tests {
 
         let gateway = Gateway::from_params(gatewap_params);
 
+        assert!(gateway.cancel_order(&CancelOrder::default()).is_ok());
+    }
+
+    // Waits until the spawned order request is acknowledged by the exchange
+    async fn wait_order_status(gateway: &Gateway, custom_order_id: &str, status: OrderStatus) {
+        for _ in 0..100 {
+            if let Some(tracked_order) = gateway.order_tracker.get(custom_order_id) {
+                if tracked_order.order_state.status == status {
+                    return;
+                }
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        panic!("Order {} isn't {}", custom_order_id, status);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn replace_order_fallback() {
+        let gateway = Gateway::from_params(GatewayParams {
+            exchange: ExchangeName::StubExchange,
+            ..GatewayParams::default()
+        });
+
+        gateway.fetch_metadata().await.unwrap();
+
+        let limit_order = LimitOrder {
+            symbol: "BTCUSDT".to_string(),
+            amount: 1.,
+            price: 90.,
+            custom_order_id: "Order1".to_string(),
+            ..LimitOrder::default()
+        };
+
+        let order_container = |order: Order| OrderContainer {
+            robot_id: "Robot1".to_string(),
+            order,
+            metainfo: StrategyParams::Stub,
+            created_at: Instant::now(),
+        };
+
+        gateway
+            .order_sender(order_container(Order::LimitOrder(limit_order.clone())))
+            .unwrap();
+        wait_order_status(gateway, "Order1", OrderStatus::New).await;
+
+        let replace_order = ReplaceOrder {
+            cancel_order: CancelOrder {
+                symbol: limit_order.symbol.clone(),
+                custom_order_id: limit_order.custom_order_id.clone(),
+                ..CancelOrder::default()
+            },
+            limit_order: LimitOrder {
+                amount: 0.5,
+                price: 85.,
+                custom_order_id: "Order2".to_string(),
+                ..limit_order
+            },
+        };
+
+        // Stub exchange doesn't support amend
+        assert!(!gateway.is_native_replace_order(&replace_order));
+
+        // Order is canceled and the new one is sent
+        gateway
+            .order_sender(order_container(Order::ReplaceOrder(replace_order)))
+            .unwrap();
+        wait_order_status(gateway, "Order2", OrderStatus::New).await;
+
+        // Cancel is confirmed by the user stream, the order isn't on the exchange already
+        assert_eq!(
+            gateway
+                .order_tracker
+                .get("Order1")
+                .unwrap()
+                .order_state
+                .status,
+            OrderStatus::PendingCancel
+        );
+
+        let open_orders = gateway
+            .exchange()
+            .fetch_open_orders(&["BTCUSDT".to_string()])
+            .unwrap();
+
+        assert_eq!(open_orders.len(), 1);
+        assert_eq!(open_orders[0].custom_order_id, "Order2");
+        assert_eq!(open_orders[0].price, 85.);
+        assert_eq!(open_orders[0].amount, 0.5);
+
+        // Funds are reserved by the new order only
+        assert_eq!(
+            gateway
+                .balance_cache
+                .reserved_by("Order1", "StubAccount", "USDT"),
+            0.
+        );
+        assert_eq!(
+            gateway
+                .balance_cache
+                .reserved_by("Order2", "StubAccount", "USDT"),
+            42.5
+        );
+    }
+
+    #[test]
+    fn flatten() {
+        let gateway = Gateway::from_params(GatewayParams::default());
+
+        for custom_order_id in ["Order1", "Order2"].iter() {
+            gateway
+                .limit_order(&LimitOrder {
+                    symbol: "BTCUSDT".to_string(),
+                    amount: 0.5,
+                    price: 90.,
+                    custom_order_id: custom_order_id.to_string(),
+                    ..LimitOrder::default()
+                })
+                .unwrap();
+        }
+
+        gateway.order_emulator.add_stop(OrderContainer {
+            order: Order::StopOrder(StopOrder::default()),
+            ..OrderContainer::default()
+        });
+
+        gateway
+            .order_containers
+            .write()
+            .unwrap()
+            .push_back(OrderContainer {
+                order: Order::LimitOrder(LimitOrder::default()),
+                ..OrderContainer::default()
+            });
+
+        assert!(gateway.flatten(None, Some("UnknownAccount")).is_err());
+
+        // Other symbol isn't touched
+        assert_eq!(gateway.flatten(Some("ETHUSDT"), None), Ok(0));
+        assert_eq!(gateway.order_emulator.stops_len(), 1);
+
+        assert_eq!(gateway.flatten(Some("BTCUSDT"), None), Ok(2));
+        assert_eq!(gateway.order_emulator.stops_len(), 0);
+        assert!(gateway.order_containers.read().unwrap().is_empty());
+        assert!(gateway
+            .exchange()
+            .fetch_open_orders(&["BTCUSDT".to_string()])
+            .unwrap()
+            .is_empty());
+    }
+
+    // Exchange which is down, every request of the account fails
+    struct UnreachableExchange;
+
+    impl ExchangeAction for UnreachableExchange {
+        fn inti(&self) {}
+
+        fn fetch_metadata(&self) -> Vec<ExchangeInstrumentInfo> {
+            vec![]
+        }
+
+        fn fetch_depth(&self, _symbol: &str) -> Result<Depth, &'static str> {
+            Err("Exchange is unreachable")
+        }
+
+        fn fetch_balances(
+            &self,
+            _instruments: Vec<Instrument>,
+        ) -> Result<HashMap<String, f64>, &'static str> {
+            Err("Exchange is unreachable")
+        }
+
+        fn limit_buy(
+            &self,
+            _symbol: &str,
+            _amount: f64,
+            _price: f64,
+            _custom_order_id: Option<String>,
+        ) -> ExchangeApiResult<PlatformTransaction> {
+            Err("Exchange is unreachable")
+        }
+
+        fn limit_sell(
+            &self,
+            _symbol: &str,
+            _amount: f64,
+            _price: f64,
+            _custom_order_id: Option<String>,
+        ) -> ExchangeApiResult<PlatformTransaction> {
+            Err("Exchange is unreachable")
+        }
+
+        fn market_buy(
+            &self,
+            _symbol: &str,
+            _amount: f64,
+        ) -> ExchangeApiResult<PlatformTransaction> {
+            Err("Exchange is unreachable")
+        }
+
+        fn market_sell(
+            &self,
+            _symbol: &str,
+            _amount: f64,
+        ) -> ExchangeApiResult<PlatformTransaction> {
+            Err("Exchange is unreachable")
+        }
+
+        fn cancel_order(
+            &self,
+            _symbol: &str,
+            _custom_order_id: &str,
+        ) -> ExchangeApiResult<PlatformTransaction> {
+            Err("Exchange is unreachable")
+        }
+
+        fn fetch_open_orders(&self, _symbols: &[String]) -> ExchangeApiResult<Vec<OpenOrder>> {
+            Err("Exchange is unreachable")
+        }
+
+        fn depth_stream(
+            &self,
+            _symbols: &[String],
+            _rate_limit: &dyn Fn(RequestClass),
+            _handler: &mut dyn FnMut(String, Depth),
+        ) -> ExchangeApiResult<()> {
+            Err("Exchange is unreachable")
+        }
+
+        fn user_stream(
+            &self,
+            _symbols: &[String],
+            _handler: &mut dyn FnMut(UserEvent),
+        ) -> ExchangeApiResult<()> {
+            Err("Exchange is unreachable")
+        }
+    }
+
+    #[test]
+    fn flatten_with_failed_account() {
+        let gateway = Gateway::from_params(GatewayParams::default());
+
+        for custom_order_id in ["Order1", "Order2"].iter() {
+            gateway
+                .limit_order(&LimitOrder {
+                    symbol: "BTCUSDT".to_string(),
+                    amount: 0.5,
+                    price: 90.,
+                    custom_order_id: custom_order_id.to_string(),
+                    ..LimitOrder::default()
+                })
+                .unwrap();
+        }
+
+        let stub_exchange = gateway.exchange();
+
+        // Unreachable account is the first one, the next account is still flattened
+        *gateway.exchanges.write().unwrap() = ExchangeAccounts::from_accounts(vec![
+            (
+                "DownAccount".to_string(),
+                Arc::new(UnreachableExchange) as Arc<dyn ExchangeAction>,
+            ),
+            ("StubAccount".to_string(), stub_exchange.clone()),
+        ]);
+
+        assert!(gateway.flatten(Some("BTCUSDT"), None).is_err());
+        assert!(stub_exchange
+            .fetch_open_orders(&["BTCUSDT".to_string()])
+            .unwrap()
+            .is_empty());
+
+        // Reachable account has nothing left to cancel
+        assert_eq!(gateway.flatten(Some("BTCUSDT"), Some("StubAccount")), Ok(0));
+    }
+
+    #[test]
+    fn emulated_stop_order() {
+        let gateway = Gateway::from_params(GatewayParams {
+            exchange: ExchangeName::StubExchange,
+            ..GatewayParams::default()
+        });
+
+        let stop_order = |custom_order_id: &str| OrderContainer {
+            robot_id: "Robot1".to_string(),
+            order: Order::StopOrder(StopOrder {
+                custom_order_id: custom_order_id.to_string(),
+                stop_price: 90.,
+                ..StopOrder::default()
+            }),
+            metainfo: StrategyParams::Stub,
+            created_at: Instant::now(),
+        };
+
+        // Stub exchange doesn't support stop orders
+        gateway.order_sender(stop_order("Stop1")).unwrap();
+        gateway.order_sender(stop_order("Stop2")).unwrap();
+        assert_eq!(gateway.order_emulator.stops_len(), 2);
+
+        gateway
+            .cancel_order(&CancelOrder {
+                custom_order_id: "Stop2".to_string(),
+                ..CancelOrder::default()
+            })
+            .unwrap();
+        assert_eq!(gateway.order_emulator.stops_len(), 1);
+
+        let depth = Depth {
+            bids: vec![Ticker {
+                price: 89.,
+                qty: 1.,
+            }],
+            ..Depth::default()
+        };
+
+        gateway.trigger_stop_orders("BTCUSDT", &depth);
+
+        let order_containers = gateway.order_containers.read().unwrap();
+        assert_eq!(order_containers.len(), 1);
+        assert_eq!(order_containers[0].robot_id, "Robot1");
+        assert!(matches!(order_containers[0].order, Order::MarketOrder(_)));
+    }
+
+    #[test]
+    fn route_order_by_account() {
+        let gatewap_params = GatewayParams {
+            exchange: ExchangeName::StubExchange,
+            accounts: vec![
+                GatewayParamsAccount::default(),
+                GatewayParamsAccount {
+                    name: "SubAccount".to_string(),
+                    ..GatewayParamsAccount::default()
+                },
+            ],
+            ..GatewayParams::default()
+        };
+
+        let gateway = Gateway::from_params(gatewap_params);
+
+        let limit_order = LimitOrder {
+            amount: 2.,
+            price: 120.,
+            order_side: OrderSide::Sell,
+            account: Some("SubAccount".to_string()),
+            ..LimitOrder::default()
+        };
+
+        gateway.limit_order(&limit_order).unwrap();
+
+        // Funds are reserved on the sub account only
+        let balances = gateway.fetch_balances().unwrap();
+        assert_eq!(balances["SubAccount"]["BTC"], 98.);
+        assert_eq!(balances["StubAccount"]["BTC"], 100.);
+
+        let cancel_order = CancelOrder {
+            custom_order_id: limit_order.custom_order_id.clone(),
+            account: limit_order.account.clone(),
+            ..CancelOrder::default()
+        };
+
+        gateway.cancel_order(&cancel_order).unwrap();
+
+        let balances = gateway.fetch_balances().unwrap();
+        assert_eq!(balances["SubAccount"]["BTC"], 100.);
+    }
+
+    #[test]
+    fn unknown_account() {
+        let gateway = Gateway::new();
+
+        let limit_order = LimitOrder {
+            account: Some("UnknownAccount".to_string()),
+            ..LimitOrder::default()
+        };
+
+        assert!(gateway.limit_order(&limit_order).is_err());
+
+        // Orders without account are sent from the default one
+        assert_eq!(
+            gateway.order_account(&Order::LimitOrder(LimitOrder::default())),
+            "StubAccount"
+        );
+    }
+
+    #[test]
+    fn configured_fill_commission() {
+        let gateway = Gateway::new();
+
+        // Stub account fee is 1 basis point
+        let commission = gateway.fill_commission(&FilledOrder::default());
+
+        assert_eq!(commission.asset, "USDT");
+        assert_eq!(format!("{:.4}", commission.quote_amount), "0.0030");
+    }
+
+    #[test]
+    fn check_balance_with_reserved_funds() {
+        let gateway = Gateway::new();
+
+        let first_order = LimitOrder {
+            amount: 1.,
+            price: 90.,
+            custom_order_id: "Order1".to_string(),
+            ..LimitOrder::default()
+        };
+
+        let second_order = LimitOrder {
+            amount: 1.,
+            price: 70.,
+            custom_order_id: "Order2".to_string(),
+            ..LimitOrder::default()
+        };
+
+        // Balance is fetched on the first check, 150 USDT
+        gateway
+            .check_balance(
+                Order::LimitOrder(first_order.clone()),
+                "Robot1".to_string(),
+                StrategyParams::Stub,
+            )
+            .unwrap();
+        gateway.limit_order(&first_order).unwrap();
+
+        // 90 USDT are reserved by the first order
+        assert!(gateway
+            .check_balance(
+                Order::LimitOrder(second_order.clone()),
+                "Robot1".to_string(),
+                StrategyParams::Stub
+            )
+            .is_err());
+
+        let cancel_order = CancelOrder {
+            custom_order_id: first_order.custom_order_id.clone(),
+            ..CancelOrder::default()
+        };
+
+        gateway.cancel_order(&cancel_order).unwrap();
+
+        assert!(gateway
+            .check_balance(
+                Order::LimitOrder(second_order),
+                "Robot1".to_string(),
+                StrategyParams::Stub
+            )
+            .is_ok());
+
         assert!(gateway
-            .cancel_order(CancelOrder::default(), &ExchangeName::StubExchange)
+            .balances_info()
+            .unwrap()
+            .contains("StubAccount USDT"));
+    }
+
+    #[test]
+    fn check_balance_of_amend() {
+        let gateway = Gateway::new();
+
+        let limit_order = LimitOrder {
+            amount: 1.,
+            price: 90.,
+            custom_order_id: "Order1".to_string(),
+            ..LimitOrder::default()
+        };
+
+        // 90 of 150 USDT are reserved
+        gateway
+            .check_balance(
+                Order::LimitOrder(limit_order.clone()),
+                "Robot1".to_string(),
+                StrategyParams::Stub,
+            )
+            .unwrap();
+
+        let amend = |price: f64, custom_order_id: &str| {
+            Order::ReplaceOrder(ReplaceOrder {
+                cancel_order: CancelOrder {
+                    custom_order_id: limit_order.custom_order_id.clone(),
+                    ..CancelOrder::default()
+                },
+                limit_order: LimitOrder {
+                    price,
+                    custom_order_id: custom_order_id.to_string(),
+                    ..limit_order.clone()
+                },
+            })
+        };
+
+        // Amend exceeds the free balance even with the funds of the replaced order
+        assert!(gateway
+            .check_balance(
+                amend(160., "Order2"),
+                "Robot1".to_string(),
+                StrategyParams::Stub
+            )
+            .is_err());
+
+        // Funds of the replaced order are used by the amend
+        assert!(gateway
+            .check_balance(
+                amend(140., "Order3"),
+                "Robot1".to_string(),
+                StrategyParams::Stub
+            )
             .is_ok());
     }
 
+    #[test]
+    fn reconcile_open_orders() {
+        let (active_order_sender, active_order_receiver) = unbounded();
+
+        let gateway = Gateway::with_active_order_sender(
+            GatewayParams {
+                reconcile_policy: ReconcilePolicy::CancelAll,
+                ..GatewayParams::default()
+            },
+            active_order_sender,
+        );
+
+        let limit_order = LimitOrder {
+            symbol: "BTCUSDT".to_string(),
+            amount: 0.5,
+            price: 90.,
+            custom_order_id: "Order1".to_string(),
+            ..LimitOrder::default()
+        };
+
+        gateway.limit_order(&limit_order).unwrap();
+
+        gateway.reconcile_open_orders().unwrap();
+
+        match active_order_receiver.try_recv() {
+            Ok(ActiveOrderMsg::OpenOrders(open_orders)) => {
+                assert_eq!(open_orders.gateway, "DefaultStub");
+                assert_eq!(open_orders.account, "StubAccount");
+                assert_eq!(open_orders.policy, ReconcilePolicy::CancelAll);
+                assert_eq!(open_orders.orders.len(), 1);
+                assert_eq!(open_orders.orders[0].custom_order_id, "Order1");
+            }
+            _ => panic!("Open orders weren't sent"),
+        }
+    }
+
     // #[test]
     // fn check_balance() {
     //     let gatewap_params = GatewayParams {
@@ -1966,14 +3448,16 @@ mod tests {
     #[test]
     #[ignore]
     fn test_ws_huobi() {
+        use crate::api::huobi::websocket_data::HuobiWS;
+
         let symbol = "btcusdt";
 
-        let mut huobi_ws = HuobiWS::connect(symbol);
+        let mut huobi_ws = HuobiWS::connect(&[symbol]).unwrap();
 
         loop {
-            let depth = huobi_ws.get_depth();
+            let event = huobi_ws.get_event();
 
-            println!("{:?}", depth);
+            println!("{:?}", event);
         }
     }
 }
@@ -73,6 +73,55 @@ impl GatewayController {
         }
     }
 
+    pub fn balances(gateway_name: &str) -> String {
+        info!("Getting balances of the {} gateway", gateway_name);
+
+        match PLATFORM
+            .environment
+            .gateway_environment
+            .balances_gateway(gateway_name)
+        {
+            Ok(balances) => balances,
+            Err(error) => format!("{}", error),
+        }
+    }
+
+    // Kill switch, dependent robots are locked first so they don't quote again
+    pub fn flatten(gateway_name: &str, symbol: Option<&str>, account: Option<&str>) -> String {
+        info!(
+            "Flattening the {} gateway: symbol {:?}, account {:?}",
+            gateway_name, symbol, account
+        );
+
+        let dependent_robots = PLATFORM
+            .environment
+            .gateway_environment
+            .get_dependent_robots(gateway_name);
+
+        for robot_name in dependent_robots {
+            match PLATFORM
+                .environment
+                .robot_environment
+                .lock_robot(&robot_name)
+            {
+                Ok(_) => info!("Robot {} was locked", robot_name),
+                Err(error) => error!("Robot {} wasn't locked: {}", robot_name, error),
+            }
+        }
+
+        match PLATFORM.environment.gateway_environment.flatten_gateway(
+            gateway_name,
+            symbol,
+            account,
+        ) {
+            Ok(canceled_orders) => format!(
+                "{} open orders of the Gateway {} have been canceled",
+                canceled_orders, gateway_name
+            ),
+            Err(error) => format!("{}", error),
+        }
+    }
+
     pub fn set_config(gateway_name: &str, config_file_path: &str) -> String {
         info!("Setting config for the {} gateway", gateway_name);
 
@@ -72,6 +72,21 @@ impl GatewayEnvironment {
         Ok(self.find_gateway(gateway_name)?.info()?)
     }
 
+    // Finds Gateway by name and get its cached balances
+    pub fn balances_gateway(&self, gateway_name: &str) -> Result<String, &'static str> {
+        self.find_gateway(gateway_name)?.balances_info()
+    }
+
+    // Finds Gateway by name and cancels its open orders of the symbol and the account
+    pub fn flatten_gateway(
+        &self,
+        gateway_name: &str,
+        symbol: Option<&str>,
+        account: Option<&str>,
+    ) -> Result<usize, &'static str> {
+        self.find_gateway(gateway_name)?.flatten(symbol, account)
+    }
+
     pub fn set_config_gateway(
         &self,
         gateway_name: &str,
@@ -235,6 +250,22 @@ mod tests {
         assert!(gateway_environment.info_gateway(gateway_name).is_ok());
     }
 
+    #[test]
+    fn balances_gateway() {
+        let gateway_environment = GatewayEnvironment::stub();
+        let gateway_name = "GatewayStub";
+        assert!(gateway_environment.balances_gateway(gateway_name).is_ok());
+    }
+
+    #[test]
+    fn flatten_gateway() {
+        let gateway_environment = GatewayEnvironment::stub();
+        let gateway_name = "GatewayStub";
+        assert!(gateway_environment
+            .flatten_gateway(gateway_name, None, None)
+            .is_ok());
+    }
+
     #[test]
     fn set_config_gateway() {
         let gateway_environment = GatewayEnvironment::stub();
@@ -1,16 +1,41 @@
-use super::GatewayConfig;
+use super::config::{Account, FixSession, Replay};
+use super::{GatewayConfig, Secret, SecretStore};
 use crate::config::ParseConfig;
+use crate::context_manager::CandleInterval;
 use std::{str::FromStr, string::ToString};
 use strum_macros::{Display, EnumString};
-use tracing::info;
+use tracing::{info, warn};
 
 #[derive(Debug, Clone)]
 pub struct GatewayParamsAccount {
     pub name: String,
     // Some exchanges need account id for make request to them, e.g. Huobi
     pub account_id: Option<String>,
-    pub api_key: String,
-    pub secret_key: String,
+    // Resolved from the config references, they're redacted in logs
+    pub api_key: Secret,
+    pub secret_key: Secret,
+    // FIX exchanges need the session, API key and secret key are its username and password
+    pub fix: Option<FixSessionParams>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FixSessionParams {
+    // host:port of the FIX acceptor
+    pub address: String,
+    pub sender_comp_id: String,
+    pub target_comp_id: String,
+    // Seconds
+    pub heartbeat_interval: u64,
+    // Sequence numbers are persisted there between restarts
+    pub store_dir: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReplayParams {
+    // Directory of the recorded gateway with the files per symbol or CSV file of depth snapshots
+    pub source: String,
+    // Replay speed relative to the original timing, 0 is as fast as possible
+    pub speed: f64,
 }
 
 #[derive(Debug, Clone)]
@@ -44,9 +69,26 @@ pub enum ExchangeName {
     Binance,
     Huobi,
     BitMEX,
+    Kraken,
+    Fix,
+    Replay,
     StubExchange,
 }
 
+// What to do on start with the orders which are open on the exchange
+#[derive(PartialEq, Debug, EnumString, Display, Clone)]
+pub enum ReconcilePolicy {
+    // Orders known from the previous run are adopted, unknown ones are canceled
+    AdoptKnown,
+    CancelAll,
+}
+
+impl Default for ReconcilePolicy {
+    fn default() -> Self {
+        ReconcilePolicy::AdoptKnown
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GatewayParams {
     pub name: String,
@@ -55,6 +97,10 @@ pub struct GatewayParams {
     pub instruments: Vec<Instrument>,
     pub fees: Vec<Fee>,
     pub exchange_time_limit: TimeLimit,
+    pub reconcile_policy: ReconcilePolicy,
+    pub replay: Option<ReplayParams>,
+    // Candles of these intervals are backfilled on start
+    pub candle_intervals: Vec<CandleInterval>,
 }
 
 pub trait GatewayParamsActions {
@@ -67,7 +113,7 @@ impl GatewayParamsActions for GatewayParams {
     fn from_config(config_file_path: &str) -> Result<GatewayParams, &'static str> {
         match GatewayConfig::from_file(config_file_path) {
             Ok(gateway_config) => match GatewayParams::validate_config(&gateway_config) {
-                Ok(_) => Ok(GatewayParams::_from_config(gateway_config)),
+                Ok(_) => GatewayParams::_from_config(gateway_config),
                 Err(_error) => Err("Config validation error"),
             },
             Err(_e) => Err("No gateway config"),
@@ -81,25 +127,66 @@ impl GatewayParamsActions for GatewayParams {
     }
 }
 
+impl FixSessionParams {
+    pub fn from_config(fix_session: &FixSession) -> Self {
+        FixSessionParams {
+            address: fix_session.address.clone(),
+            sender_comp_id: fix_session.sender_comp_id.clone(),
+            target_comp_id: fix_session.target_comp_id.clone(),
+            heartbeat_interval: fix_session.heartbeat_interval.unwrap_or(30),
+            store_dir: fix_session
+                .store_dir
+                .clone()
+                .unwrap_or_else(|| "fix_store".to_string()),
+        }
+    }
+}
+
+impl GatewayParamsAccount {
+    pub fn from_config(
+        account: &Account,
+        secret_store: &SecretStore,
+    ) -> Result<Self, &'static str> {
+        Ok(GatewayParamsAccount {
+            name: account.name.clone(),
+            account_id: account.account_id.clone(),
+            api_key: secret_store.resolve(&account.api_key)?,
+            secret_key: secret_store.resolve(&account.secret_key)?,
+            fix: account.fix.as_ref().map(FixSessionParams::from_config),
+        })
+    }
+}
+
+impl ReplayParams {
+    pub fn from_config(replay: &Replay) -> Self {
+        ReplayParams {
+            source: replay.source.clone(),
+            speed: replay.speed.unwrap_or(1.),
+        }
+    }
+}
+
+impl ReconcilePolicy {
+    // Missing or unknown policy in the config is the default one
+    pub fn from_config(gateway_config: &GatewayConfig) -> Self {
+        gateway_config
+            .reconcile_policy
+            .as_deref()
+            .and_then(|policy| ReconcilePolicy::from_str(policy).ok())
+            .unwrap_or_default()
+    }
+}
+
 // Implement private methods
 impl GatewayParams {
-    fn _from_config(gateway_config: GatewayConfig) -> Self {
-        GatewayParams {
+    fn _from_config(gateway_config: GatewayConfig) -> Result<Self, &'static str> {
+        Ok(GatewayParams {
+            accounts: GatewayParams::accounts(&gateway_config)?,
+
             name: gateway_config.gateway_name,
 
             exchange: ExchangeName::from_str(&gateway_config.exchange).unwrap(),
 
-            accounts: gateway_config
-                .accounts
-                .iter()
-                .map(|a| GatewayParamsAccount {
-                    name: a.name.clone(),
-                    account_id: a.account_id.clone(),
-                    api_key: a.api_key.clone(),
-                    secret_key: a.secret_key.clone(),
-                })
-                .collect(),
-
             instruments: gateway_config
                 .instruments
                 .iter()
@@ -124,7 +211,45 @@ impl GatewayParams {
             exchange_time_limit: TimeLimit {
                 rpc: gateway_config.limit.rps,
             },
-        }
+
+            reconcile_policy: ReconcilePolicy::from_config(&gateway_config),
+
+            replay: gateway_config
+                .replay
+                .as_ref()
+                .map(ReplayParams::from_config),
+
+            candle_intervals: GatewayParams::candle_intervals(&gateway_config),
+        })
+    }
+
+    // Secrets of every account are resolved, the config is rejected if any of them is missing
+    pub fn accounts(
+        gateway_config: &GatewayConfig,
+    ) -> Result<Vec<GatewayParamsAccount>, &'static str> {
+        let secret_store = SecretStore::from_config(gateway_config.secrets.as_ref());
+
+        gateway_config
+            .accounts
+            .iter()
+            .map(|account| GatewayParamsAccount::from_config(account, &secret_store))
+            .collect()
+    }
+
+    // Unknown intervals of the config are skipped
+    pub fn candle_intervals(gateway_config: &GatewayConfig) -> Vec<CandleInterval> {
+        gateway_config
+            .candles
+            .iter()
+            .flatten()
+            .filter_map(|interval| match CandleInterval::from_str(interval) {
+                Ok(interval) => Some(interval),
+                Err(_) => {
+                    warn!("Unknown candle interval {} in the gateway config", interval);
+                    None
+                }
+            })
+            .collect()
     }
 }
 
@@ -133,8 +258,9 @@ impl Default for GatewayParamsAccount {
         GatewayParamsAccount {
             name: "StubAccount".to_string(),
             account_id: None,
-            api_key: "API_KEY".to_string(),
-            secret_key: "SECRE_KEY".to_string(),
+            api_key: Secret::from("API_KEY"),
+            secret_key: Secret::from("SECRE_KEY"),
+            fix: None,
         }
     }
 }
@@ -164,6 +290,9 @@ impl Default for GatewayParams {
             }],
             fees: vec![Fee::default()],
             exchange_time_limit: TimeLimit::default(),
+            reconcile_policy: ReconcilePolicy::default(),
+            replay: None,
+            candle_intervals: Vec::new(),
         }
     }
 }
@@ -171,7 +300,7 @@ impl Default for GatewayParams {
 #[cfg(test)]
 pub mod test_utils {
 
-    use super::GatewayParamsAccount;
+    use super::{GatewayParamsAccount, Secret};
     pub struct GatewayParamsUtils;
 
     impl GatewayParamsUtils {
@@ -182,8 +311,9 @@ pub mod test_utils {
             GatewayParamsAccount {
                 name: "BinanceTestAccount".to_string(),
                 account_id: None,
-                api_key: binance_api_key,
-                secret_key: binance_secret_key,
+                api_key: Secret::new(binance_api_key),
+                secret_key: Secret::new(binance_secret_key),
+                fix: None,
             }
         }
 
@@ -195,8 +325,9 @@ pub mod test_utils {
             GatewayParamsAccount {
                 name: "HuobiTestAccount".to_string(),
                 account_id: Some(huobi_account_id),
-                api_key: huobi_api_key,
-                secret_key: huobi_secret_key,
+                api_key: Secret::new(huobi_api_key),
+                secret_key: Secret::new(huobi_secret_key),
+                fix: None,
             }
         }
 
@@ -207,8 +338,9 @@ pub mod test_utils {
             GatewayParamsAccount {
                 name: "BitMexTestAccount".to_string(),
                 account_id: None,
-                api_key: bitmex_api_key,
-                secret_key: bitmex_secret_key,
+                api_key: Secret::new(bitmex_api_key),
+                secret_key: Secret::new(bitmex_secret_key),
+                fix: None,
             }
         }
     }
@@ -226,6 +358,41 @@ mod tests {
         assert!(GatewayParams::from_config(config_file_path).is_ok());
     }
 
+    #[test]
+    fn redacted_account() {
+        let account = format!("{:?}", GatewayParamsAccount::default());
+
+        assert!(!account.contains("API_KEY"));
+        assert!(!account.contains("SECRE_KEY"));
+    }
+
+    #[test]
+    fn reconcile_policy_from_config() {
+        let mut gateway_config = GatewayConfig::default();
+        assert_eq!(
+            ReconcilePolicy::from_config(&gateway_config),
+            ReconcilePolicy::AdoptKnown
+        );
+
+        gateway_config.reconcile_policy = Some("CancelAll".to_string());
+        assert_eq!(
+            ReconcilePolicy::from_config(&gateway_config),
+            ReconcilePolicy::CancelAll
+        );
+    }
+
+    #[test]
+    fn candle_intervals_from_config() {
+        let mut gateway_config = GatewayConfig::default();
+        assert!(GatewayParams::candle_intervals(&gateway_config).is_empty());
+
+        gateway_config.candles = Some(vec!["1m".to_string(), "2m".to_string(), "1h".to_string()]);
+        assert_eq!(
+            GatewayParams::candle_intervals(&gateway_config),
+            vec![CandleInterval::M1, CandleInterval::H1]
+        );
+    }
+
     #[test]
     fn validate_config() {
         let gateway_config = GatewayConfig::default();
@@ -0,0 +1,282 @@
+use super::gateway::ExchangeInstrumentInfo;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use strum_macros::{Display, EnumString};
+use tracing::{debug, warn};
+
+lazy_static! {
+    // Instruments of all gateways on the platform, every gateway registers its metadata
+    pub static ref INSTRUMENT_REGISTRY: InstrumentRegistry = InstrumentRegistry::new();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, Display)]
+pub enum InstrumentType {
+    Spot,
+    Perpetual,
+    Future,
+}
+
+impl Default for InstrumentType {
+    fn default() -> Self {
+        InstrumentType::Spot
+    }
+}
+
+// Gateway instrument with its canonical id and the exchange symbol
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisteredInstrument {
+    // It's the same on all exchanges, e.g. BTC-USDT or BTC-USD-PERP
+    pub id: String,
+    pub gateway: String,
+    // Exchange symbol, e.g. BTCUSDT on Binance or XBTUSD on BitMEX
+    pub symbol: String,
+    pub base: String,
+    pub quote: String,
+    pub instrument_type: InstrumentType,
+}
+
+// Asset names of the exchanges which differ from the common ones
+pub fn canonical_asset(asset: &str) -> String {
+    let asset = asset.to_uppercase();
+
+    match asset.as_str() {
+        "XBT" => "BTC".to_string(),
+        "XDG" => "DOGE".to_string(),
+        _ => asset,
+    }
+}
+
+// BASE-QUOTE of spot, derivatives have the type suffix
+pub fn canonical_id(base: &str, quote: &str, instrument_type: InstrumentType) -> String {
+    let id = format!("{}-{}", canonical_asset(base), canonical_asset(quote));
+
+    match instrument_type {
+        InstrumentType::Spot => id,
+        InstrumentType::Perpetual => format!("{}-PERP", id),
+        InstrumentType::Future => format!("{}-FUT", id),
+    }
+}
+
+#[derive(Debug, Default)]
+struct Instruments {
+    // <(Gateway, Canonical id), Instrument>
+    by_id: HashMap<(String, String), RegisteredInstrument>,
+    // <(Gateway, Exchange symbol), Canonical id>
+    by_symbol: HashMap<(String, String), String>,
+}
+
+// Maps exchange symbols of every gateway to the canonical instruments and back.
+// Symbols which aren't canonical ids are passed as they are, so exchange symbols still work.
+#[derive(Debug, Default)]
+pub struct InstrumentRegistry {
+    instruments: RwLock<Instruments>,
+}
+
+impl InstrumentRegistry {
+    pub fn new() -> Self {
+        InstrumentRegistry::default()
+    }
+
+    pub fn register(&self, gateway: &str, instruments_info: &[ExchangeInstrumentInfo]) {
+        for instrument_info in instruments_info {
+            self.register_instrument(
+                gateway,
+                &instrument_info.symbol,
+                &instrument_info.base,
+                &instrument_info.quote,
+                instrument_info.instrument_type,
+            );
+        }
+
+        debug!(
+            "[Instruments] {} instruments of {} are registered",
+            instruments_info.len(),
+            gateway
+        );
+    }
+
+    // Canonical id of the other exchange symbol is kept, e.g. for the second future of the asset.
+    // Such symbol is addressed by the exchange symbol only.
+    pub fn register_instrument(
+        &self,
+        gateway: &str,
+        symbol: &str,
+        base: &str,
+        quote: &str,
+        instrument_type: InstrumentType,
+    ) {
+        let id = canonical_id(base, quote, instrument_type);
+        let mut instruments = self.instruments.write().unwrap();
+
+        let id_key = (gateway.to_string(), id.clone());
+
+        if let Some(registered) = instruments.by_id.get(&id_key) {
+            if registered.symbol != symbol {
+                warn!(
+                    "[Instruments] {} of {} is {} already, {} isn't mapped",
+                    id, gateway, registered.symbol, symbol
+                );
+                return;
+            }
+        }
+
+        instruments
+            .by_symbol
+            .insert((gateway.to_string(), symbol.to_string()), id.clone());
+
+        instruments.by_id.insert(
+            id_key,
+            RegisteredInstrument {
+                id,
+                gateway: gateway.to_string(),
+                symbol: symbol.to_string(),
+                base: canonical_asset(base),
+                quote: canonical_asset(quote),
+                instrument_type,
+            },
+        );
+    }
+
+    pub fn instrument(&self, gateway: &str, id: &str) -> Option<RegisteredInstrument> {
+        self.instruments
+            .read()
+            .unwrap()
+            .by_id
+            .get(&(gateway.to_string(), id.to_string()))
+            .cloned()
+    }
+
+    pub fn canonical_id(&self, gateway: &str, symbol: &str) -> Option<String> {
+        self.instruments
+            .read()
+            .unwrap()
+            .by_symbol
+            .get(&(gateway.to_string(), symbol.to_string()))
+            .cloned()
+    }
+
+    // Exchange symbol of the canonical id, other symbols are returned as they are
+    pub fn exchange_symbol(&self, gateway: &str, symbol: &str) -> String {
+        self.instrument(gateway, symbol)
+            .map_or_else(|| symbol.to_string(), |instrument| instrument.symbol)
+    }
+
+    // The instrument on every gateway which trades it
+    pub fn gateways_instruments(&self, id: &str) -> Vec<RegisteredInstrument> {
+        self.instruments
+            .read()
+            .unwrap()
+            .by_id
+            .values()
+            .filter(|instrument| instrument.id == id)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn instrument_info(
+        symbol: &str,
+        base: &str,
+        quote: &str,
+        instrument_type: InstrumentType,
+    ) -> ExchangeInstrumentInfo {
+        ExchangeInstrumentInfo {
+            base: base.to_string(),
+            quote: quote.to_string(),
+            symbol: symbol.to_string(),
+            instrument_type,
+            precision: 2,
+            qty_step: 0.00001,
+            min_qty: 0.00001,
+            min_notional: 0.,
+        }
+    }
+
+    #[test]
+    fn canonical_ids() {
+        assert_eq!(
+            canonical_id("btc", "usdt", InstrumentType::Spot),
+            "BTC-USDT"
+        );
+        assert_eq!(
+            canonical_id("XBT", "USD", InstrumentType::Perpetual),
+            "BTC-USD-PERP"
+        );
+    }
+
+    #[test]
+    fn cross_exchange_symbols() {
+        let instrument_registry = InstrumentRegistry::new();
+
+        instrument_registry.register(
+            "Binance",
+            &[instrument_info(
+                "BTCUSDT",
+                "BTC",
+                "USDT",
+                InstrumentType::Spot,
+            )],
+        );
+        instrument_registry.register(
+            "Huobi",
+            &[instrument_info(
+                "btcusdt",
+                "btc",
+                "usdt",
+                InstrumentType::Spot,
+            )],
+        );
+        instrument_registry.register(
+            "BitMEX",
+            &[
+                instrument_info("XBTUSD", "XBT", "USD", InstrumentType::Perpetual),
+                instrument_info("XBTZ21", "XBT", "USD", InstrumentType::Future),
+                instrument_info("XBTH22", "XBT", "USD", InstrumentType::Future),
+            ],
+        );
+
+        assert_eq!(
+            instrument_registry.exchange_symbol("Huobi", "BTC-USDT"),
+            "btcusdt"
+        );
+        assert_eq!(
+            instrument_registry.exchange_symbol("BitMEX", "BTC-USD-PERP"),
+            "XBTUSD"
+        );
+        assert_eq!(
+            instrument_registry.canonical_id("Binance", "BTCUSDT"),
+            Some("BTC-USDT".to_string())
+        );
+
+        // Exchange symbols and unknown ids are passed as they are
+        assert_eq!(
+            instrument_registry.exchange_symbol("Binance", "BTCUSDT"),
+            "BTCUSDT"
+        );
+        assert_eq!(
+            instrument_registry.exchange_symbol("Binance", "ETH-USDT"),
+            "ETH-USDT"
+        );
+
+        // The second future keeps its exchange symbol only
+        assert_eq!(
+            instrument_registry.exchange_symbol("BitMEX", "BTC-USD-FUT"),
+            "XBTZ21"
+        );
+        assert_eq!(instrument_registry.canonical_id("BitMEX", "XBTH22"), None);
+
+        let mut gateways = instrument_registry
+            .gateways_instruments("BTC-USDT")
+            .into_iter()
+            .map(|instrument| instrument.gateway)
+            .collect::<Vec<String>>();
+        gateways.sort();
+        assert_eq!(gateways, vec!["Binance", "Huobi"]);
+    }
+}
@@ -1,18 +1,47 @@
+mod balance_cache;
+mod book_sync;
+mod clock_sync;
 mod config;
 mod error;
 mod exchange;
-mod gateway; 
+mod fees;
+mod gateway;
 mod gateway_controller;
 mod gateway_environment;
 mod gateway_params;
+mod instrument_registry;
+mod order_emulator;
+mod order_rules;
+mod order_tracker;
 mod orderbook;
+mod rate_limiter;
+mod secrets;
+mod stream_supervisor;
 
+pub use balance_cache::{Balance, BalanceCache};
+pub use book_sync::{BookSnapshot, BookSync, BookUpdate, SnapshotSchedule};
+pub use clock_sync::{ClockSample, ClockSync, CLOCK_SYNC_INTERVAL};
 pub use config::GatewayConfig;
-pub use gateway::{Depth, Gateway, GatewayStatus, Ticker};
+pub use exchange::OpenOrder;
+pub use fees::{fee_rate, fill_commission};
+pub use gateway::{Depth, Gateway, GatewayStatus, PublicTrade, Ticker};
 pub use gateway_controller::GatewayController;
 pub use gateway_environment::GatewayEnvironment;
 pub use gateway_params::{
-    ExchangeName, Fee, GatewayParams, GatewayParamsAccount, GatewayParamsActions, Instrument,
-    TimeLimit,
+    ExchangeName, Fee, FixSessionParams, GatewayParams, GatewayParamsAccount, GatewayParamsActions,
+    Instrument, ReconcilePolicy, ReplayParams, TimeLimit,
 };
+pub use instrument_registry::{
+    canonical_asset, canonical_id, InstrumentRegistry, InstrumentType, RegisteredInstrument,
+    INSTRUMENT_REGISTRY,
+};
+pub use order_emulator::OrderEmulator;
+pub use order_rules::{OrderRules, RejectReason};
+pub use order_tracker::{OrderTracker, TrackedOrder};
 pub use orderbook::{CumulativeOrderBook, OrderBook, Volume};
+pub use rate_limiter::{RateLimiter, RequestClass, ThrottleStats, TokenBucket};
+pub use secrets::{
+    Keystore, KeystoreEntry, Secret, SecretStore, SecretsConfig, KEYSTORE_FILE,
+    KEYSTORE_PASSPHRASE_ENV, SECRETS_FILE,
+};
+pub use stream_supervisor::{Backoff, ConnectionStatus, StreamKind, StreamSupervisor};
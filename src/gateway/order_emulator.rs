@@ -0,0 +1,317 @@
+use super::{Depth, RejectReason};
+use crate::order_manager::{
+    CancelOrder, LimitOrder, Order, OrderContainer, OrderSide, StopOrder, TimeInForce,
+};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Instant;
+
+// Order types which the exchange doesn't support natively.
+// Stop orders wait for their trigger on depth updates, GTD orders are canceled on expiration,
+// post-only and FOK orders are checked against the last depth before they're sent.
+#[derive(Debug, Default)]
+pub struct OrderEmulator {
+    // <Symbol, Last depth>
+    depths: RwLock<HashMap<String, Depth>>,
+    // Stop orders with their robots
+    stop_orders: RwLock<Vec<OrderContainer>>,
+    // <Custom order id, (Expiration time in unix milliseconds, Cancel order container)>
+    expiring_orders: RwLock<HashMap<String, (u64, OrderContainer)>>,
+}
+
+impl OrderEmulator {
+    pub fn new() -> Self {
+        OrderEmulator::default()
+    }
+
+    pub fn add_stop(&self, order_container: OrderContainer) {
+        self.stop_orders.write().unwrap().push(order_container);
+    }
+
+    // It returns true if the stop order was waiting for its trigger
+    pub fn remove_stop(&self, custom_order_id: &str) -> bool {
+        let mut stop_orders = self.stop_orders.write().unwrap();
+        let stop_orders_len = stop_orders.len();
+
+        stop_orders.retain(|order_container| match &order_container.order {
+            Order::StopOrder(stop_order) => stop_order.custom_order_id != custom_order_id,
+            _ => true,
+        });
+
+        stop_orders.len() != stop_orders_len
+    }
+
+    // Removes the stop orders matched by the filter, it returns their count
+    pub fn remove_stops<F>(&self, filter: F) -> usize
+    where
+        F: Fn(&OrderContainer) -> bool,
+    {
+        let mut stop_orders = self.stop_orders.write().unwrap();
+        let stop_orders_len = stop_orders.len();
+
+        stop_orders.retain(|order_container| !filter(order_container));
+
+        stop_orders_len - stop_orders.len()
+    }
+
+    pub fn stops_len(&self) -> usize {
+        self.stop_orders.read().unwrap().len()
+    }
+
+    // Keeps the last depth of the symbol.
+    // It returns triggered stop orders replaced by their market or limit orders.
+    pub fn update_depth(&self, symbol: &str, depth: Depth) -> Vec<OrderContainer> {
+        let mut triggered_orders = Vec::new();
+
+        self.stop_orders
+            .write()
+            .unwrap()
+            .retain(|order_container| match &order_container.order {
+                Order::StopOrder(stop_order)
+                    if stop_order.symbol == symbol && Self::is_triggered(stop_order, &depth) =>
+                {
+                    triggered_orders.push(OrderContainer {
+                        order: stop_order.triggered_order(),
+                        created_at: Instant::now(),
+                        ..order_container.clone()
+                    });
+                    false
+                }
+                _ => true,
+            });
+
+        self.depths
+            .write()
+            .unwrap()
+            .insert(symbol.to_string(), depth);
+
+        triggered_orders
+    }
+
+    fn is_triggered(stop_order: &StopOrder, depth: &Depth) -> bool {
+        match stop_order.order_side {
+            OrderSide::Buy => depth
+                .asks
+                .first()
+                .map_or(false, |ask| ask.price >= stop_order.stop_price),
+            OrderSide::Sell => depth
+                .bids
+                .first()
+                .map_or(false, |bid| bid.price <= stop_order.stop_price),
+        }
+    }
+
+    // Post-only order which would take liquidity and FOK order without enough liquidity
+    // are rejected. Order passes if the symbol depth isn't received yet.
+    pub fn check_limit(&self, limit_order: &LimitOrder) -> Result<(), RejectReason> {
+        let depths = self.depths.read().unwrap();
+
+        let depth = match depths.get(&limit_order.symbol) {
+            Some(depth) => depth,
+            None => return Ok(()),
+        };
+
+        // Opposite side levels which the order would take
+        let crossed_levels = match limit_order.order_side {
+            OrderSide::Buy => depth
+                .asks
+                .iter()
+                .filter(|ask| ask.price <= limit_order.price)
+                .collect::<Vec<_>>(),
+            OrderSide::Sell => depth
+                .bids
+                .iter()
+                .filter(|bid| bid.price >= limit_order.price)
+                .collect::<Vec<_>>(),
+        };
+
+        match limit_order.time_in_force {
+            TimeInForce::PostOnly if !crossed_levels.is_empty() => {
+                Err(RejectReason::WouldTakeLiquidity)
+            }
+            TimeInForce::Fok => {
+                let available = crossed_levels.iter().map(|level| level.qty).sum::<f64>();
+
+                if available < limit_order.amount {
+                    Err(RejectReason::NotEnoughLiquidity {
+                        amount: limit_order.amount,
+                        available,
+                    })
+                } else {
+                    Ok(())
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn expire_at(&self, expire_at: u64, cancel_order: CancelOrder, robot_id: &str) {
+        let custom_order_id = cancel_order.custom_order_id.clone();
+
+        let order_container = OrderContainer {
+            robot_id: robot_id.to_string(),
+            order: Order::CancelOrder(cancel_order),
+            ..OrderContainer::default()
+        };
+
+        self.expiring_orders
+            .write()
+            .unwrap()
+            .insert(custom_order_id, (expire_at, order_container));
+    }
+
+    pub fn remove_expiring(&self, custom_order_id: &str) {
+        self.expiring_orders
+            .write()
+            .unwrap()
+            .remove(custom_order_id);
+    }
+
+    // Cancel orders of the orders expired by the time in unix milliseconds
+    pub fn expired(&self, now: u64) -> Vec<OrderContainer> {
+        let mut expiring_orders = self.expiring_orders.write().unwrap();
+
+        let expired_ids = expiring_orders
+            .iter()
+            .filter(|(_, (expire_at, _))| *expire_at <= now)
+            .map(|(custom_order_id, _)| custom_order_id.clone())
+            .collect::<Vec<String>>();
+
+        expired_ids
+            .iter()
+            .filter_map(|custom_order_id| expiring_orders.remove(custom_order_id))
+            .map(|(_, order_container)| order_container)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::OrderEmulator;
+    use crate::gateway::{Depth, RejectReason, Ticker};
+    use crate::order_manager::{
+        CancelOrder, LimitOrder, Order, OrderContainer, OrderSide, StopOrder, TimeInForce,
+    };
+
+    fn depth(bid: f64, ask: f64) -> Depth {
+        Depth {
+            exchange: "StubExchange".to_string(),
+            bids: vec![Ticker {
+                price: bid,
+                qty: 1.,
+            }],
+            asks: vec![Ticker {
+                price: ask,
+                qty: 1.,
+            }],
+        }
+    }
+
+    #[test]
+    fn stop_orders() {
+        let order_emulator = OrderEmulator::new();
+
+        // Sell stop market and buy stop limit
+        order_emulator.add_stop(OrderContainer {
+            order: Order::StopOrder(StopOrder {
+                custom_order_id: "Stop1".to_string(),
+                stop_price: 90.,
+                ..StopOrder::default()
+            }),
+            ..OrderContainer::default()
+        });
+        order_emulator.add_stop(OrderContainer {
+            order: Order::StopOrder(StopOrder {
+                custom_order_id: "Stop2".to_string(),
+                stop_price: 110.,
+                limit_price: Some(111.),
+                order_side: OrderSide::Buy,
+                ..StopOrder::default()
+            }),
+            ..OrderContainer::default()
+        });
+
+        assert!(order_emulator
+            .update_depth("BTCUSDT", depth(100., 101.))
+            .is_empty());
+        assert!(order_emulator
+            .update_depth("ETHUSDT", depth(80., 81.))
+            .is_empty());
+
+        let triggered_orders = order_emulator.update_depth("BTCUSDT", depth(89., 90.));
+        assert_eq!(triggered_orders.len(), 1);
+        assert!(matches!(triggered_orders[0].order, Order::MarketOrder(_)));
+
+        let triggered_orders = order_emulator.update_depth("BTCUSDT", depth(109., 110.));
+        match &triggered_orders[0].order {
+            Order::LimitOrder(limit_order) => {
+                assert_eq!(limit_order.price, 111.);
+                assert_eq!(limit_order.custom_order_id, "Stop2");
+            }
+            order => panic!("Unexpected order {:?}", order),
+        }
+
+        assert_eq!(order_emulator.stops_len(), 0);
+    }
+
+    #[test]
+    fn check_limit() {
+        let order_emulator = OrderEmulator::new();
+
+        let post_only = LimitOrder {
+            price: 101.,
+            time_in_force: TimeInForce::PostOnly,
+            ..LimitOrder::default()
+        };
+
+        // Depth isn't known yet
+        assert!(order_emulator.check_limit(&post_only).is_ok());
+
+        order_emulator.update_depth("BTCUSDT", depth(100., 101.));
+
+        assert_eq!(
+            order_emulator.check_limit(&post_only),
+            Err(RejectReason::WouldTakeLiquidity)
+        );
+
+        let fill_or_kill = LimitOrder {
+            price: 101.,
+            amount: 2.,
+            time_in_force: TimeInForce::Fok,
+            ..LimitOrder::default()
+        };
+
+        assert_eq!(
+            order_emulator.check_limit(&fill_or_kill),
+            Err(RejectReason::NotEnoughLiquidity {
+                amount: 2.,
+                available: 1.
+            })
+        );
+    }
+
+    #[test]
+    fn expired_orders() {
+        let order_emulator = OrderEmulator::new();
+
+        let cancel_order = |custom_order_id: &str| CancelOrder {
+            custom_order_id: custom_order_id.to_string(),
+            ..CancelOrder::default()
+        };
+
+        order_emulator.expire_at(1000, cancel_order("Order1"), "Robot1");
+        order_emulator.expire_at(2000, cancel_order("Order2"), "Robot1");
+        order_emulator.expire_at(3000, cancel_order("Order3"), "Robot1");
+
+        // Canceled order doesn't expire
+        order_emulator.remove_expiring("Order2");
+
+        let expired_orders = order_emulator.expired(2500);
+        assert_eq!(expired_orders.len(), 1);
+        assert_eq!(expired_orders[0].robot_id, "Robot1");
+
+        assert!(order_emulator.expired(2500).is_empty());
+        assert_eq!(order_emulator.expired(3000).len(), 1);
+    }
+}
@@ -0,0 +1,334 @@
+use super::gateway::ExchangeInstrumentInfo;
+use super::Instrument;
+use crate::order_manager::{LimitOrder, MarketOrder, StopOrder};
+use std::fmt;
+
+// Tolerance for float division on the step boundary, e.g. 0.3 / 0.1
+const STEP_EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RejectReason {
+    // No exchange metadata for the symbol
+    UnknownInstrument,
+    AmountBelowMinimum { amount: f64, min_qty: f64 },
+    NotionalBelowMinimum { notional: f64, min_notional: f64 },
+    // Post-only order crosses the book
+    WouldTakeLiquidity,
+    // Fill or kill order can't be filled completely
+    NotEnoughLiquidity { amount: f64, available: f64 },
+    // Order wasn't accepted by the exchange or the gateway
+    Exchange(String),
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RejectReason::UnknownInstrument => write!(f, "Unknown instrument"),
+            RejectReason::AmountBelowMinimum { amount, min_qty } => {
+                write!(f, "Amount {} is less than minimum {}", amount, min_qty)
+            }
+            RejectReason::NotionalBelowMinimum {
+                notional,
+                min_notional,
+            } => write!(
+                f,
+                "Notional {} is less than minimum {}",
+                notional, min_notional
+            ),
+            RejectReason::WouldTakeLiquidity => write!(f, "Post-only order would take liquidity"),
+            RejectReason::NotEnoughLiquidity { amount, available } => write!(
+                f,
+                "Amount {} is more than available liquidity {}",
+                amount, available
+            ),
+            RejectReason::Exchange(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+// Price and size filters of the instrument.
+// Exchange filters are combined with the config ones, the stricter value wins.
+// Zero step or minimum means no limit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderRules {
+    pub price_precision: u8,
+    pub qty_step: f64,
+    pub min_qty: f64,
+    pub min_notional: f64,
+}
+
+impl OrderRules {
+    pub fn new(instrument_info: &ExchangeInstrumentInfo, instrument: Option<&Instrument>) -> Self {
+        let (lot_size, min_order_size) = match instrument {
+            Some(instrument) => (instrument.lot_size, instrument.min_order_size),
+            None => (0., 0.),
+        };
+
+        OrderRules {
+            price_precision: instrument_info.precision,
+            qty_step: instrument_info.qty_step.max(lot_size),
+            min_qty: instrument_info.min_qty.max(min_order_size),
+            min_notional: instrument_info.min_notional,
+        }
+    }
+
+    pub fn round_price(&self, price: f64) -> f64 {
+        format!("{:.1$}", price, self.price_precision as usize)
+            .parse::<f64>()
+            .unwrap()
+    }
+
+    // Amount is rounded down, so the order never exceeds the requested size
+    pub fn round_amount(&self, amount: f64) -> f64 {
+        if self.qty_step <= 0. {
+            return amount;
+        }
+
+        let steps = (amount / self.qty_step + STEP_EPSILON).floor();
+
+        format!(
+            "{:.1$}",
+            steps * self.qty_step,
+            step_precision(self.qty_step)
+        )
+        .parse::<f64>()
+        .unwrap()
+    }
+
+    // It returns the order as it's sent to the exchange or the reason to reject it
+    pub fn normalize_limit(&self, limit_order: &LimitOrder) -> Result<LimitOrder, RejectReason> {
+        let price = self.round_price(limit_order.price);
+        let amount = self.round_amount(limit_order.amount);
+
+        self.check_amount(amount)?;
+
+        let notional = amount * price;
+
+        if notional < self.min_notional {
+            return Err(RejectReason::NotionalBelowMinimum {
+                notional,
+                min_notional: self.min_notional,
+            });
+        }
+
+        Ok(LimitOrder {
+            price,
+            amount,
+            ..limit_order.clone()
+        })
+    }
+
+    // Market order has no price, its notional is checked by the exchange
+    pub fn normalize_market(
+        &self,
+        market_order: &MarketOrder,
+    ) -> Result<MarketOrder, RejectReason> {
+        let amount = self.round_amount(market_order.amount);
+
+        self.check_amount(amount)?;
+
+        Ok(MarketOrder {
+            amount,
+            ..market_order.clone()
+        })
+    }
+
+    // Stop and limit prices are rounded as the price of the limit order
+    pub fn normalize_stop(&self, stop_order: &StopOrder) -> Result<StopOrder, RejectReason> {
+        let amount = self.round_amount(stop_order.amount);
+
+        self.check_amount(amount)?;
+
+        Ok(StopOrder {
+            amount,
+            stop_price: self.round_price(stop_order.stop_price),
+            limit_price: stop_order
+                .limit_price
+                .map(|limit_price| self.round_price(limit_price)),
+            ..stop_order.clone()
+        })
+    }
+
+    fn check_amount(&self, amount: f64) -> Result<(), RejectReason> {
+        if amount <= 0. || amount < self.min_qty {
+            return Err(RejectReason::AmountBelowMinimum {
+                amount,
+                min_qty: self.min_qty,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// Number of decimals in the step, e.g. 3 for 0.001 and 2 for 0.25
+pub fn step_precision(step: f64) -> usize {
+    if step <= 0. {
+        return 0;
+    }
+
+    // f64 is displayed as the shortest decimal which is parsed back to it, without exponent
+    step.to_string()
+        .split('.')
+        .nth(1)
+        .map_or(0, |decimals| decimals.len())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{step_precision, OrderRules, RejectReason};
+    use crate::gateway::gateway::ExchangeInstrumentInfo;
+    use crate::gateway::{Instrument, InstrumentType};
+    use crate::order_manager::{LimitOrder, MarketOrder, OrderSide, StopOrder};
+
+    fn rules() -> OrderRules {
+        OrderRules {
+            price_precision: 2,
+            qty_step: 0.001,
+            min_qty: 0.01,
+            min_notional: 10.,
+        }
+    }
+
+    #[test]
+    fn step_precisions() {
+        assert_eq!(step_precision(0.001), 3);
+        assert_eq!(step_precision(0.00001), 5);
+        assert_eq!(step_precision(0.5), 1);
+        assert_eq!(step_precision(0.25), 2);
+        assert_eq!(step_precision(0.025), 3);
+        assert_eq!(step_precision(1.), 0);
+        assert_eq!(step_precision(0.), 0);
+    }
+
+    #[test]
+    fn round_limit_order() {
+        let limit_order = LimitOrder {
+            amount: 0.12345,
+            price: 100.126,
+            ..LimitOrder::default()
+        };
+
+        let normalized = rules().normalize_limit(&limit_order).unwrap();
+
+        assert_eq!(normalized.amount, 0.123);
+        assert_eq!(normalized.price, 100.13);
+    }
+
+    #[test]
+    fn round_stop_order() {
+        let stop_order = StopOrder {
+            amount: 0.12345,
+            stop_price: 99.994,
+            limit_price: Some(99.456),
+            ..StopOrder::default()
+        };
+
+        let normalized = rules().normalize_stop(&stop_order).unwrap();
+
+        assert_eq!(normalized.amount, 0.123);
+        assert_eq!(normalized.stop_price, 99.99);
+        assert_eq!(normalized.limit_price, Some(99.46));
+    }
+
+    #[test]
+    fn amount_on_step_boundary() {
+        let rules = OrderRules {
+            qty_step: 0.1,
+            ..rules()
+        };
+
+        assert_eq!(rules.round_amount(0.3), 0.3);
+    }
+
+    #[test]
+    fn amount_of_not_decimal_step() {
+        let rules = OrderRules {
+            qty_step: 0.25,
+            ..rules()
+        };
+
+        assert_eq!(rules.round_amount(0.75), 0.75);
+        assert_eq!(rules.round_amount(0.8), 0.75);
+
+        let rules = OrderRules {
+            qty_step: 0.025,
+            ..rules
+        };
+
+        assert_eq!(rules.round_amount(0.174), 0.15);
+    }
+
+    #[test]
+    fn reject_small_orders() {
+        let small_amount = LimitOrder {
+            amount: 0.0099,
+            price: 2000.,
+            ..LimitOrder::default()
+        };
+
+        assert_eq!(
+            rules().normalize_limit(&small_amount),
+            Err(RejectReason::AmountBelowMinimum {
+                amount: 0.009,
+                min_qty: 0.01
+            })
+        );
+
+        let small_notional = LimitOrder {
+            amount: 0.05,
+            price: 100.,
+            ..LimitOrder::default()
+        };
+
+        match rules().normalize_limit(&small_notional) {
+            Err(RejectReason::NotionalBelowMinimum { min_notional, .. }) => {
+                assert_eq!(min_notional, 10.)
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let market_order = MarketOrder {
+            gateway: "Binance".to_string(),
+            symbol: "BTCUSDT".to_string(),
+            amount: 0.0005,
+            order_side: OrderSide::Sell,
+            account: None,
+        };
+
+        assert!(rules().normalize_market(&market_order).is_err());
+    }
+
+    #[test]
+    fn config_rules_are_stricter() {
+        let instrument_info = ExchangeInstrumentInfo {
+            base: "BTC".to_string(),
+            quote: "USDT".to_string(),
+            symbol: "BTCUSDT".to_string(),
+            instrument_type: InstrumentType::Spot,
+            precision: 2,
+            qty_step: 0.00001,
+            min_qty: 0.00001,
+            min_notional: 10.,
+        };
+
+        let instrument = Instrument {
+            name: "BTCUSDT".to_string(),
+            base: "BTC".to_string(),
+            quote: "USDT".to_string(),
+            lot_size: 0.001,
+            min_order_size: 0.002,
+        };
+
+        let rules = OrderRules::new(&instrument_info, Some(&instrument));
+
+        assert_eq!(rules.qty_step, 0.001);
+        assert_eq!(rules.min_qty, 0.002);
+        assert_eq!(rules.min_notional, 10.);
+
+        let rules = OrderRules::new(&instrument_info, None);
+
+        assert_eq!(rules.qty_step, 0.00001);
+    }
+}
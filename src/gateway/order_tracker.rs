@@ -0,0 +1,135 @@
+use crate::order_manager::{OrderEvent, OrderState};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedOrder {
+    pub robot_id: String,
+    pub account: String,
+    pub order_state: OrderState,
+}
+
+// States of the orders sent by the gateway until they're final.
+// Exchange events are matched by custom order id or by exchange order id,
+// e.g. Binance cancel event has the custom id of the cancel request.
+#[derive(Debug, Default)]
+pub struct OrderTracker {
+    // <Custom order id, Tracked order>
+    orders: RwLock<HashMap<String, TrackedOrder>>,
+    // <Exchange order id, Custom order id>
+    order_ids: RwLock<HashMap<u64, String>>,
+}
+
+impl OrderTracker {
+    pub fn new() -> Self {
+        OrderTracker::default()
+    }
+
+    // Order is pending until the exchange acknowledges it
+    pub fn track(&self, robot_id: &str, account: &str, order_state: OrderState) {
+        self.orders.write().unwrap().insert(
+            order_state.custom_order_id.clone(),
+            TrackedOrder {
+                robot_id: robot_id.to_string(),
+                account: account.to_string(),
+                order_state,
+            },
+        );
+    }
+
+    pub fn set_order_id(&self, custom_order_id: &str, order_id: u64) {
+        if let Some(tracked_order) = self.orders.write().unwrap().get_mut(custom_order_id) {
+            tracked_order.order_state.order_id = order_id;
+
+            self.order_ids
+                .write()
+                .unwrap()
+                .insert(order_id, custom_order_id.to_string());
+        }
+    }
+
+    // It returns the order if the event changed it, final order isn't tracked anymore
+    pub fn apply(
+        &self,
+        custom_order_id: &str,
+        order_id: u64,
+        event: &OrderEvent,
+    ) -> Option<TrackedOrder> {
+        let mut orders = self.orders.write().unwrap();
+
+        let custom_order_id = if orders.contains_key(custom_order_id) {
+            custom_order_id.to_string()
+        } else {
+            self.order_ids.read().unwrap().get(&order_id)?.clone()
+        };
+
+        let tracked_order = orders.get_mut(&custom_order_id)?;
+
+        match tracked_order.order_state.apply(event) {
+            Ok(true) => {}
+            _ => return None,
+        }
+
+        let tracked_order = tracked_order.clone();
+
+        if tracked_order.order_state.status.is_final() {
+            orders.remove(&custom_order_id);
+
+            self.order_ids
+                .write()
+                .unwrap()
+                .retain(|_, tracked_id| *tracked_id != custom_order_id);
+        }
+
+        Some(tracked_order)
+    }
+
+    pub fn get(&self, custom_order_id: &str) -> Option<TrackedOrder> {
+        self.orders.read().unwrap().get(custom_order_id).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.read().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::OrderTracker;
+    use crate::order_manager::{OrderEvent, OrderSide, OrderState, OrderStatus};
+
+    #[test]
+    fn order_found_by_exchange_id() {
+        let order_tracker = OrderTracker::new();
+
+        order_tracker.track(
+            "Robot1",
+            "Account1",
+            OrderState::new("Order1", "BTCUSDT", OrderSide::Buy, 1., 100.),
+        );
+        order_tracker.set_order_id("Order1", 42);
+
+        let tracked_order = order_tracker
+            .apply("Order1", 42, &OrderEvent::Accepted)
+            .unwrap();
+        assert_eq!(tracked_order.order_state.status, OrderStatus::New);
+
+        // Repeated acknowledge doesn't change the order
+        assert!(order_tracker
+            .apply("Order1", 42, &OrderEvent::Accepted)
+            .is_none());
+
+        let tracked_order = order_tracker
+            .apply("CancelRequest1", 42, &OrderEvent::Cancelled)
+            .unwrap();
+        assert_eq!(tracked_order.robot_id, "Robot1");
+        assert_eq!(tracked_order.order_state.status, OrderStatus::Cancelled);
+
+        // Final order isn't tracked
+        assert_eq!(order_tracker.len(), 0);
+        assert!(order_tracker
+            .apply("Order1", 42, &OrderEvent::Cancelled)
+            .is_none());
+    }
+}
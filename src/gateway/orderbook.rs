@@ -251,6 +251,32 @@ impl OrderBook {
         }
     }
 
+    // Sets size of bid level, zero size removes the level
+    pub fn update_bid(&mut self, price: f64, qty: f64) {
+        Self::update_level(&mut self.bids, price, qty);
+    }
+
+    // Sets size of ask level, zero size removes the level
+    pub fn update_ask(&mut self, price: f64, qty: f64) {
+        Self::update_level(&mut self.asks, price, qty);
+    }
+
+    // Removes all levels
+    pub fn clear(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+    }
+
+    fn update_level(side: &mut HashMap<Price, f64>, price: f64, qty: f64) {
+        let price_obj = Price::get_price_obj(price);
+
+        if qty > 0. {
+            side.insert(price_obj, qty);
+        } else {
+            side.remove(&price_obj);
+        }
+    }
+
     // Get amount by price for Bid
     fn bids_get(&self, price: f64) -> Option<&f64> {
         let price_obj = Price::get_price_obj(price);
@@ -679,6 +705,17 @@ mod tests {
         assert_eq!(order_book.bids.len(), 2);
     }
 
+    #[test]
+    fn update_level() {
+        let mut order_book = OrderBook::default();
+
+        order_book.update_bid(999., 5.);
+        order_book.update_ask(1001., 0.);
+
+        assert_eq!(order_book.bids_volume_iter().next(), Some((999., 5.)));
+        assert_eq!(order_book.asks_volume_iter().next(), Some((1002., 40.)));
+    }
+
     #[test]
     fn get_bids() {
         let order_book = OrderBook::stub();
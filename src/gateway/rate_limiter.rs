@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use strum_macros::Display;
+use tracing::debug;
+
+// Exchange endpoints which are limited separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display)]
+pub enum RequestClass {
+    Order,
+    Cancel,
+    Info,
+}
+
+// Bucket is refilled by rps tokens per second, it holds at most one second of requests
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_rate: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rps: u8, now: Instant) -> Self {
+        TokenBucket {
+            capacity: rps as f64,
+            tokens: rps as f64,
+            refill_rate: rps as f64,
+            refilled_at: now,
+        }
+    }
+
+    // Takes token or returns time to wait for the next one
+    pub fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * self.refill_rate).min(self.capacity);
+        self.refilled_at = now;
+
+        if self.tokens >= 1. {
+            self.tokens -= 1.;
+            return Ok(());
+        }
+
+        Err(Duration::from_secs_f64(
+            (1. - self.tokens) / self.refill_rate,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThrottleStats {
+    // Requests which waited for a token
+    pub throttled: u64,
+    pub total_delay: Duration,
+    pub max_delay: Duration,
+}
+
+// Token bucket per account, all request classes of the account share its limit.
+// Requests over the limit wait for their turn instead of failing.
+#[derive(Debug)]
+pub struct RateLimiter {
+    // Requests per second of every account, 0 means no limit
+    rps: Mutex<u8>,
+    // <Account, Bucket>
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+    // <(Account, Class), Stats>
+    stats: Mutex<HashMap<(String, RequestClass), ThrottleStats>>,
+    // <(Account, Class), First refused try> of the requests which don't block
+    throttled_since: Mutex<HashMap<(String, RequestClass), Instant>>,
+    // Requests waiting for a token right now
+    waiting: AtomicUsize,
+}
+
+impl RateLimiter {
+    pub fn new(rps: u8) -> Self {
+        RateLimiter {
+            rps: Mutex::new(rps),
+            buckets: Mutex::new(HashMap::new()),
+            stats: Mutex::new(HashMap::new()),
+            throttled_since: Mutex::new(HashMap::new()),
+            waiting: AtomicUsize::new(0),
+        }
+    }
+
+    // New limit is applied to all buckets from scratch
+    pub fn set_rps(&self, rps: u8) {
+        *self.rps.lock().unwrap() = rps;
+
+        self.buckets.lock().unwrap().clear();
+    }
+
+    // Blocks until request is allowed, it returns the time it waited
+    pub fn acquire(&self, account: &str, class: RequestClass) -> Duration {
+        let started_at = Instant::now();
+        let mut waiting = false;
+
+        loop {
+            match self.take_token(account, Instant::now()) {
+                Ok(_) => break,
+                Err(wait) => {
+                    if !waiting {
+                        waiting = true;
+                        self.waiting.fetch_add(1, Ordering::SeqCst);
+                    }
+
+                    debug!("[Rate Limiter] {} {} waits {:?}", account, class, wait);
+
+                    thread::sleep(wait);
+                }
+            }
+        }
+
+        if !waiting {
+            return Duration::from_secs(0);
+        }
+
+        let delay = started_at.elapsed();
+
+        self.waiting.fetch_sub(1, Ordering::SeqCst);
+
+        self.add_delay(account, class, delay);
+
+        delay
+    }
+
+    // Request which doesn't block, e.g. the queued order, it returns the time to wait on refuse.
+    // Delay is counted from the first refused try when the request is allowed.
+    pub fn try_acquire(&self, account: &str, class: RequestClass) -> Result<(), Duration> {
+        let now = Instant::now();
+        let key = (account.to_string(), class);
+
+        if let Err(wait) = self.take_token(account, now) {
+            self.throttled_since
+                .lock()
+                .unwrap()
+                .entry(key)
+                .or_insert(now);
+
+            return Err(wait);
+        }
+
+        let throttled_since = self.throttled_since.lock().unwrap().remove(&key);
+
+        if let Some(throttled_since) = throttled_since {
+            self.add_delay(
+                account,
+                class,
+                now.saturating_duration_since(throttled_since),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn take_token(&self, account: &str, now: Instant) -> Result<(), Duration> {
+        let rps = *self.rps.lock().unwrap();
+
+        if rps == 0 {
+            return Ok(());
+        }
+
+        self.buckets
+            .lock()
+            .unwrap()
+            .entry(account.to_string())
+            .or_insert_with(|| TokenBucket::new(rps, now))
+            .try_acquire(now)
+    }
+
+    fn add_delay(&self, account: &str, class: RequestClass, delay: Duration) {
+        let mut stats_lock = self.stats.lock().unwrap();
+        let stats = stats_lock
+            .entry((account.to_string(), class))
+            .or_insert_with(ThrottleStats::default);
+
+        stats.throttled += 1;
+        stats.total_delay += delay;
+        stats.max_delay = stats.max_delay.max(delay);
+    }
+
+    pub fn waiting(&self) -> usize {
+        self.waiting.load(Ordering::SeqCst)
+    }
+
+    pub fn stats(&self, account: &str, class: RequestClass) -> ThrottleStats {
+        self.stats
+            .lock()
+            .unwrap()
+            .get(&(account.to_string(), class))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // Throttle stats of all buckets in readable form
+    pub fn info(&self) -> String {
+        let stats_lock = self.stats.lock().unwrap();
+
+        let mut lines = stats_lock
+            .iter()
+            .map(|((account, class), stats)| {
+                format!(
+                    "{} {}: throttled {}, total delay {:?}, max delay {:?}",
+                    account, class, stats.throttled, stats.total_delay, stats.max_delay
+                )
+            })
+            .collect::<Vec<String>>();
+
+        lines.sort();
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{RateLimiter, RequestClass, TokenBucket};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn bucket_burst_and_refill() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2, now);
+
+        assert!(bucket.try_acquire(now).is_ok());
+        assert!(bucket.try_acquire(now).is_ok());
+
+        // Half a second for the next token
+        let wait = bucket.try_acquire(now).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(500));
+
+        assert!(bucket.try_acquire(now + Duration::from_millis(500)).is_ok());
+    }
+
+    #[test]
+    fn bucket_capacity() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2, now);
+
+        // Long silence doesn't allow more than one second burst
+        let later = now + Duration::from_secs(10);
+
+        assert!(bucket.try_acquire(later).is_ok());
+        assert!(bucket.try_acquire(later).is_ok());
+        assert!(bucket.try_acquire(later).is_err());
+    }
+
+    #[test]
+    fn throttle_over_limit() {
+        let rate_limiter = RateLimiter::new(10);
+
+        for _ in 0..10 {
+            assert_eq!(
+                rate_limiter.acquire("Account1", RequestClass::Order),
+                Duration::from_secs(0)
+            );
+        }
+
+        // Other account has its own bucket
+        assert_eq!(
+            rate_limiter.acquire("Account2", RequestClass::Order),
+            Duration::from_secs(0)
+        );
+
+        // Other class shares the bucket of the account
+        let delay = rate_limiter.acquire("Account1", RequestClass::Cancel);
+        assert!(delay >= Duration::from_millis(50));
+
+        let stats = rate_limiter.stats("Account1", RequestClass::Cancel);
+        assert_eq!(stats.throttled, 1);
+        assert_eq!(stats.max_delay, delay);
+        assert_eq!(
+            rate_limiter
+                .stats("Account1", RequestClass::Order)
+                .throttled,
+            0
+        );
+
+        assert_eq!(rate_limiter.waiting(), 0);
+    }
+
+    #[test]
+    fn try_acquire_over_limit() {
+        let rate_limiter = RateLimiter::new(10);
+
+        for _ in 0..10 {
+            assert!(rate_limiter
+                .try_acquire("Account1", RequestClass::Order)
+                .is_ok());
+        }
+
+        // Refused request doesn't wait
+        let wait = rate_limiter
+            .try_acquire("Account1", RequestClass::Info)
+            .unwrap_err();
+        assert!(wait <= Duration::from_millis(100));
+        assert!(rate_limiter
+            .try_acquire("Account2", RequestClass::Order)
+            .is_ok());
+
+        std::thread::sleep(wait + Duration::from_millis(10));
+
+        // Delay is counted from the first refused try
+        assert!(rate_limiter
+            .try_acquire("Account1", RequestClass::Info)
+            .is_ok());
+
+        let stats = rate_limiter.stats("Account1", RequestClass::Info);
+        assert_eq!(stats.throttled, 1);
+        assert!(stats.max_delay >= wait);
+        assert_eq!(rate_limiter.waiting(), 0);
+    }
+
+    #[test]
+    fn no_limit() {
+        let rate_limiter = RateLimiter::new(0);
+
+        for _ in 0..100 {
+            assert_eq!(
+                rate_limiter.acquire("Account1", RequestClass::Info),
+                Duration::from_secs(0)
+            );
+        }
+    }
+}
@@ -0,0 +1,426 @@
+use data_encoding::BASE64;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{aead, digest, pbkdf2};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::atomic::{compiler_fence, Ordering};
+use std::{env, fmt, ptr};
+use tracing::{error, warn};
+
+pub const SECRETS_FILE: &str = "conf/secrets.toml";
+pub const KEYSTORE_FILE: &str = "conf/keystore.toml";
+
+// Passphrase of the keystore is never kept in the config
+pub const KEYSTORE_PASSPHRASE_ENV: &str = "MX_KEYSTORE_PASSPHRASE";
+
+const KEYSTORE_ITERATIONS: u32 = 100_000;
+const KEYSTORE_SALT_LEN: usize = 16;
+const KEYSTORE_KEY_LEN: usize = 32;
+
+// Sensitive string, e.g. API key. It's redacted in Debug and Display, its memory is zeroed on drop.
+#[derive(Clone, Default, PartialEq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: String) -> Self {
+        Secret(value)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Secret(value.to_string())
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret(***)")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "***")
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        // Zero bytes are still valid UTF-8, volatile writes aren't optimized out
+        unsafe {
+            for byte in self.0.as_bytes_mut() {
+                ptr::write_volatile(byte, 0);
+            }
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Secret)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+fn zero(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        unsafe { ptr::write_volatile(byte, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+// Secrets file is TOML of name = value, it must be readable by the owner only
+#[cfg(unix)]
+fn check_permissions(path: &str) -> Result<(), &'static str> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(path)
+        .map_err(|_| "Can't read secrets file")?
+        .permissions()
+        .mode();
+
+    if mode & 0o077 != 0 {
+        error!(
+            "[Secrets] {} has {:o} permissions, it must be 600",
+            path,
+            mode & 0o777
+        );
+        return Err("Secrets file is accessible by other users");
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_path: &str) -> Result<(), &'static str> {
+    Ok(())
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct KeystoreEntry {
+    pub iterations: u32,
+    // Base64
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+// Secrets encrypted by AES-256-GCM with the key derived from the passphrase by PBKDF2
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct Keystore {
+    // <Name, Entry>
+    entries: HashMap<String, KeystoreEntry>,
+}
+
+impl Keystore {
+    // Missing keystore is empty, e.g. before the first secret is added
+    pub fn load(path: &str) -> Result<Self, &'static str> {
+        match fs::read_to_string(path) {
+            Ok(keystore) => toml::from_str(&keystore).map_err(|e| {
+                error!("[Secrets] Keystore {} has an error: {}", path, e);
+                "Keystore file error"
+            }),
+            Err(_) => Ok(Keystore::default()),
+        }
+    }
+
+    // Keystore is written readable by the owner only
+    pub fn save(&self, path: &str) -> Result<(), &'static str> {
+        let keystore = toml::to_string(self).map_err(|_| "Can't serialize keystore")?;
+
+        let mut options = OpenOptions::new();
+        options.create(true).write(true).truncate(true);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        options
+            .open(path)
+            .and_then(|mut file| file.write_all(keystore.as_bytes()))
+            .map_err(|e| {
+                error!("[Secrets] Can't write keystore {}: {}", path, e);
+                "Can't write keystore"
+            })
+    }
+
+    fn derive_key(passphrase: &Secret, salt: &[u8], iterations: u32) -> [u8; KEYSTORE_KEY_LEN] {
+        let mut key = [0; KEYSTORE_KEY_LEN];
+        pbkdf2::derive(
+            &digest::SHA256,
+            iterations,
+            salt,
+            passphrase.expose().as_bytes(),
+            &mut key,
+        );
+        key
+    }
+
+    pub fn insert(
+        &mut self,
+        name: &str,
+        secret: &Secret,
+        passphrase: &Secret,
+    ) -> Result<(), &'static str> {
+        let rng = SystemRandom::new();
+        let mut salt = [0; KEYSTORE_SALT_LEN];
+        let mut nonce = vec![0; aead::AES_256_GCM.nonce_len()];
+        rng.fill(&mut salt).map_err(|_| "Random error")?;
+        rng.fill(&mut nonce).map_err(|_| "Random error")?;
+
+        let mut key = Self::derive_key(passphrase, &salt, KEYSTORE_ITERATIONS);
+        let sealing_key = aead::SealingKey::new(&aead::AES_256_GCM, &key);
+        zero(&mut key);
+        let sealing_key = sealing_key.map_err(|_| "Keystore key error")?;
+
+        let tag_len = aead::AES_256_GCM.tag_len();
+        let mut in_out = secret.expose().as_bytes().to_vec();
+        in_out.extend(vec![0; tag_len]);
+
+        let sealed =
+            aead::seal_in_place(&sealing_key, &nonce, name.as_bytes(), &mut in_out, tag_len);
+
+        let ciphertext = match sealed {
+            Ok(len) => BASE64.encode(&in_out[..len]),
+            Err(_) => {
+                zero(&mut in_out);
+                return Err("Can't encrypt secret");
+            }
+        };
+
+        self.entries.insert(
+            name.to_string(),
+            KeystoreEntry {
+                iterations: KEYSTORE_ITERATIONS,
+                salt: BASE64.encode(&salt),
+                nonce: BASE64.encode(&nonce),
+                ciphertext,
+            },
+        );
+
+        Ok(())
+    }
+
+    // Name is authenticated with the secret, so entries can't be swapped
+    pub fn get(&self, name: &str, passphrase: &Secret) -> Result<Secret, &'static str> {
+        let entry = self.entries.get(name).ok_or("No secret in keystore")?;
+
+        let decode = |value: &str| {
+            BASE64
+                .decode(value.as_bytes())
+                .map_err(|_| "Keystore entry isn't base64")
+        };
+        let salt = decode(&entry.salt)?;
+        let nonce = decode(&entry.nonce)?;
+        let mut in_out = decode(&entry.ciphertext)?;
+
+        let mut key = Self::derive_key(passphrase, &salt, entry.iterations);
+        let opening_key = aead::OpeningKey::new(&aead::AES_256_GCM, &key);
+        zero(&mut key);
+        let opening_key = opening_key.map_err(|_| "Keystore key error")?;
+
+        let len = aead::open_in_place(&opening_key, &nonce, name.as_bytes(), 0, &mut in_out)
+            .map_err(|_| "Wrong keystore passphrase or damaged secret")?
+            .len();
+        in_out.truncate(len);
+
+        String::from_utf8(in_out).map(Secret).map_err(|e| {
+            zero(&mut e.into_bytes());
+            "Keystore secret isn't UTF-8"
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct SecretsConfig {
+    // SECRETS_FILE if it's missing
+    pub file: Option<String>,
+    // KEYSTORE_FILE if it's missing
+    pub keystore: Option<String>,
+}
+
+// Resolves secrets of the gateway config by their references:
+// env:NAME - environment variable,
+// file:NAME - value of the secrets file,
+// keystore:NAME - secret of the keystore, its passphrase is in MX_KEYSTORE_PASSPHRASE.
+// Other values are plain secrets of the config.
+#[derive(Debug, Clone)]
+pub struct SecretStore {
+    file: String,
+    keystore: String,
+}
+
+impl Default for SecretStore {
+    fn default() -> Self {
+        SecretStore {
+            file: SECRETS_FILE.to_string(),
+            keystore: KEYSTORE_FILE.to_string(),
+        }
+    }
+}
+
+impl SecretStore {
+    pub fn from_config(secrets_config: Option<&SecretsConfig>) -> Self {
+        match secrets_config {
+            Some(secrets_config) => SecretStore {
+                file: secrets_config
+                    .file
+                    .clone()
+                    .unwrap_or_else(|| SECRETS_FILE.to_string()),
+                keystore: secrets_config
+                    .keystore
+                    .clone()
+                    .unwrap_or_else(|| KEYSTORE_FILE.to_string()),
+            },
+            None => SecretStore::default(),
+        }
+    }
+
+    // Empty value is left empty, e.g. for FIX sessions without logon
+    pub fn resolve(&self, value: &Secret) -> Result<Secret, &'static str> {
+        let value = value.expose();
+
+        if let Some(name) = value.strip_prefix("env:") {
+            env::var(name).map(Secret).map_err(|_| {
+                error!("[Secrets] No {} environment variable", name);
+                "No secret in environment"
+            })
+        } else if let Some(name) = value.strip_prefix("file:") {
+            self.file_secret(name)
+        } else if let Some(name) = value.strip_prefix("keystore:") {
+            self.keystore_secret(name)
+        } else {
+            if !value.is_empty() {
+                warn!("[Secrets] Plain secret in the gateway config, use env, file or keystore");
+            }
+            Ok(Secret::from(value))
+        }
+    }
+
+    fn file_secret(&self, name: &str) -> Result<Secret, &'static str> {
+        check_permissions(&self.file)?;
+
+        let contents = Secret(fs::read_to_string(&self.file).map_err(|_| "No secrets file")?);
+
+        let secrets: HashMap<String, Secret> =
+            toml::from_str(contents.expose()).map_err(|_| "Secrets file error")?;
+
+        secrets.get(name).cloned().ok_or_else(|| {
+            error!("[Secrets] No {} in {}", name, self.file);
+            "No secret in secrets file"
+        })
+    }
+
+    fn keystore_secret(&self, name: &str) -> Result<Secret, &'static str> {
+        let passphrase = env::var(KEYSTORE_PASSPHRASE_ENV)
+            .map(Secret)
+            .map_err(|_| "No keystore passphrase")?;
+
+        Keystore::load(&self.keystore)?
+            .get(name, &passphrase)
+            .map_err(|e| {
+                error!("[Secrets] Can't get {} from keystore: {}", name, e);
+                e
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn redacted_secret() {
+        let secret = Secret::from("SECRET_KEY");
+
+        assert_eq!(format!("{:?}", secret), "Secret(***)");
+        assert_eq!(secret.to_string(), "***");
+        assert_eq!(secret.expose(), "SECRET_KEY");
+    }
+
+    #[test]
+    fn keystore_secret() {
+        let mut keystore = Keystore::default();
+        let passphrase = Secret::from("passphrase");
+
+        keystore
+            .insert("binance_secret", &Secret::from("SECRET_KEY"), &passphrase)
+            .unwrap();
+
+        assert_eq!(
+            keystore.get("binance_secret", &passphrase).unwrap(),
+            Secret::from("SECRET_KEY")
+        );
+        assert!(keystore
+            .get("binance_secret", &Secret::from("wrong"))
+            .is_err());
+        assert!(keystore.get("huobi_secret", &passphrase).is_err());
+    }
+
+    #[test]
+    fn resolve_references() {
+        let dir = env::temp_dir().join(format!("mx_secrets_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let file = dir.join("secrets.toml");
+        fs::write(&file, "binance_api_key = \"API_KEY\"\n").unwrap();
+
+        let secret_store = SecretStore::from_config(Some(&SecretsConfig {
+            file: Some(file.to_str().unwrap().to_string()),
+            keystore: Some(dir.join("keystore.toml").to_str().unwrap().to_string()),
+        }));
+
+        // Secrets file is readable by others
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(secret_store
+            .resolve(&Secret::from("file:binance_api_key"))
+            .is_err());
+
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(
+            secret_store
+                .resolve(&Secret::from("file:binance_api_key"))
+                .unwrap(),
+            Secret::from("API_KEY")
+        );
+
+        env::set_var("MX_TEST_SECRET_KEY", "SECRET_KEY");
+        assert_eq!(
+            secret_store
+                .resolve(&Secret::from("env:MX_TEST_SECRET_KEY"))
+                .unwrap(),
+            Secret::from("SECRET_KEY")
+        );
+        assert!(secret_store
+            .resolve(&Secret::from("env:MX_TEST_MISSING_KEY"))
+            .is_err());
+
+        assert_eq!(
+            secret_store.resolve(&Secret::from("PLAIN_KEY")).unwrap(),
+            Secret::from("PLAIN_KEY")
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
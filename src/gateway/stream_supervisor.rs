@@ -0,0 +1,223 @@
+use super::exchange::ExchangeApiResult;
+use std::thread;
+use std::time::{Duration, Instant};
+use strum_macros::Display;
+use tracing::{info, warn};
+
+// First reconnect delay, it's doubled on every failed attempt
+const STREAM_BACKOFF_INITIAL: Duration = Duration::from_millis(500);
+
+const STREAM_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+// Stream that worked longer than that is reconnected with the initial delay
+const STREAM_STABLE_TIME: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display)]
+pub enum StreamKind {
+    Depth,
+    User,
+    Trade,
+}
+
+#[derive(Debug, Clone, PartialEq, Display)]
+pub enum ConnectionStatus {
+    Connecting,
+    Connected,
+    Disconnected(String),
+}
+
+// Exponential backoff between reconnects
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Backoff {
+            initial,
+            max,
+            current: initial,
+        }
+    }
+
+    // It returns delay before the next attempt
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+
+        self.current = std::cmp::min(self.current * 2, self.max);
+
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.current = self.initial;
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff::new(STREAM_BACKOFF_INITIAL, STREAM_BACKOFF_MAX)
+    }
+}
+
+// Keeps exchange stream alive: broken stream is connected again after backoff delay.
+// Stream is connected and subscribed from scratch on every attempt.
+pub struct StreamSupervisor {
+    stream: StreamKind,
+    backoff: Backoff,
+}
+
+impl StreamSupervisor {
+    pub fn new(stream: StreamKind) -> Self {
+        StreamSupervisor {
+            stream,
+            backoff: Backoff::default(),
+        }
+    }
+
+    pub fn with_backoff(stream: StreamKind, backoff: Backoff) -> Self {
+        StreamSupervisor { stream, backoff }
+    }
+
+    // Runs stream until it's closed without error or supervisor is not running anymore.
+    // Stream gets callback to report that connection is alive, e.g. on the first message.
+    pub fn run(
+        &mut self,
+        stream: &mut dyn FnMut(&mut dyn FnMut()) -> ExchangeApiResult<()>,
+        health: &mut dyn FnMut(StreamKind, ConnectionStatus),
+        running: &dyn Fn() -> bool,
+    ) {
+        let stream_kind = self.stream;
+
+        while running() {
+            health(stream_kind, ConnectionStatus::Connecting);
+
+            let started_at = Instant::now();
+            let mut connected = false;
+
+            let result = stream(&mut || {
+                if !connected {
+                    connected = true;
+
+                    health(stream_kind, ConnectionStatus::Connected);
+                }
+            });
+
+            match result {
+                Ok(_) => {
+                    info!("{} stream was closed", stream_kind);
+                    break;
+                }
+                Err(error) => {
+                    health(
+                        stream_kind,
+                        ConnectionStatus::Disconnected(error.to_string()),
+                    );
+
+                    if started_at.elapsed() >= STREAM_STABLE_TIME {
+                        self.backoff.reset();
+                    }
+
+                    let delay = self.backoff.next_delay();
+
+                    warn!(
+                        "{} stream error: {}. Reconnect in {:?}",
+                        stream_kind, error, delay
+                    );
+
+                    thread::sleep(delay);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Backoff, ConnectionStatus, StreamKind, StreamSupervisor};
+    use std::time::Duration;
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let mut backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(500));
+
+        let delays = (0..5)
+            .map(|_| backoff.next_delay().as_millis())
+            .collect::<Vec<u128>>();
+
+        assert_eq!(delays, vec![100, 200, 400, 500, 500]);
+
+        backoff.reset();
+
+        assert_eq!(backoff.next_delay(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn reconnect_broken_stream() {
+        let mut supervisor = StreamSupervisor::with_backoff(
+            StreamKind::Depth,
+            Backoff::new(Duration::from_millis(1), Duration::from_millis(2)),
+        );
+
+        let mut attempts = 0;
+        let mut statuses = Vec::new();
+
+        supervisor.run(
+            &mut |connected| {
+                attempts += 1;
+
+                match attempts {
+                    // Can't connect
+                    1 => Err("Can't connect"),
+                    // Connection is lost after the first message
+                    2 => {
+                        connected();
+                        Err("Error reading message")
+                    }
+                    _ => {
+                        connected();
+                        Ok(())
+                    }
+                }
+            },
+            &mut |_stream, status| statuses.push(status),
+            &|| true,
+        );
+
+        assert_eq!(attempts, 3);
+        assert_eq!(
+            statuses,
+            vec![
+                ConnectionStatus::Connecting,
+                ConnectionStatus::Disconnected("Can't connect".to_string()),
+                ConnectionStatus::Connecting,
+                ConnectionStatus::Connected,
+                ConnectionStatus::Disconnected("Error reading message".to_string()),
+                ConnectionStatus::Connecting,
+                ConnectionStatus::Connected,
+            ]
+        );
+    }
+
+    #[test]
+    fn stop_reconnecting() {
+        let mut supervisor = StreamSupervisor::new(StreamKind::User);
+
+        let mut attempts = 0;
+
+        supervisor.run(
+            &mut |_connected| {
+                attempts += 1;
+                Err("Error")
+            },
+            &mut |_stream, _status| {},
+            &|| false,
+        );
+
+        assert_eq!(attempts, 0);
+    }
+}
@@ -1,13 +1,16 @@
 mod error;
 mod models;
 mod order_manager;
+mod order_state;
 mod orderbook;
 
 pub mod utils;
 
 pub use order_manager::OrderManager;
+pub use order_state::{OrderEvent, OrderState, OrderStatus};
 
 pub use models::{
-    ActiveOrderMsg, CancelOrder, LimitOrder, MarketOrder, Order, OrderContainer, OrderMetaInfo,
-    OrderMsg, OrderParams, OrderRequestType, OrderSide,
+    AccountOpenOrders, ActiveOrderMsg, CancelOrder, LimitOrder, MarketOrder, Order, OrderContainer,
+    OrderMetaInfo, OrderMsg, OrderParams, OrderRequestType, OrderSide, ReplaceOrder, StopOrder,
+    TimeInForce,
 };
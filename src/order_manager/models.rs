@@ -1,4 +1,5 @@
-use crate::context_manager::{ActiveOrder, FilledOrder};
+use crate::context_manager::{ActiveOrder, FilledOrder, OrderStateMsg};
+use crate::gateway::{self, ReconcilePolicy};
 use crate::robot::strategy::StrategyParams;
 use serde;
 use serde::{Deserialize, Serialize};
@@ -84,6 +85,48 @@ pub enum Order {
     LimitOrder(LimitOrder),
     MarketOrder(MarketOrder),
     CancelOrder(CancelOrder),
+    StopOrder(StopOrder),
+    ReplaceOrder(ReplaceOrder),
+}
+
+impl Order {
+    // Replaces the order symbols, e.g. canonical instrument ids by the exchange symbols
+    pub fn map_symbols<F>(&mut self, map: F)
+    where
+        F: Fn(&str) -> String,
+    {
+        match self {
+            Order::LimitOrder(limit_order) => limit_order.symbol = map(&limit_order.symbol),
+            Order::MarketOrder(market_order) => market_order.symbol = map(&market_order.symbol),
+            Order::CancelOrder(cancel_order) => cancel_order.symbol = map(&cancel_order.symbol),
+            Order::StopOrder(stop_order) => stop_order.symbol = map(&stop_order.symbol),
+            Order::ReplaceOrder(replace_order) => {
+                replace_order.cancel_order.symbol = map(&replace_order.cancel_order.symbol);
+                replace_order.limit_order.symbol = map(&replace_order.limit_order.symbol);
+            }
+        }
+    }
+}
+
+// How long the limit order rests on the exchange
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimeInForce {
+    // Good till cancel
+    Gtc,
+    // Order is canceled instead of taking liquidity
+    PostOnly,
+    // Immediate or cancel, unfilled part is canceled at once
+    Ioc,
+    // Fill or kill, order is filled completely at once or canceled
+    Fok,
+    // Good till date, expiration time in unix milliseconds
+    Gtd(u64),
+}
+
+impl Default for TimeInForce {
+    fn default() -> Self {
+        TimeInForce::Gtc
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -94,6 +137,9 @@ pub struct LimitOrder {
     pub price: f64,
     pub order_side: OrderSide,
     pub custom_order_id: String,
+    // Gateway account to send the order from, None means the default account
+    pub account: Option<String>,
+    pub time_in_force: TimeInForce,
 }
 
 impl Default for LimitOrder {
@@ -105,6 +151,8 @@ impl Default for LimitOrder {
             price: 10.,
             order_side: OrderSide::Buy,
             custom_order_id: "Custom_Order_ID".to_string(),
+            account: None,
+            time_in_force: TimeInForce::Gtc,
         }
     }
 }
@@ -115,6 +163,74 @@ pub struct MarketOrder {
     pub symbol: String,
     pub amount: f64,
     pub order_side: OrderSide,
+    // Gateway account to send the order from, None means the default account
+    pub account: Option<String>,
+}
+
+// Order which is sent when the market reaches the stop price:
+// buy stop when the best ask rises to it, sell stop when the best bid falls to it.
+// It's sent as market order without limit price and as limit order with it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StopOrder {
+    pub gateway: String,
+    pub symbol: String,
+    pub amount: f64,
+    pub stop_price: f64,
+    pub limit_price: Option<f64>,
+    pub order_side: OrderSide,
+    pub custom_order_id: String,
+    // Gateway account to send the order from, None means the default account
+    pub account: Option<String>,
+}
+
+impl Default for StopOrder {
+    fn default() -> Self {
+        StopOrder {
+            gateway: "Binance".to_string(),
+            symbol: "BTCUSDT".to_string(),
+            amount: 1.,
+            stop_price: 10.,
+            limit_price: None,
+            order_side: OrderSide::Sell,
+            custom_order_id: "Custom_Order_ID".to_string(),
+            account: None,
+        }
+    }
+}
+
+impl StopOrder {
+    // Order which replaces the stop order when it's triggered
+    pub fn triggered_order(&self) -> Order {
+        match self.limit_price {
+            Some(price) => Order::LimitOrder(LimitOrder {
+                gateway: self.gateway.clone(),
+                symbol: self.symbol.clone(),
+                amount: self.amount,
+                price,
+                order_side: self.order_side.clone(),
+                custom_order_id: self.custom_order_id.clone(),
+                account: self.account.clone(),
+                time_in_force: TimeInForce::Gtc,
+            }),
+            None => Order::MarketOrder(MarketOrder {
+                gateway: self.gateway.clone(),
+                symbol: self.symbol.clone(),
+                amount: self.amount,
+                order_side: self.order_side.clone(),
+                account: self.account.clone(),
+            }),
+        }
+    }
+}
+
+// Open order is amended to the new price and amount where the exchange supports it,
+// otherwise it's canceled and the new order is sent after that
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplaceOrder {
+    // Order which is replaced
+    pub cancel_order: CancelOrder,
+    // Order which replaces it
+    pub limit_order: LimitOrder,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -129,6 +245,8 @@ pub struct CancelOrder {
     pub amount: f64,
     pub order_side: OrderSide,
     pub custom_order_id: String,
+    // Account the canceled order was sent from
+    pub account: Option<String>,
 }
 
 impl Default for CancelOrder {
@@ -142,6 +260,7 @@ impl Default for CancelOrder {
             price: 1.,
             order_side: OrderSide::Buy,
             custom_order_id: "Custom Order ID".to_string(),
+            account: None,
         }
     }
 }
@@ -173,6 +292,8 @@ impl Default for Order {
             price: 10.,
             order_side: OrderSide::Buy,
             custom_order_id: "Custom Order ID".to_string(),
+            account: None,
+            time_in_force: TimeInForce::Gtc,
         })
     }
 }
@@ -180,6 +301,17 @@ impl Default for Order {
 pub enum ActiveOrderMsg {
     ActiveStateOrder(ActiveOrder),
     FilledOrder(FilledOrder),
+    OpenOrders(AccountOpenOrders),
+    OrderState(OrderStateMsg),
+}
+
+// Orders which are open on the exchange account when the gateway starts
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountOpenOrders {
+    pub gateway: String,
+    pub account: String,
+    pub policy: ReconcilePolicy,
+    pub orders: Vec<gateway::OpenOrder>,
 }
 
 enum Process {
@@ -1,18 +1,25 @@
 use super::models::{
-    ActiveOrderMsg, CancelOrder, Order, OrderContainer, OrderManagerState, OrderMsg,
+    AccountOpenOrders, ActiveOrderMsg, CancelOrder, LimitOrder, Order, OrderContainer,
+    OrderManagerState, OrderMsg, ReplaceOrder,
 };
 use crate::context_manager::ActiveOrder;
-use crate::gateway::Gateway;
+use crate::gateway::{self, Gateway, ReconcilePolicy};
+use crate::robot::strategy::StrategyParams;
 use crossbeam::channel::{bounded, Receiver, Sender};
 use std::collections::HashMap;
 use std::io::{Read, Write};
+use std::path::Path;
 use std::sync::RwLock;
 use std::thread;
-use std::{collections::hash_map::Entry, fs::File};
+use std::time::Instant;
+use std::{collections::hash_map::Entry, fs, fs::File};
 use tracing::{debug, error, info, warn};
 
 const SENT_ORDERS_FILE_PATH: &str = "./data/active_orders.bin";
 
+// Robot id of cancel orders for the open orders nobody knows
+const RECONCILIATION_ROBOT_ID: &str = "Reconciliation";
+
 #[derive(Debug)]
 pub struct OrderManager {
     // Received orders from Robots
@@ -27,6 +34,10 @@ pub struct OrderManager {
     // <Robot name, [open orders]>
     pub(super) active_orders: RwLock<HashMap<String, Vec<ActiveOrder>>>,
 
+    // Sent orders of the previous run loaded from persistent storage
+    // <Custom order id, order container>
+    pub(super) recovered_orders: RwLock<HashMap<String, OrderContainer>>,
+
     // orders which will be send to gateways
     // orders_to_place: RwLock<HashMap<String, Vec<LimitOrder>>>,
 
@@ -70,6 +81,8 @@ impl<'a> OrderManager {
 
             active_orders: RwLock::new(HashMap::new()),
 
+            recovered_orders: RwLock::new(HashMap::new()),
+
             // orders_to_send: RwLock::new(Vec::new()),
             ask_stop_channel: bounded(0),
             send_stop_channel: bounded(0),
@@ -131,19 +144,19 @@ impl<'a> OrderManager {
                         }
                     });
 
-                    // Runs thread for receiving active orders from the exchanges
-                    // thread::spawn(move || {
-                    //     info!("[Order Manager] Starting receive active orders from exchanges");
+                    // Runs thread for receiving active and open orders from the exchanges
+                    thread::spawn(move || {
+                        info!("[Order Manager] Starting receive active orders from exchanges");
 
-                    //     loop {
-                    //         self.receive_active_orders().unwrap();
+                        loop {
+                            self.receive_active_orders_msg().unwrap();
 
-                    //         match self.state().unwrap() {
-                    //             OrderManagerState::Started => {}
-                    //             OrderManagerState::Stopped => break,
-                    //         }
-                    //     }
-                    // });
+                            match self.state().unwrap() {
+                                OrderManagerState::Started => {}
+                                OrderManagerState::Stopped => break,
+                            }
+                        }
+                    });
 
                     Ok(())
                 }
@@ -218,18 +231,15 @@ impl<'a> OrderManager {
                     let order_containers = &wanted_orders[robot_id];
 
                     for order_container in order_containers {
-                        let mut orders_to_send = Vec::new();
-
                         // Check if an order is open.
                         // Open order is an order with the same parameters that jwas already sent to Gateway
-                        // We should cancel these orders
-                        let orders_to_cancel = self.check_order(&order_container);
-
-                        // Add all cancel orders for previous orders
-                        orders_to_send.extend(orders_to_cancel);
+                        // We should replace these orders
+                        let orders_to_send = self.check_order(&order_container);
 
-                        // Add new current order
-                        orders_to_send.push(order_container.clone());
+                        // Unchanged order isn't sent again
+                        if orders_to_send.is_empty() {
+                            continue;
+                        }
 
                         let gateway = Gateway::extract_gateway_name(&Self::get_gateway(
                             orders_to_send.first().unwrap(),
@@ -289,18 +299,29 @@ impl<'a> OrderManager {
     fn save_sent_orders(&self, gateway: &str, orders: Vec<OrderContainer>) {
         match self.sent_orders.write() {
             Ok(mut sent_orders) => {
-                // Save limit orders only
+                // Save limit orders only, replace order is saved as its new limit order
                 let limit_orders = orders
                     .iter()
-                    .filter(|order_container| match order_container.order {
-                        Order::LimitOrder(_) => true,
-                        Order::MarketOrder(_) => false,
-                        Order::CancelOrder(_) => false,
+                    .filter_map(|order_container| match &order_container.order {
+                        Order::LimitOrder(_) => Some(order_container.clone()),
+                        Order::ReplaceOrder(replace_order) => Some(OrderContainer {
+                            order: Order::LimitOrder(replace_order.limit_order.clone()),
+                            ..order_container.clone()
+                        }),
+                        Order::MarketOrder(_) => None,
+                        Order::CancelOrder(_) => None,
+                        Order::StopOrder(_) => None,
                     })
-                    .cloned()
                     .collect::<Vec<_>>();
 
-                sent_orders.insert(gateway.to_string(), limit_orders);
+                // Replaced and canceled orders were already removed on check
+                sent_orders
+                    .entry(gateway.to_string())
+                    .or_default()
+                    .extend(limit_orders);
+
+                // Sent orders are recovered from storage if the platform isn't stopped properly
+                Self::store_sent_orders(&sent_orders);
             }
             Err(e) => {
                 error!("Poison error {}", e)
@@ -308,13 +329,28 @@ impl<'a> OrderManager {
         }
     }
 
-    // Save sent orders to persist storage before stopping platform
-    fn store_sent_orders(orders: HashMap<String, Vec<OrderContainer>>) {
+    // Save sent orders to persist storage
+    fn store_sent_orders(orders: &HashMap<String, Vec<OrderContainer>>) {
+        if let Some(storage_dir) = Path::new(SENT_ORDERS_FILE_PATH).parent() {
+            if let Err(error) = fs::create_dir_all(storage_dir) {
+                error!("Can't create storage directory for sent orders: {}", error);
+                return;
+            }
+        }
+
+        let serialized_orders = match bincode::serialize(orders) {
+            Ok(serialized_orders) => serialized_orders,
+            Err(error) => {
+                error!("Can't serialize sent orders: {}", error);
+                return;
+            }
+        };
+
         match File::create(SENT_ORDERS_FILE_PATH) {
             Ok(mut active_orders) => {
-                let serialized_orders = bincode::serialize(&orders).unwrap();
-
-                active_orders.write_all(&serialized_orders).unwrap();
+                if let Err(error) = active_orders.write_all(&serialized_orders) {
+                    error!("Can't store sent orders: {}", error);
+                }
             }
             Err(error) => {
                 error!("Can't create storage for active orders: {}", error);
@@ -322,23 +358,30 @@ impl<'a> OrderManager {
         }
     }
 
-    // Load sent orders from persist storage after starting platform for cancel them
+    // Load sent orders from persist storage after starting platform for reconcile them
     fn load_send_orders() -> HashMap<String, Vec<OrderContainer>> {
-        let mut orders = HashMap::new();
+        let mut buf = Vec::new();
 
         match File::open(SENT_ORDERS_FILE_PATH) {
             Ok(mut sent_orders_file) => {
-                let mut buf = Vec::new();
-
-                sent_orders_file.read_to_end(&mut buf).unwrap();
-
-                orders = bincode::deserialize(&buf).unwrap();
+                if let Err(error) = sent_orders_file.read_to_end(&mut buf) {
+                    warn!("Can't read sent orders: {}", error);
+                    return HashMap::new();
+                }
             }
             Err(error) => {
-                error!("Can't open file: {}", error);
+                warn!("Can't open file: {}", error);
+                return HashMap::new();
+            }
+        }
+
+        match bincode::deserialize(&buf) {
+            Ok(orders) => orders,
+            Err(error) => {
+                warn!("Sent orders file is empty or damaged: {}", error);
+                HashMap::new()
             }
         }
-        orders
     }
 
     // Order Manager should cancel all open orders before stop platform
@@ -380,9 +423,27 @@ impl<'a> OrderManager {
     fn on_start(&self) {
         info!("[Order Manager] Do tasks on start Order Manager");
 
-        // let open_orders = Self::load_send_orders();
+        // Orders of the previous run are reconciled when gateways send their open orders
+        let recovered_orders = Self::load_send_orders()
+            .into_iter()
+            .flat_map(|(_gateway, orders)| orders)
+            .filter_map(|order_container| match &order_container.order {
+                Order::LimitOrder(limit_order) => {
+                    Some((limit_order.custom_order_id.clone(), order_container.clone()))
+                }
+                _ => None,
+            })
+            .collect::<HashMap<String, OrderContainer>>();
 
-        // self.sent_orders();
+        info!(
+            "[Order Manager] Recovered {} sent orders",
+            recovered_orders.len()
+        );
+
+        match self.recovered_orders.write() {
+            Ok(mut recovered_orders_lock) => *recovered_orders_lock = recovered_orders,
+            Err(e) => error!("Poison error {}", e),
+        }
     }
 
     // Do tasks on finish Order Manager
@@ -416,6 +477,7 @@ impl<'a> OrderManager {
                     amount: limit_order.amount,
                     order_side: limit_order.order_side.clone(),
                     custom_order_id: limit_order.custom_order_id.clone(),
+                    account: limit_order.account.clone(),
                 }),
                 metainfo: order_container.metainfo.clone(),
                 created_at: order_container.created_at,
@@ -425,6 +487,41 @@ impl<'a> OrderManager {
         }
     }
 
+    // Replace order of the sent limit order by the new one
+    fn convert_limit_to_replace(
+        sent_order: &OrderContainer,
+        order_container: &OrderContainer,
+    ) -> Option<OrderContainer> {
+        let cancel_order = match Self::convert_limit_to_cancel(sent_order)?.order {
+            Order::CancelOrder(cancel_order) => cancel_order,
+            _ => return None,
+        };
+
+        match &order_container.order {
+            Order::LimitOrder(limit_order) => Some(OrderContainer {
+                order: Order::ReplaceOrder(ReplaceOrder {
+                    cancel_order,
+                    limit_order: limit_order.clone(),
+                }),
+                ..order_container.clone()
+            }),
+            _ => None,
+        }
+    }
+
+    // Sent order with the same price and amount doesn't need the request
+    fn is_unchanged(sent_order: &OrderContainer, limit_order: &LimitOrder) -> bool {
+        match &sent_order.order {
+            Order::LimitOrder(sent_limit_order) => {
+                sent_limit_order.price == limit_order.price
+                    && sent_limit_order.amount == limit_order.amount
+                    && sent_limit_order.time_in_force == limit_order.time_in_force
+                    && sent_limit_order.account == limit_order.account
+            }
+            _ => false,
+        }
+    }
+
     // Group orders by Gateways from Robot
     fn group_by_gateways(orders: Vec<OrderContainer>) -> HashMap<String, Vec<OrderContainer>> {
         let mut orders_by_gateways = HashMap::new();
@@ -444,6 +541,7 @@ impl<'a> OrderManager {
     // Receives active orders from Gateways
     fn receive_active_orders_msg(&self) -> Result<(), &'static str> {
         match self.active_order_msg_receiver.try_recv() {
+            Ok(ActiveOrderMsg::OpenOrders(open_orders)) => self.reconcile_open_orders(open_orders),
             Ok(active_order_msg) => self.handle_active_order(active_order_msg),
             Err(_e) => {}
         }
@@ -451,6 +549,177 @@ impl<'a> OrderManager {
         Ok(())
     }
 
+    // Open orders of the exchange account are adopted if they were sent by the previous run,
+    // the rest are canceled. Robots replace adopted orders as the ones they've sent.
+    fn reconcile_open_orders(&self, open_orders: AccountOpenOrders) {
+        let gateway = open_orders.gateway;
+        let account = open_orders.account;
+
+        let mut adopted_orders = Vec::new();
+        let mut cancel_orders = Vec::new();
+
+        for open_order in open_orders.orders {
+            let known_order = match open_orders.policy {
+                ReconcilePolicy::AdoptKnown => self.known_order(&open_order.custom_order_id),
+                ReconcilePolicy::CancelAll => None,
+            };
+
+            match known_order {
+                Some(order_container) => {
+                    adopted_orders.push(self.adopt_order(&account, &open_order, order_container))
+                }
+                None => cancel_orders.push(Self::open_to_cancel(&gateway, &account, &open_order)),
+            }
+        }
+
+        info!(
+            "[Order Manager] {} {} reconciliation ({}): adopted {} {:?}, canceled {} {:?}",
+            gateway,
+            account,
+            open_orders.policy,
+            adopted_orders.len(),
+            Self::custom_order_ids(&adopted_orders),
+            cancel_orders.len(),
+            Self::custom_order_ids(&cancel_orders)
+        );
+
+        if !cancel_orders.is_empty() {
+            self.send_orders_to_gateway(&gateway, cancel_orders);
+        }
+
+        // Sending replaces sent orders of the gateway, so adopted ones are added after it
+        match self.sent_orders.write() {
+            Ok(mut sent_orders) => {
+                let adopted_ids = Self::custom_order_ids(&adopted_orders);
+
+                let gateway_sent_orders = sent_orders.entry(gateway).or_default();
+
+                // Order of the current run is replaced by the adopted one
+                gateway_sent_orders
+                    .retain(|sent_order| !adopted_ids.contains(&Self::custom_order_id(sent_order)));
+                gateway_sent_orders.extend(adopted_orders);
+
+                Self::store_sent_orders(&sent_orders);
+            }
+            Err(e) => error!("Poison error {}", e),
+        }
+    }
+
+    // Sent order with the custom id from the previous or the current run
+    fn known_order(&self, custom_order_id: &str) -> Option<OrderContainer> {
+        let recovered_order = match self.recovered_orders.write() {
+            Ok(mut recovered_orders) => recovered_orders.remove(custom_order_id),
+            Err(e) => {
+                error!("Poison error {}", e);
+                None
+            }
+        };
+
+        if recovered_order.is_some() {
+            return recovered_order;
+        }
+
+        match self.sent_orders.read() {
+            Ok(sent_orders) => sent_orders
+                .values()
+                .flatten()
+                .find(|order_container| match &order_container.order {
+                    Order::LimitOrder(limit_order) => {
+                        limit_order.custom_order_id == custom_order_id
+                    }
+                    _ => false,
+                })
+                .cloned(),
+            Err(e) => {
+                error!("Poison error {}", e);
+                None
+            }
+        }
+    }
+
+    // Saves open order as active order of the robot which sent it.
+    // It returns the sent order with the unfilled amount.
+    fn adopt_order(
+        &self,
+        account: &str,
+        open_order: &gateway::OpenOrder,
+        order_container: OrderContainer,
+    ) -> OrderContainer {
+        let mut adopted_order = order_container;
+
+        if let Order::LimitOrder(limit_order) = &mut adopted_order.order {
+            limit_order.amount = open_order.amount;
+            limit_order.price = open_order.price;
+            limit_order.account = Some(account.to_string());
+
+            self.handle_active_order(ActiveOrderMsg::ActiveStateOrder(ActiveOrder {
+                custom_order_id: open_order.custom_order_id.clone(),
+                robot_id: adopted_order.robot_id.clone(),
+                gateway: limit_order.gateway.clone(),
+                symbol: open_order.symbol.clone(),
+                amount: open_order.amount,
+                price: open_order.price,
+                order_side: open_order.order_side.clone(),
+                strategy_params: adopted_order.metainfo.clone(),
+            }));
+        }
+
+        adopted_order
+    }
+
+    // Cancel order for the open order on the exchange account
+    fn open_to_cancel(
+        gateway: &str,
+        account: &str,
+        open_order: &gateway::OpenOrder,
+    ) -> OrderContainer {
+        OrderContainer {
+            robot_id: RECONCILIATION_ROBOT_ID.to_string(),
+            order: Order::CancelOrder(CancelOrder {
+                order_id: open_order.order_id,
+                gateway: gateway.to_string(),
+                symbol: open_order.symbol.clone(),
+                price: open_order.price,
+                amount: open_order.amount,
+                order_side: open_order.order_side.clone(),
+                custom_order_id: open_order.custom_order_id.clone(),
+                account: Some(account.to_string()),
+            }),
+            metainfo: StrategyParams::Stub,
+            created_at: Instant::now(),
+        }
+    }
+
+    // Market orders have no custom id
+    fn custom_order_id(order_container: &OrderContainer) -> String {
+        match &order_container.order {
+            Order::LimitOrder(limit_order) => limit_order.custom_order_id.clone(),
+            Order::CancelOrder(cancel_order) => cancel_order.custom_order_id.clone(),
+            Order::StopOrder(stop_order) => stop_order.custom_order_id.clone(),
+            Order::ReplaceOrder(replace_order) => replace_order.limit_order.custom_order_id.clone(),
+            Order::MarketOrder(_) => String::new(),
+        }
+    }
+
+    fn custom_order_ids(order_containers: &[OrderContainer]) -> Vec<String> {
+        order_containers.iter().map(Self::custom_order_id).collect()
+    }
+
+    fn remove_sent_order(&self, gateway: &str, custom_order_id: &str) {
+        match self.sent_orders.write() {
+            Ok(mut sent_orders) => {
+                if let Some(gateway_sent_orders) = sent_orders.get_mut(gateway) {
+                    gateway_sent_orders.retain(|order_container| {
+                        Self::custom_order_id(order_container) != custom_order_id
+                    });
+                }
+            }
+            Err(e) => {
+                error!("Poison error {}", e);
+            }
+        }
+    }
+
     fn handle_active_order(&self, active_order_msg: ActiveOrderMsg) {
         match self.active_orders.write() {
             Ok(mut active_orders_lock) => match active_order_msg {
@@ -466,6 +735,40 @@ impl<'a> OrderManager {
                         }
                     }
                 }
+                // Open orders are reconciled on receive
+                ActiveOrderMsg::OpenOrders(_) => {}
+
+                // Final order is removed, partially filled one keeps the unfilled amount
+                ActiveOrderMsg::OrderState(order_state_msg) => {
+                    let order_state = &order_state_msg.order_state;
+
+                    if let Some(active_orders) =
+                        active_orders_lock.get_mut(&order_state_msg.robot_id)
+                    {
+                        if order_state.status.is_final() {
+                            active_orders.retain(|active_order| {
+                                active_order.custom_order_id != order_state.custom_order_id
+                            });
+                        } else {
+                            active_orders
+                                .iter_mut()
+                                .filter(|active_order| {
+                                    active_order.custom_order_id == order_state.custom_order_id
+                                })
+                                .for_each(|active_order| {
+                                    active_order.amount = order_state.left_amount()
+                                });
+                        }
+                    }
+
+                    if order_state.status.is_final() {
+                        self.remove_sent_order(
+                            &order_state_msg.gateway_name,
+                            &order_state.custom_order_id,
+                        );
+                    }
+                }
+
                 // If it is a filled order remove active order
                 ActiveOrderMsg::FilledOrder(_filled_order) => {
                     // let robot_id = filled_order.robot_id.clone();
@@ -529,11 +832,12 @@ impl<'a> OrderManager {
         }
     }
 
-    // Checks if an order is open
-    // If sent orders found return new Cancel Orders
+    // Checks if an order is open and returns orders to send instead of the new one.
+    // The first sent order is replaced by the new one or kept if it's unchanged,
+    // the rest of sent orders are canceled.
     fn check_order(&self, order_container: &OrderContainer) -> Vec<OrderContainer> {
         let robot_id = order_container.robot_id.clone();
-        let mut cancel_orders = Vec::new();
+        let mut orders_to_send = Vec::new();
 
         match &order_container.order {
             Order::LimitOrder(limit_order) => {
@@ -542,29 +846,52 @@ impl<'a> OrderManager {
                         // Gets Orders for specific gateway
                         match sent_orders.get_mut(&limit_order.gateway) {
                             Some(gateway_sent_orders) => {
-                                // Gets Orders for specific robot id
-
-                                // match gateway_sent_orders.get_mut(robot_id) {
-                                //     Some(robot_sent_orders) => todo!(),
-                                //     None => todo!(),
-                                // }
-
-                                //
                                 let indexes =
                                     Self::find_sent_orders(&gateway_sent_orders, &order_container);
 
-                                for index in indexes {
-                                    // Remove sent order from store
-                                    let sent_order = gateway_sent_orders.remove(index);
+                                // Remove sent orders from store, from the end to keep indexes valid
+                                let mut found_orders = indexes
+                                    .into_iter()
+                                    .rev()
+                                    .map(|index| gateway_sent_orders.remove(index))
+                                    .collect::<Vec<_>>();
+                                found_orders.reverse();
+
+                                let mut found_orders = found_orders.into_iter();
+                                let replaced_order = found_orders.next();
 
-                                    cancel_orders
+                                for sent_order in found_orders {
+                                    orders_to_send
                                         .push(Self::convert_limit_to_cancel(&sent_order).unwrap());
                                 }
+
+                                match replaced_order {
+                                    Some(sent_order)
+                                        if Self::is_unchanged(&sent_order, limit_order) =>
+                                    {
+                                        debug!(
+                                            "[Order Manager] Order of {} robot is unchanged",
+                                            robot_id
+                                        );
+
+                                        gateway_sent_orders.push(sent_order);
+                                    }
+                                    Some(sent_order) => orders_to_send.push(
+                                        Self::convert_limit_to_replace(
+                                            &sent_order,
+                                            order_container,
+                                        )
+                                        .unwrap(),
+                                    ),
+                                    None => orders_to_send.push(order_container.clone()),
+                                }
                             }
 
                             None => {
                                 // Not an error
                                 warn!("Robot Id not found. Perhaps, there are no active orders for that Robot. Robot ID: {}", robot_id);
+
+                                orders_to_send.push(order_container.clone());
                             }
                         }
                     }
@@ -574,14 +901,11 @@ impl<'a> OrderManager {
                 }
             }
 
-            // Don't check market orders for sent order
-            Order::MarketOrder(_) => {}
-
-            // And don't check cancel order
-            Order::CancelOrder(_) => {}
+            // Market, cancel and stop orders are sent as they are
+            _ => orders_to_send.push(order_container.clone()),
         }
 
-        cancel_orders
+        orders_to_send
     }
 
     // Find orders that Robot(strategy) sent on previous step
@@ -611,6 +935,8 @@ impl<'a> OrderManager {
 
                     // And don't check cancel order
                     Order::CancelOrder(_) => {}
+
+                    Order::StopOrder(_) | Order::ReplaceOrder(_) => {}
                 }
             }
         }
@@ -623,6 +949,8 @@ impl<'a> OrderManager {
             Order::LimitOrder(limit_order) => limit_order.gateway.clone(),
             Order::MarketOrder(market_order) => market_order.gateway.clone(),
             Order::CancelOrder(cancel_order) => cancel_order.gateway.clone(),
+            Order::StopOrder(stop_order) => stop_order.gateway.clone(),
+            Order::ReplaceOrder(replace_order) => replace_order.limit_order.gateway.clone(),
         }
     }
 
@@ -649,9 +977,10 @@ impl<'a> OrderManager {
 mod tests {
 
     use super::*;
-    use crate::context_manager::ActiveOrder;
-    use crate::order_manager::models::{LimitOrder, OrderSide};
+    use crate::context_manager::{ActiveOrder, OrderStateMsg};
+    use crate::order_manager::models::{LimitOrder, OrderSide, TimeInForce};
     use crate::order_manager::utils::OrderManagerUtils;
+    use crate::order_manager::{OrderEvent, OrderState};
     use crate::robot::strategy::StrategyParams;
     use crossbeam::channel::unbounded;
     use std::time::Instant;
@@ -714,100 +1043,103 @@ mod tests {
         assert!(order_manager.stop().is_err());
     }
 
-    #[test]
-    fn check_order() {
-        let active_orders = vec![
-            // Other Robot
-            ActiveOrder {
-                robot_id: "Robot2".to_string(),
-                order_id: 123,
-                symbol: "BTCUSDT".to_string(),
+    fn sent_order(
+        robot_id: &str,
+        gateway: &str,
+        symbol: &str,
+        order_side: OrderSide,
+        custom_order_id: &str,
+    ) -> OrderContainer {
+        OrderContainer {
+            robot_id: robot_id.to_string(),
+            order: Order::LimitOrder(LimitOrder {
+                gateway: gateway.to_string(),
+                symbol: symbol.to_string(),
                 amount: 1.,
                 price: 1.,
-                gateway: "Binance".to_string(),
-                order_side: OrderSide::Buy,
-            },
+                order_side,
+                custom_order_id: custom_order_id.to_string(),
+                account: None,
+                time_in_force: TimeInForce::Gtc,
+            }),
+            metainfo: StrategyParams::Stub,
+            created_at: Instant::now(),
+        }
+    }
+
+    fn stub_sent_orders() -> OrderManager {
+        let order_manager = OrderManagerUtils::stub_active_orders(HashMap::new());
+
+        let sent_orders = vec![
+            // Other Robot
+            sent_order("Robot2", "Binance", "BTCUSDT", OrderSide::Buy, "Order1"),
             // Other Gateway
-            ActiveOrder {
-                robot_id: "Robot1".to_string(),
-                order_id: 456,
-                symbol: "BTCUSDT".to_string(),
-                amount: 1.,
-                price: 1.,
-                gateway: "Huobi".to_string(),
-                order_side: OrderSide::Buy,
-            },
+            sent_order("Robot1", "Huobi", "BTCUSDT", OrderSide::Buy, "Order2"),
             // Other symbol
-            ActiveOrder {
-                robot_id: "Robot1".to_string(),
-                order_id: 789,
-                symbol: "ETHUSDT".to_string(),
-                amount: 1.,
-                price: 1.,
-                gateway: "Binance".to_string(),
-                order_side: OrderSide::Buy,
-            },
+            sent_order("Robot1", "Binance", "ETHUSDT", OrderSide::Buy, "Order3"),
             // Other Side
-            ActiveOrder {
-                robot_id: "Robot1".to_string(),
-                order_id: 1011,
-                symbol: "BTCUSDT".to_string(),
-                amount: 1.,
-                price: 1.,
-                gateway: "Binance".to_string(),
-                order_side: OrderSide::Sell,
-            },
+            sent_order("Robot1", "Binance", "BTCUSDT", OrderSide::Sell, "Order4"),
             // Found
-            ActiveOrder {
-                robot_id: "Robot1".to_string(),
-                order_id: 1213,
-                symbol: "BTCUSDT".to_string(),
-                amount: 1.,
-                price: 1.,
-                gateway: "Binance".to_string(),
-                order_side: OrderSide::Buy,
-            },
+            sent_order("Robot1", "Binance", "BTCUSDT", OrderSide::Buy, "Order5"),
         ];
 
-        let mut robot_active_orders = HashMap::new();
-        robot_active_orders.insert("Robot1".to_string(), active_orders);
+        order_manager
+            .sent_orders
+            .write()
+            .unwrap()
+            .insert("Binance".to_string(), sent_orders);
 
-        let order_manager = OrderManagerUtils::stub_active_orders(robot_active_orders);
+        order_manager
+    }
 
-        let order = OrderContainer {
-            robot_id: "Robot1".to_string(),
-            order: Order::LimitOrder(LimitOrder {
-                // robot_id: "Robot1".to_string(),
-                gateway: "Binance".to_string(),
-                symbol: "BTCUSDT".to_string(),
-                amount: 1.,
-                price: 1.,
-                order_side: OrderSide::Buy,
-                custom_order_id: "Custom Order ID".to_string(),
-            }),
+    #[test]
+    fn check_order() {
+        let order_manager = stub_sent_orders();
 
-            metainfo: StrategyParams::Stub,
-            created_at: Instant::now(),
-        };
+        let mut order = sent_order("Robot1", "Binance", "BTCUSDT", OrderSide::Buy, "Order6");
+        if let Order::LimitOrder(limit_order) = &mut order.order {
+            limit_order.price = 2.;
+        }
 
-        let close_order = OrderContainer {
-            robot_id: "Robot1".to_string(),
-            order: Order::CancelOrder(CancelOrder {
-                // robot_id: "Robot1".to_string(),
-                gateway: "Binance".to_string(),
-                symbol: "BTCUSDT".to_string(),
-                order_id: 1213,
-                amount: 1.,
-                price: 1.,
-                order_side: OrderSide::Buy,
-                custom_order_id: "Custom Order ID".to_string(),
+        let replace_order = OrderContainer {
+            order: Order::ReplaceOrder(ReplaceOrder {
+                cancel_order: CancelOrder {
+                    order_id: 0,
+                    gateway: "Binance".to_string(),
+                    symbol: "BTCUSDT".to_string(),
+                    price: 1.,
+                    amount: 1.,
+                    order_side: OrderSide::Buy,
+                    custom_order_id: "Order5".to_string(),
+                    account: None,
+                },
+                limit_order: match &order.order {
+                    Order::LimitOrder(limit_order) => limit_order.clone(),
+                    _ => unreachable!(),
+                },
             }),
-
-            metainfo: StrategyParams::Stub,
-            created_at: Instant::now(),
+            ..order.clone()
         };
 
-        assert_eq!(order_manager.check_order(&order), vec![close_order]);
+        assert_eq!(order_manager.check_order(&order), vec![replace_order]);
+        assert_eq!(
+            order_manager.sent_orders.read().unwrap()["Binance"].len(),
+            4
+        );
+    }
+
+    #[test]
+    fn check_order_unchanged() {
+        let order_manager = stub_sent_orders();
+
+        let order = sent_order("Robot1", "Binance", "BTCUSDT", OrderSide::Buy, "Order6");
+
+        // Open order with the same price and amount stays on the exchange
+        assert!(order_manager.check_order(&order).is_empty());
+        assert_eq!(
+            order_manager.sent_orders.read().unwrap()["Binance"].len(),
+            5
+        );
     }
 
     #[test]
@@ -850,4 +1182,138 @@ mod tests {
             sent_orders_result
         );
     }
+
+    #[test]
+    fn reconcile_open_orders() {
+        let gateway = "Binance";
+
+        let (order_sender_from_order_manager, order_receiver_to_gateway): (
+            Sender<OrderMsg>,
+            Receiver<OrderMsg>,
+        ) = unbounded();
+
+        let mut senders = HashMap::new();
+        senders.insert(gateway.to_string(), order_sender_from_order_manager);
+
+        let order_manager = OrderManagerUtils::with_channels(senders, unbounded().1);
+
+        let known_order = OrderContainer {
+            robot_id: "Robot1".to_string(),
+            order: Order::LimitOrder(LimitOrder {
+                custom_order_id: "Known".to_string(),
+                ..LimitOrder::default()
+            }),
+            metainfo: StrategyParams::Stub,
+            created_at: Instant::now(),
+        };
+
+        order_manager
+            .recovered_orders
+            .write()
+            .unwrap()
+            .insert("Known".to_string(), known_order);
+
+        let open_order = |custom_order_id: &str| gateway::OpenOrder {
+            symbol: "BTCUSDT".to_string(),
+            order_id: 1,
+            custom_order_id: custom_order_id.to_string(),
+            order_side: OrderSide::Buy,
+            price: 10.,
+            amount: 0.4,
+        };
+
+        order_manager.reconcile_open_orders(AccountOpenOrders {
+            gateway: gateway.to_string(),
+            account: "Account1".to_string(),
+            policy: ReconcilePolicy::AdoptKnown,
+            orders: vec![open_order("Known"), open_order("Unknown")],
+        });
+
+        // Unknown order is canceled on its account
+        match order_receiver_to_gateway.try_recv() {
+            Ok(OrderMsg::OrderContainers(order_containers)) => {
+                assert_eq!(order_containers.len(), 1);
+
+                match &order_containers[0].order {
+                    Order::CancelOrder(cancel_order) => {
+                        assert_eq!(cancel_order.custom_order_id, "Unknown");
+                        assert_eq!(cancel_order.account, Some("Account1".to_string()));
+                    }
+                    order => panic!("Unexpected order {:?}", order),
+                }
+            }
+            _ => panic!("Cancel order wasn't sent"),
+        }
+
+        // Known order is adopted by its robot with the unfilled amount
+        let active_orders = order_manager.active_orders.read().unwrap();
+        assert_eq!(active_orders["Robot1"].len(), 1);
+        assert_eq!(active_orders["Robot1"][0].custom_order_id, "Known");
+        assert_eq!(active_orders["Robot1"][0].amount, 0.4);
+
+        let sent_orders = order_manager.sent_orders.read().unwrap();
+        assert_eq!(sent_orders[gateway].len(), 1);
+        assert_eq!(sent_orders[gateway][0].robot_id, "Robot1");
+
+        assert!(order_manager.recovered_orders.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn handle_order_state() {
+        let gateway = "Binance";
+
+        let order_manager = OrderManagerUtils::with_channels(HashMap::new(), unbounded().1);
+
+        order_manager.sent_orders.write().unwrap().insert(
+            gateway.to_string(),
+            vec![OrderContainer {
+                robot_id: "Robot1".to_string(),
+                order: Order::LimitOrder(LimitOrder {
+                    custom_order_id: "Order1".to_string(),
+                    ..LimitOrder::default()
+                }),
+                metainfo: StrategyParams::Stub,
+                created_at: Instant::now(),
+            }],
+        );
+
+        order_manager.handle_active_order(ActiveOrderMsg::ActiveStateOrder(ActiveOrder {
+            custom_order_id: "Order1".to_string(),
+            robot_id: "Robot1".to_string(),
+            amount: 2.,
+            ..ActiveOrder::default()
+        }));
+
+        let mut order_state = OrderState::new("Order1", "BTCUSDT", OrderSide::Buy, 2., 100.);
+
+        let order_state_msg = |order_state: &OrderState| {
+            ActiveOrderMsg::OrderState(OrderStateMsg {
+                robot_id: "Robot1".to_string(),
+                gateway_name: gateway.to_string(),
+                account: "Account1".to_string(),
+                order_state: order_state.clone(),
+                instrument_id: None,
+                created_at: Instant::now(),
+            })
+        };
+
+        order_state
+            .apply(&OrderEvent::Fill {
+                amount: 0.5,
+                price: 100.,
+            })
+            .unwrap();
+        order_manager.handle_active_order(order_state_msg(&order_state));
+
+        assert_eq!(
+            order_manager.active_orders.read().unwrap()["Robot1"][0].amount,
+            1.5
+        );
+
+        order_state.apply(&OrderEvent::Cancelled).unwrap();
+        order_manager.handle_active_order(order_state_msg(&order_state));
+
+        assert!(order_manager.active_orders.read().unwrap()["Robot1"].is_empty());
+        assert!(order_manager.sent_orders.read().unwrap()[gateway].is_empty());
+    }
 }
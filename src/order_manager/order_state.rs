@@ -0,0 +1,233 @@
+use super::OrderSide;
+use strum_macros::Display;
+
+// Filled amount closer than that to the order amount is considered full fill
+const FILL_EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum OrderStatus {
+    // Sent to the exchange, not acknowledged yet
+    PendingNew,
+    New,
+    PartiallyFilled,
+    Filled,
+    // Cancel is requested, the order still can be filled
+    PendingCancel,
+    Cancelled,
+    Rejected,
+    Expired,
+}
+
+impl OrderStatus {
+    // Order in the final status doesn't change anymore
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Filled
+                | OrderStatus::Cancelled
+                | OrderStatus::Rejected
+                | OrderStatus::Expired
+        )
+    }
+}
+
+// Event of the order reported by the exchange or by the gateway request
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderEvent {
+    Accepted,
+    // Last fill of the order
+    Fill { amount: f64, price: f64 },
+    CancelRequested,
+    Cancelled,
+    Rejected(String),
+    Expired,
+}
+
+// Order state machine, it tracks cumulative filled amount and average fill price
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderState {
+    pub custom_order_id: String,
+    pub order_id: u64,
+    pub symbol: String,
+    pub order_side: OrderSide,
+    pub amount: f64,
+    pub price: f64,
+    pub status: OrderStatus,
+    pub filled_amount: f64,
+    pub average_price: f64,
+    // Reason of the reject
+    pub reason: Option<String>,
+}
+
+impl OrderState {
+    pub fn new(
+        custom_order_id: &str,
+        symbol: &str,
+        order_side: OrderSide,
+        amount: f64,
+        price: f64,
+    ) -> Self {
+        OrderState {
+            custom_order_id: custom_order_id.to_string(),
+            order_id: 0,
+            symbol: symbol.to_string(),
+            order_side,
+            amount,
+            price,
+            status: OrderStatus::PendingNew,
+            filled_amount: 0.,
+            average_price: 0.,
+            reason: None,
+        }
+    }
+
+    pub fn left_amount(&self) -> f64 {
+        (self.amount - self.filled_amount).max(0.)
+    }
+
+    // It returns true if the status is changed or the order is filled more.
+    // Event which doesn't apply to the status, e.g. late acknowledge, is ignored.
+    pub fn apply(&mut self, event: &OrderEvent) -> Result<bool, &'static str> {
+        if self.status.is_final() {
+            return Err("Order is already in the final status");
+        }
+
+        let status = match (self.status, event) {
+            (_, OrderEvent::Fill { amount, price }) => {
+                self.fill(*amount, *price);
+
+                if self.left_amount() <= FILL_EPSILON {
+                    OrderStatus::Filled
+                } else if self.status == OrderStatus::PendingCancel {
+                    OrderStatus::PendingCancel
+                } else {
+                    OrderStatus::PartiallyFilled
+                }
+            }
+
+            (OrderStatus::PendingNew, OrderEvent::Accepted) => OrderStatus::New,
+            (_, OrderEvent::Accepted) => return Ok(false),
+
+            (OrderStatus::PendingCancel, OrderEvent::CancelRequested) => return Ok(false),
+            (_, OrderEvent::CancelRequested) => OrderStatus::PendingCancel,
+
+            (_, OrderEvent::Cancelled) => OrderStatus::Cancelled,
+            (_, OrderEvent::Expired) => OrderStatus::Expired,
+
+            (OrderStatus::PendingNew, OrderEvent::Rejected(reason))
+            | (OrderStatus::New, OrderEvent::Rejected(reason)) => {
+                self.reason = Some(reason.clone());
+                OrderStatus::Rejected
+            }
+            // Partially filled order can't be rejected
+            (_, OrderEvent::Rejected(_)) => return Err("Order can't be rejected"),
+        };
+
+        let changed = status != self.status || matches!(event, OrderEvent::Fill { .. });
+
+        self.status = status;
+
+        Ok(changed)
+    }
+
+    fn fill(&mut self, amount: f64, price: f64) {
+        let filled_amount = self.filled_amount + amount;
+
+        if filled_amount > 0. {
+            self.average_price =
+                (self.average_price * self.filled_amount + price * amount) / filled_amount;
+        }
+
+        self.filled_amount = filled_amount;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{OrderEvent, OrderState, OrderStatus};
+    use crate::order_manager::OrderSide;
+
+    fn order_state() -> OrderState {
+        OrderState::new("Order1", "BTCUSDT", OrderSide::Buy, 2., 100.)
+    }
+
+    #[test]
+    fn partial_fills() {
+        let mut order_state = order_state();
+
+        assert_eq!(order_state.apply(&OrderEvent::Accepted), Ok(true));
+        assert_eq!(order_state.status, OrderStatus::New);
+
+        order_state
+            .apply(&OrderEvent::Fill {
+                amount: 0.5,
+                price: 100.,
+            })
+            .unwrap();
+        assert_eq!(order_state.status, OrderStatus::PartiallyFilled);
+
+        order_state
+            .apply(&OrderEvent::Fill {
+                amount: 1.5,
+                price: 96.,
+            })
+            .unwrap();
+        assert_eq!(order_state.status, OrderStatus::Filled);
+        assert_eq!(order_state.filled_amount, 2.);
+        assert_eq!(order_state.average_price, 97.);
+
+        assert!(order_state.apply(&OrderEvent::Cancelled).is_err());
+    }
+
+    #[test]
+    fn fill_before_acknowledge() {
+        let mut order_state = order_state();
+
+        order_state
+            .apply(&OrderEvent::Fill {
+                amount: 1.,
+                price: 100.,
+            })
+            .unwrap();
+
+        // Late acknowledge doesn't move the order back
+        assert_eq!(order_state.apply(&OrderEvent::Accepted), Ok(false));
+        assert_eq!(order_state.status, OrderStatus::PartiallyFilled);
+    }
+
+    #[test]
+    fn cancel() {
+        let mut order_state = order_state();
+        order_state.apply(&OrderEvent::Accepted).unwrap();
+
+        order_state.apply(&OrderEvent::CancelRequested).unwrap();
+        assert_eq!(order_state.status, OrderStatus::PendingCancel);
+
+        // Order is filled while the cancel is pending
+        order_state
+            .apply(&OrderEvent::Fill {
+                amount: 0.5,
+                price: 100.,
+            })
+            .unwrap();
+        assert_eq!(order_state.status, OrderStatus::PendingCancel);
+
+        order_state.apply(&OrderEvent::Cancelled).unwrap();
+        assert_eq!(order_state.status, OrderStatus::Cancelled);
+        assert_eq!(order_state.left_amount(), 1.5);
+    }
+
+    #[test]
+    fn reject() {
+        let mut order_state = order_state();
+
+        order_state
+            .apply(&OrderEvent::Rejected("Not enough balance".to_string()))
+            .unwrap();
+
+        assert_eq!(order_state.status, OrderStatus::Rejected);
+        assert!(order_state.status.is_final());
+        assert_eq!(order_state.reason, Some("Not enough balance".to_string()));
+    }
+}
@@ -2,6 +2,7 @@ use super::{ActiveOrderMsg, Order, OrderContainer, OrderManager, OrderMsg};
 use crate::context_manager::ActiveOrder;
 use crate::order_manager::models::{
     CancelOrder, LimitOrder, MarketOrder, OrderManagerState, OrderRequestType, OrderSide,
+    TimeInForce,
 };
 use crossbeam::channel::{bounded, unbounded, Receiver, Sender};
 use std::collections::HashMap;
@@ -25,6 +26,18 @@ impl OrderUtils {
                 "Cancel {} {:?} {} {}",
                 cancel.gateway, cancel.order_side, cancel.price, cancel.custom_order_id
             ),
+            Order::StopOrder(stop) => info!(
+                "Stop {} {:?} {} {}",
+                stop.gateway, stop.order_side, stop.stop_price, stop.custom_order_id
+            ),
+            Order::ReplaceOrder(replace) => info!(
+                "Replace {} {:?} {} {} by {}",
+                replace.limit_order.gateway,
+                replace.limit_order.order_side,
+                replace.limit_order.price,
+                replace.cancel_order.custom_order_id,
+                replace.limit_order.custom_order_id
+            ),
         });
         info!("-");
     }
@@ -49,6 +62,8 @@ impl OrderUtils {
                 price: price.unwrap(),
                 order_side,
                 custom_order_id: custom_order_id.to_string(),
+                account: None,
+                time_in_force: TimeInForce::Gtc,
             }),
             OrderRequestType::Market => Order::MarketOrder(MarketOrder {
                 // robot_id: robot_id.to_string(),
@@ -56,6 +71,7 @@ impl OrderUtils {
                 symbol: symbol.to_string(),
                 amount,
                 order_side,
+                account: None,
             }),
             OrderRequestType::Cancel => Order::CancelOrder(CancelOrder {
                 order_id: order_id.unwrap(),
@@ -66,6 +82,7 @@ impl OrderUtils {
                 amount: amount,
                 order_side: order_side,
                 custom_order_id: custom_order_id.to_string(),
+                account: None,
             }),
         }
     }
@@ -116,6 +133,7 @@ impl OrderManagerUtils {
             active_order_msg_receiver,
 
             active_orders: RwLock::new(active_orders),
+            recovered_orders: RwLock::new(HashMap::new()),
             // orders_to_send: RwLock::new(orders_to_send),
             ask_stop_channel: bounded(0),
             send_stop_channel: bounded(0),
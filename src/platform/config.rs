@@ -14,6 +14,9 @@ pub struct Robot {
 pub struct Gateway {
     pub name: String,
     pub config_file_path: String,
+    // Depths and fills of the gateway are written by Market Recorder
+    #[serde(default)]
+    pub record: bool,
 }
 
 #[derive(Deserialize, Debug)]
@@ -50,7 +53,9 @@ impl PartialEq for Robot {
 
 impl PartialEq for Gateway {
     fn eq(&self, other: &Self) -> bool {
-        self.name == other.name && self.config_file_path == other.config_file_path
+        self.name == other.name
+            && self.config_file_path == other.config_file_path
+            && self.record == other.record
     }
 }
 
@@ -87,10 +92,12 @@ mod tests {
                     Gateway {
                         name: "Huobi".to_string(),
                         config_file_path: format!("{}/gateway_huobi_config.toml", TEST_DIR),
+                        record: false,
                     },
                     Gateway {
                         name: "Binance".to_string(),
                         config_file_path: format!("{}/gateway_binance_config.toml", TEST_DIR),
+                        record: false,
                     },
                 ],
 
@@ -1,3 +1,4 @@
+use std::collections::VecDeque;
 use std::{ops::Add, time::Duration};
 pub struct Metrics {
     data: Vec<Duration>,
@@ -83,6 +84,33 @@ impl Metrics {
     }
 }
 
+// The latest samples of the endless series, the oldest one is dropped when the window is full
+pub struct SampleWindow {
+    samples: VecDeque<Duration>,
+    capacity: usize,
+}
+
+impl SampleWindow {
+    pub fn new(capacity: usize) -> Self {
+        SampleWindow {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, sample: Duration) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(sample);
+    }
+
+    pub fn samples(&self) -> Vec<Duration> {
+        self.samples.iter().copied().collect()
+    }
+}
+
 fn test_data() -> Vec<Duration> {
     let times = vec![
         Duration::from_micros(1),
@@ -99,7 +127,7 @@ fn test_data() -> Vec<Duration> {
 #[cfg(test)]
 mod tests {
 
-    use super::Metrics;
+    use super::{Metrics, SampleWindow};
     use crate::platform::metrics::test_data;
     use std::time::Duration;
 
@@ -210,4 +238,22 @@ mod tests {
         let max = metrics.max();
         assert_eq!(max, Duration::from_secs(20));
     }
+
+    #[test]
+    fn sample_window() {
+        let mut window = SampleWindow::new(3);
+
+        for millis in 1..=5 {
+            window.push(Duration::from_millis(millis));
+        }
+
+        assert_eq!(
+            window.samples(),
+            vec![
+                Duration::from_millis(3),
+                Duration::from_millis(4),
+                Duration::from_millis(5)
+            ]
+        );
+    }
 }
@@ -10,7 +10,8 @@ mod platform_controller;
 pub use config::{PlatformConfig, PLATFORM_CONFIG_FILE_PATH};
 pub use environment::{Environment, EnvironmentAction};
 pub use platform::{
-    Platform, PlatformUtils, Status, GATEWAY_TO_ROBOT_TIMES, ROBOT_TO_GATEWAY_TIMES,
+    Platform, PlatformUtils, Status, EXCHANGE_TO_GATEWAY_TIMES, GATEWAY_TO_ROBOT_TIMES,
+    ROBOT_TO_GATEWAY_TIMES,
 };
 
 pub use platform_controller::PlatforomController;
@@ -1,8 +1,8 @@
-use super::metrics::Metrics; 
+use super::metrics::{Metrics, SampleWindow}; 
 use super::utils::uppercase_first_letter; 
 use super::{Environment, PlatformConfig, PLATFORM_CONFIG_FILE_PATH};
 use crate::{
-    context_manager::{ContextManager, ContextMsg, GatewayMsg},  
+    context_manager::{ContextManager, ContextMsg, GatewayMsg, MarketRecorder, RECORDS_DIR},  
     gateway::{GatewayEnvironment, GatewayStatus},
     order_manager::{ActiveOrderMsg, OrderManager, OrderMsg},
     robot::{RobotEnvironment, RobotStatus},
@@ -17,9 +17,14 @@ use std::{collections::HashMap, sync::RwLock};
 use strum_macros::{Display, EnumString};
 use tracing::{debug, info}; 
 
+// Public trades are endless, so only the latest latencies are kept for the metrics
+const EXCHANGE_TO_GATEWAY_SAMPLES: usize = 100_000;
+
 lazy_static! {
     pub static ref ROBOT_TO_GATEWAY_TIMES: Mutex<Vec<Duration>> = Mutex::new(Vec::new());
     pub static ref GATEWAY_TO_ROBOT_TIMES: Mutex<Vec<Duration>> = Mutex::new(Vec::new());
+    pub static ref EXCHANGE_TO_GATEWAY_TIMES: Mutex<SampleWindow> =
+        Mutex::new(SampleWindow::new(EXCHANGE_TO_GATEWAY_SAMPLES));
 }
 
 #[derive(Debug, EnumString, Display, PartialEq)] 
@@ -144,6 +149,15 @@ impl<'a> Platform {
                     .map(|g| g.name.clone())
                     .collect(),
                 PlatformUtils::get_robot_subscriptions(&platform_config),
+                MarketRecorder::new(
+                    RECORDS_DIR,
+                    platform_config
+                        .gateways
+                        .iter()
+                        .filter(|g| g.record)
+                        .map(|g| g.name.clone())
+                        .collect(),
+                ),
             ),
 
             order_manager: OrderManager::init(
@@ -237,6 +251,13 @@ impl<'a> Platform {
 
         println!("Gateway to Robot {}", gateway_to_robot.calc());
 
+        // Public trades are only streamed by some exchanges
+        if let Some(exchange_to_gateway) =
+            Metrics::init(EXCHANGE_TO_GATEWAY_TIMES.lock().unwrap().samples())
+        {
+            println!("Exchange to Gateway {}", exchange_to_gateway.calc());
+        }
+
         Ok(())
     }
 
@@ -192,12 +192,13 @@ impl RiskControl {
         let _total_pnl: i32 = self.total_pnl();
     }
 
+    // PnL is net of paid commissions
     fn calc_pnl(positions: &Vec<Position>) -> f64 {
         positions
             .iter()
             .map(|p| match p.order_side {
-                OrderSide::Buy => -p.amount * p.price,
-                OrderSide::Sell => p.amount * p.price,
+                OrderSide::Buy => -p.amount * p.price - p.fee,
+                OrderSide::Sell => p.amount * p.price - p.fee,
             })
             .sum()
     }
@@ -319,4 +320,23 @@ mod tests {
         let pnl_format = format!("{:.2}", pnl);
         assert_eq!("-1.10", pnl_format);
     }
+
+    #[test]
+    fn calc_pnl_net_of_fees() {
+        let positions = vec![
+            Position {
+                fee: 0.1,
+                ..Position::init_bid_stub_position(1.3)
+            },
+            Position {
+                fee: 0.05,
+                ..Position::init_ask_stub_position(1.1)
+            },
+        ];
+
+        let pnl = RiskControl::calc_pnl(&positions);
+
+        let pnl_format = format!("{:.2}", pnl);
+        assert_eq!("0.05", pnl_format);
+    }
 }
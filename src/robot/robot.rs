@@ -5,7 +5,7 @@ use super::{
     PNLComponent, RobotGateways, RobotPNL, RobotParams, RobotParamsActions, RobotStrategyType,
 };
 use crate::context_manager::{ContextInfo, ContextMsg};
-use crate::order_manager::{LimitOrder, MarketOrder, Order, OrderContainer, OrderMsg};
+use crate::order_manager::{LimitOrder, MarketOrder, Order, OrderContainer, OrderMsg, StopOrder};
 use crate::platform::GATEWAY_TO_ROBOT_TIMES;
 use crate::storage::SensorMsg;
 use crate::{config::ParseConfig, storage::sensors::InfluxPoint};
@@ -16,7 +16,7 @@ use std::hash::{Hash, Hasher};
 use std::time::Instant;
 use std::{cmp::PartialEq, fmt::Debug, str::FromStr, sync::RwLock, thread, thread::JoinHandle};
 use strum_macros::Display;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 #[derive(Debug, PartialEq, Clone, Display)]
 pub enum RobotStatus {
@@ -195,7 +195,10 @@ impl Robot {
         // Strategy calculates orders
         match self.calc() {
             Ok((orders, sensors)) => {
-                if orders.len() > 0 {
+                // Locked Robot doesn't quote anymore
+                let is_locked = matches!(*self.status.read().unwrap(), RobotStatus::Locked);
+
+                if orders.len() > 0 && !is_locked {
                     self.send_order(orders)?;
                 }
 
@@ -272,12 +275,28 @@ impl Robot {
                                     "{}",
                                     OrderParams::calculate_hash(&order_params)
                                 ),
+                                account: action.account,
+                                time_in_force: limit.time_in_force,
+                            }),
+                            OrderType::Stop(stop) => Order::StopOrder(StopOrder {
+                                gateway: action.exchange,
+                                symbol: action.symbol,
+                                amount: action.amount,
+                                stop_price: stop.stop_price,
+                                limit_price: stop.limit_price,
+                                order_side: action.order_side,
+                                custom_order_id: format!(
+                                    "{}",
+                                    OrderParams::calculate_hash(&order_params)
+                                ),
+                                account: action.account,
                             }),
                             OrderType::Market(_market) => Order::MarketOrder(MarketOrder {
                                 gateway: action.exchange,
                                 symbol: action.symbol,
                                 amount: action.amount,
                                 order_side: action.order_side,
+                                account: action.account,
                             }),
                         },
                         metainfo: action.extended_strategy_params,
@@ -332,6 +351,31 @@ impl Robot {
                                 Err(_e) => error!("Can't get access to Risk Control"),
                             }
                         }
+                        ContextMsg::RejectedOrder(rejected_order) => {
+                            warn!(
+                                "[Robot] Order to {} was rejected: {}",
+                                rejected_order.gateway_name, rejected_order.reason
+                            );
+
+                            self.robot_params
+                                .read()
+                                .unwrap()
+                                .strategy
+                                .on_rejected_order(&rejected_order);
+                        }
+                        ContextMsg::OrderState(order_state_msg) => {
+                            debug!(
+                                "[Robot] Order {} is {}",
+                                order_state_msg.order_state.custom_order_id,
+                                order_state_msg.order_state.status
+                            );
+
+                            self.robot_params
+                                .read()
+                                .unwrap()
+                                .strategy
+                                .on_order_state(&order_state_msg);
+                        }
                     },
                     Err(_e) => {
                         error!("RwLock error");
@@ -146,6 +146,10 @@ impl<'a> RobotEnvironment {
         self.find_robot(robot_name)?.stop()
     }
 
+    pub fn lock_robot(&self, robot_name: &str) -> Result<(), &'static str> {
+        self.find_robot(robot_name)?.lock()
+    }
+
     pub fn status_robot(&self, robot_name: &str) -> Result<RobotStatus, &'static str> {
         match self.find_robot(robot_name)?.status() {
             Ok(status) => Ok(status.clone()),
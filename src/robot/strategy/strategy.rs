@@ -3,12 +3,14 @@ use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher}; 
 
 use crate::storage::{self, sensors::InfluxPoint};
-use crate::{context_manager::ContextInfo, order_manager::OrderSide};
+use crate::context_manager::{ContextInfo, OrderStateMsg, RejectedOrder};
+use crate::order_manager::{OrderSide, TimeInForce};
 
 #[derive(Debug, Clone, PartialEq)] 
 pub enum OrderType {
     Market(Market), 
     Limit(Limit),
+    Stop(Stop),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -17,6 +19,14 @@ pub struct Market {}
 #[derive(Debug, Clone, PartialEq)]
 pub struct Limit {
     pub price: f64,
+    pub time_in_force: TimeInForce,
+}
+
+// Stop market order without limit price, stop limit order with it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stop {
+    pub stop_price: f64,
+    pub limit_price: Option<f64>,
 }
 
 #[derive(Debug)]
@@ -26,6 +36,8 @@ pub struct Action {
     pub exchange: String,
     pub order_type: OrderType,
     pub order_side: OrderSide,
+    // Gateway account for the order, None means the default account
+    pub account: Option<String>,
     pub extended_strategy_params: StrategyParams,
 }
 
@@ -55,8 +67,12 @@ impl Default for Action {
             amount: 1.1,
             symbol: "BTCUSDT".to_string(),
             exchange: "Binance".to_string(),
-            order_type: OrderType::Limit(Limit { price: 1.1 }),
+            order_type: OrderType::Limit(Limit {
+                price: 1.1,
+                time_in_force: TimeInForce::Gtc,
+            }),
             order_side: OrderSide::Buy,
+            account: None,
             extended_strategy_params: StrategyParams::ArbitrationParams(ArbitrationParams {
                 axes_id: "Binance".to_string(),
                 level: "1".to_string(),
@@ -103,6 +119,12 @@ pub trait Strategy: Sync + Send + std::fmt::Debug {
     fn sense(&mut self) -> Vec<storage::SensorMsg> {
         Vec::new()
     }
+
+    // Gateway didn't send the order, e.g. it's less than the exchange minimum
+    fn on_rejected_order(&self, _rejected_order: &RejectedOrder) {}
+
+    // Order changed its state, e.g. it's partially filled or canceled
+    fn on_order_state(&self, _order_state: &OrderStateMsg) {}
 }
 
 #[cfg(test)]
@@ -14,6 +14,13 @@ pub struct ConfigRequestParams {
     config_file_path: String,
 }
 
+// Empty symbol or account means all of them
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FlattenRequestParams {
+    symbol: Option<String>,
+    account: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PlatformConfigRequestParams {
     config_file_path: String,
@@ -135,6 +142,21 @@ pub async fn gateway_info(req: HttpRequest) -> impl Responder {
     GatewayController::info(name)
 }
 
+pub async fn gateway_balances(req: HttpRequest) -> impl Responder {
+    let name = req.match_info().get("name").unwrap();
+
+    GatewayController::balances(name)
+}
+
+pub async fn gateway_flatten(
+    req: HttpRequest,
+    params: web::Form<FlattenRequestParams>,
+) -> impl Responder {
+    let name = req.match_info().get("name").unwrap();
+
+    GatewayController::flatten(name, params.symbol.as_deref(), params.account.as_deref())
+}
+
 pub async fn gateway_set_config(
     _req: HttpRequest,
     params: web::Form<ConfigRequestParams>,
@@ -36,6 +36,8 @@ pub mod paths {
     pub const GATEWAY_STOP: &str = "gateway/stop/{name}";
     pub const GATEWAY_STATUS: &str = "gateway/status/{name}";
     pub const GATEWAY_INFO: &str = "gateway/info/{name}";
+    pub const GATEWAY_BALANCES: &str = "gateway/balances/{name}";
+    pub const GATEWAY_FLATTEN: &str = "gateway/flatten/{name}";
     pub const GATEWAY_SET_CONFIG: &str = "gateway/set_config/{name}";
     pub const GATEWAY_UP: &str = "gateway/up";
     pub const GATEWAY_LIST: &str = "gateway/list";
@@ -95,6 +97,8 @@ impl Server {
                 .route(GATEWAY_STOP, web::post().to(gateway_stop))
                 .route(GATEWAY_STATUS, web::get().to(gateway_status))
                 .route(GATEWAY_INFO, web::get().to(gateway_info))
+                .route(GATEWAY_BALANCES, web::get().to(gateway_balances))
+                .route(GATEWAY_FLATTEN, web::post().to(gateway_flatten))
                 .route(GATEWAY_SET_CONFIG, web::post().to(gateway_set_config))
                 .route(GATEWAY_UP, web::post().to(gateway_up))
                 .route(GATEWAY_LIST, web::get().to(gateway_list))